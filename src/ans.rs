@@ -0,0 +1,220 @@
+/// Aptos Name Service resolution through the ANS router's view functions.
+use crate::{Aptos, types::ViewRequest, wallet::format_address_aip40};
+use serde_json::{Value, json};
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// how long resolved names and addresses are cached
+pub const ANS_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// a parsed `.apt` name, e.g. `pay.alice.apt`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnsName {
+    pub domain: String,
+    pub subdomain: Option<String>,
+}
+
+impl AnsName {
+    /// parse `alice`, `alice.apt` or `sub.alice.apt`, case insensitive
+    pub fn parse(name: &str) -> Result<Self, String> {
+        let lowercase = name.trim().to_lowercase();
+        let bare = lowercase.strip_suffix(".apt").unwrap_or(&lowercase);
+        let labels: Vec<&str> = bare.split('.').collect();
+        let (subdomain, domain) = match labels.as_slice() {
+            [domain] => (None, *domain),
+            [subdomain, domain] => (Some(*subdomain), *domain),
+            _ => return Err(format!("invalid ans name: {}", name)),
+        };
+        for label in subdomain.iter().chain([&domain]) {
+            let valid_chars = label
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+            if !(3..=63).contains(&label.len())
+                || !valid_chars
+                || label.starts_with('-')
+                || label.ends_with('-')
+            {
+                return Err(format!("invalid ans name: {}", name));
+            }
+        }
+        Ok(AnsName {
+            domain: domain.to_string(),
+            subdomain: subdomain.map(|s| s.to_string()),
+        })
+    }
+}
+
+impl fmt::Display for AnsName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.subdomain {
+            Some(subdomain) => write!(f, "{}.{}.apt", subdomain, self.domain),
+            None => write!(f, "{}.apt", self.domain),
+        }
+    }
+}
+
+/// short lived cache of ans lookups, shared by clones of a client
+#[derive(Debug, Default)]
+pub(crate) struct AnsCache {
+    entries: Mutex<HashMap<String, (Instant, Option<String>)>>,
+}
+
+impl AnsCache {
+    fn get(&self, key: &str) -> Option<Option<String>> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(key)
+            .filter(|(cached_at, _)| cached_at.elapsed() < ANS_CACHE_TTL)
+            .map(|(_, value)| value.clone())
+    }
+
+    fn insert(&self, key: String, value: Option<String>) {
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(key, (Instant::now(), value));
+    }
+}
+
+/// inner value of a move `Option` in view output
+fn option_value(value: &Value) -> Option<&Value> {
+    value.get("vec")?.as_array()?.first()
+}
+
+impl Aptos {
+    /// ANS router of the client's network
+    fn ans_router(&self) -> Result<&str, String> {
+        self.network_config()
+            .ans_router
+            .as_deref()
+            .ok_or_else(|| "ANS is not deployed on this network".to_string())
+    }
+
+    /// address a name such as `alice.apt` or `pay.alice.apt` points to, `None` if the
+    /// name is unregistered or expired
+    pub async fn resolve_ans_name(&self, name: &str) -> Result<Option<String>, String> {
+        let name = AnsName::parse(name)?;
+        let key = format!("name:{}", name);
+        if let Some(address) = self.ans_cache.get(&key) {
+            return Ok(address);
+        }
+        let subdomain = match &name.subdomain {
+            Some(subdomain) => json!({ "vec": [subdomain] }),
+            None => json!({ "vec": [] }),
+        };
+        let result = self
+            .view(
+                &ViewRequest {
+                    function: format!("{}::router::get_target_addr", self.ans_router()?),
+                    type_arguments: vec![],
+                    arguments: vec![json!(name.domain), subdomain],
+                },
+                None,
+            )
+            .await?;
+        let address = result
+            .first()
+            .and_then(option_value)
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+        self.ans_cache.insert(key, address.clone());
+        Ok(address)
+    }
+
+    /// primary name of `address`, e.g. `alice.apt`, `None` if it has not set one
+    pub async fn reverse_resolve_ans(&self, address: &str) -> Result<Option<String>, String> {
+        let address = format_address_aip40(address)?;
+        let key = format!("address:{}", address);
+        if let Some(name) = self.ans_cache.get(&key) {
+            return Ok(name);
+        }
+        let result = self
+            .view(
+                &ViewRequest {
+                    function: format!("{}::router::get_primary_name", self.ans_router()?),
+                    type_arguments: vec![],
+                    arguments: vec![json!(address)],
+                },
+                None,
+            )
+            .await?;
+        let label = |index: usize| {
+            result
+                .get(index)
+                .and_then(option_value)
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+        };
+        let name = label(1).map(|domain| {
+            AnsName {
+                domain,
+                subdomain: label(0),
+            }
+            .to_string()
+        });
+        self.ans_cache.insert(key, name.clone());
+        Ok(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AptosType, transport::MockTransport};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_parse_ans_name() {
+        let alice = AnsName::parse("Alice.apt").unwrap();
+        assert_eq!((alice.domain.as_str(), alice.subdomain), ("alice", None));
+        let pay = AnsName::parse("pay.alice.apt").unwrap();
+        assert_eq!(pay.subdomain.as_deref(), Some("pay"));
+        assert_eq!(pay.to_string(), "pay.alice.apt");
+        assert_eq!(AnsName::parse("alice").unwrap().to_string(), "alice.apt");
+        assert!(AnsName::parse("al.apt").is_err());
+        assert!(AnsName::parse("a.b.alice.apt").is_err());
+        assert!(AnsName::parse("-alice.apt").is_err());
+        assert!(AnsName::parse("ali_ce.apt").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_ans_name_and_reverse() {
+        let transport = Arc::new(
+            MockTransport::new()
+                .on_post("/view", 200, r#"[{"vec":["0xa11ce"]}]"#)
+                .on_post("/view", 200, r#"[{"vec":["pay"]},{"vec":["alice"]}]"#),
+        );
+        let client = Aptos::with_transport(AptosType::Mainnet, Arc::clone(&transport) as _);
+        assert_eq!(
+            client.resolve_ans_name("pay.alice.apt").await.unwrap(),
+            Some("0xa11ce".to_string())
+        );
+        let request = &transport.requests()[0];
+        assert_eq!(
+            request.body.as_ref().unwrap()["arguments"],
+            json!(["alice", {"vec": ["pay"]}])
+        );
+        // served from the cache
+        assert_eq!(
+            client.resolve_ans_name("PAY.alice.apt").await.unwrap(),
+            Some("0xa11ce".to_string())
+        );
+        assert_eq!(transport.requests().len(), 1);
+        assert_eq!(
+            client.reverse_resolve_ans("0xa11ce").await.unwrap(),
+            Some("pay.alice.apt".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unregistered_ans_name_is_none() {
+        let transport = MockTransport::new().on_post("/view", 200, r#"[{"vec":[]}]"#);
+        let client = Aptos::with_transport(AptosType::Mainnet, Arc::new(transport));
+        assert_eq!(client.resolve_ans_name("nobody.apt").await.unwrap(), None);
+        assert!(client.resolve_ans_name("not a name").await.is_err());
+    }
+}
@@ -1,7 +1,10 @@
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
-use crate::{Aptos, trade::TransactionInfo};
+use crate::{
+    Aptos,
+    trade::{TransactionInfo, TransactionType},
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Block {
@@ -15,6 +18,51 @@ pub struct Block {
     pub transactions: Option<Vec<TransactionInfo>>,
 }
 
+impl Block {
+    /// user transactions of a block fetched with transactions
+    pub fn user_transactions(&self) -> Vec<&TransactionInfo> {
+        self.transactions
+            .iter()
+            .flatten()
+            .filter(|txn| txn.is_user_transaction())
+            .collect()
+    }
+
+    /// metadata of the block's `block_metadata_transaction`, `None` if the block was
+    /// fetched without transactions
+    pub fn metadata(&self) -> Option<BlockMetadata> {
+        self.transactions.iter().flatten().find_map(|txn| {
+            let TransactionType::BlockMetadataTransaction(metadata) = &txn.transaction_type else {
+                return None;
+            };
+            // the flattened `TransactionInfo` takes the timestamp
+            let timestamp = txn.timestamp.as_deref().unwrap_or(&metadata.timestamp);
+            Some(BlockMetadata {
+                id: metadata.id.clone(),
+                epoch: metadata.epoch.parse().unwrap_or(0),
+                round: metadata.round.parse().unwrap_or(0),
+                proposer: metadata.proposer.clone(),
+                failed_proposer_indices: metadata.failed_proposer_indices.clone(),
+                timestamp: timestamp.parse().unwrap_or(0),
+            })
+        })
+    }
+}
+
+/// Typed block metadata
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockMetadata {
+    /// Block id
+    pub id: String,
+    pub epoch: u64,
+    pub round: u64,
+    /// Proposer address
+    pub proposer: String,
+    pub failed_proposer_indices: Vec<u64>,
+    /// Block timestamp in microseconds since epoch
+    pub timestamp: u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlockInfo {
     /// Block height
@@ -116,8 +164,152 @@ mod tests {
     use crate::AptosType;
 
     use super::*;
+    use crate::transport::MockTransport;
+    use futures::StreamExt;
     use std::sync::Arc;
 
+    fn block_json(height: u64) -> String {
+        let first = height * 10;
+        format!(
+            r#"{{
+                "block_height": "{height}",
+                "block_hash": "0x{height:064x}",
+                "block_timestamp": "1700000000000000",
+                "first_version": "{first}",
+                "last_version": "{last}",
+                "transactions": [
+                    {{
+                        "version": "{first}",
+                        "hash": "0x01",
+                        "state_change_hash": "0x02",
+                        "event_root_hash": "0x03",
+                        "state_checkpoint_hash": null,
+                        "gas_used": "0",
+                        "success": true,
+                        "vm_status": "Executed successfully",
+                        "accumulator_root_hash": "0x04",
+                        "changes": [],
+                        "id": "0x05",
+                        "epoch": "7",
+                        "round": "1",
+                        "events": [],
+                        "previous_block_votes_bitvec": [255, 1],
+                        "proposer": "0x06",
+                        "failed_proposer_indices": [],
+                        "timestamp": "1700000000000000",
+                        "type": "block_metadata_transaction"
+                    }},
+                    {{
+                        "version": "{last}",
+                        "hash": "0x07",
+                        "state_change_hash": "0x08",
+                        "event_root_hash": "0x09",
+                        "state_checkpoint_hash": "0x0a",
+                        "gas_used": "0",
+                        "success": true,
+                        "vm_status": "Executed successfully",
+                        "accumulator_root_hash": "0x0b",
+                        "changes": [],
+                        "timestamp": "1700000000000000",
+                        "type": "state_checkpoint_transaction"
+                    }},
+                    {{
+                        "version": "{last}",
+                        "hash": "0x0c",
+                        "state_checkpoint_hash": "0x0d",
+                        "gas_used": "0",
+                        "success": true,
+                        "vm_status": "Executed successfully",
+                        "changes": [],
+                        "timestamp": "1700000000000000",
+                        "block_end_info": null,
+                        "type": "block_epilogue_transaction"
+                    }}
+                ]
+            }}"#,
+            height = height,
+            first = first,
+            last = first + 1
+        )
+    }
+
+    #[tokio::test]
+    async fn test_get_block_with_transactions() {
+        let transport =
+            Arc::new(MockTransport::new().on_get("/blocks/by_height/5", 200, &block_json(5)));
+        let aptos = Aptos::with_transport(AptosType::Mainnet, transport.clone());
+        let block = aptos.get_block_by_height(5, true).await.unwrap();
+        assert!(
+            transport.requests()[0]
+                .url
+                .ends_with("/blocks/by_height/5?with_transactions=true")
+        );
+        let transactions = block.transactions.unwrap();
+        assert_eq!(transactions.len(), 3);
+        assert_eq!(transactions[0].version, "50");
+        assert!(!transactions[0].is_user_transaction());
+    }
+
+    #[test]
+    fn test_block_metadata_and_user_transactions() {
+        let mut block: serde_json::Value = serde_json::from_str(&block_json(5)).unwrap();
+        block["transactions"]
+            .as_array_mut()
+            .unwrap()
+            .insert(
+                1,
+                serde_json::json!({
+                    "version": "51",
+                    "hash": "0x0e",
+                    "state_checkpoint_hash": null,
+                    "success": true,
+                    "timestamp": "1700000000000000",
+                    "type": "user_transaction",
+                    "sender": "0xa",
+                    "sequence_number": "0",
+                    "payload": {"type": "entry_function_payload", "function": "0x1::coin::transfer", "type_arguments": [], "arguments": []},
+                    "signature": {"type": "ed25519_signature", "public_key": "0x0b", "signature": "0x0c"}
+                }),
+            );
+        let block: Block = serde_json::from_value(block).unwrap();
+        let user_transactions = block.user_transactions();
+        assert_eq!(user_transactions.len(), 1);
+        assert_eq!(user_transactions[0].get_sender(), Some("0xa"));
+        assert_eq!(
+            block.metadata(),
+            Some(BlockMetadata {
+                id: "0x05".to_string(),
+                epoch: 7,
+                round: 1,
+                proposer: "0x06".to_string(),
+                failed_proposer_indices: vec![],
+                timestamp: 1700000000000000,
+            })
+        );
+        let without_transactions = Block {
+            transactions: None,
+            ..block
+        };
+        assert!(without_transactions.user_transactions().is_empty());
+        assert_eq!(without_transactions.metadata(), None);
+    }
+
+    #[tokio::test]
+    async fn test_blocks_stream_in_order() {
+        let transport = MockTransport::new()
+            .on_get("/blocks/by_height/3?", 200, &block_json(3))
+            .on_get("/blocks/by_height/4?", 200, &block_json(4))
+            .on_get("/blocks/by_height/5?", 200, &block_json(5));
+        let aptos = Aptos::with_transport(AptosType::Mainnet, Arc::new(transport));
+        let heights: Vec<String> = aptos
+            .blocks(3, 5)
+            .map(|block| block.unwrap().block_height)
+            .collect()
+            .await;
+        assert_eq!(heights, vec!["3", "4", "5"]);
+        assert_eq!(aptos.blocks(6, 5).count().await, 0);
+    }
+
     #[tokio::test]
     async fn test_get_latest_block() {
         let aptos = Arc::new(Aptos::new(AptosType::Testnet));
@@ -125,7 +317,7 @@ mod tests {
         match chain_height_result {
             Ok(height) => {
                 println!("Chain height: {}", height);
-                match aptos.get_block_by_height(height).await {
+                match aptos.get_block_by_height(height, false).await {
                     Ok(block) => {
                         let block_info = BlockInfo::from_aptos_block(&block);
                         println!("✅ Successfully got latest block");
@@ -1,4 +1,8 @@
-use crate::{Aptos, types::ContractCall, wallet::Wallet};
+use crate::{
+    Aptos,
+    types::{CallKind, ContractCall, MoveArgument},
+    wallet::Wallet,
+};
 use serde_json::{Value, json};
 use std::sync::Arc;
 
@@ -21,10 +25,11 @@ impl SystemBridge {
             function_name: "transfer_to_chain".to_string(),
             type_arguments: vec![token_type.to_string()],
             arguments: vec![
-                json!(target_chain),
-                json!(amount.to_string()),
-                json!(recipient),
+                json!(target_chain).into(),
+                MoveArgument::U64(amount),
+                MoveArgument::Address(recipient.to_string()),
             ],
+            kind: CallKind::Entry,
         };
         crate::contract::Contract::write(client, wallet, contract_call)
             .await
@@ -43,7 +48,8 @@ impl SystemBridge {
             module_name: "bridge".to_string(),
             function_name: "claim_from_chain".to_string(),
             type_arguments: vec![],
-            arguments: vec![json!(source_chain), json!(transaction_hash)],
+            arguments: vec![json!(source_chain).into(), json!(transaction_hash).into()],
+            kind: CallKind::Entry,
         };
         crate::contract::Contract::write(client, wallet, contract_call)
             .await
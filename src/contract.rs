@@ -1,14 +1,15 @@
-use aptos_network_tool::address::address_to_bytes;
 use futures::future::join_all;
 // src/contract.rs
 use serde_json::{Value, json};
 use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::sync::watch;
 
 use crate::{
     Aptos,
+    log::log_warn,
     trade::Trade,
     types::{
-        ContractCall, ContractReadResult, ContractWriteResult, EntryFunctionPayload, Event,
+        CallKind, ContractCall, ContractReadResult, ContractWriteResult, Event, MoveArgument,
         ViewRequest,
     },
     wallet::Wallet,
@@ -25,6 +26,73 @@ pub const REGISTER: &str = "register";
 pub const MINT: &str = "mint";
 pub const BURN: &str = "burn";
 
+/// max gas amount of writes that are not simulated first
+pub const WRITE_MAX_GAS_AMOUNT: u64 = 2000;
+
+/// options of `Contract::write_with_options`
+#[derive(Debug, Clone)]
+pub struct WriteOptions {
+    /// the transaction expires this many seconds from now
+    pub expiration_secs: u64,
+    /// how long to wait for the commit and the confirmations
+    pub timeout_secs: u64,
+    /// versions committed on top of the transaction before the write returns, see
+    /// `Aptos::wait_for_finality`
+    pub confirmations: Option<u64>,
+    /// simulate the call first and sign it with the node's gas estimate instead of
+    /// `WRITE_MAX_GAS_AMOUNT`
+    pub estimate_gas: bool,
+    /// simulate the call first and do not submit it if the simulation aborts
+    pub require_simulation: bool,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        WriteOptions {
+            expiration_secs: 30,
+            timeout_secs: 30,
+            confirmations: None,
+            estimate_gas: false,
+            require_simulation: false,
+        }
+    }
+}
+
+/// Stops the event listeners of `Contract`, clones share the signal.
+#[derive(Debug, Clone)]
+pub struct StopSignal {
+    sender: Arc<watch::Sender<bool>>,
+}
+
+impl StopSignal {
+    pub fn new() -> Self {
+        StopSignal {
+            sender: Arc::new(watch::channel(false).0),
+        }
+    }
+
+    /// stop every listener holding this signal, after its current poll
+    pub fn stop(&self) {
+        self.sender.send_replace(true);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        *self.sender.borrow()
+    }
+
+    /// resolves once `stop` is called
+    async fn stopped(&self) {
+        let mut receiver = self.sender.subscribe();
+        let _ = receiver.wait_for(|stopped| *stopped).await;
+    }
+}
+
+impl Default for StopSignal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub struct Contract {}
 impl Contract {
     /// read contract data (view read)
@@ -39,9 +107,9 @@ impl Contract {
         let view_request = ViewRequest {
             function,
             type_arguments: contract_call.type_arguments.clone(),
-            arguments: contract_call.arguments.clone(),
+            arguments: contract_call.json_arguments(),
         };
-        match client.view(&view_request).await {
+        match client.view(&view_request, None).await {
             Ok(result) => Ok(ContractReadResult {
                 success: true,
                 data: Value::Array(result),
@@ -61,91 +129,122 @@ impl Contract {
         wallet: Arc<Wallet>,
         contract_call: ContractCall,
     ) -> Result<ContractWriteResult, String> {
-        let function_str = format!(
-            "{}::{}::{}",
-            contract_call.module_address, contract_call.module_name, contract_call.function_name
-        );
-        let function_vec = function_str.as_bytes().to_vec();
-        let mut type_args: Vec<Vec<u8>> = Vec::new();
-        contract_call
-            .type_arguments
-            .iter()
-            .for_each(|s| type_args.push(s.as_bytes().to_vec()));
-        let mut args: Vec<Vec<u8>> = Vec::new();
-        contract_call
-            .arguments
-            .iter()
-            .for_each(|s| args.push(s.as_str().unwrap().to_string().as_bytes().to_vec()));
-        let payload = EntryFunctionPayload {
-            module_address: address_to_bytes(&contract_call.module_address)
-                .unwrap()
-                .to_vec(),
-            module_name: address_to_bytes(&contract_call.module_name)
-                .unwrap()
-                .to_vec(),
-            function_name: function_vec,
-            type_arguments: type_args,
-            arguments: args,
+        Self::write_with_expiration(client, wallet, contract_call, 30).await
+    }
+
+    /// write contract, the transaction expires `expiration_secs` from now
+    pub async fn write_with_expiration(
+        client: Arc<Aptos>,
+        wallet: Arc<Wallet>,
+        contract_call: ContractCall,
+        expiration_secs: u64,
+    ) -> Result<ContractWriteResult, String> {
+        let options = WriteOptions {
+            expiration_secs,
+            ..WriteOptions::default()
         };
-        let raw_txn = Trade::create_call_contract_tx(
+        Self::write_with_options(client, wallet, contract_call, &options).await
+    }
+
+    /// Write contract with an expiration, a confirmation timeout and an optional
+    /// number of confirmations to wait for.
+    ///
+    /// The call is only simulated with `estimate_gas` or `require_simulation`. With
+    /// `require_simulation` a call whose simulation fails is not submitted, the error
+    /// carries the simulated `vm_status`.
+    pub async fn write_with_options(
+        client: Arc<Aptos>,
+        wallet: Arc<Wallet>,
+        contract_call: ContractCall,
+        options: &WriteOptions,
+    ) -> Result<ContractWriteResult, String> {
+        let mut raw_txn = Trade::create_entry_function_tx(
             Arc::clone(&client),
             Arc::clone(&wallet),
-            None,
-            30,
-            2000,
-            100,
-            payload,
+            contract_call.entry_function_payload(),
+            options.expiration_secs,
         )
-        .await;
-        // use wallet sign
-        let signature = wallet.sign(&serde_json::to_vec(&raw_txn).unwrap()).unwrap();
-        let signed_txn = json!({
-            "transaction": raw_txn,
-            "signature": {
-                "type": "ed25519_signature",
-                "public_key": wallet.public_key_hex()?,
-                "signature": hex::encode(signature)
+        .await?;
+        if options.estimate_gas || options.require_simulation {
+            // the gas estimate simulates the very transaction that is signed
+            let simulated =
+                Trade::estimate_gas(Arc::clone(&client), Arc::clone(&wallet), &mut raw_txn).await?;
+            if options.require_simulation && !simulated.success {
+                return Err(format!(
+                    "simulation failed, transaction not submitted: {}",
+                    simulated.vm_status
+                ));
             }
-        });
-        match client.submit_transaction(&signed_txn).await {
+        } else {
+            raw_txn["max_gas_amount"] = json!(WRITE_MAX_GAS_AMOUNT.to_string());
+        }
+        let signed_txn =
+            Trade::sign_contract_call(&client, wallet, raw_txn, &contract_call).await?;
+        Self::submit_and_wait_with(&client, &signed_txn, options).await
+    }
+
+    /// submit a signed transaction and wait for its confirmation
+    pub(crate) async fn submit_and_wait(
+        client: &Aptos,
+        signed_txn: &Value,
+    ) -> Result<ContractWriteResult, String> {
+        Self::submit_and_wait_with(client, signed_txn, &WriteOptions::default()).await
+    }
+
+    async fn submit_and_wait_with(
+        client: &Aptos,
+        signed_txn: &Value,
+        options: &WriteOptions,
+    ) -> Result<ContractWriteResult, String> {
+        match client.submit_transaction(signed_txn).await {
             Ok(transaction) => {
                 // awaiting
-                if let Ok(confirmed_txn) = client.waiting_transaction(&transaction.hash, 30).await {
-                    Ok(ContractWriteResult {
-                        success: confirmed_txn.success,
-                        transaction_hash: confirmed_txn.hash,
-                        gas_used: confirmed_txn.max_gas_amount.unwrap(),
-                        events: confirmed_txn
-                            .events
-                            .into_iter()
-                            .map(|e| {
-                                json!({
-                                    "type": e.r#type,
-                                    "data": e.data,
-                                    "sequence_number": e.sequence_number
+                let confirmed = client.wait_for_finality(
+                    &transaction.hash,
+                    options.confirmations.unwrap_or(0),
+                    options.timeout_secs,
+                );
+                match confirmed.await {
+                    Ok(confirmed_txn) => {
+                        let gas_unit_price = confirmed_txn.get_gas_unit_price();
+                        Ok(ContractWriteResult {
+                            success: confirmed_txn.success,
+                            transaction_hash: confirmed_txn.hash,
+                            gas_used: confirmed_txn.gas_used,
+                            gas_unit_price,
+                            events: confirmed_txn
+                                .events
+                                .into_iter()
+                                .map(|e| {
+                                    json!({
+                                        "type": e.r#type,
+                                        "data": e.data,
+                                        "sequence_number": e.sequence_number
+                                    })
                                 })
-                            })
-                            .collect(),
-                        error: if confirmed_txn.success {
-                            None
-                        } else {
-                            Some(confirmed_txn.vm_status)
-                        },
-                    })
-                } else {
-                    Ok(ContractWriteResult {
+                                .collect(),
+                            error: if confirmed_txn.success {
+                                None
+                            } else {
+                                Some(confirmed_txn.vm_status)
+                            },
+                        })
+                    }
+                    Err(e) => Ok(ContractWriteResult {
                         success: false,
                         transaction_hash: transaction.hash,
                         gas_used: "0".to_string(),
+                        gas_unit_price: None,
                         events: Vec::new(),
-                        error: Some("Transaction confirmation timeout".to_string()),
-                    })
+                        error: Some(format!("Transaction confirmation timeout: {}", e)),
+                    }),
                 }
             }
             Err(e) => Ok(ContractWriteResult {
                 success: false,
                 transaction_hash: String::new(),
                 gas_used: "0".to_string(),
+                gas_unit_price: None,
                 events: Vec::new(),
                 error: Some(e.to_string()),
             }),
@@ -157,58 +256,76 @@ impl Contract {
         client: Arc<Aptos>,
         calls: Vec<ContractCall>,
     ) -> Result<Vec<ContractReadResult>, String> {
-        let mut results = Vec::new();
-        for call in calls {
-            results.push(Contract::read(Arc::clone(&client), &call).await.unwrap());
-        }
-        Ok(results)
+        Ok(Contract::view_batch(client, &calls).await)
+    }
+
+    /// Read several views at once, results are in call order.
+    ///
+    /// The node api has no multicall endpoint and there is no aggregator module
+    /// deployed to call through, so every view is still its own request, but all of
+    /// them are in flight together and the batch costs one round trip of latency.
+    pub async fn view_batch(client: Arc<Aptos>, calls: &[ContractCall]) -> Vec<ContractReadResult> {
+        join_all(
+            calls
+                .iter()
+                .map(|call| Contract::read(Arc::clone(&client), call)),
+        )
+        .await
+        .into_iter()
+        .map(|result| {
+            result.unwrap_or_else(|e| ContractReadResult {
+                success: false,
+                data: Value::Null,
+                error: Some(e),
+            })
+        })
+        .collect()
     }
 
-    /// listen contract events
+    /// Listen contract events, calling `callback` with the data of each new event.
+    ///
+    /// Polls every `interval_secs` until `stop` is signaled or `max_duration` has
+    /// passed, then returns `Ok(())`.
     pub async fn listen_events(
         client: Arc<Aptos>,
         address: &str,
         event_type: &str,
         callback: impl Fn(Result<Value, String>),
         interval_secs: u64,
+        stop: StopSignal,
+        max_duration: Option<Duration>,
     ) -> Result<(), ()> {
-        let mut last_sequence_number: Option<u64> = None;
-        loop {
-            match client
-                .get_account_event_vec(address, event_type, Some(100), None)
-                .await
-            {
-                Ok(events) => {
-                    for event in events {
-                        if let Ok(current_seq) = event.sequence_number.parse::<u64>() {
-                            if let Some(last_seq) = last_sequence_number {
-                                if current_seq > last_seq {
-                                    callback(Ok(event.data.clone()));
-                                }
-                            } else {
-                                callback(Ok(event.data.clone()));
-                            }
-                            // update last sequence number
-                            last_sequence_number = Some(current_seq);
-                        }
-                    }
-                }
-                Err(e) => callback(Err(format!("no event exists: {:?}", e).to_string())),
-            }
-            tokio::time::sleep(Duration::from_secs(interval_secs)).await;
-        }
+        Self::listen_events_all_info(
+            client,
+            address,
+            event_type,
+            |event| callback(event.map(|event| event.data)),
+            interval_secs,
+            stop,
+            max_duration,
+        )
+        .await
     }
 
-    /// Event Listener - contains complete event information
+    /// Event Listener - contains complete event information, stops like
+    /// `listen_events`
     pub async fn listen_events_all_info(
         client: Arc<Aptos>,
         address: &str,
         event_type: &str,
         callback: impl Fn(Result<Event, String>),
         interval_secs: u64,
+        stop: StopSignal,
+        max_duration: Option<Duration>,
     ) -> Result<(), ()> {
+        let deadline = max_duration.map(|duration| tokio::time::Instant::now() + duration);
         let mut last_sequence_number: Option<u64> = None;
         loop {
+            if stop.is_stopped()
+                || deadline.is_some_and(|deadline| tokio::time::Instant::now() >= deadline)
+            {
+                return Ok(());
+            }
             match client
                 .get_account_event_vec(address, event_type, Some(100), None)
                 .await
@@ -229,7 +346,12 @@ impl Contract {
                 }
                 Err(e) => callback(Err(format!("no event exists: {:?}", e).to_string())),
             }
-            tokio::time::sleep(Duration::from_secs(interval_secs)).await;
+            let next_poll = tokio::time::Instant::now() + Duration::from_secs(interval_secs);
+            let wake = deadline.map_or(next_poll, |deadline| deadline.min(next_poll));
+            tokio::select! {
+                _ = tokio::time::sleep_until(wake) => {}
+                _ = stop.stopped() => return Ok(()),
+            }
         }
     }
 
@@ -265,7 +387,7 @@ impl Contract {
                 }
                 Err(e) => {
                     snapshot.insert(resource_type.to_string(), None);
-                    eprintln!("Error fetching resource {}: {}", resource_type, e);
+                    log_warn!("Error fetching resource {}: {}", resource_type, e);
                 }
             }
         }
@@ -290,6 +412,107 @@ impl Contract {
         Ok(())
     }
 
+    /// Verify a call against the on-chain function abi: the function exists, is a view
+    /// or entry function as `kind` says, and the type argument and argument counts and
+    /// argument json types match.
+    pub async fn validate_contract_call_abi(
+        client: Arc<Aptos>,
+        contract_call: &ContractCall,
+    ) -> Result<(), String> {
+        Self::validate_contract_call(contract_call)?;
+        let abi = client
+            .get_function_abi(
+                &contract_call.module_address,
+                &contract_call.module_name,
+                &contract_call.function_name,
+            )
+            .await?
+            .ok_or_else(|| format!("function not found: {}", contract_call.function_id()))?;
+        Self::validate_against_abi(contract_call, &abi)
+    }
+
+    /// check a call against an `exposed_functions` abi entry
+    pub fn validate_against_abi(contract_call: &ContractCall, abi: &Value) -> Result<(), String> {
+        let function = contract_call.function_id();
+        match contract_call.kind {
+            CallKind::View if abi["is_view"].as_bool() != Some(true) => {
+                return Err(format!("{} is not a view function", function));
+            }
+            CallKind::Entry if abi["is_entry"].as_bool() != Some(true) => {
+                return Err(format!("{} is not an entry function", function));
+            }
+            _ => {}
+        }
+        let type_params = abi["generic_type_params"].as_array().map_or(0, |p| p.len());
+        if type_params != contract_call.type_arguments.len() {
+            return Err(format!(
+                "{} expects {} type args, got {}",
+                function,
+                type_params,
+                contract_call.type_arguments.len()
+            ));
+        }
+        // the signer is supplied by the transaction, not the arguments
+        let params: Vec<&str> = abi["params"]
+            .as_array()
+            .map(|params| params.iter().filter_map(|p| p.as_str()).collect())
+            .unwrap_or_default();
+        let params: Vec<&str> = params
+            .into_iter()
+            .skip_while(|p| *p == "signer" || *p == "&signer")
+            .collect();
+        if params.len() != contract_call.arguments.len() {
+            return Err(format!(
+                "function expects {} args, got {}",
+                params.len(),
+                contract_call.arguments.len()
+            ));
+        }
+        for (index, (param, argument)) in params.iter().zip(&contract_call.arguments).enumerate() {
+            let argument = argument.to_json();
+            if !Self::argument_matches(param, &argument) {
+                return Err(format!(
+                    "argument {} expects {}, got {}",
+                    index, param, argument
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// whether a json argument can encode a value of move type `param`
+    fn argument_matches(param: &str, argument: &Value) -> bool {
+        let is_unsigned = |argument: &Value| match argument {
+            Value::Number(n) => n.is_u64(),
+            Value::String(s) => !s.is_empty() && s.chars().all(|c| c.is_ascii_digit()),
+            _ => false,
+        };
+        let is_address = |argument: &Value| {
+            argument.as_str().is_some_and(|s| {
+                s.strip_prefix("0x").is_some_and(|hex| {
+                    !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit())
+                })
+            })
+        };
+        match param {
+            "bool" => argument.is_boolean() || matches!(argument.as_str(), Some("true" | "false")),
+            "u8" | "u16" | "u32" | "u64" | "u128" | "u256" => is_unsigned(argument),
+            "address" => is_address(argument),
+            "0x1::string::String" => argument.is_string(),
+            "vector<u8>" => argument.is_string() || argument.is_array(),
+            _ if param.starts_with("0x1::object::Object<") => is_address(argument),
+            _ if param.starts_with("vector<") => match argument.as_array() {
+                Some(items) => {
+                    let inner = &param["vector<".len()..param.len() - 1];
+                    items.iter().all(|item| Self::argument_matches(inner, item))
+                }
+                None => false,
+            },
+            // structs and options are checked by the node
+            _ => true,
+        }
+    }
+
     /// Estimating contract call gas fees
     pub async fn estimate_gas_cost(
         client: Arc<Aptos>,
@@ -324,10 +547,10 @@ impl Contract {
             {
                 Ok(result) if result.success => return Ok(result),
                 Ok(result) => {
-                    eprintln!("Call failed on attempt {}: {:?}", retries + 1, result.error);
+                    log_warn!("Call failed on attempt {}: {:?}", retries + 1, result.error);
                 }
                 Err(e) => {
-                    eprintln!("Error on attempt {}: {}", retries + 1, e);
+                    log_warn!("Error on attempt {}: {}", retries + 1, e);
                 }
             }
             retries += 1;
@@ -338,34 +561,26 @@ impl Contract {
         Err(format!("Failed after {} retries", max_retries))
     }
 
-    /// Batch resource query
+    /// Batch resource query, a missing resource maps to `None`; fails with the first
+    /// resource that could not be read.
     pub async fn batch_get_resources(
         client: Arc<Aptos>,
         address: &str,
         resource_types: Vec<&str>,
     ) -> Result<HashMap<String, Option<Value>>, String> {
-        let mut tasks = Vec::new();
-        for resource_type in resource_types {
-            let client_clone = Arc::clone(&client);
+        let tasks = resource_types.into_iter().map(|resource_type| {
+            let client = Arc::clone(&client);
             let address = address.to_string();
             let resource_type = resource_type.to_string();
-            tasks.push(async move {
-                match client_clone
+            async move {
+                let resource = client
                     .get_account_resource(&address, &resource_type)
                     .await
-                {
-                    Ok(Some(resource)) => (resource_type, Some(resource.data)),
-                    Ok(None) => (resource_type, None),
-                    Err(_) => (resource_type, None),
-                }
-            });
-        }
-        let results = join_all(tasks).await;
-        let mut resource_map = HashMap::new();
-        for (resource_type, data) in results {
-            resource_map.insert(resource_type, data);
-        }
-        Ok(resource_map)
+                    .map_err(|e| format!("{} of {}: {}", resource_type, address, e))?;
+                Ok::<_, String>((resource_type, resource.map(|resource| resource.data)))
+            }
+        });
+        join_all(tasks).await.into_iter().collect()
     }
 
     /// Batch call contract write function
@@ -387,30 +602,27 @@ impl Contract {
         Ok(results)
     }
 
-    /// Simulate contract call execution (estimate Gas)
+    /// Simulate contract call execution (estimate Gas), returns `gas_used`, `success`,
+    /// `vm_status` and the simulated `changes` and `events`
     pub async fn simulate_call_contract(
         client: Arc<Aptos>,
         wallet: Arc<Wallet>,
         contract_call: &ContractCall,
     ) -> Result<Value, String> {
-        let function = format!(
-            "{}::{}::{}",
-            contract_call.module_address, contract_call.module_name, contract_call.function_name
-        );
-        todo!();
-        let payload = json!({
-            "function": function,
-            "type_arguments": contract_call.type_arguments,
-            "arguments": contract_call.arguments,
-            "sender": wallet.address().map_err(|e| e.to_string())?,
-        });
-
-        // test data
-        todo!();
+        let raw_txn = Trade::create_entry_function_tx(
+            Arc::clone(&client),
+            Arc::clone(&wallet),
+            contract_call.entry_function_payload(),
+            60,
+        )
+        .await?;
+        let simulated = Trade::simulate(client, wallet, raw_txn).await?;
         Ok(json!({
-            "gas_used": "1000",
-            "success": true,
-            "vm_status": "Executed successfully"
+            "gas_used": simulated.gas_used,
+            "success": simulated.success,
+            "vm_status": simulated.vm_status,
+            "changes": simulated.changes,
+            "events": simulated.events
         }))
     }
 
@@ -420,7 +632,10 @@ impl Contract {
         module_address: &str,
         module_name: &str,
     ) -> Result<Option<Value>, String> {
-        Ok(None)
+        Ok(client
+            .get_account_module(module_address, module_name)
+            .await?
+            .and_then(|module| module.abi))
     }
 
     /// Check if the contract has been published
@@ -504,11 +719,12 @@ impl Contract {
     ) -> Result<Value, String> {
         // Use existing transaction build and commit logic
         let contract_call = ContractCall {
-            module_address: wallet.address().map_err(|e| e.to_string())?,
+            module_address: wallet.address().to_string(),
             module_name: "".to_string(), // Deploying a contract does not require a module name
             function_name: "deploy".to_string(),
             type_arguments: vec![],
-            arguments: vec![json!(hex::encode(module_bytes))],
+            arguments: vec![json!(hex::encode(module_bytes)).into()],
+            kind: CallKind::Entry,
         };
         Self::write(client, wallet, contract_call)
             .await
@@ -523,11 +739,12 @@ impl Contract {
         new_module_bytes: Vec<u8>,
     ) -> Result<Value, String> {
         let contract_call = ContractCall {
-            module_address: wallet.address().map_err(|e| e.to_string())?,
+            module_address: wallet.address().to_string(),
             module_name: module_name.to_string(),
             function_name: "upgrade".to_string(),
             type_arguments: vec![],
-            arguments: vec![json!(hex::encode(new_module_bytes))],
+            arguments: vec![json!(hex::encode(new_module_bytes)).into()],
+            kind: CallKind::Entry,
         };
         Self::write(client, wallet, contract_call)
             .await
@@ -552,7 +769,8 @@ impl ContractUtils {
             module_name: module_name.to_string(),
             function_name: function_name.to_string(),
             type_arguments,
-            arguments,
+            arguments: arguments.into_iter().map(MoveArgument::from).collect(),
+            kind: CallKind::Entry,
         }
     }
 
@@ -581,3 +799,455 @@ impl ContractUtils {
         format!("0x{}", hex::encode(hasher.finalize()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AptosType, transport::MockTransport};
+
+    fn coin_module() -> String {
+        json!({
+            "bytecode": "0x00",
+            "abi": {
+                "address": "0x1",
+                "name": "coin",
+                "exposed_functions": [
+                    {
+                        "name": "transfer",
+                        "is_entry": true,
+                        "is_view": false,
+                        "generic_type_params": [{"constraints": []}],
+                        "params": ["&signer", "address", "u64"],
+                        "return": []
+                    },
+                    {
+                        "name": "balance",
+                        "is_entry": false,
+                        "is_view": true,
+                        "generic_type_params": [{"constraints": []}],
+                        "params": ["address"],
+                        "return": ["u64"]
+                    }
+                ]
+            }
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_validate_contract_call_abi() {
+        let client = Arc::new(Aptos::with_transport(
+            AptosType::Mainnet,
+            Arc::new(MockTransport::new().on_get("/accounts/0x1/module/coin", 200, &coin_module())),
+        ));
+        let apt = vec![APTOS_COIN.to_string()];
+        let transfer = ContractCall::entry(
+            "0x1",
+            "coin",
+            "transfer",
+            apt.clone(),
+            vec![json!("0xb0b"), json!("100")],
+        );
+        assert!(
+            Contract::validate_contract_call_abi(Arc::clone(&client), &transfer)
+                .await
+                .is_ok()
+        );
+        let missing_arg =
+            ContractCall::entry("0x1", "coin", "transfer", apt.clone(), vec![json!("0xb0b")]);
+        assert_eq!(
+            Contract::validate_contract_call_abi(Arc::clone(&client), &missing_arg)
+                .await
+                .unwrap_err(),
+            "function expects 2 args, got 1"
+        );
+        let bad_amount = ContractCall::entry(
+            "0x1",
+            "coin",
+            "transfer",
+            apt.clone(),
+            vec![json!("0xb0b"), json!(true)],
+        );
+        assert_eq!(
+            Contract::validate_contract_call_abi(Arc::clone(&client), &bad_amount)
+                .await
+                .unwrap_err(),
+            "argument 1 expects u64, got true"
+        );
+        let no_type_arg = ContractCall::view("0x1", "coin", "balance", vec![], vec![json!("0xa")]);
+        assert!(
+            Contract::validate_contract_call_abi(Arc::clone(&client), &no_type_arg)
+                .await
+                .unwrap_err()
+                .contains("expects 1 type args, got 0")
+        );
+        let balance_as_entry =
+            ContractCall::entry("0x1", "coin", "balance", apt.clone(), vec![json!("0xa")]);
+        assert_eq!(
+            Contract::validate_contract_call_abi(Arc::clone(&client), &balance_as_entry)
+                .await
+                .unwrap_err(),
+            "0x1::coin::balance is not an entry function"
+        );
+        let balance = ContractCall::view("0x1", "coin", "balance", apt.clone(), vec![json!("0xa")]);
+        assert!(
+            Contract::validate_contract_call_abi(Arc::clone(&client), &balance)
+                .await
+                .is_ok()
+        );
+        let unknown = ContractCall::entry("0x1", "coin", "nope", apt, vec![]);
+        assert!(
+            Contract::validate_contract_call_abi(client, &unknown)
+                .await
+                .unwrap_err()
+                .starts_with("function not found")
+        );
+    }
+
+    /// view transport answering each call with its first argument after a delay
+    #[derive(Debug, Default)]
+    struct SlowViewTransport {
+        in_flight: std::sync::atomic::AtomicUsize,
+        max_in_flight: std::sync::atomic::AtomicUsize,
+    }
+
+    impl crate::transport::HttpTransport for SlowViewTransport {
+        fn get<'a>(
+            &'a self,
+            _url: &'a str,
+        ) -> futures::future::BoxFuture<'a, Result<crate::transport::HttpResponse, String>>
+        {
+            Box::pin(async { Err("unexpected get".to_string()) })
+        }
+
+        fn post<'a>(
+            &'a self,
+            _url: &'a str,
+            body: &'a Value,
+        ) -> futures::future::BoxFuture<'a, Result<crate::transport::HttpResponse, String>>
+        {
+            use std::sync::atomic::Ordering;
+            Box::pin(async move {
+                let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                self.max_in_flight.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                self.in_flight.fetch_sub(1, Ordering::SeqCst);
+                let result = json!([body["arguments"][0]]).to_string();
+                Ok(crate::transport::HttpResponse::new(200, &result))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_listen_events_stops_on_signal_and_deadline() {
+        let events = json!([{
+            "guid": {"creation_number": "1", "account_address": "0x1"},
+            "sequence_number": "3",
+            "type": "0x1::coin::WithdrawEvent",
+            "data": {"amount": "10"}
+        }]);
+        let transport = MockTransport::new().on_get(
+            "/accounts/0x1/events/0x1::coin::WithdrawEvent",
+            200,
+            &events.to_string(),
+        );
+        let client = Arc::new(Aptos::with_transport(
+            AptosType::Mainnet,
+            Arc::new(transport),
+        ));
+        let stop = StopSignal::new();
+        let (sender, mut received) = tokio::sync::mpsc::unbounded_channel();
+        let listener = tokio::spawn({
+            let (client, stop) = (Arc::clone(&client), stop.clone());
+            async move {
+                Contract::listen_events(
+                    client,
+                    "0x1",
+                    "0x1::coin::WithdrawEvent",
+                    move |event| sender.send(event).unwrap(),
+                    3600,
+                    stop,
+                    None,
+                )
+                .await
+            }
+        });
+        let event = received.recv().await.unwrap().unwrap();
+        assert_eq!(event["amount"], "10");
+        // the listener is sleeping for an hour, stopping wakes it up
+        stop.stop();
+        let stopped = tokio::time::timeout(Duration::from_secs(1), listener).await;
+        assert_eq!(stopped.unwrap().unwrap(), Ok(()));
+        // a stopped signal ends a new listener before its first poll
+        let calls = std::sync::atomic::AtomicUsize::new(0);
+        let result = Contract::listen_events(
+            Arc::clone(&client),
+            "0x1",
+            "0x1::coin::WithdrawEvent",
+            |_| {
+                calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            },
+            1,
+            stop,
+            None,
+        )
+        .await;
+        assert_eq!(result, Ok(()));
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 0);
+        let timed = tokio::time::timeout(
+            Duration::from_secs(1),
+            Contract::listen_events_all_info(
+                client,
+                "0x1",
+                "0x1::coin::WithdrawEvent",
+                |_| {},
+                3600,
+                StopSignal::new(),
+                Some(Duration::from_millis(50)),
+            ),
+        )
+        .await;
+        assert_eq!(timed.unwrap(), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_view_batch_runs_concurrently() {
+        let transport = Arc::new(SlowViewTransport::default());
+        let client = Arc::new(Aptos::with_transport(
+            AptosType::Mainnet,
+            Arc::clone(&transport) as Arc<dyn crate::transport::HttpTransport>,
+        ));
+        let calls: Vec<ContractCall> = (0..5)
+            .map(|i| ContractCall::view("0x1", "coin", "balance", vec![], vec![json!(i)]))
+            .collect();
+        let results = Contract::view_batch(client, &calls).await;
+        assert_eq!(
+            transport
+                .max_in_flight
+                .load(std::sync::atomic::Ordering::SeqCst),
+            5
+        );
+        let data: Vec<Value> = results.into_iter().map(|r| r.data).collect();
+        assert_eq!(data, (0..5).map(|i| json!([i])).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_read_result_typed_accessors() {
+        let transport = MockTransport::new()
+            .on_post("/view", 200, r#"["12345"]"#)
+            .on_post("/view", 200, r#"["0xa", true, "7", [1, 2]]"#);
+        let client = Arc::new(Aptos::with_transport(
+            AptosType::Mainnet,
+            Arc::new(transport),
+        ));
+        let balance_call = ContractCall::view(
+            "0x1",
+            "coin",
+            "balance",
+            vec!["0x1::aptos_coin::AptosCoin".to_string()],
+            vec![json!("0xa")],
+        );
+        let balance = Contract::read(Arc::clone(&client), &balance_call)
+            .await
+            .unwrap();
+        assert_eq!(balance.as_u64(0).unwrap(), 12345);
+        assert!(balance.as_u64(1).is_err());
+        assert!(balance.as_bool(0).is_err());
+
+        let tuple = Contract::read(client, &balance_call).await.unwrap();
+        assert_eq!(tuple.as_str(0).unwrap(), "0xa");
+        assert!(tuple.as_bool(1).unwrap());
+        assert_eq!(tuple.as_u64(2).unwrap(), 7);
+        assert_eq!(tuple.get::<Vec<u8>>(3).unwrap(), vec![1, 2]);
+        // malformed values are errors, not zero
+        assert!(tuple.as_u64(0).is_err());
+
+        let failed = ContractReadResult {
+            success: false,
+            data: Value::Null,
+            error: Some("view error".to_string()),
+        };
+        assert_eq!(failed.as_u64(0).unwrap_err(), "view error");
+    }
+
+    #[tokio::test]
+    async fn test_require_simulation_blocks_aborting_call() {
+        let wallet = Arc::new(Wallet::new().unwrap());
+        let aborted = json!([{
+            "version": "0",
+            "hash": "0x01",
+            "state_checkpoint_hash": null,
+            "gas_used": "7",
+            "success": false,
+            "vm_status": "Move abort in 0x1::coin: EINSUFFICIENT_BALANCE(0x10006)",
+            "type": "user_transaction",
+            "sender": wallet.address(),
+            "sequence_number": "3",
+            "max_gas_amount": "200000",
+            "gas_unit_price": "100",
+            "expiration_timestamp_secs": "1700000060",
+            "payload": {"type": "entry_function_payload", "function": "0x1::coin::transfer", "type_arguments": [APTOS_COIN], "arguments": ["0xb", "100"]},
+            "signature": {"type": "ed25519_signature", "public_key": "0x0b", "signature": "0x0c"}
+        }]);
+        let transport = Arc::new(
+            MockTransport::new()
+                .on_get(
+                    "/v1/",
+                    200,
+                    r#"{"chain_id":1,"epoch":"1","ledger_version":"1","ledger_timestamp":"1","node_role":"full_node","block_height":"1"}"#,
+                )
+                .on_get(
+                    &format!("/accounts/{}", wallet.address()),
+                    200,
+                    r#"{"sequence_number":"3","authentication_key":"0x00"}"#,
+                )
+                .on_get("/estimate_gas_price", 200, r#"{"gas_estimate":100}"#)
+                .on_post("/transactions/simulate", 200, &aborted.to_string()),
+        );
+        let client = Arc::new(Aptos::with_transport(
+            AptosType::Mainnet,
+            Arc::clone(&transport) as _,
+        ));
+        let call = ContractCall::entry(
+            "0x1",
+            "coin",
+            "transfer",
+            vec![APTOS_COIN.to_string()],
+            vec![json!("0xb"), json!("100")],
+        );
+        let options = WriteOptions {
+            require_simulation: true,
+            ..WriteOptions::default()
+        };
+        let err = Contract::write_with_options(client, wallet, call, &options)
+            .await
+            .unwrap_err();
+        assert!(err.contains("not submitted"));
+        assert!(err.contains("EINSUFFICIENT_BALANCE"));
+        let posts: Vec<String> = transport
+            .requests()
+            .into_iter()
+            .filter(|r| r.method == "POST")
+            .map(|r| r.url)
+            .collect();
+        assert_eq!(posts.len(), 1);
+        assert!(posts[0].contains("/transactions/simulate"));
+    }
+
+    #[tokio::test]
+    async fn test_write_signs_bcs_with_estimated_gas() {
+        let wallet = Arc::new(Wallet::new().unwrap());
+        let committed = json!({
+            "version": "5",
+            "hash": "0x01",
+            "state_checkpoint_hash": null,
+            "gas_used": "7",
+            "success": true,
+            "vm_status": "Executed successfully",
+            "type": "user_transaction",
+            "sender": wallet.address(),
+            "sequence_number": "3",
+            "max_gas_amount": "14",
+            "gas_unit_price": "120",
+            "expiration_timestamp_secs": "1700000060",
+            "payload": {"type": "entry_function_payload", "function": "0x1::coin::transfer", "type_arguments": [APTOS_COIN], "arguments": ["0xb", "100"]},
+            "signature": {"type": "ed25519_signature", "public_key": "0x0b", "signature": "0x0c"}
+        });
+        let transport = Arc::new(
+            MockTransport::new()
+                .on_get(
+                    "/v1/",
+                    200,
+                    r#"{"chain_id":1,"epoch":"1","ledger_version":"5","ledger_timestamp":"1","node_role":"full_node","block_height":"1"}"#,
+                )
+                .on_get(
+                    &format!("/accounts/{}", wallet.address()),
+                    200,
+                    r#"{"sequence_number":"3","authentication_key":"0x00"}"#,
+                )
+                .on_get("/estimate_gas_price", 200, r#"{"gas_estimate":100}"#)
+                .on_post(
+                    "/transactions/simulate",
+                    200,
+                    &json!([committed]).to_string(),
+                )
+                .on_post("/transactions", 202, &committed.to_string())
+                .on_get("/transactions/by_hash/", 200, &committed.to_string()),
+        );
+        let client = Arc::new(Aptos::with_transport(
+            AptosType::Mainnet,
+            Arc::clone(&transport) as _,
+        ));
+        let call = ContractCall::entry(
+            "0x1",
+            "coin",
+            "transfer",
+            vec![APTOS_COIN.to_string()],
+            vec![json!("0xb"), json!("100")],
+        );
+        // a plain write is not simulated
+        let result = Contract::write(Arc::clone(&client), Arc::clone(&wallet), call.clone())
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert!(
+            transport
+                .requests()
+                .iter()
+                .all(|r| !r.url.contains("/transactions/simulate"))
+        );
+        let submitted = transport
+            .requests()
+            .into_iter()
+            .rfind(|r| r.method == "POST")
+            .unwrap();
+        assert_eq!(
+            submitted.body.unwrap()["transaction"]["max_gas_amount"],
+            WRITE_MAX_GAS_AMOUNT.to_string()
+        );
+        let options = WriteOptions {
+            estimate_gas: true,
+            ..WriteOptions::default()
+        };
+        let result =
+            Contract::write_with_options(Arc::clone(&client), Arc::clone(&wallet), call, &options)
+                .await
+                .unwrap();
+        assert!(result.success);
+        let submitted = transport
+            .requests()
+            .into_iter()
+            .rfind(|r| r.method == "POST" && r.url.ends_with("/transactions"))
+            .unwrap()
+            .body
+            .unwrap();
+        let transaction = &submitted["transaction"];
+        // a single simulation, of the transaction that was signed
+        let simulations: Vec<Value> = transport
+            .requests()
+            .into_iter()
+            .filter(|r| r.url.contains("/transactions/simulate"))
+            .filter_map(|r| r.body)
+            .collect();
+        assert_eq!(simulations.len(), 1);
+        for field in [
+            "sender",
+            "sequence_number",
+            "expiration_timestamp_secs",
+            "payload",
+        ] {
+            assert_eq!(simulations[0]["transaction"][field], transaction[field]);
+        }
+        // the node's estimates from the simulation
+        assert_eq!(transaction["max_gas_amount"], "14");
+        assert_eq!(transaction["gas_unit_price"], "120");
+        assert_eq!(transaction["payload"]["function"], "0x1::coin::transfer");
+        let raw_txn_bcs = client.raw_transaction_bcs(transaction).await.unwrap();
+        let signature = hex::decode(submitted["signature"]["signature"].as_str().unwrap()).unwrap();
+        assert!(
+            wallet
+                .verify(&crate::signing::signing_message(&raw_txn_bcs), &signature)
+                .unwrap()
+        );
+    }
+}
@@ -1,10 +1,24 @@
 use crate::{
-    Aptos, event::EventData, global::mainnet::protocol_address::ANIMESWAP_PROTOCOL_ADDRESS,
-    types::ContractCall, wallet::Wallet,
+    Aptos,
+    dex::{
+        DexUtils, NormalizedLiquidity, NormalizedSwap, canonical_order, event_kind_matches,
+        event_string, event_timestamp_secs, event_type_args, event_u64, resolve_xy_swap,
+    },
+    event::{EventBlockResolver, EventData},
+    types::{CallKind, ContractCall, MoveArgument},
+    wallet::Wallet,
 };
 use serde_json::{Value, json};
-use std::sync::Arc;
-use tokio::sync::broadcast;
+use std::{sync::Arc, time::Duration};
+use tokio::{sync::broadcast, task::JoinHandle};
+
+/// AnimeSwap address on the client's network
+fn protocol_address(client: &Aptos) -> Result<String, String> {
+    client
+        .network_config()
+        .dex_address("AnimeSwap")
+        .map(str::to_string)
+}
 
 /// Implementation of interoperability functions for AnimeSwap.
 pub struct AnimeSwap;
@@ -12,48 +26,21 @@ pub struct AnimeSwap;
 impl AnimeSwap {
     /// get swap event
     pub async fn get_swap_events(client: Arc<Aptos>) -> Result<Vec<EventData>, String> {
-        let event_type = format!("{}::swap::SwapEvent", ANIMESWAP_PROTOCOL_ADDRESS);
+        let protocol = protocol_address(&client)?;
+        let event_type = format!("{}::swap::SwapEvent", protocol);
         Self::get_events_by_time_range(client, &event_type).await
     }
     async fn get_events_by_time_range(
         client: Arc<Aptos>,
         event_type: &str,
     ) -> Result<Vec<EventData>, String> {
-        let mut all_events = Vec::new();
-        let mut start_seq: Option<u64> = None;
-        loop {
-            let events = client
-                .get_account_event_vec(ANIMESWAP_PROTOCOL_ADDRESS, event_type, Some(100), start_seq)
-                .await
-                .map_err(|e| e.to_string())?;
-            let events_count = events.len();
-            if events.is_empty() {
-                break;
-            }
-            for event in events {
-                if let Ok(sequence) = event.sequence_number.parse::<u64>() {
-                    let event_timestamp = std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap()
-                        .as_secs();
-                    let event_data = EventData {
-                        event_type: event.r#type.clone(),
-                        event_data: event.data.clone(),
-                        sequence_number: sequence,
-                        transaction_hash: "".to_string(),
-                        block_height: 0,
-                    };
-                    all_events.push(event_data);
-                    start_seq = Some(sequence);
-                }
-            }
-            if events_count < 100 {
-                break;
-            }
-        }
-        Ok(all_events)
+        let protocol = protocol_address(&client)?;
+        let events = client
+            .get_all_account_events(&protocol, event_type, None, None)
+            .await?;
+        Ok(EventBlockResolver::new(client).events_data(&events).await)
     }
-    /// add liquidity
+    /// add liquidity, `slippage` is a fraction in `[0, 1]`
     pub async fn add_liquidity(
         client: Arc<Aptos>,
         wallet: Arc<Wallet>,
@@ -63,20 +50,22 @@ impl AnimeSwap {
         amount_b: u64,
         slippage: f64,
     ) -> Result<Value, String> {
-        let min_amount_a = (amount_a as f64 * (1.0 - slippage)) as u64;
-        let min_amount_b = (amount_b as f64 * (1.0 - slippage)) as u64;
+        let protocol = protocol_address(&client)?;
+        let (min_amount_a, min_amount_b) =
+            DexUtils::min_liquidity_amounts(amount_a, amount_b, slippage)?;
 
         let contract_call = ContractCall {
-            module_address: ANIMESWAP_PROTOCOL_ADDRESS.to_string(),
+            module_address: protocol,
             module_name: "router".to_string(),
             function_name: "add_liquidity".to_string(),
             type_arguments: vec![coin_a.to_string(), coin_b.to_string()],
             arguments: vec![
-                json!(amount_a.to_string()),
-                json!(amount_b.to_string()),
-                json!(min_amount_a.to_string()),
-                json!(min_amount_b.to_string()),
+                MoveArgument::U64(amount_a),
+                MoveArgument::U64(amount_b),
+                MoveArgument::U64(min_amount_a),
+                MoveArgument::U64(min_amount_b),
             ],
+            kind: CallKind::Entry,
         };
 
         crate::contract::Contract::write(client, wallet, contract_call)
@@ -85,74 +74,102 @@ impl AnimeSwap {
     }
 
     /// swap exact tokens for tokens
+    ///
+    /// `deadline_secs` - seconds until the transaction expires
     pub async fn swap_exact_tokens_for_tokens(
         client: Arc<Aptos>,
         wallet: Arc<Wallet>,
         path: Vec<&str>,
         amount_in: u64,
         min_amount_out: u64,
+        deadline_secs: u64,
     ) -> Result<Value, String> {
+        let protocol = protocol_address(&client)?;
         if path.len() < 2 {
             return Err("Path must contain at least 2 tokens".to_string());
         }
         let type_arguments: Vec<String> = path.iter().map(|s| s.to_string()).collect();
         let path_arguments: Vec<Value> = path.iter().map(|s| json!(s)).collect();
         let contract_call = ContractCall {
-            module_address: ANIMESWAP_PROTOCOL_ADDRESS.to_string(),
+            module_address: protocol,
             module_name: "router".to_string(),
             function_name: "swap_exact_tokens_for_tokens".to_string(),
             type_arguments,
             arguments: vec![
-                json!(amount_in.to_string()),
-                json!(min_amount_out.to_string()),
-                json!(path_arguments),
+                MoveArgument::U64(amount_in),
+                MoveArgument::U64(min_amount_out),
+                json!(path_arguments).into(),
             ],
+            kind: CallKind::Entry,
         };
-        crate::contract::Contract::write(client, wallet, contract_call)
-            .await
-            .map(|result| json!(result))
+        crate::contract::Contract::write_with_expiration(
+            client,
+            wallet,
+            contract_call,
+            deadline_secs,
+        )
+        .await
+        .map(|result| json!(result))
     }
 
-    /// get reserves
+    /// Get reserves in the caller's `(coin_a, coin_b)` order.
+    ///
+    /// Pairs are stored in canonical order, the reversed type arguments are tried
+    /// when that resource does not exist. `(0, 0)` if there is no pool.
     pub async fn get_reserves(
         client: Arc<Aptos>,
         coin_a: &str,
         coin_b: &str,
     ) -> Result<(u64, u64), String> {
+        let (token_x, token_y, swapped) = canonical_order(coin_a, coin_b);
+        let reserves = match Self::get_stored_reserves(&client, token_x, token_y).await? {
+            Some(reserves) => Some((reserves, swapped)),
+            None => Self::get_stored_reserves(&client, token_y, token_x)
+                .await?
+                .map(|reserves| (reserves, !swapped)),
+        };
+        Ok(match reserves {
+            Some(((reserve_x, reserve_y), false)) => (reserve_x, reserve_y),
+            Some(((reserve_x, reserve_y), true)) => (reserve_y, reserve_x),
+            None => (0, 0),
+        })
+    }
+
+    /// reserves of the `TokenPairReserve<coin_x, coin_y>` resource, in that order
+    async fn get_stored_reserves(
+        client: &Aptos,
+        coin_x: &str,
+        coin_y: &str,
+    ) -> Result<Option<(u64, u64)>, String> {
+        let protocol = protocol_address(client)?;
         let resource_type = format!(
             "{}::swap::TokenPairReserve<{}, {}>",
-            ANIMESWAP_PROTOCOL_ADDRESS, coin_a, coin_b
+            protocol, coin_x, coin_y
         );
-        match client
-            .get_account_resource(ANIMESWAP_PROTOCOL_ADDRESS, &resource_type)
-            .await
-        {
-            Ok(Some(resource)) => {
-                let reserve_a = resource
-                    .data
-                    .get("reserve_a")
-                    .and_then(|v| v.as_str())
-                    .and_then(|s| s.parse().ok())
-                    .unwrap_or(0);
-                let reserve_b = resource
+        let resource = client
+            .get_account_resource(&protocol, &resource_type)
+            .await?;
+        Ok(resource.map(|resource| {
+            let reserve = |field: &str| {
+                resource
                     .data
-                    .get("reserve_b")
+                    .get(field)
                     .and_then(|v| v.as_str())
                     .and_then(|s| s.parse().ok())
-                    .unwrap_or(0);
-                Ok((reserve_a, reserve_b))
-            }
-            Ok(None) => Ok((0, 0)),
-            Err(e) => Err(e),
-        }
+                    .unwrap_or(0)
+            };
+            (reserve("reserve_a"), reserve("reserve_b"))
+        }))
     }
 
-    /// listen events
+    /// listen events, returns one polling task per event type
     pub async fn listen_events(
         client: Arc<Aptos>,
         event_sender: broadcast::Sender<EventData>,
         filters: AnimeSwapEventFilters,
-    ) -> Result<(), String> {
+    ) -> Result<Vec<JoinHandle<()>>, String> {
+        let protocol = protocol_address(&client)?;
+        let mut handles = Vec::new();
         let event_types = vec![
             "swap_events".to_string(),
             "mint_events".to_string(),
@@ -160,34 +177,24 @@ impl AnimeSwap {
         ];
         for event_type in event_types {
             let client_clone = Arc::clone(&client);
+            let protocol = protocol.clone();
             let sender_clone = event_sender.clone();
+            let poll_interval = filters.poll_interval;
             let filters_clone = filters.clone();
-            tokio::spawn(async move {
+            handles.push(tokio::spawn(async move {
                 let mut last_sequence: Option<u64> = None;
                 loop {
-                    if let Ok(events) = client_clone
-                        .get_account_event_vec(
-                            ANIMESWAP_PROTOCOL_ADDRESS,
-                            &event_type,
-                            Some(100),
-                            last_sequence,
-                        )
-                        .await
+                    // paused while nobody is subscribed
+                    if sender_clone.receiver_count() > 0
+                        && let Ok(events) = client_clone
+                            .get_account_event_vec(&protocol, &event_type, Some(100), last_sequence)
+                            .await
                     {
+                        let mut resolver = EventBlockResolver::new(Arc::clone(&client_clone));
                         for event in events {
                             if let Ok(sequence) = event.sequence_number.parse::<u64>() {
                                 if last_sequence.map(|last| sequence > last).unwrap_or(true) {
-                                    let event_data = EventData {
-                                        event_type: event.r#type.clone(),
-                                        event_data: event.data.clone(),
-                                        sequence_number: sequence,
-                                        transaction_hash: "".to_string(),
-                                        block_height: client_clone
-                                            .get_chain_height()
-                                            .await
-                                            .unwrap_or(0)
-                                            as u64,
-                                    };
+                                    let event_data = resolver.event_data(&event, sequence).await;
                                     if AnimeSwapEventFilter::apply_filters(
                                         &event_data,
                                         &filters_clone,
@@ -199,11 +206,11 @@ impl AnimeSwap {
                             }
                         }
                     }
-                    tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
+                    tokio::time::sleep(poll_interval).await;
                 }
-            });
+            }));
         }
-        Ok(())
+        Ok(handles)
     }
 }
 
@@ -213,6 +220,8 @@ pub struct AnimeSwapEventFilters {
     pub min_swap_amount: Option<u64>,
     pub tracked_tokens: Option<Vec<String>>,
     pub min_liquidity_amount: Option<u64>,
+    /// delay between polls of each event handle
+    pub poll_interval: Duration,
 }
 
 /// anime swap event filter
@@ -283,6 +292,79 @@ impl AnimeSwapEventFilter {
     }
 }
 
+/// anime swap event parser
+pub struct AnimeSwapEventParser;
+
+impl AnimeSwapEventParser {
+    pub fn parse_swap_event(event_data: &EventData) -> Option<NormalizedSwap> {
+        if !event_kind_matches(&event_data.event_type, &["swap_events", "SwapEvent"]) {
+            return None;
+        }
+        let data = &event_data.event_data;
+        let (token_x, token_y) = Self::pair_tokens(event_data);
+        let (token_in, token_out, amount_in, amount_out) = resolve_xy_swap(
+            data,
+            &["amount0_in", "amount_x_in"],
+            &["amount1_in", "amount_y_in"],
+            &["amount0_out", "amount_x_out"],
+            &["amount1_out", "amount_y_out"],
+            token_x,
+            token_y,
+        )?;
+        Some(NormalizedSwap {
+            dex: "AnimeSwap".to_string(),
+            sender: event_string(data, &["sender", "user"]).unwrap_or_default(),
+            token_in,
+            token_out,
+            amount_in,
+            amount_out,
+            ts: event_timestamp_secs(event_data).unwrap_or(0),
+        })
+    }
+
+    pub fn parse_mint_event(event_data: &EventData) -> Option<NormalizedLiquidity> {
+        if !event_kind_matches(&event_data.event_type, &["mint_events", "MintEvent"]) {
+            return None;
+        }
+        Self::parse_liquidity_event(event_data)
+    }
+
+    pub fn parse_burn_event(event_data: &EventData) -> Option<NormalizedLiquidity> {
+        if !event_kind_matches(&event_data.event_type, &["burn_events", "BurnEvent"]) {
+            return None;
+        }
+        Self::parse_liquidity_event(event_data)
+    }
+
+    fn parse_liquidity_event(event_data: &EventData) -> Option<NormalizedLiquidity> {
+        let data = &event_data.event_data;
+        let (token_a, token_b) = Self::pair_tokens(event_data);
+        Some(NormalizedLiquidity {
+            dex: "AnimeSwap".to_string(),
+            provider: event_string(data, &["sender", "user"]).unwrap_or_default(),
+            token_a,
+            token_b,
+            amount_a: event_u64(data, &["amount0", "amount_x"])?,
+            amount_b: event_u64(data, &["amount1", "amount_y"])?,
+            liquidity: event_u64(data, &["liquidity"]).unwrap_or(0),
+            ts: event_timestamp_secs(event_data).unwrap_or(0),
+        })
+    }
+
+    fn pair_tokens(event_data: &EventData) -> (String, String) {
+        let data = &event_data.event_data;
+        let mut type_args = event_type_args(&event_data.event_type).into_iter();
+        (
+            event_string(data, &["token0"])
+                .or_else(|| type_args.next())
+                .unwrap_or_default(),
+            event_string(data, &["token1"])
+                .or_else(|| type_args.next())
+                .unwrap_or_default(),
+        )
+    }
+}
+
 /// animeSwap Price Calculator
 pub struct AnimeSwapPriceCalculator;
 
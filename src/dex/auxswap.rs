@@ -1,84 +1,80 @@
 use crate::{
-    Aptos, event::EventData, global::mainnet::protocol_address::AUXSWAP_PROTOCOL_ADDRESS,
-    types::ContractCall, wallet::Wallet,
+    Aptos,
+    dex::{
+        NormalizedLiquidity, NormalizedSwap, event_kind_matches, event_string,
+        event_timestamp_secs, event_u64,
+    },
+    event::{EventBlockResolver, EventData},
+    types::{CallKind, ContractCall, MoveArgument},
+    wallet::Wallet,
 };
 use serde_json::{Value, json};
-use std::sync::Arc;
-use tokio::sync::broadcast;
+use std::{sync::Arc, time::Duration};
+use tokio::{sync::broadcast, task::JoinHandle};
+
+/// Aux exchange address on the client's network
+fn protocol_address(client: &Aptos) -> Result<String, String> {
+    client
+        .network_config()
+        .dex_address("AuxExchange")
+        .map(str::to_string)
+}
 
 /// Implementation of Aux Exchange AMM functions.
 pub struct AuxExchange;
 
 impl AuxExchange {
-     /// get swap events
-    pub async fn get_swap_events(
-        client: Arc<Aptos>,
-    ) -> Result<Vec<EventData>, String> {
-        let event_type = format!("{}::amm::SwapEvent", AUXSWAP_PROTOCOL_ADDRESS);
+    /// get swap events
+    pub async fn get_swap_events(client: Arc<Aptos>) -> Result<Vec<EventData>, String> {
+        let protocol = protocol_address(&client)?;
+        let event_type = format!("{}::amm::SwapEvent", protocol);
         Self::get_recent_events(client, &event_type).await
     }
     async fn get_recent_events(
         client: Arc<Aptos>,
         event_type: &str,
     ) -> Result<Vec<EventData>, String> {
-        let mut all_events = Vec::new();
-        let mut start_seq: Option<u64> = None;
+        let protocol = protocol_address(&client)?;
         let events = client
-            .get_account_event_vec(AUXSWAP_PROTOCOL_ADDRESS, event_type, Some(100), start_seq)
-            .await
-            .map_err(|e| e.to_string())?;
-        for event in events {
-            if let Ok(sequence) = event.sequence_number.parse::<u64>() {
-                let event_data = EventData {
-                    event_type: event.r#type.clone(),
-                    event_data: event.data.clone(),
-                    sequence_number: sequence,
-                    transaction_hash: "".to_string(),
-                    block_height: 0,
-                };
-                all_events.push(event_data);
-            }
-        }
-        Ok(all_events)
+            .get_all_account_events(&protocol, event_type, None, None)
+            .await?;
+        Ok(EventBlockResolver::new(client).events_data(&events).await)
     }
-    
-    /// listen Aux Exchange events
+
+    /// listen Aux Exchange events, returns the polling tasks
     pub async fn listen_events(
         client: Arc<Aptos>,
         event_sender: broadcast::Sender<EventData>,
-        event_types: Vec<AuxEventType>,
-    ) -> Result<(), String> {
-        for event_type in event_types {
+        config: AuxEventConfig,
+    ) -> Result<Vec<JoinHandle<()>>, String> {
+        let protocol = protocol_address(&client)?;
+        let mut handles = Vec::new();
+        for event_type in config.event_types {
             let client_clone = Arc::clone(&client);
+            let protocol = protocol.clone();
             let sender_clone = event_sender.clone();
+            let poll_interval = config.poll_interval;
             let event_handle = event_type.get_event_handle();
 
-            tokio::spawn(async move {
+            handles.push(tokio::spawn(async move {
                 let mut last_sequence: Option<u64> = None;
                 loop {
-                    if let Ok(events) = client_clone
-                        .get_account_event_vec(
-                            AUXSWAP_PROTOCOL_ADDRESS,
-                            &event_handle,
-                            Some(100),
-                            last_sequence,
-                        )
-                        .await
+                    // paused while nobody is subscribed
+                    if sender_clone.receiver_count() > 0
+                        && let Ok(events) = client_clone
+                            .get_account_event_vec(
+                                &protocol,
+                                &event_handle,
+                                Some(100),
+                                last_sequence,
+                            )
+                            .await
                     {
+                        let mut resolver = EventBlockResolver::new(Arc::clone(&client_clone));
                         for event in events {
                             if let Ok(sequence) = event.sequence_number.parse::<u64>() {
                                 if last_sequence.map(|last| sequence > last).unwrap_or(true) {
-                                    let event_data = EventData {
-                                        event_type: event.r#type.clone(),
-                                        event_data: event.data.clone(),
-                                        sequence_number: sequence,
-                                        transaction_hash: "".to_string(),
-                                        block_height: client_clone
-                                            .get_chain_height()
-                                            .await
-                                            .unwrap_or(0)
-                                            as u64,
-                                    };
+                                    let event_data = resolver.event_data(&event, sequence).await;
 
                                     if let Some(filtered_event) =
                                         event_type.filter_event(&event_data)
@@ -91,13 +87,14 @@ impl AuxExchange {
                             }
                         }
                     }
-                    tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+                    tokio::time::sleep(poll_interval).await;
                 }
-            });
+            }));
         }
-        Ok(())
+        Ok(handles)
     }
 
+    /// `deadline_secs` - seconds until the transaction expires
     pub async fn swap_exact_input(
         client: Arc<Aptos>,
         wallet: Arc<Wallet>,
@@ -105,23 +102,32 @@ impl AuxExchange {
         to_coin: &str,
         amount_in: u64,
         min_amount_out: u64,
+        deadline_secs: u64,
     ) -> Result<Value, String> {
+        let protocol = protocol_address(&client)?;
         let contract_call = ContractCall {
-            module_address: AUXSWAP_PROTOCOL_ADDRESS.to_string(),
+            module_address: protocol,
             module_name: "amm".to_string(),
             function_name: "swap_exact_input".to_string(),
             type_arguments: vec![from_coin.to_string(), to_coin.to_string()],
             arguments: vec![
-                json!(amount_in.to_string()),
-                json!(min_amount_out.to_string()),
+                MoveArgument::U64(amount_in),
+                MoveArgument::U64(min_amount_out),
             ],
+            kind: CallKind::Entry,
         };
 
-        crate::contract::Contract::write(client, wallet, contract_call)
-            .await
-            .map(|result| json!(result))
+        crate::contract::Contract::write_with_expiration(
+            client,
+            wallet,
+            contract_call,
+            deadline_secs,
+        )
+        .await
+        .map(|result| json!(result))
     }
 
+    /// `deadline_secs` - seconds until the transaction expires
     pub async fn swap_exact_output(
         client: Arc<Aptos>,
         wallet: Arc<Wallet>,
@@ -129,21 +135,29 @@ impl AuxExchange {
         to_coin: &str,
         max_amount_in: u64,
         amount_out: u64,
+        deadline_secs: u64,
     ) -> Result<Value, String> {
+        let protocol = protocol_address(&client)?;
         let contract_call = ContractCall {
-            module_address: AUXSWAP_PROTOCOL_ADDRESS.to_string(),
+            module_address: protocol,
             module_name: "amm".to_string(),
             function_name: "swap_exact_output".to_string(),
             type_arguments: vec![from_coin.to_string(), to_coin.to_string()],
             arguments: vec![
-                json!(max_amount_in.to_string()),
-                json!(amount_out.to_string()),
+                MoveArgument::U64(max_amount_in),
+                MoveArgument::U64(amount_out),
             ],
+            kind: CallKind::Entry,
         };
 
-        crate::contract::Contract::write(client, wallet, contract_call)
-            .await
-            .map(|result| json!(result))
+        crate::contract::Contract::write_with_expiration(
+            client,
+            wallet,
+            contract_call,
+            deadline_secs,
+        )
+        .await
+        .map(|result| json!(result))
     }
 
     /// add liquidity
@@ -156,16 +170,18 @@ impl AuxExchange {
         amount_b: u64,
         min_lp_amount: u64,
     ) -> Result<Value, String> {
+        let protocol = protocol_address(&client)?;
         let contract_call = ContractCall {
-            module_address: AUXSWAP_PROTOCOL_ADDRESS.to_string(),
+            module_address: protocol,
             module_name: "amm".to_string(),
             function_name: "add_liquidity".to_string(),
             type_arguments: vec![coin_a.to_string(), coin_b.to_string()],
             arguments: vec![
-                json!(amount_a.to_string()),
-                json!(amount_b.to_string()),
-                json!(min_lp_amount.to_string()),
+                MoveArgument::U64(amount_a),
+                MoveArgument::U64(amount_b),
+                MoveArgument::U64(min_lp_amount),
             ],
+            kind: CallKind::Entry,
         };
 
         crate::contract::Contract::write(client, wallet, contract_call)
@@ -183,16 +199,18 @@ impl AuxExchange {
         min_amount_a: u64,
         min_amount_b: u64,
     ) -> Result<Value, String> {
+        let protocol = protocol_address(&client)?;
         let contract_call = ContractCall {
-            module_address: AUXSWAP_PROTOCOL_ADDRESS.to_string(),
+            module_address: protocol,
             module_name: "amm".to_string(),
             function_name: "remove_liquidity".to_string(),
             type_arguments: vec![coin_a.to_string(), coin_b.to_string()],
             arguments: vec![
-                json!(lp_amount.to_string()),
-                json!(min_amount_a.to_string()),
-                json!(min_amount_b.to_string()),
+                MoveArgument::U64(lp_amount),
+                MoveArgument::U64(min_amount_a),
+                MoveArgument::U64(min_amount_b),
             ],
+            kind: CallKind::Entry,
         };
 
         crate::contract::Contract::write(client, wallet, contract_call)
@@ -206,13 +224,11 @@ impl AuxExchange {
         coin_a: &str,
         coin_b: &str,
     ) -> Result<Value, String> {
-        let resource_type = format!(
-            "{}::amm::Pool<{}, {}>",
-            AUXSWAP_PROTOCOL_ADDRESS, coin_a, coin_b
-        );
+        let protocol = protocol_address(&client)?;
+        let resource_type = format!("{}::amm::Pool<{}, {}>", protocol, coin_a, coin_b);
 
         client
-            .get_account_resource(AUXSWAP_PROTOCOL_ADDRESS, &resource_type)
+            .get_account_resource(&protocol, &resource_type)
             .await
             .map(|opt| opt.map(|r| r.data).unwrap_or(Value::Null))
             .map_err(|e| e.to_string())
@@ -250,10 +266,8 @@ impl AuxExchange {
         coin_a: &str,
         coin_b: &str,
     ) -> Result<Value, String> {
-        let resource_type = format!(
-            "{}::amm::LPToken<{}, {}>",
-            AUXSWAP_PROTOCOL_ADDRESS, coin_a, coin_b
-        );
+        let protocol = protocol_address(&client)?;
+        let resource_type = format!("{}::amm::LPToken<{}, {}>", protocol, coin_a, coin_b);
 
         client
             .get_account_resource(user_address, &resource_type)
@@ -263,6 +277,23 @@ impl AuxExchange {
     }
 }
 
+/// Aux Exchange event listening config
+#[derive(Debug, Clone)]
+pub struct AuxEventConfig {
+    pub event_types: Vec<AuxEventType>,
+    pub poll_interval: Duration,
+}
+
+impl Default for AuxEventConfig {
+    fn default() -> Self {
+        Self {
+            event_types: vec![],
+            poll_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum AuxEventType {
     Swap,
     AddLiquidity,
@@ -335,6 +366,63 @@ impl AuxEventParser {
                 .to_string(),
         })
     }
+
+    /// parse a swap event into the cross-dex `NormalizedSwap`
+    pub fn parse_normalized_swap_event(event_data: &EventData) -> Option<NormalizedSwap> {
+        if !event_kind_matches(&event_data.event_type, &["swap_events", "SwapEvent"]) {
+            return None;
+        }
+        let data = &event_data.event_data;
+        Some(NormalizedSwap {
+            dex: "AuxExchange".to_string(),
+            sender: event_string(data, &["sender"]).unwrap_or_default(),
+            token_in: event_string(data, &["from_coin", "in_coin_type"]).unwrap_or_default(),
+            token_out: event_string(data, &["to_coin", "out_coin_type"]).unwrap_or_default(),
+            amount_in: event_u64(data, &["amount_in", "in_au"])?,
+            amount_out: event_u64(data, &["amount_out", "out_au"])?,
+            ts: event_timestamp_secs(event_data).unwrap_or(0),
+        })
+    }
+
+    pub fn parse_mint_event(event_data: &EventData) -> Option<NormalizedLiquidity> {
+        if !event_kind_matches(
+            &event_data.event_type,
+            &["add_liquidity_events", "AddLiquidityEvent"],
+        ) {
+            return None;
+        }
+        let data = &event_data.event_data;
+        Some(NormalizedLiquidity {
+            dex: "AuxExchange".to_string(),
+            provider: event_string(data, &["sender", "provider"]).unwrap_or_default(),
+            token_a: event_string(data, &["x_coin_type"]).unwrap_or_default(),
+            token_b: event_string(data, &["y_coin_type"]).unwrap_or_default(),
+            amount_a: event_u64(data, &["x_added_au"])?,
+            amount_b: event_u64(data, &["y_added_au"])?,
+            liquidity: event_u64(data, &["lp_minted_au"]).unwrap_or(0),
+            ts: event_timestamp_secs(event_data).unwrap_or(0),
+        })
+    }
+
+    pub fn parse_burn_event(event_data: &EventData) -> Option<NormalizedLiquidity> {
+        if !event_kind_matches(
+            &event_data.event_type,
+            &["remove_liquidity_events", "RemoveLiquidityEvent"],
+        ) {
+            return None;
+        }
+        let data = &event_data.event_data;
+        Some(NormalizedLiquidity {
+            dex: "AuxExchange".to_string(),
+            provider: event_string(data, &["sender", "provider"]).unwrap_or_default(),
+            token_a: event_string(data, &["x_coin_type"]).unwrap_or_default(),
+            token_b: event_string(data, &["y_coin_type"]).unwrap_or_default(),
+            amount_a: event_u64(data, &["x_removed_au"])?,
+            amount_b: event_u64(data, &["y_removed_au"])?,
+            liquidity: event_u64(data, &["lp_burned_au"]).unwrap_or(0),
+            ts: event_timestamp_secs(event_data).unwrap_or(0),
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
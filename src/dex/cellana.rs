@@ -1,21 +1,36 @@
 use crate::{
-    Aptos, contract::Contract, event::EventData,
-    global::mainnet::protocol_address::CELLANASWAP_PROTOCOL_ADDRESS, types::ContractCall,
+    Aptos,
+    contract::Contract,
+    dex::{
+        DexUtils, NormalizedLiquidity, NormalizedSwap, PoolInfo, event_kind_matches, event_string,
+        event_timestamp_secs, event_type_args, event_u64,
+    },
+    event::{EventBlockResolver, EventData},
+    types::{CallKind, ContractCall, MoveArgument},
     wallet::Wallet,
 };
 use serde_json::{Value, json};
-use std::sync::Arc;
-use tokio::sync::broadcast;
+use std::{sync::Arc, time::Duration};
+use tokio::{sync::broadcast, task::JoinHandle};
 
 const LISTEN_EVENT_TYPE: [&str; 3] = ["swap_events", "liquidity_events", "cell_farming_events"];
 
+/// Cellana address on the client's network
+fn protocol_address(client: &Aptos) -> Result<String, String> {
+    client
+        .network_config()
+        .dex_address("Cellana")
+        .map(str::to_string)
+}
+
 /// Interoperability implementation for Cellana Dex.
 pub struct Cellana;
 
 impl Cellana {
     /// get swap events
     pub async fn get_swap_events(client: Arc<Aptos>) -> Result<Vec<EventData>, String> {
-        let event_type = format!("{}::router::SwapEvent", CELLANASWAP_PROTOCOL_ADDRESS);
+        let protocol = protocol_address(&client)?;
+        let event_type = format!("{}::router::SwapEvent", protocol);
         Self::get_recent_events(client, &event_type).await
     }
 
@@ -23,33 +38,14 @@ impl Cellana {
         client: Arc<Aptos>,
         event_type: &str,
     ) -> Result<Vec<EventData>, String> {
-        let mut all_events = Vec::new();
-        let mut start_seq: Option<u64> = None;
+        let protocol = protocol_address(&client)?;
         let events = client
-            .get_account_event_vec(
-                CELLANASWAP_PROTOCOL_ADDRESS,
-                event_type,
-                Some(100),
-                start_seq,
-            )
-            .await
-            .map_err(|e| e.to_string())?;
-        for event in events {
-            if let Ok(sequence) = event.sequence_number.parse::<u64>() {
-                let event_data = EventData {
-                    event_type: event.r#type.clone(),
-                    event_data: event.data.clone(),
-                    sequence_number: sequence,
-                    transaction_hash: "".to_string(),
-                    block_height: 0,
-                };
-                all_events.push(event_data);
-            }
-        }
-        Ok(all_events)
+            .get_all_account_events(&protocol, event_type, None, None)
+            .await?;
+        Ok(EventBlockResolver::new(client).events_data(&events).await)
     }
 
-    /// add liquidity
+    /// add liquidity, `slippage` is a fraction in `[0, 1]`
     pub async fn add_liquidity(
         client: Arc<Aptos>,
         wallet: Arc<Wallet>,
@@ -59,19 +55,21 @@ impl Cellana {
         amount_y: u64,
         slippage: f64,
     ) -> Result<Value, String> {
-        let min_amount_x = (amount_x as f64 * (1.0 - slippage)) as u64;
-        let min_amount_y = (amount_y as f64 * (1.0 - slippage)) as u64;
+        let protocol = protocol_address(&client)?;
+        let (min_amount_x, min_amount_y) =
+            DexUtils::min_liquidity_amounts(amount_x, amount_y, slippage)?;
         let contract_call = ContractCall {
-            module_address: CELLANASWAP_PROTOCOL_ADDRESS.to_string(),
+            module_address: protocol,
             module_name: "liquidity_pool".to_string(),
             function_name: "add_liquidity".to_string(),
             type_arguments: vec![coin_x.to_string(), coin_y.to_string()],
             arguments: vec![
-                json!(amount_x.to_string()),
-                json!(amount_y.to_string()),
-                json!(min_amount_x.to_string()),
-                json!(min_amount_y.to_string()),
+                MoveArgument::U64(amount_x),
+                MoveArgument::U64(amount_y),
+                MoveArgument::U64(min_amount_x),
+                MoveArgument::U64(min_amount_y),
             ],
+            kind: CallKind::Entry,
         };
         Contract::write(client, wallet, contract_call)
             .await
@@ -79,6 +77,8 @@ impl Cellana {
     }
 
     /// swap token
+    ///
+    /// `deadline_secs` - seconds until the transaction expires
     pub async fn swap(
         client: Arc<Aptos>,
         wallet: Arc<Wallet>,
@@ -86,18 +86,21 @@ impl Cellana {
         to_coin: &str,
         amount_in: u64,
         min_amount_out: u64,
+        deadline_secs: u64,
     ) -> Result<Value, String> {
+        let protocol = protocol_address(&client)?;
         let contract_call = ContractCall {
-            module_address: CELLANASWAP_PROTOCOL_ADDRESS.to_string(),
+            module_address: protocol,
             module_name: "router".to_string(),
             function_name: "swap".to_string(),
             type_arguments: vec![from_coin.to_string(), to_coin.to_string()],
             arguments: vec![
-                json!(amount_in.to_string()),
-                json!(min_amount_out.to_string()),
+                MoveArgument::U64(amount_in),
+                MoveArgument::U64(min_amount_out),
             ],
+            kind: CallKind::Entry,
         };
-        Contract::write(client, wallet, contract_call)
+        Contract::write_with_expiration(client, wallet, contract_call, deadline_secs)
             .await
             .map(|result| json!(result))
     }
@@ -108,20 +111,42 @@ impl Cellana {
         coin_x: &str,
         coin_y: &str,
     ) -> Result<Value, String> {
-        let resource_type = format!(
-            "{}::liquidity_pool::Pool<{}, {}>",
-            CELLANASWAP_PROTOCOL_ADDRESS, coin_x, coin_y
-        );
+        let protocol = protocol_address(&client)?;
+        let resource_type = format!("{}::liquidity_pool::Pool<{}, {}>", protocol, coin_x, coin_y);
         client
-            .get_account_resource(CELLANASWAP_PROTOCOL_ADDRESS, &resource_type)
+            .get_account_resource(&protocol, &resource_type)
             .await
             .map(|opt| opt.map(|r| r.data).unwrap_or(Value::Null))
             .map_err(|e| e.to_string())
     }
 
+    /// whether the pool of `coin_x` and `coin_y` exists, without fetching it
+    pub async fn pool_exists(
+        client: Arc<Aptos>,
+        coin_x: &str,
+        coin_y: &str,
+    ) -> Result<bool, String> {
+        let protocol = protocol_address(&client)?;
+        let resource_type = format!("{}::liquidity_pool::Pool<{}, {}>", protocol, coin_x, coin_y);
+        client
+            .account_resource_exists(&protocol, &resource_type)
+            .await
+    }
+
+    /// get pool info mapped into the normalized `PoolInfo`
+    pub async fn get_pool_info_typed(
+        client: Arc<Aptos>,
+        coin_x: &str,
+        coin_y: &str,
+    ) -> Result<PoolInfo, String> {
+        let data = Self::get_pool_info(client, coin_x, coin_y).await?;
+        PoolInfo::from_resource(&data, "reserve_x", "reserve_y", Some("swap_fee_bps"), 30)
+    }
+
     /// get cell token price
     pub async fn get_cell_price(client: Arc<Aptos>) -> Result<f64, String> {
-        let cell_coin = format!("{}::cell_coin::CELL", CELLANASWAP_PROTOCOL_ADDRESS);
+        let protocol = protocol_address(&client)?;
+        let cell_coin = format!("{}::cell_coin::CELL", protocol);
         let apt_coin = "0x1::aptos_coin::AptosCoin";
 
         Self::get_price(client, &cell_coin, apt_coin, 100000000).await // 1 CELL
@@ -156,42 +181,39 @@ impl Cellana {
         }
     }
 
-    /// listen cellana event
+    /// listen cellana event, returns the polling tasks
     pub async fn listen_events(
         client: Arc<Aptos>,
         event_sender: broadcast::Sender<EventData>,
         event_config: CellanaEventConfig,
-    ) -> Result<(), String> {
+    ) -> Result<Vec<JoinHandle<()>>, String> {
+        let protocol = protocol_address(&client)?;
+        let mut handles = Vec::new();
         for event_handle in LISTEN_EVENT_TYPE {
             let client_clone = Arc::clone(&client);
+            let protocol = protocol.clone();
             let sender_clone = event_sender.clone();
+            let poll_interval = event_config.poll_interval;
             let config_clone = event_config.clone();
-            tokio::spawn(async move {
+            handles.push(tokio::spawn(async move {
                 let mut last_sequence: Option<u64> = None;
                 loop {
-                    if let Ok(events) = client_clone
-                        .get_account_event_vec(
-                            CELLANASWAP_PROTOCOL_ADDRESS,
-                            &event_handle,
-                            Some(100),
-                            last_sequence,
-                        )
-                        .await
+                    // paused while nobody is subscribed
+                    if sender_clone.receiver_count() > 0
+                        && let Ok(events) = client_clone
+                            .get_account_event_vec(
+                                &protocol,
+                                &event_handle,
+                                Some(100),
+                                last_sequence,
+                            )
+                            .await
                     {
+                        let mut resolver = EventBlockResolver::new(Arc::clone(&client_clone));
                         for event in events {
                             if let Ok(sequence) = event.sequence_number.parse::<u64>() {
                                 if last_sequence.map(|last| sequence > last).unwrap_or(true) {
-                                    let event_data = EventData {
-                                        event_type: event.r#type.clone(),
-                                        event_data: event.data.clone(),
-                                        sequence_number: sequence,
-                                        transaction_hash: "".to_string(),
-                                        block_height: client_clone
-                                            .get_chain_height()
-                                            .await
-                                            .unwrap_or(0)
-                                            as u64,
-                                    };
+                                    let event_data = resolver.event_data(&event, sequence).await;
                                     if CellanaEventFilter::should_include(
                                         &event_data,
                                         &config_clone,
@@ -204,11 +226,11 @@ impl Cellana {
                             }
                         }
                     }
-                    tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+                    tokio::time::sleep(poll_interval).await;
                 }
-            });
+            }));
         }
-        Ok(())
+        Ok(handles)
     }
 }
 
@@ -219,6 +241,8 @@ pub struct CellanaEventConfig {
     pub min_swap_amount: u64,
     pub monitor_farming: bool,
     pub tracked_tokens: Vec<String>,
+    /// delay between polls of each event handle
+    pub poll_interval: Duration,
 }
 
 /// cellana event filter
@@ -276,6 +300,71 @@ impl CellanaEventFilter {
     }
 }
 
+/// cellana event parser
+pub struct CellanaEventParser;
+
+impl CellanaEventParser {
+    pub fn parse_swap_event(event_data: &EventData) -> Option<NormalizedSwap> {
+        if !event_kind_matches(&event_data.event_type, &["swap_events", "SwapEvent"]) {
+            return None;
+        }
+        let data = &event_data.event_data;
+        let mut type_args = event_type_args(&event_data.event_type).into_iter();
+        Some(NormalizedSwap {
+            dex: "Cellana".to_string(),
+            sender: event_string(data, &["sender", "user"]).unwrap_or_default(),
+            token_in: event_string(data, &["from_token", "coin_x"])
+                .or_else(|| type_args.next())
+                .unwrap_or_default(),
+            token_out: event_string(data, &["to_token", "coin_y"])
+                .or_else(|| type_args.next())
+                .unwrap_or_default(),
+            amount_in: event_u64(data, &["amount_in"])?,
+            amount_out: event_u64(data, &["amount_out"])?,
+            ts: event_timestamp_secs(event_data).unwrap_or(0),
+        })
+    }
+
+    pub fn parse_mint_event(event_data: &EventData) -> Option<NormalizedLiquidity> {
+        if !event_kind_matches(&event_data.event_type, &["AddLiquidityEvent", "MintEvent"]) {
+            return None;
+        }
+        Self::parse_liquidity_event(event_data, &["lp_amount", "liquidity"])
+    }
+
+    pub fn parse_burn_event(event_data: &EventData) -> Option<NormalizedLiquidity> {
+        if !event_kind_matches(
+            &event_data.event_type,
+            &["RemoveLiquidityEvent", "BurnEvent"],
+        ) {
+            return None;
+        }
+        Self::parse_liquidity_event(event_data, &["amount_lp", "lp_amount", "liquidity"])
+    }
+
+    fn parse_liquidity_event(
+        event_data: &EventData,
+        liquidity_fields: &[&str],
+    ) -> Option<NormalizedLiquidity> {
+        let data = &event_data.event_data;
+        let mut type_args = event_type_args(&event_data.event_type).into_iter();
+        Some(NormalizedLiquidity {
+            dex: "Cellana".to_string(),
+            provider: event_string(data, &["lp", "sender", "user"]).unwrap_or_default(),
+            token_a: event_string(data, &["coin_x", "token_1"])
+                .or_else(|| type_args.next())
+                .unwrap_or_default(),
+            token_b: event_string(data, &["coin_y", "token_2"])
+                .or_else(|| type_args.next())
+                .unwrap_or_default(),
+            amount_a: event_u64(data, &["amount_1", "amount_x"])?,
+            amount_b: event_u64(data, &["amount_2", "amount_y"])?,
+            liquidity: event_u64(data, liquidity_fields).unwrap_or(0),
+            ts: event_timestamp_secs(event_data).unwrap_or(0),
+        })
+    }
+}
+
 /// cellana farming
 pub struct CellanaFarming;
 
@@ -287,12 +376,14 @@ impl CellanaFarming {
         pool_id: u64,
         amount: u64,
     ) -> Result<Value, String> {
+        let protocol = protocol_address(&client)?;
         let contract_call = ContractCall {
-            module_address: CELLANASWAP_PROTOCOL_ADDRESS.to_string(),
+            module_address: protocol,
             module_name: "farming".to_string(),
             function_name: "stake".to_string(),
             type_arguments: vec![],
-            arguments: vec![json!(pool_id.to_string()), json!(amount.to_string())],
+            arguments: vec![MoveArgument::U64(pool_id), MoveArgument::U64(amount)],
+            kind: CallKind::Entry,
         };
         Contract::write(client, wallet, contract_call)
             .await
@@ -305,12 +396,14 @@ impl CellanaFarming {
         wallet: Arc<Wallet>,
         pool_id: u64,
     ) -> Result<Value, String> {
+        let protocol = protocol_address(&client)?;
         let contract_call = ContractCall {
-            module_address: CELLANASWAP_PROTOCOL_ADDRESS.to_string(),
+            module_address: protocol,
             module_name: "farming".to_string(),
             function_name: "harvest".to_string(),
             type_arguments: vec![],
-            arguments: vec![json!(pool_id.to_string())],
+            arguments: vec![MoveArgument::U64(pool_id)],
+            kind: CallKind::Entry,
         };
 
         Contract::write(client, wallet, contract_call)
@@ -1,11 +1,18 @@
 /// Liquidswap Module
 use crate::{
-    Aptos, event::EventData, global::mainnet::protocol_address::LIQUIDSWAP_PROTOCOL_ADDRESS,
-    types::ContractCall, wallet::Wallet,
+    Aptos,
+    dex::{
+        DexUtils, NormalizedSwap, PoolInfo, event_kind_matches, event_string, event_timestamp_secs,
+        event_type_args, event_u64, resolve_xy_swap,
+    },
+    event::{EventBlockResolver, EventData, string_or_u64},
+    types::{CallKind, ContractCall, MoveArgument},
+    wallet::Wallet,
 };
+use serde::Deserialize;
 use serde_json::{Value, json};
-use std::sync::Arc;
-use tokio::sync::broadcast;
+use std::{sync::Arc, time::Duration};
+use tokio::{sync::broadcast, task::JoinHandle};
 
 const MODULE_LIQUIDITY_POOL: &str = "liquidity_pool";
 const MODULE_ROUTER: &str = "router";
@@ -16,13 +23,22 @@ const FUNC_SWAP_EXACT_INPUT: &str = "swap_exact_input";
 
 const FUNC_SWAP_EXACT_OUTPUT: &str = "swap_exact_output";
 
+/// Liquidswap address on the client's network
+fn protocol_address(client: &Aptos) -> Result<String, String> {
+    client
+        .network_config()
+        .dex_address("Liquidswap")
+        .map(str::to_string)
+}
+
 /// Liquidswap interoperability implementation.
 pub struct Liquidswap;
 
 impl Liquidswap {
     /// get swap events
     pub async fn get_swap_events(client: Arc<Aptos>) -> Result<Vec<EventData>, String> {
-        let event_type = format!("{}::router::SwapEvent", LIQUIDSWAP_PROTOCOL_ADDRESS);
+        let protocol = protocol_address(&client)?;
+        let event_type = format!("{}::router::SwapEvent", protocol);
         Self::get_recent_events(client, &event_type).await
     }
 
@@ -30,36 +46,14 @@ impl Liquidswap {
         client: Arc<Aptos>,
         event_type: &str,
     ) -> Result<Vec<EventData>, String> {
-        let mut all_events = Vec::new();
-        let mut start_seq: Option<u64> = None;
-
+        let protocol = protocol_address(&client)?;
         let events = client
-            .get_account_event_vec(
-                LIQUIDSWAP_PROTOCOL_ADDRESS,
-                event_type,
-                Some(100),
-                start_seq,
-            )
-            .await
-            .map_err(|e| e.to_string())?;
-
-        for event in events {
-            if let Ok(sequence) = event.sequence_number.parse::<u64>() {
-                let event_data = EventData {
-                    event_type: event.r#type.clone(),
-                    event_data: event.data.clone(),
-                    sequence_number: sequence,
-                    transaction_hash: "".to_string(),
-                    block_height: 0,
-                };
-                all_events.push(event_data);
-            }
-        }
-
-        Ok(all_events)
+            .get_all_account_events(&protocol, event_type, None, None)
+            .await?;
+        Ok(EventBlockResolver::new(client).events_data(&events).await)
     }
 
-    /// add liquidity
+    /// add liquidity, `slippage` is a fraction in `[0, 1]`
     pub async fn add_liquidity(
         client: Arc<Aptos>,
         wallet: Arc<Wallet>,
@@ -69,19 +63,21 @@ impl Liquidswap {
         amount_y: u64,
         slippage: f64,
     ) -> Result<Value, String> {
-        let min_amount_x = (amount_x as f64 * (1.0 - slippage)) as u64;
-        let min_amount_y = (amount_y as f64 * (1.0 - slippage)) as u64;
+        let protocol = protocol_address(&client)?;
+        let (min_amount_x, min_amount_y) =
+            DexUtils::min_liquidity_amounts(amount_x, amount_y, slippage)?;
         let contract_call = ContractCall {
-            module_address: LIQUIDSWAP_PROTOCOL_ADDRESS.to_string(),
+            module_address: protocol,
             module_name: MODULE_LIQUIDITY_POOL.to_string(),
             function_name: FUNC_ADD_LIQUIDITY.to_string(),
             type_arguments: vec![coin_x.to_string(), coin_y.to_string()],
             arguments: vec![
-                json!(amount_x.to_string()),
-                json!(amount_y.to_string()),
-                json!(min_amount_x.to_string()),
-                json!(min_amount_y.to_string()),
+                MoveArgument::U64(amount_x),
+                MoveArgument::U64(amount_y),
+                MoveArgument::U64(min_amount_x),
+                MoveArgument::U64(min_amount_y),
             ],
+            kind: CallKind::Entry,
         };
         crate::contract::Contract::write(client, wallet, contract_call)
             .await
@@ -96,18 +92,21 @@ impl Liquidswap {
         coin_y: &str,
         liquidity_amount: u64,
     ) -> Result<Value, String> {
+        let protocol = protocol_address(&client)?;
         let contract_call = ContractCall {
-            module_address: LIQUIDSWAP_PROTOCOL_ADDRESS.to_string(),
+            module_address: protocol,
             module_name: MODULE_LIQUIDITY_POOL.to_string(),
             function_name: FUNC_REMOVE_LIQUIDITY.to_string(),
             type_arguments: vec![coin_x.to_string(), coin_y.to_string()],
-            arguments: vec![json!(liquidity_amount.to_string())],
+            arguments: vec![MoveArgument::U64(liquidity_amount)],
+            kind: CallKind::Entry,
         };
         crate::contract::Contract::write(client, wallet, contract_call)
             .await
             .map(|result| json!(result))
     }
 
+    /// `deadline_secs` - seconds until the transaction expires
     pub async fn swap_exact_input(
         client: Arc<Aptos>,
         wallet: Arc<Wallet>,
@@ -115,23 +114,32 @@ impl Liquidswap {
         to_coin: &str,
         amount_in: u64,
         min_amount_out: u64,
+        deadline_secs: u64,
     ) -> Result<Value, String> {
+        let protocol = protocol_address(&client)?;
         let contract_call = ContractCall {
-            module_address: LIQUIDSWAP_PROTOCOL_ADDRESS.to_string(),
+            module_address: protocol,
             module_name: MODULE_ROUTER.to_string(),
             function_name: FUNC_SWAP_EXACT_INPUT.to_string(),
             type_arguments: vec![from_coin.to_string(), to_coin.to_string()],
             arguments: vec![
-                json!(amount_in.to_string()),
-                json!(min_amount_out.to_string()),
+                MoveArgument::U64(amount_in),
+                MoveArgument::U64(min_amount_out),
             ],
+            kind: CallKind::Entry,
         };
 
-        crate::contract::Contract::write(client, wallet, contract_call)
-            .await
-            .map(|result| json!(result))
+        crate::contract::Contract::write_with_expiration(
+            client,
+            wallet,
+            contract_call,
+            deadline_secs,
+        )
+        .await
+        .map(|result| json!(result))
     }
 
+    /// `deadline_secs` - seconds until the transaction expires
     pub async fn swap_exact_output(
         client: Arc<Aptos>,
         wallet: Arc<Wallet>,
@@ -139,21 +147,29 @@ impl Liquidswap {
         to_coin: &str,
         amount_out: u64,
         max_amount_in: u64,
+        deadline_secs: u64,
     ) -> Result<Value, String> {
+        let protocol = protocol_address(&client)?;
         let contract_call = ContractCall {
-            module_address: LIQUIDSWAP_PROTOCOL_ADDRESS.to_string(),
+            module_address: protocol,
             module_name: MODULE_ROUTER.to_string(),
             function_name: FUNC_SWAP_EXACT_OUTPUT.to_string(),
             type_arguments: vec![from_coin.to_string(), to_coin.to_string()],
             arguments: vec![
-                json!(amount_out.to_string()),
-                json!(max_amount_in.to_string()),
+                MoveArgument::U64(amount_out),
+                MoveArgument::U64(max_amount_in),
             ],
+            kind: CallKind::Entry,
         };
 
-        crate::contract::Contract::write(client, wallet, contract_call)
-            .await
-            .map(|result| json!(result))
+        crate::contract::Contract::write_with_expiration(
+            client,
+            wallet,
+            contract_call,
+            deadline_secs,
+        )
+        .await
+        .map(|result| json!(result))
     }
 
     /// get pool info
@@ -162,53 +178,77 @@ impl Liquidswap {
         coin_x: &str,
         coin_y: &str,
     ) -> Result<Value, String> {
+        let protocol = protocol_address(&client)?;
         let resource_type = format!(
             "{}::liquidity_pool::LiquidityPool<{}, {}>",
-            LIQUIDSWAP_PROTOCOL_ADDRESS, coin_x, coin_y
+            protocol, coin_x, coin_y
         );
         client
-            .get_account_resource(LIQUIDSWAP_PROTOCOL_ADDRESS, &resource_type)
+            .get_account_resource(&protocol, &resource_type)
             .await
             .map(|opt| opt.map(|r| r.data).unwrap_or(Value::Null))
             .map_err(|e| e.to_string())
     }
 
-    /// listen Liquidswap events
+    /// whether the pool of `coin_x` and `coin_y` exists, without fetching it
+    pub async fn pool_exists(
+        client: Arc<Aptos>,
+        coin_x: &str,
+        coin_y: &str,
+    ) -> Result<bool, String> {
+        let protocol = protocol_address(&client)?;
+        let resource_type = format!(
+            "{}::liquidity_pool::LiquidityPool<{}, {}>",
+            protocol, coin_x, coin_y
+        );
+        client
+            .account_resource_exists(&protocol, &resource_type)
+            .await
+    }
+
+    /// get pool info mapped into the normalized `PoolInfo`
+    pub async fn get_pool_info_typed(
+        client: Arc<Aptos>,
+        coin_x: &str,
+        coin_y: &str,
+    ) -> Result<PoolInfo, String> {
+        let data = Self::get_pool_info(client, coin_x, coin_y).await?;
+        PoolInfo::from_resource(&data, "coin_x_reserve", "coin_y_reserve", Some("fee"), 30)
+    }
+
+    /// listen Liquidswap events, returns one polling task per event type
     pub async fn listen_events(
         client: Arc<Aptos>,
         event_sender: broadcast::Sender<EventData>,
-        event_types: Vec<LiquidswapEventType>,
-    ) -> Result<(), String> {
-        for event_type in event_types {
+        config: LiquidswapEventConfig,
+    ) -> Result<Vec<JoinHandle<()>>, String> {
+        let protocol = protocol_address(&client)?;
+        let mut handles = Vec::new();
+        for event_type in config.event_types {
             let client_clone = Arc::clone(&client);
+            let protocol = protocol.clone();
             let sender_clone = event_sender.clone();
+            let poll_interval = config.poll_interval;
             let event_handle = event_type.get_event_handle();
-            tokio::spawn(async move {
+            handles.push(tokio::spawn(async move {
                 let mut last_sequence: Option<u64> = None;
                 loop {
-                    if let Ok(events) = client_clone
-                        .get_account_event_vec(
-                            LIQUIDSWAP_PROTOCOL_ADDRESS,
-                            &event_handle,
-                            Some(100),
-                            last_sequence,
-                        )
-                        .await
+                    // paused while nobody is subscribed
+                    if sender_clone.receiver_count() > 0
+                        && let Ok(events) = client_clone
+                            .get_account_event_vec(
+                                &protocol,
+                                &event_handle,
+                                Some(100),
+                                last_sequence,
+                            )
+                            .await
                     {
+                        let mut resolver = EventBlockResolver::new(Arc::clone(&client_clone));
                         for event in events {
                             if let Ok(sequence) = event.sequence_number.parse::<u64>() {
                                 if last_sequence.map(|last| sequence > last).unwrap_or(true) {
-                                    let event_data = EventData {
-                                        event_type: event.r#type.clone(),
-                                        event_data: event.data.clone(),
-                                        sequence_number: sequence,
-                                        transaction_hash: "".to_string(),
-                                        block_height: client_clone
-                                            .get_chain_height()
-                                            .await
-                                            .unwrap_or(0)
-                                            as u64,
-                                    };
+                                    let event_data = resolver.event_data(&event, sequence).await;
                                     if let Some(filtered_event) =
                                         event_type.filter_event(&event_data)
                                     {
@@ -220,11 +260,11 @@ impl Liquidswap {
                             }
                         }
                     }
-                    tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+                    tokio::time::sleep(poll_interval).await;
                 }
-            });
+            }));
         }
-        Ok(())
+        Ok(handles)
     }
 
     /// get price
@@ -257,7 +297,24 @@ impl Liquidswap {
     }
 }
 
+/// Liquidswap event listening config
+#[derive(Debug, Clone)]
+pub struct LiquidswapEventConfig {
+    pub event_types: Vec<LiquidswapEventType>,
+    pub poll_interval: Duration,
+}
+
+impl Default for LiquidswapEventConfig {
+    fn default() -> Self {
+        Self {
+            event_types: vec![],
+            poll_interval: Duration::from_secs(2),
+        }
+    }
+}
+
 /// Liquidswap event type
+#[derive(Debug, Clone)]
 pub enum LiquidswapEventType {
     SwapEvent,
     AddLiquidityEvent,
@@ -355,6 +412,77 @@ impl LiquidswapEventParser {
         })
     }
 
+    /// Normalize a swap of the pool's `SwapEvent<X, Y, Curve> { x_in, x_out, y_in, y_out }`,
+    /// in the direction its amounts flowed. Events without per-side amounts carry no
+    /// direction and are not normalized.
+    pub fn parse_normalized_swap_event(event_data: &EventData) -> Option<NormalizedSwap> {
+        if !event_kind_matches(&event_data.event_type, &["swap_events", "SwapEvent"]) {
+            return None;
+        }
+        let data = &event_data.event_data;
+        let mut type_args = event_type_args(&event_data.event_type).into_iter();
+        let token_x = type_args
+            .next()
+            .or_else(|| event_string(data, &["coin_x"]))
+            .unwrap_or_default();
+        let token_y = type_args
+            .next()
+            .or_else(|| event_string(data, &["coin_y"]))
+            .unwrap_or_default();
+        let (token_in, token_out, amount_in, amount_out) = resolve_xy_swap(
+            data,
+            &["x_in", "amount_x_in"],
+            &["y_in", "amount_y_in"],
+            &["x_out", "amount_x_out"],
+            &["y_out", "amount_y_out"],
+            token_x,
+            token_y,
+        )?;
+        Some(NormalizedSwap {
+            dex: "Liquidswap".to_string(),
+            sender: event_string(data, &["sender"]).unwrap_or_default(),
+            token_in,
+            token_out,
+            amount_in,
+            amount_out,
+            ts: event_timestamp_secs(event_data).unwrap_or(0),
+        })
+    }
+
+    /// Parse a flash loan event, either the pool's `FlashloanEvent { x_in, x_out, y_in, y_out }`
+    /// or the `flash_swap_events` handle shape with explicit amounts and fee.
+    ///
+    /// Liquidswap's `liquidity_pool::flashloan` returns a hot potato that must be repaid
+    /// in the same transaction, so it can only be driven from a Move script or module,
+    /// there is no router entry function to call from the sdk.
+    pub fn parse_flash_swap_event(event_data: &EventData) -> Option<FlashSwapEvent> {
+        if !event_data.event_type.contains("flash_swap_events")
+            && !event_data.event_type.contains("FlashloanEvent")
+        {
+            return None;
+        }
+        let data = &event_data.event_data;
+        let borrower = event_string(data, &["borrower", "sender"]).unwrap_or_default();
+        if data.get("x_out").is_some() || data.get("y_out").is_some() {
+            let x_in = event_u64(data, &["x_in"]).unwrap_or(0);
+            let x_out = event_u64(data, &["x_out"]).unwrap_or(0);
+            let y_in = event_u64(data, &["y_in"]).unwrap_or(0);
+            let y_out = event_u64(data, &["y_out"]).unwrap_or(0);
+            return Some(FlashSwapEvent {
+                borrower,
+                amount_x: x_out,
+                amount_y: y_out,
+                fee: x_in.saturating_sub(x_out) + y_in.saturating_sub(y_out),
+            });
+        }
+        Some(FlashSwapEvent {
+            borrower,
+            amount_x: event_u64(data, &["amount_x", "amount_x_out"]).unwrap_or(0),
+            amount_y: event_u64(data, &["amount_y", "amount_y_out"]).unwrap_or(0),
+            fee: event_u64(data, &["fee", "fee_amount"]).unwrap_or(0),
+        })
+    }
+
     pub fn parse_add_liquidity_event(
         event_data: &EventData,
     ) -> Option<LiquidswapAddLiquidityEvent> {
@@ -403,13 +531,30 @@ impl LiquidswapEventParser {
     }
 }
 
-#[derive(Debug, Clone)]
+/// Liquidswap flash loan event.
+///
+/// `amount_x` / `amount_y` are the borrowed amounts, `fee` is what was repaid on top
+/// of them, in the borrowed coin's units (loans are usually one-sided).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FlashSwapEvent {
+    /// empty when the event does not record it
+    pub borrower: String,
+    pub amount_x: u64,
+    pub amount_y: u64,
+    pub fee: u64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
 pub struct LiquidswapSwapEvent {
     pub sender: String,
+    #[serde(deserialize_with = "string_or_u64")]
     pub amount_in: u64,
+    #[serde(deserialize_with = "string_or_u64")]
     pub amount_out: u64,
     pub coin_x: String,
     pub coin_y: String,
+    #[serde(deserialize_with = "string_or_u64")]
     pub timestamp: u64,
 }
 
@@ -1,3 +1,21 @@
+//! DEX integrations and the `DexAggregator` routing across them.
+//!
+//! # Router deadline vs transaction expiration
+//!
+//! A swap can be bounded in time in two places: a `deadline` argument the router
+//! checks on chain, and the transaction's `expiration_timestamp_secs`, after which
+//! the node drops it from mempool and it can never be committed. Per DEX:
+//!
+//! - PancakeSwap: the router takes a `deadline` unix timestamp and aborts past it.
+//!   `PancakeSwap::swap_exact_tokens_for_tokens` also expires the transaction at
+//!   the deadline and refuses a deadline that already passed.
+//! - Liquidswap, AnimeSwap, Thala, Cellana and AuxExchange: the routers take no
+//!   deadline, `deadline_secs` only becomes the transaction expiration, counted
+//!   from the moment it is built.
+//!
+//! Either way a swap that is not committed by then never executes, an expired
+//! transaction is simply never committed while a router deadline aborts and still
+//! charges gas.
 pub mod animeswap;
 pub mod auxswap;
 pub mod cellana;
@@ -7,12 +25,12 @@ pub mod thala;
 use crate::{
     Aptos,
     dex::{
-        animeswap::{AnimeSwap, AnimeSwapEventFilters},
-        auxswap::AuxExchange,
-        cellana::{Cellana, CellanaEventConfig},
-        liquidswap::Liquidswap,
-        pancakeswap::{PancakeSwap, PancakeSwapEventFilters},
-        thala::Thala,
+        animeswap::{AnimeSwap, AnimeSwapEventFilters, AnimeSwapEventParser},
+        auxswap::{AuxEventConfig, AuxEventParser, AuxExchange},
+        cellana::{Cellana, CellanaEventConfig, CellanaEventParser},
+        liquidswap::{Liquidswap, LiquidswapEventConfig, LiquidswapEventParser},
+        pancakeswap::{PancakeSwap, PancakeSwapEventFilters, PancakeSwapEventParser},
+        thala::{Thala, ThalaEventConfig, ThalaEventParser},
     },
     event::EventData,
     global::mainnet::{
@@ -21,13 +39,20 @@ use crate::{
             LIQUIDSWAP_PROTOCOL_ADDRESS, PANCAKESWAP_FACTORY_PROTOCOL_ADDRESS,
             THALA_PROTOCOL_ADDRESS,
         },
-        token_address::{APT, THL, USDC, USDT, WORMHOLE_USDC},
+        token_address::APT,
     },
+    log::log_warn,
+    oracle::{Oracle, OracleConfig},
     wallet::Wallet,
 };
 use serde_json::Value;
-use std::{collections::HashMap, sync::Arc};
-use tokio::sync::broadcast;
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::{sync::broadcast, task::JoinHandle};
 
 /// token ptice
 #[derive(Debug, Clone)]
@@ -40,6 +65,27 @@ pub struct TokenPrice {
     pub timestamp: u64,
 }
 
+/// token prices with the DEXs that could not be checked
+#[derive(Debug, Clone, Default)]
+pub struct TokenPriceReport {
+    /// highest price first
+    pub prices: Vec<TokenPrice>,
+    /// `(dex, error)`
+    pub failures: Vec<(String, String)>,
+}
+
+/// token price in APT and USD
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenPriceUsd {
+    pub token_address: String,
+    pub price_apt: f64,
+    pub price_usd: f64,
+    /// oracle confidence interval of `price_usd`, in USD
+    pub confidence: f64,
+    /// DEX the APT price was taken from, `None` for APT itself
+    pub dex: Option<String>,
+}
+
 /// liquidity pool info
 #[derive(Debug, Clone)]
 pub struct LiquidityPool {
@@ -52,6 +98,239 @@ pub struct LiquidityPool {
     pub fee_rate: f64,
 }
 
+/// default seconds a swap may wait in the mempool
+pub const DEFAULT_SWAP_DEADLINE_SECS: u64 = 300;
+
+/// options of `DexAggregator::exe_best_swap_with_options`
+#[derive(Debug, Clone)]
+pub struct SwapOptions {
+    /// a fraction in `[0, 1]` (0.005 = 0.5%)
+    pub slippage: f64,
+    /// quote the selected dex again right before submitting
+    pub requote: bool,
+    /// seconds the swap may wait in the mempool
+    pub deadline_secs: u64,
+}
+
+impl Default for SwapOptions {
+    fn default() -> Self {
+        SwapOptions {
+            slippage: 0.005,
+            requote: false,
+            deadline_secs: DEFAULT_SWAP_DEADLINE_SECS,
+        }
+    }
+}
+
+/// normalized pool info, independent of each dex's on-chain field names
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PoolInfo {
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+    /// swap fee in basis points (30 = 0.3%)
+    pub fee_bps: u64,
+    /// total lp supply, if the pool resource exposes it (u128 on chain)
+    pub total_lp_supply: Option<u128>,
+}
+
+impl PoolInfo {
+    /// pool info from bare `(reserve_a, reserve_b)`, at the default 0.3% fee
+    pub(crate) fn from_reserves((reserve_a, reserve_b): (u64, u64)) -> Self {
+        PoolInfo {
+            reserve_a,
+            reserve_b,
+            fee_bps: 30,
+            total_lp_supply: None,
+        }
+    }
+
+    /// build pool info from a raw pool resource using the dex's own field names
+    pub(crate) fn from_resource(
+        data: &Value,
+        reserve_a_field: &str,
+        reserve_b_field: &str,
+        fee_field: Option<&str>,
+        default_fee_bps: u64,
+    ) -> Result<Self, String> {
+        if data.is_null() {
+            return Err("pool not found".to_string());
+        }
+        let reserve_a = Self::parse_amount_field(data, reserve_a_field)
+            .ok_or_else(|| format!("pool field not found: {}", reserve_a_field))?;
+        let reserve_b = Self::parse_amount_field(data, reserve_b_field)
+            .ok_or_else(|| format!("pool field not found: {}", reserve_b_field))?;
+        let fee_bps = fee_field
+            .and_then(|field| Self::parse_amount_field(data, field))
+            .unwrap_or(default_fee_bps);
+        let total_lp_supply = ["lp_supply", "total_lp_supply", "lp_coin_supply"]
+            .iter()
+            .find_map(|field| Self::parse_amount_field(data, field));
+        Ok(PoolInfo {
+            reserve_a,
+            reserve_b,
+            fee_bps,
+            total_lp_supply,
+        })
+    }
+
+    /// read an amount stored as a string, a number or a `{ "value": .. }` coin wrapper
+    fn parse_amount_field<T: std::str::FromStr + From<u64>>(
+        data: &Value,
+        field: &str,
+    ) -> Option<T> {
+        let value = data.get(field)?;
+        let value = value.get("value").unwrap_or(value);
+        match value {
+            Value::String(s) => s.parse().ok(),
+            Value::Number(n) => n.as_u64().map(T::from),
+            _ => None,
+        }
+    }
+
+    /// sum of both reserves
+    pub fn total_liquidity(&self) -> u64 {
+        self.reserve_a.saturating_add(self.reserve_b)
+    }
+}
+
+/// cross-dex normalized swap event
+#[derive(Debug, Clone, PartialEq)]
+pub struct NormalizedSwap {
+    pub dex: String,
+    pub sender: String,
+    pub token_in: String,
+    pub token_out: String,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    /// seconds, 0 when the event carries no timestamp
+    pub ts: u64,
+}
+
+impl NormalizedSwap {
+    /// normalize a swap event of any supported dex
+    pub fn from_event(dex: &str, event: &EventData) -> Option<Self> {
+        match dex {
+            "Liquidswap" => LiquidswapEventParser::parse_normalized_swap_event(event),
+            "Thala" => ThalaEventParser::parse_swap_event(event),
+            "PancakeSwap" => PancakeSwapEventParser::parse_swap_event(event),
+            "AnimeSwap" => AnimeSwapEventParser::parse_swap_event(event),
+            "AuxExchange" => AuxEventParser::parse_normalized_swap_event(event),
+            "Cellana" => CellanaEventParser::parse_swap_event(event),
+            _ => None,
+        }
+    }
+}
+
+/// cross-dex normalized liquidity event, a mint (add) or a burn (remove)
+#[derive(Debug, Clone, PartialEq)]
+pub struct NormalizedLiquidity {
+    pub dex: String,
+    pub provider: String,
+    pub token_a: String,
+    pub token_b: String,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    /// lp tokens minted or burned
+    pub liquidity: u64,
+    pub ts: u64,
+}
+
+/// whether the event type or handle name matches one of `kinds`
+pub(crate) fn event_kind_matches(event_type: &str, kinds: &[&str]) -> bool {
+    kinds.iter().any(|kind| event_type.contains(kind))
+}
+
+/// first of `fields` present in the event data, as u64 (string or number encoded)
+pub(crate) fn event_u64(data: &Value, fields: &[&str]) -> Option<u64> {
+    fields.iter().find_map(|field| match data.get(field)? {
+        Value::String(s) => s.parse().ok(),
+        Value::Number(n) => n.as_u64(),
+        _ => None,
+    })
+}
+
+/// `(coin_a, coin_b)` in the lexicographic order AMM pairs are stored in, and
+/// whether the caller's order was swapped
+pub(crate) fn canonical_order<'a>(coin_a: &'a str, coin_b: &'a str) -> (&'a str, &'a str, bool) {
+    if coin_a < coin_b {
+        (coin_a, coin_b, false)
+    } else {
+        (coin_b, coin_a, true)
+    }
+}
+
+/// first of `fields` present in the event data, as string
+pub(crate) fn event_string(data: &Value, fields: &[&str]) -> Option<String> {
+    fields
+        .iter()
+        .find_map(|field| data.get(field)?.as_str().map(|s| s.to_string()))
+}
+
+/// event timestamp in seconds, from the event data or else the event's block;
+/// microsecond timestamps are scaled down
+pub(crate) fn event_timestamp_secs(event: &EventData) -> Option<u64> {
+    let Some(ts) = event_u64(&event.event_data, &["timestamp"]) else {
+        return (event.timestamp_secs > 0).then_some(event.timestamp_secs);
+    };
+    Some(if ts > 1_000_000_000_000 {
+        ts / 1_000_000
+    } else {
+        ts
+    })
+}
+
+/// top level type arguments of a move type, `SwapEvent<A, B<C>>` -> `[A, B<C>]`
+pub(crate) fn event_type_args(event_type: &str) -> Vec<String> {
+    let Some(start) = event_type.find('<') else {
+        return vec![];
+    };
+    let inner = &event_type[start + 1..event_type.rfind('>').unwrap_or(event_type.len())];
+    let mut args = Vec::new();
+    let mut depth = 0;
+    let mut current = String::new();
+    for c in inner.chars() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            ',' if depth == 0 => {
+                args.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    if !current.trim().is_empty() {
+        args.push(current.trim().to_string());
+    }
+    args
+}
+
+/// Resolve an x/y style swap (`amount_x_in`, `amount_y_out`, ..) into
+/// `(token_in, token_out, amount_in, amount_out)`.
+pub(crate) fn resolve_xy_swap(
+    data: &Value,
+    x_in: &[&str],
+    y_in: &[&str],
+    x_out: &[&str],
+    y_out: &[&str],
+    token_x: String,
+    token_y: String,
+) -> Option<(String, String, u64, u64)> {
+    let amount_x_in = event_u64(data, x_in).unwrap_or(0);
+    let amount_y_in = event_u64(data, y_in).unwrap_or(0);
+    if amount_x_in > 0 {
+        let out = event_u64(data, y_out).unwrap_or(0);
+        Some((token_x, token_y, amount_x_in, out))
+    } else if amount_y_in > 0 {
+        let out = event_u64(data, x_out).unwrap_or(0);
+        Some((token_y, token_x, amount_y_in, out))
+    } else {
+        None
+    }
+}
+
 /// token metadata
 #[derive(Debug, Clone)]
 pub struct TokenMetadata {
@@ -59,7 +338,8 @@ pub struct TokenMetadata {
     pub name: String,
     pub symbol: String,
     pub decimals: u8,
-    pub supply: u64,
+    /// u128 on chain, may exceed u64 for high decimal tokens
+    pub supply: u128,
 }
 
 /// dex price
@@ -78,6 +358,195 @@ pub struct TokenPriceComparison {
     pub prices: Vec<DexPrice>,
 }
 
+/// weight of the latest fetch in a pool's moving average of reserves
+pub const RESERVE_EMA_ALPHA: f64 = 0.2;
+
+/// a cached pool, oriented as `(token_in, token_out)`
+#[derive(Debug, Clone)]
+struct ReserveEntry {
+    /// when `pool` was fetched, `None` once invalidated
+    fetched: Option<Instant>,
+    pool: PoolInfo,
+    /// moving average of `(reserve_in, reserve_out)`, kept across invalidations
+    ema: (f64, f64),
+}
+
+/// `(dex, token_in, token_out)`
+type ReserveKey = (String, String, String);
+
+#[derive(Debug, Default)]
+struct ReserveEntries {
+    /// bumped by `ReserveCache::clear`
+    clears: u64,
+    /// bumped per dex by `ReserveCache::invalidate_dex`
+    dex_invalidations: HashMap<String, u64>,
+    /// bumped per pool direction by `ReserveCache::invalidate`
+    pool_invalidations: HashMap<ReserveKey, u64>,
+    pools: HashMap<ReserveKey, ReserveEntry>,
+}
+
+impl ReserveEntries {
+    /// changes whenever the pool is invalidated, the counters only grow
+    fn generation(&self, key: &ReserveKey) -> u64 {
+        self.clears
+            + self.dex_invalidations.get(&key.0).copied().unwrap_or(0)
+            + self.pool_invalidations.get(key).copied().unwrap_or(0)
+    }
+}
+
+/// Pool reserves cached per dex and token pair, see `Aptos::with_reserve_cache`.
+///
+/// Entries are served for at most `ttl` after they were fetched. A running
+/// `DexEventMonitor` drops the entries of a pool as soon as it observes an event of
+/// that pool, see `invalidate_on_events`. Each pool also keeps an exponential moving
+/// average of its reserves over all fetches, see `ema_reserves`.
+#[derive(Debug)]
+pub struct ReserveCache {
+    ttl: Duration,
+    entries: Mutex<ReserveEntries>,
+}
+
+impl ReserveCache {
+    pub fn new(ttl: Duration) -> Self {
+        ReserveCache {
+            ttl,
+            entries: Mutex::new(ReserveEntries::default()),
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Invalidation counter of a pool, read before fetching and passed to `insert`.
+    ///
+    /// A fetch that started before an invalidation of the pool may return the
+    /// reserves the invalidation was meant to drop, so its write is discarded.
+    /// Invalidating other pools leaves the counter as is.
+    pub fn generation(&self, dex: &str, token_in: &str, token_out: &str) -> u64 {
+        let key = (dex.to_string(), token_in.to_string(), token_out.to_string());
+        self.entries.lock().unwrap().generation(&key)
+    }
+
+    /// a pool fetched within the ttl, reserves in `(token_in, token_out)` order
+    pub fn get(&self, dex: &str, token_in: &str, token_out: &str) -> Option<PoolInfo> {
+        let key = (dex.to_string(), token_in.to_string(), token_out.to_string());
+        self.entries
+            .lock()
+            .unwrap()
+            .pools
+            .get(&key)
+            .filter(|entry| {
+                entry
+                    .fetched
+                    .is_some_and(|fetched| fetched.elapsed() < self.ttl)
+            })
+            .map(|entry| entry.pool.clone())
+    }
+
+    /// Cache a pool fetched at `generation`, ignored if the pool was invalidated since.
+    pub fn insert(
+        &self,
+        dex: &str,
+        token_in: &str,
+        token_out: &str,
+        pool: PoolInfo,
+        generation: u64,
+    ) {
+        let key = (dex.to_string(), token_in.to_string(), token_out.to_string());
+        let mut entries = self.entries.lock().unwrap();
+        if entries.generation(&key) != generation {
+            return;
+        }
+        let reserves = (pool.reserve_a as f64, pool.reserve_b as f64);
+        let ema = match entries.pools.get(&key) {
+            Some(entry) => (
+                RESERVE_EMA_ALPHA * reserves.0 + (1.0 - RESERVE_EMA_ALPHA) * entry.ema.0,
+                RESERVE_EMA_ALPHA * reserves.1 + (1.0 - RESERVE_EMA_ALPHA) * entry.ema.1,
+            ),
+            None => reserves,
+        };
+        entries.pools.insert(
+            key,
+            ReserveEntry {
+                fetched: Some(Instant::now()),
+                pool,
+                ema,
+            },
+        );
+    }
+
+    /// Moving average of `(reserve_in, reserve_out)` over every fetch of a pool.
+    ///
+    /// Smooths out single swaps for hot pools and stays available after the pool is
+    /// invalidated, but unlike `get` it is no quote of the current reserves.
+    pub fn ema_reserves(&self, dex: &str, token_in: &str, token_out: &str) -> Option<(f64, f64)> {
+        let key = (dex.to_string(), token_in.to_string(), token_out.to_string());
+        self.entries
+            .lock()
+            .unwrap()
+            .pools
+            .get(&key)
+            .map(|entry| entry.ema)
+    }
+
+    /// drop both directions of a pool, e.g. after one of its swaps
+    pub fn invalidate(&self, dex: &str, token_a: &str, token_b: &str) {
+        let mut entries = self.entries.lock().unwrap();
+        for (token_in, token_out) in [(token_a, token_b), (token_b, token_a)] {
+            let key = (dex.to_string(), token_in.to_string(), token_out.to_string());
+            if let Some(entry) = entries.pools.get_mut(&key) {
+                entry.fetched = None;
+            }
+            *entries.pool_invalidations.entry(key).or_default() += 1;
+        }
+    }
+
+    /// drop every pool of a dex
+    pub fn invalidate_dex(&self, dex: &str) {
+        let mut entries = self.entries.lock().unwrap();
+        for (key, entry) in entries.pools.iter_mut() {
+            if key.0 == dex {
+                entry.fetched = None;
+            }
+        }
+        *entries
+            .dex_invalidations
+            .entry(dex.to_string())
+            .or_default() += 1;
+    }
+
+    pub fn clear(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.clears += 1;
+        entries.pools.clear();
+    }
+
+    /// Invalidate pools on the events of `dex` received on `events`.
+    ///
+    /// A swap drops its pool. Other events, e.g. liquidity changes, name no pair in a
+    /// common format and drop every pool of the dex, as does falling behind.
+    pub fn invalidate_on_events(
+        self: Arc<Self>,
+        dex: &str,
+        mut events: broadcast::Receiver<EventData>,
+    ) -> JoinHandle<()> {
+        let dex = dex.to_string();
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => match NormalizedSwap::from_event(&dex, &event) {
+                        Some(swap) => self.invalidate(&dex, &swap.token_in, &swap.token_out),
+                        None => self.invalidate_dex(&dex),
+                    },
+                    Err(broadcast::error::RecvError::Lagged(_)) => self.invalidate_dex(&dex),
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+}
+
 pub struct DexAggregator;
 
 impl DexAggregator {
@@ -122,8 +591,8 @@ impl DexAggregator {
         if quotes.is_empty() {
             return Err("No suitable DEX found for this trade".to_string());
         }
-        // Sort by output amount and select the best quote
-        quotes.sort_by(|a, b| b.amount_out.cmp(&a.amount_out));
+        // Sort by output amount and select the best quote, ties are broken deterministically
+        quotes.sort_by(DexSwapQuote::cmp_best);
         Ok(quotes.first().unwrap().clone())
     }
 
@@ -136,10 +605,80 @@ impl DexAggregator {
         amount_in: u64,
         slippage: f64,
     ) -> Result<Value, String> {
+        let options = SwapOptions {
+            slippage,
+            ..SwapOptions::default()
+        };
+        Self::exe_best_swap_with_options(client, wallet, from_token, to_token, amount_in, &options)
+            .await
+    }
+
+    /// Perform optimal exchange.
+    ///
+    /// With `options.requote` the selected dex is quoted again right before
+    /// submitting, and the swap is aborted with a "price moved" error if its output
+    /// already dropped below `min_amount_out`.
+    ///
+    /// `options.deadline_secs` bounds how long the swap may sit in the mempool. PancakeSwap's
+    /// router checks it as a `deadline` argument, Liquidswap, AnimeSwap, Thala, Cellana
+    /// and AuxExchange take none, so it becomes the transaction's
+    /// `expiration_timestamp_secs` instead.
+    pub async fn exe_best_swap_with_options(
+        client: Arc<Aptos>,
+        wallet: Arc<Wallet>,
+        from_token: &str,
+        to_token: &str,
+        amount_in: u64,
+        options: &SwapOptions,
+    ) -> Result<Value, String> {
+        let SwapOptions {
+            slippage,
+            requote,
+            deadline_secs,
+        } = *options;
+        DexUtils::validate_slippage(slippage)?;
         let quote =
             Self::find_best_swap(Arc::clone(&client), from_token, to_token, amount_in).await?;
-        let min_amount_out = (quote.amount_out as f64 * (1.0 - slippage)) as u64;
-        match quote.dex.as_str() {
+        let min_amount_out = DexUtils::min_amount_out(quote.amount_out, slippage);
+        if requote {
+            let fresh = Self::get_dex_quote(
+                Arc::clone(&client),
+                &quote.dex,
+                from_token,
+                to_token,
+                amount_in,
+            )
+            .await?;
+            if fresh.amount_out < min_amount_out {
+                return Err(format!(
+                    "price moved: {} now returns {} which is below min_amount_out {}",
+                    quote.dex, fresh.amount_out, min_amount_out
+                ));
+            }
+        }
+        Self::swap_on_dex(
+            client,
+            wallet,
+            &quote.dex,
+            (from_token, to_token),
+            amount_in,
+            min_amount_out,
+            deadline_secs,
+        )
+        .await
+    }
+
+    /// submit a swap of `amount_in` on `dex`, `tokens` as `(from_token, to_token)`
+    async fn swap_on_dex(
+        client: Arc<Aptos>,
+        wallet: Arc<Wallet>,
+        dex: &str,
+        (from_token, to_token): (&str, &str),
+        amount_in: u64,
+        min_amount_out: u64,
+        deadline_secs: u64,
+    ) -> Result<Value, String> {
+        match dex {
             "Liquidswap" => {
                 Liquidswap::swap_exact_input(
                     client,
@@ -148,6 +687,7 @@ impl DexAggregator {
                     to_token,
                     amount_in,
                     min_amount_out,
+                    deadline_secs,
                 )
                 .await
             }
@@ -158,6 +698,7 @@ impl DexAggregator {
                     vec![from_token, to_token],
                     amount_in,
                     min_amount_out,
+                    deadline_secs,
                 )
                 .await
             }
@@ -169,11 +710,12 @@ impl DexAggregator {
                     to_token,
                     amount_in,
                     min_amount_out,
+                    deadline_secs,
                 )
                 .await
             }
             "PancakeSwap" => {
-                let wallet_address = wallet.address().map_err(|e| e.to_string())?;
+                let wallet_address = wallet.address().to_string();
                 PancakeSwap::swap_exact_tokens_for_tokens(
                     client,
                     wallet,
@@ -181,7 +723,7 @@ impl DexAggregator {
                     min_amount_out,
                     vec![from_token, to_token],
                     &wallet_address,
-                    Self::get_deadline(300),
+                    Self::get_deadline(deadline_secs),
                 )
                 .await
             }
@@ -193,6 +735,7 @@ impl DexAggregator {
                     to_token,
                     amount_in,
                     min_amount_out,
+                    deadline_secs,
                 )
                 .await
             }
@@ -204,10 +747,211 @@ impl DexAggregator {
                     to_token,
                     amount_in,
                     min_amount_out,
+                    deadline_secs,
+                )
+                .await
+            }
+            _ => Err(format!("Unsupported DEX: {}", dex)),
+        }
+    }
+
+    /// Zap `amount_in` of `from_token` into the `pool_tokens` pool.
+    ///
+    /// Swaps part of the input to the other pool token on the best quoting dex, then
+    /// adds both to the deepest pool among Liquidswap, PancakeSwap, AnimeSwap and
+    /// Cellana. The swapped part follows the pool's reserve ratio rather than a flat
+    /// half, so little of either token is left over.
+    pub async fn zap_in(
+        client: Arc<Aptos>,
+        wallet: Arc<Wallet>,
+        from_token: &str,
+        pool_tokens: (&str, &str),
+        amount_in: u64,
+        slippage: f64,
+    ) -> Result<ZapResult, String> {
+        DexUtils::validate_slippage(slippage)?;
+        let other_token = if from_token == pool_tokens.0 {
+            pool_tokens.1
+        } else if from_token == pool_tokens.1 {
+            pool_tokens.0
+        } else {
+            return Err(format!(
+                "{} is not a token of the {}/{} pool",
+                from_token, pool_tokens.0, pool_tokens.1
+            ));
+        };
+        let pools =
+            futures::future::join_all(ZAP_LP_DEXES.iter().map(|dex| {
+                Self::get_pool_reserves(Arc::clone(&client), dex, from_token, other_token)
+            }))
+            .await;
+        let (lp_dex, (reserve_in, reserve_out)) = ZAP_LP_DEXES
+            .iter()
+            .zip(pools)
+            .filter_map(|(dex, reserves)| reserves.ok().map(|reserves| (*dex, reserves)))
+            .filter(|(_, (reserve_in, reserve_out))| *reserve_in > 0 && *reserve_out > 0)
+            .max_by_key(|(_, (reserve_in, _))| *reserve_in)
+            .ok_or_else(|| format!("no pool found for {}/{}", from_token, other_token))?;
+        let half_quote =
+            Self::find_best_swap(Arc::clone(&client), from_token, other_token, amount_in / 2)
+                .await?;
+        let swap_amount_in = Self::zap_swap_amount(
+            amount_in,
+            reserve_in,
+            reserve_out,
+            half_quote.price,
+            half_quote.dex == lp_dex,
+        );
+        // the swap runs on the dex that quoted the split, quoted again for the final amount
+        let quote = Self::get_dex_quote(
+            Arc::clone(&client),
+            &half_quote.dex,
+            from_token,
+            other_token,
+            swap_amount_in,
+        )
+        .await?;
+        let swap = Self::swap_on_dex(
+            Arc::clone(&client),
+            Arc::clone(&wallet),
+            &quote.dex,
+            (from_token, other_token),
+            swap_amount_in,
+            DexUtils::min_amount_out(quote.amount_out, slippage),
+            DEFAULT_SWAP_DEADLINE_SECS,
+        )
+        .await?;
+        if swap.get("success").and_then(|v| v.as_bool()) == Some(false) {
+            return Err(format!(
+                "zap swap failed: {}",
+                swap.get("error").and_then(|v| v.as_str()).unwrap_or("")
+            ));
+        }
+        // the liquidity leg takes the slippage off the expected amounts, once
+        let amount_a = amount_in - swap_amount_in;
+        let amount_b = quote.amount_out;
+        let liquidity = match lp_dex {
+            "Liquidswap" => {
+                Liquidswap::add_liquidity(
+                    client,
+                    wallet,
+                    from_token,
+                    other_token,
+                    amount_a,
+                    amount_b,
+                    slippage,
+                )
+                .await
+            }
+            "PancakeSwap" => {
+                let wallet_address = wallet.address().to_string();
+                PancakeSwap::add_liquidity(
+                    client,
+                    wallet,
+                    from_token,
+                    other_token,
+                    amount_a,
+                    amount_b,
+                    DexUtils::min_amount_out(amount_a, slippage),
+                    DexUtils::min_amount_out(amount_b, slippage),
+                    &wallet_address,
+                    Self::get_deadline(DEFAULT_SWAP_DEADLINE_SECS),
+                )
+                .await
+            }
+            "AnimeSwap" => {
+                AnimeSwap::add_liquidity(
+                    client,
+                    wallet,
+                    from_token,
+                    other_token,
+                    amount_a,
+                    amount_b,
+                    slippage,
+                )
+                .await
+            }
+            "Cellana" => {
+                Cellana::add_liquidity(
+                    client,
+                    wallet,
+                    from_token,
+                    other_token,
+                    amount_a,
+                    amount_b,
+                    slippage,
                 )
                 .await
             }
-            _ => Err(format!("Unsupported DEX: {}", quote.dex)),
+            _ => Err(format!("Unsupported DEX: {}", lp_dex)),
+        }?;
+        Ok(ZapResult {
+            swap_dex: quote.dex,
+            lp_dex: lp_dex.to_string(),
+            swap_amount_in,
+            amount_a,
+            amount_b,
+            swap,
+            liquidity,
+        })
+    }
+
+    /// Part of `amount_in` to swap so that the rest and the swap output match the
+    /// pool's reserve ratio.
+    ///
+    /// A swap on another dex leaves the pool untouched, so the split only needs the
+    /// swap price. A swap through the pool itself moves its reserves, which the
+    /// constant product closed form (0.3% fee) accounts for.
+    fn zap_swap_amount(
+        amount_in: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        price: f64,
+        same_pool: bool,
+    ) -> u64 {
+        let (amount, r_in, r_out) = (amount_in as f64, reserve_in as f64, reserve_out as f64);
+        let swap = if same_pool {
+            let fee = 0.003;
+            let a = r_in * (2.0 - fee);
+            ((a * a + 4.0 * (1.0 - fee) * amount * r_in).sqrt() - a) / (2.0 * (1.0 - fee))
+        } else if price > 0.0 {
+            amount * r_out / (r_out + price * r_in)
+        } else {
+            amount / 2.0
+        };
+        (swap.max(0.0) as u64).min(amount_in)
+    }
+
+    /// reserves of a zap pool, in `(token_in, token_out)` order
+    async fn get_pool_reserves(
+        client: Arc<Aptos>,
+        dex: &str,
+        token_in: &str,
+        token_out: &str,
+    ) -> Result<(u64, u64), String> {
+        match dex {
+            "PancakeSwap" => PancakeSwap::get_reserves(client, token_in, token_out).await,
+            "AnimeSwap" => AnimeSwap::get_reserves(client, token_in, token_out).await,
+            "Liquidswap" | "Cellana" => {
+                let pool_info = |x: &str, y: &str| {
+                    let (client, x, y) = (Arc::clone(&client), x.to_string(), y.to_string());
+                    async move {
+                        if dex == "Liquidswap" {
+                            Liquidswap::get_pool_info_typed(client, &x, &y).await
+                        } else {
+                            Cellana::get_pool_info_typed(client, &x, &y).await
+                        }
+                    }
+                };
+                match pool_info(token_in, token_out).await {
+                    Ok(pool) => Ok((pool.reserve_a, pool.reserve_b)),
+                    Err(_) => {
+                        let pool = pool_info(token_out, token_in).await?;
+                        Ok((pool.reserve_b, pool.reserve_a))
+                    }
+                }
+            }
+            _ => Err(format!("Unsupported DEX: {}", dex)),
         }
     }
 
@@ -249,54 +993,61 @@ impl DexAggregator {
         {
             quotes.push(quote);
         }
-        quotes.sort_by(|a, b| b.amount_out.cmp(&a.amount_out));
+        quotes.sort_by(DexSwapQuote::cmp_best);
         Ok(quotes)
     }
 
-    // How to obtain quotes from various DEXs
-    async fn get_liquidswap_quote(
+    /// quote a single dex by name
+    async fn get_dex_quote(
         client: Arc<Aptos>,
+        dex_name: &str,
         from_token: &str,
         to_token: &str,
         amount_in: u64,
     ) -> Result<DexSwapQuote, String> {
-        match Liquidswap::get_price(Arc::clone(&client), from_token, to_token, amount_in).await {
-            Ok(price) => {
-                let amount_out = (price * amount_in as f64) as u64;
-                Ok(DexSwapQuote {
-                    dex: "Liquidswap".to_string(),
-                    amount_out,
-                    price,
-                    dex_address: LIQUIDSWAP_PROTOCOL_ADDRESS.to_string(),
-                })
+        match dex_name {
+            "Liquidswap" => {
+                Self::get_liquidswap_quote(client, from_token, to_token, amount_in).await
+            }
+            "AnimeSwap" => Self::get_animeswap_quote(client, from_token, to_token, amount_in).await,
+            "Thala" => Self::get_thala_quote(client, from_token, to_token, amount_in).await,
+            "PancakeSwap" => {
+                Self::get_pancakeswap_quote(client, from_token, to_token, amount_in).await
             }
-            Err(e) => Err(e),
+            "Cellana" => Self::get_cellana_quote(client, from_token, to_token, amount_in).await,
+            "AuxExchange" => Self::get_aux_quote(client, from_token, to_token, amount_in).await,
+            _ => Err(format!("Unsupported DEX: {}", dex_name)),
         }
     }
 
+    // How to obtain quotes from various DEXs
+    async fn get_liquidswap_quote(
+        client: Arc<Aptos>,
+        from_token: &str,
+        to_token: &str,
+        amount_in: u64,
+    ) -> Result<DexSwapQuote, String> {
+        let pool = Self::cached_pool(&client, "Liquidswap", from_token, to_token, async {
+            Liquidswap::get_pool_info_typed(Arc::clone(&client), from_token, to_token).await
+        })
+        .await?;
+        Self::quote_from_pool(&client, "Liquidswap", amount_in, &pool)
+    }
+
     async fn get_animeswap_quote(
         client: Arc<Aptos>,
         from_token: &str,
         to_token: &str,
         amount_in: u64,
     ) -> Result<DexSwapQuote, String> {
-        match AnimeSwap::get_reserves(Arc::clone(&client), from_token, to_token).await {
-            Ok((reserve_in, reserve_out)) => {
-                let amount_out = Self::calculate_amm_output(amount_in, reserve_in, reserve_out);
-                let price = if amount_in > 0 {
-                    amount_out as f64 / amount_in as f64
-                } else {
-                    0.0
-                };
-                Ok(DexSwapQuote {
-                    dex: "AnimeSwap".to_string(),
-                    amount_out,
-                    price,
-                    dex_address: ANIMESWAP_PROTOCOL_ADDRESS.to_string(),
-                })
-            }
-            Err(_) => Err("Failed to get AnimeSwap reserves".to_string()),
-        }
+        let pool = Self::cached_pool(&client, "AnimeSwap", from_token, to_token, async {
+            AnimeSwap::get_reserves(Arc::clone(&client), from_token, to_token)
+                .await
+                .map(PoolInfo::from_reserves)
+                .map_err(|_| "Failed to get AnimeSwap reserves".to_string())
+        })
+        .await?;
+        Self::quote_from_pool(&client, "AnimeSwap", amount_in, &pool)
     }
 
     async fn get_thala_quote(
@@ -305,18 +1056,11 @@ impl DexAggregator {
         to_token: &str,
         amount_in: u64,
     ) -> Result<DexSwapQuote, String> {
-        match Thala::get_price(Arc::clone(&client), from_token, to_token, amount_in).await {
-            Ok(price) => {
-                let amount_out = (price * amount_in as f64) as u64;
-                Ok(DexSwapQuote {
-                    dex: "Thala".to_string(),
-                    amount_out,
-                    price,
-                    dex_address: THALA_PROTOCOL_ADDRESS.to_string(),
-                })
-            }
-            Err(e) => Err(e),
-        }
+        let pool = Self::cached_pool(&client, "Thala", from_token, to_token, async {
+            Thala::get_pool_info_typed(Arc::clone(&client), from_token, to_token).await
+        })
+        .await?;
+        Self::quote_from_pool(&client, "Thala", amount_in, &pool)
     }
 
     async fn get_pancakeswap_quote(
@@ -325,23 +1069,14 @@ impl DexAggregator {
         to_token: &str,
         amount_in: u64,
     ) -> Result<DexSwapQuote, String> {
-        match PancakeSwap::get_reserves(Arc::clone(&client), from_token, to_token).await {
-            Ok((reserve_in, reserve_out)) => {
-                let amount_out = Self::calculate_amm_output(amount_in, reserve_in, reserve_out);
-                let price = if amount_in > 0 {
-                    amount_out as f64 / amount_in as f64
-                } else {
-                    0.0
-                };
-                Ok(DexSwapQuote {
-                    dex: "PancakeSwap".to_string(),
-                    amount_out,
-                    price,
-                    dex_address: PANCAKESWAP_FACTORY_PROTOCOL_ADDRESS.to_string(),
-                })
-            }
-            Err(_) => Err("Failed to get PancakeSwap reserves".to_string()),
-        }
+        let pool = Self::cached_pool(&client, "PancakeSwap", from_token, to_token, async {
+            PancakeSwap::get_reserves(Arc::clone(&client), from_token, to_token)
+                .await
+                .map(PoolInfo::from_reserves)
+                .map_err(|_| "Failed to get PancakeSwap reserves".to_string())
+        })
+        .await?;
+        Self::quote_from_pool(&client, "PancakeSwap", amount_in, &pool)
     }
 
     async fn get_cellana_quote(
@@ -350,18 +1085,59 @@ impl DexAggregator {
         to_token: &str,
         amount_in: u64,
     ) -> Result<DexSwapQuote, String> {
-        match Cellana::get_price(Arc::clone(&client), from_token, to_token, amount_in).await {
-            Ok(price) => {
-                let amount_out = (price * amount_in as f64) as u64;
-                Ok(DexSwapQuote {
-                    dex: "Cellana".to_string(),
-                    amount_out,
-                    price,
-                    dex_address: CELLANASWAP_PROTOCOL_ADDRESS.to_string(),
-                })
-            }
-            Err(e) => Err(e),
-        }
+        let pool = Self::cached_pool(&client, "Cellana", from_token, to_token, async {
+            Cellana::get_pool_info_typed(Arc::clone(&client), from_token, to_token).await
+        })
+        .await?;
+        Self::quote_from_pool(&client, "Cellana", amount_in, &pool)
+    }
+
+    /// pool oriented as `(from_token, to_token)` from the client's reserve cache, or from `fetch`
+    async fn cached_pool(
+        client: &Aptos,
+        dex: &str,
+        from_token: &str,
+        to_token: &str,
+        fetch: impl Future<Output = Result<PoolInfo, String>>,
+    ) -> Result<PoolInfo, String> {
+        let cache = client.reserve_cache();
+        if let Some(pool) = cache.and_then(|cache| cache.get(dex, from_token, to_token)) {
+            return Ok(pool);
+        }
+        let generation = cache.map(|cache| cache.generation(dex, from_token, to_token));
+        let pool = fetch.await?;
+        if let (Some(cache), Some(generation)) = (cache, generation) {
+            cache.insert(dex, from_token, to_token, pool.clone(), generation);
+        }
+        Ok(pool)
+    }
+
+    /// constant product quote of `amount_in` against a pool, with the pool's own fee
+    fn quote_from_pool(
+        client: &Aptos,
+        dex: &str,
+        amount_in: u64,
+        pool: &PoolInfo,
+    ) -> Result<DexSwapQuote, String> {
+        let (reserve_in, reserve_out) = (pool.reserve_a, pool.reserve_b);
+        let amount_out =
+            Self::calculate_amm_output_with_fee(amount_in, reserve_in, reserve_out, pool.fee_bps);
+        let price = if amount_in > 0 {
+            amount_out as f64 / amount_in as f64
+        } else {
+            0.0
+        };
+        Ok(DexSwapQuote {
+            dex: dex.to_string(),
+            amount_out,
+            price,
+            dex_address: client.network_config().dex_address(dex)?.to_string(),
+            price_impact: Some(DexUtils::calculate_price_impact(
+                amount_in,
+                reserve_in,
+                reserve_out,
+            )),
+        })
     }
 
     async fn get_aux_quote(
@@ -381,7 +1157,11 @@ impl DexAggregator {
                     dex: "AuxExchange".to_string(),
                     amount_out,
                     price,
-                    dex_address: AUXSWAP_PROTOCOL_ADDRESS.to_string(),
+                    dex_address: client
+                        .network_config()
+                        .dex_address("AuxExchange")?
+                        .to_string(),
+                    price_impact: None,
                 })
             }
             Err(e) => {
@@ -397,19 +1177,12 @@ impl DexAggregator {
                                 .and_then(|v| v.as_str())
                                 .and_then(|s| s.parse::<u64>().ok()),
                         ) {
-                            let amount_out =
-                                Self::calculate_amm_output(amount_in, reserve_in, reserve_out);
-                            let price = if amount_in > 0 {
-                                amount_out as f64 / amount_in as f64
-                            } else {
-                                0.0
-                            };
-                            Ok(DexSwapQuote {
-                                dex: "AuxExchange".to_string(),
-                                amount_out,
-                                price,
-                                dex_address: AUXSWAP_PROTOCOL_ADDRESS.to_string(),
-                            })
+                            Self::quote_from_pool(
+                                &client,
+                                "AuxExchange",
+                                amount_in,
+                                &PoolInfo::from_reserves((reserve_in, reserve_out)),
+                            )
                         } else {
                             Err(format!("Failed to parse pool reserves: {}", e))
                         }
@@ -425,16 +1198,24 @@ impl DexAggregator {
 
     /// Calculate AMM output amount
     fn calculate_amm_output(amount_in: u64, reserve_in: u64, reserve_out: u64) -> u64 {
-        if reserve_in == 0 || reserve_out == 0 {
-            return 0;
-        }
-        let amount_in_with_fee = amount_in * 997;
-        let numerator = amount_in_with_fee * reserve_out;
-        let denominator = reserve_in * 1000 + amount_in_with_fee;
-        if denominator == 0 {
+        // 0.3% fee, in u128 so large reserves do not overflow
+        Self::calculate_amm_output_with_fee(amount_in, reserve_in, reserve_out, 30)
+    }
+
+    /// constant product output with a fee in basis points
+    fn calculate_amm_output_with_fee(
+        amount_in: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        fee_bps: u64,
+    ) -> u64 {
+        if reserve_in == 0 || reserve_out == 0 || fee_bps >= 10_000 {
             return 0;
         }
-        numerator / denominator
+        let amount_in_with_fee = amount_in as u128 * (10_000 - fee_bps) as u128;
+        let numerator = amount_in_with_fee * reserve_out as u128;
+        let denominator = reserve_in as u128 * 10_000 + amount_in_with_fee;
+        (numerator / denominator) as u64
     }
 
     /// Get transaction deadline timestamp
@@ -505,65 +1286,104 @@ impl DexAggregator {
         client: Arc<Aptos>,
         token_address: &str,
     ) -> Result<Vec<TokenPrice>, String> {
+        Ok(Self::get_token_price_detailed(client, token_address)
+            .await
+            .prices)
+    }
+
+    /// Like `get_token_price`, but also reports the DEXs whose price check failed.
+    ///
+    /// The checks run concurrently.
+    pub async fn get_token_price_detailed(
+        client: Arc<Aptos>,
+        token_address: &str,
+    ) -> TokenPriceReport {
         let apt_coin = "0x1::aptos_coin::AptosCoin";
-        let mut prices = Vec::new();
-        let dex_checks = vec![
-            (
-                "Liquidswap",
-                Self::get_token_price_on_dex(
-                    Arc::clone(&client),
-                    "Liquidswap",
-                    token_address,
-                    apt_coin,
-                ),
-            ),
-            (
-                "Thala",
-                Self::get_token_price_on_dex(Arc::clone(&client), "Thala", token_address, apt_coin),
-            ),
-            (
-                "PancakeSwap",
-                Self::get_token_price_on_dex(
-                    Arc::clone(&client),
-                    "PancakeSwap",
-                    token_address,
-                    apt_coin,
-                ),
-            ),
-            (
-                "AnimeSwap",
-                Self::get_token_price_on_dex(
-                    Arc::clone(&client),
-                    "AnimeSwap",
-                    token_address,
-                    apt_coin,
-                ),
-            ),
-            (
-                "Cellana",
-                Self::get_token_price_on_dex(
-                    Arc::clone(&client),
-                    "Cellana",
-                    token_address,
-                    apt_coin,
-                ),
-            ),
-        ];
-        for (dex_name, check_future) in dex_checks {
-            if let Ok(price) = check_future.await {
-                prices.push(price);
+        let checks = PRICE_DEXES.iter().map(|dex| {
+            Self::get_token_price_on_dex(Arc::clone(&client), dex, token_address, apt_coin)
+        });
+        let mut report = TokenPriceReport::default();
+        for (dex, result) in PRICE_DEXES
+            .iter()
+            .zip(futures::future::join_all(checks).await)
+        {
+            match result {
+                Ok(price) => report.prices.push(price),
+                Err(e) => report.failures.push((dex.to_string(), e)),
             }
         }
-        prices.sort_by(|a, b| {
+        report.prices.sort_by(|a, b| {
             b.price
                 .partial_cmp(&a.price)
                 .unwrap_or(std::cmp::Ordering::Equal)
         });
-        Ok(prices)
+        report
+    }
+
+    /// USD price of a token, its APT price on the deepest DEX pool valued with the
+    /// pyth APT/USD feed
+    pub async fn get_token_price_usd(
+        client: Arc<Aptos>,
+        token_address: &str,
+    ) -> Result<TokenPriceUsd, String> {
+        let config = OracleConfig::from_network(client.network_config())?;
+        Self::get_token_price_usd_with_config(client, token_address, &config).await
+    }
+
+    pub async fn get_token_price_usd_with_config(
+        client: Arc<Aptos>,
+        token_address: &str,
+        config: &OracleConfig,
+    ) -> Result<TokenPriceUsd, String> {
+        let (price_apt, dex) = if token_address == APT {
+            (1.0, None)
+        } else {
+            let report = Self::get_token_price_detailed(Arc::clone(&client), token_address).await;
+            let deepest = report
+                .prices
+                .into_iter()
+                .filter(|price| price.price > 0.0)
+                .max_by_key(|price| price.liquidity)
+                .ok_or_else(|| format!("no DEX price for {}", token_address))?;
+            (deepest.price, Some(deepest.dex))
+        };
+        let apt_usd = Oracle::get_apt_usd_price(client, config).await?;
+        Ok(TokenPriceUsd {
+            token_address: token_address.to_string(),
+            price_apt,
+            price_usd: price_apt * apt_usd.price,
+            confidence: price_apt * apt_usd.confidence,
+            dex,
+        })
+    }
+
+    /// APT price in USD from the deepest APT/USDC or APT/USDT pool of the client's
+    /// network, taking the stablecoin at $1
+    pub(crate) async fn get_apt_price_in_stablecoins(client: Arc<Aptos>) -> Result<f64, String> {
+        let network = client.network_config();
+        let apt = network.apt.clone();
+        let stablecoins: Vec<String> = [&network.usdc, &network.usdt]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect();
+        let checks = stablecoins.iter().flat_map(|stablecoin| {
+            let (client, apt) = (&client, &apt);
+            PRICE_DEXES.iter().map(move |dex| {
+                Self::get_token_price_on_dex(Arc::clone(client), dex, apt, stablecoin)
+            })
+        });
+        let deepest = futures::future::join_all(checks)
+            .await
+            .into_iter()
+            .flatten()
+            .filter(|price| price.price > 0.0)
+            .max_by_key(|price| price.liquidity)
+            .ok_or("no DEX quotes APT in a stablecoin")?;
+        Ok(deepest.price * 10f64.powi(APT_DECIMALS - STABLECOIN_DECIMALS))
     }
 
-    /// Get token prices on a specific DEX
-    async fn get_token_price_on_dex(
+    pub(crate) async fn get_token_price_on_dex(
         client: Arc<Aptos>,
         dex_name: &str,
         token_address: &str,
@@ -618,61 +1438,254 @@ impl DexAggregator {
         token_a: &str,
         token_b: &str,
     ) -> Result<u64, String> {
+        match Self::get_dex_pool(client, dex_name, token_a, token_b).await {
+            Err(e) if e.starts_with("Unsupported DEX") => Ok(0),
+            result => result.map(|pool| pool.total_liquidity()),
+        }
+    }
+
+    /// pool reserves of a dex, `reserve_a` is the `token_a` side
+    async fn get_dex_pool(
+        client: Arc<Aptos>,
+        dex_name: &str,
+        token_a: &str,
+        token_b: &str,
+    ) -> Result<PoolInfo, String> {
         match dex_name {
-            "Liquidswap" => {
-                let pool_info = Liquidswap::get_pool_info(client, token_a, token_b).await?;
-                let reserve_a = pool_info
-                    .get("coin_x_reserve")
-                    .and_then(|v| v.as_str())
-                    .and_then(|s| s.parse().ok())
-                    .unwrap_or(0);
-                let reserve_b = pool_info
-                    .get("coin_y_reserve")
-                    .and_then(|v| v.as_str())
-                    .and_then(|s| s.parse().ok())
-                    .unwrap_or(0);
-                Ok(reserve_a + reserve_b)
-            }
-            "Thala" => {
-                let pool_info = Thala::get_pool_info(client, token_a, token_b).await?;
-                let reserve_a = pool_info
-                    .get("reserve_x")
-                    .and_then(|v| v.as_str())
-                    .and_then(|s| s.parse().ok())
-                    .unwrap_or(0);
-                let reserve_b = pool_info
-                    .get("reserve_y")
-                    .and_then(|v| v.as_str())
-                    .and_then(|s| s.parse().ok())
-                    .unwrap_or(0);
-                Ok(reserve_a + reserve_b)
-            }
+            "Liquidswap" => Liquidswap::get_pool_info_typed(client, token_a, token_b).await,
+            "Thala" => Thala::get_pool_info_typed(client, token_a, token_b).await,
             "AnimeSwap" => {
                 let (reserve_a, reserve_b) =
                     AnimeSwap::get_reserves(client, token_a, token_b).await?;
-                Ok(reserve_a + reserve_b)
+                Ok(PoolInfo {
+                    reserve_a,
+                    reserve_b,
+                    fee_bps: 30,
+                    total_lp_supply: None,
+                })
             }
             "PancakeSwap" => {
                 let (reserve_a, reserve_b) =
                     PancakeSwap::get_reserves(client, token_a, token_b).await?;
-                Ok(reserve_a + reserve_b)
+                Ok(PoolInfo {
+                    reserve_a,
+                    reserve_b,
+                    fee_bps: 30,
+                    total_lp_supply: None,
+                })
             }
-            "Cellana" => {
-                let pool_info = Cellana::get_pool_info(client, token_a, token_b).await?;
-                let reserve_a = pool_info
-                    .get("reserve_x")
-                    .and_then(|v| v.as_str())
-                    .and_then(|s| s.parse().ok())
-                    .unwrap_or(0);
-                let reserve_b = pool_info
-                    .get("reserve_y")
-                    .and_then(|v| v.as_str())
-                    .and_then(|s| s.parse().ok())
-                    .unwrap_or(0);
-                Ok(reserve_a + reserve_b)
+            "Cellana" => Cellana::get_pool_info_typed(client, token_a, token_b).await,
+            "AuxExchange" => {
+                let data = AuxExchange::get_pool_info(client, token_a, token_b).await?;
+                PoolInfo::from_resource(
+                    &data,
+                    "coin_a_reserve",
+                    "coin_b_reserve",
+                    Some("fee_bps"),
+                    30,
+                )
+            }
+            _ => Err(format!("Unsupported DEX: {}", dex_name)),
+        }
+    }
+
+    /// Look for a round trip that buys `token_b` with `amount` of `token_a` on one DEX
+    /// and sells it back on another for more `token_a` than it cost.
+    ///
+    /// Both legs are priced from pool reserves with each pool's fee, so price impact of
+    /// `amount` is included. The gas of the two swaps is subtracted when one side of the
+    /// pair is APT, otherwise it cannot be priced in `token_a` and is left out.
+    pub async fn find_arbitrage(
+        client: Arc<Aptos>,
+        token_a: &str,
+        token_b: &str,
+        amount: u64,
+    ) -> Option<ArbOpportunity> {
+        if amount == 0 {
+            return None;
+        }
+        let lookups = ARB_DEXES.iter().map(|dex| {
+            let client = Arc::clone(&client);
+            async move {
+                Self::get_dex_pool(client, dex, token_a, token_b)
+                    .await
+                    .ok()
+                    .filter(|pool| pool.reserve_a > 0 && pool.reserve_b > 0)
+                    .map(|pool| (dex.to_string(), pool))
+            }
+        });
+        let pools: Vec<(String, PoolInfo)> = futures::future::join_all(lookups)
+            .await
+            .into_iter()
+            .flatten()
+            .collect();
+        if pools.len() < 2 {
+            return None;
+        }
+        let gas_octas = client
+            .estimate_gas_price()
+            .await
+            .unwrap_or(ARB_FALLBACK_GAS_OCTAS)
+            .saturating_mul(2);
+        Self::best_arbitrage(&pools, token_a, token_b, amount, gas_octas)
+    }
+
+    /// most profitable buy/sell pair over already fetched pools
+    fn best_arbitrage(
+        pools: &[(String, PoolInfo)],
+        token_a: &str,
+        token_b: &str,
+        amount: u64,
+        gas_octas: u64,
+    ) -> Option<ArbOpportunity> {
+        let mut best: Option<ArbOpportunity> = None;
+        for (buy_dex, buy_pool) in pools {
+            let bought = Self::calculate_amm_output_with_fee(
+                amount,
+                buy_pool.reserve_a,
+                buy_pool.reserve_b,
+                buy_pool.fee_bps,
+            );
+            if bought == 0 {
+                continue;
+            }
+            let gas_cost = if token_a == APT {
+                gas_octas
+            } else if token_b == APT {
+                (gas_octas as u128 * buy_pool.reserve_a as u128 / buy_pool.reserve_b as u128)
+                    .min(u64::MAX as u128) as u64
+            } else {
+                0
+            };
+            for (sell_dex, sell_pool) in pools {
+                if sell_dex == buy_dex {
+                    continue;
+                }
+                let gross_out = Self::calculate_amm_output_with_fee(
+                    bought,
+                    sell_pool.reserve_b,
+                    sell_pool.reserve_a,
+                    sell_pool.fee_bps,
+                );
+                let est_profit = gross_out.saturating_sub(amount).saturating_sub(gas_cost);
+                if est_profit > 0 && best.as_ref().is_none_or(|b| est_profit > b.est_profit) {
+                    best = Some(ArbOpportunity {
+                        buy_dex: buy_dex.clone(),
+                        sell_dex: sell_dex.clone(),
+                        amount_in: amount,
+                        gross_out,
+                        est_profit,
+                    });
+                }
+            }
+        }
+        best
+    }
+
+    /// Split a `from` -> `to` swap of `amount_in` across up to `max_splits` DEX pools
+    /// to get the most out.
+    ///
+    /// The amount is allocated greedily in `SPLIT_ROUTE_STEPS` parts, each to the pool
+    /// giving the most for it given what it already received, priced from the pool's
+    /// reserves and fee.
+    pub async fn find_split_route(
+        client: Arc<Aptos>,
+        from: &str,
+        to: &str,
+        amount_in: u64,
+        max_splits: usize,
+    ) -> Result<SplitRoute, String> {
+        if amount_in == 0 || max_splits == 0 {
+            return Err("amount_in and max_splits must be above 0".to_string());
+        }
+        let lookups = ARB_DEXES.iter().map(|dex| {
+            let client = Arc::clone(&client);
+            async move {
+                Self::get_dex_pool(client, dex, from, to)
+                    .await
+                    .ok()
+                    .filter(|pool| pool.reserve_a > 0 && pool.reserve_b > 0)
+                    .map(|pool| (dex.to_string(), pool))
+            }
+        });
+        let pools: Vec<(String, PoolInfo)> = futures::future::join_all(lookups)
+            .await
+            .into_iter()
+            .flatten()
+            .collect();
+        Self::best_split(&pools, amount_in, max_splits)
+            .ok_or_else(|| format!("no DEX pool for {} -> {}", from, to))
+    }
+
+    /// greedy split over already fetched pools, `reserve_a` is the input side
+    fn best_split(
+        pools: &[(String, PoolInfo)],
+        amount_in: u64,
+        max_splits: usize,
+    ) -> Option<SplitRoute> {
+        if pools.is_empty() {
+            return None;
+        }
+        let mut chosen: Vec<&PoolInfo> = pools.iter().map(|(_, pool)| pool).collect();
+        let mut names: Vec<&str> = pools.iter().map(|(dex, _)| dex.as_str()).collect();
+        let mut allocations = Self::allocate_split(&chosen, amount_in);
+        if allocations.iter().filter(|a| **a > 0).count() > max_splits {
+            // keep the pools that took the most and allocate again among them
+            let mut ranked: Vec<usize> = (0..chosen.len()).collect();
+            ranked.sort_by(|a, b| allocations[*b].cmp(&allocations[*a]));
+            ranked.truncate(max_splits);
+            ranked.sort();
+            chosen = ranked.iter().map(|index| chosen[*index]).collect();
+            names = ranked.iter().map(|index| names[*index]).collect();
+            allocations = Self::allocate_split(&chosen, amount_in);
+        }
+        let legs: Vec<(String, u64, u64)> = names
+            .into_iter()
+            .zip(chosen)
+            .zip(allocations)
+            .filter(|(_, allocated)| *allocated > 0)
+            .map(|((dex, pool), allocated)| {
+                let amount_out = Self::calculate_amm_output_with_fee(
+                    allocated,
+                    pool.reserve_a,
+                    pool.reserve_b,
+                    pool.fee_bps,
+                );
+                (dex.to_string(), allocated, amount_out)
+            })
+            .collect();
+        Some(SplitRoute {
+            amount_in,
+            amount_out: legs.iter().map(|(_, _, amount_out)| amount_out).sum(),
+            legs,
+        })
+    }
+
+    /// amount given to each pool, every part goes to the pool with the highest
+    /// marginal output, the first one on ties
+    fn allocate_split(pools: &[&PoolInfo], amount_in: u64) -> Vec<u64> {
+        let steps = SPLIT_ROUTE_STEPS.min(amount_in);
+        let mut allocations = vec![0u64; pools.len()];
+        for step in 0..steps {
+            let part = amount_in / steps + u64::from(step < amount_in % steps);
+            let mut best = (0, 0);
+            for (index, pool) in pools.iter().enumerate() {
+                let output = |amount| {
+                    Self::calculate_amm_output_with_fee(
+                        amount,
+                        pool.reserve_a,
+                        pool.reserve_b,
+                        pool.fee_bps,
+                    )
+                };
+                let marginal = output(allocations[index] + part) - output(allocations[index]);
+                if marginal > best.1 {
+                    best = (index, marginal);
+                }
             }
-            _ => Ok(0),
+            allocations[best.0] += part;
         }
+        allocations
     }
 
     /// Find the liquidity pools of a token across all DEXs
@@ -680,9 +1693,13 @@ impl DexAggregator {
         client: Arc<Aptos>,
         token_address: &str,
     ) -> Result<Vec<LiquidityPool>, String> {
-        let common_tokens = vec![APT, USDC, USDT, WORMHOLE_USDC];
+        let network = client.network_config().clone();
+        let common_tokens = std::iter::once(Some(network.apt))
+            .chain([network.usdc, network.usdt, network.wormhole_usdc])
+            .flatten();
         let mut pools = Vec::new();
         for base_token in common_tokens {
+            let base_token = base_token.as_str();
             let dex_checks = vec![
                 (
                     "Liquidswap",
@@ -766,38 +1783,20 @@ impl DexAggregator {
         Ok(None)
     }
 
-    /// Get the metadata information of the token
+    /// Get the metadata information of the token, `Aptos::get_coin_info` with a
+    /// placeholder for unknown tokens
     pub async fn get_token_metadata(
         client: Arc<Aptos>,
         token_address: &str,
     ) -> Result<TokenMetadata, String> {
-        let coin_info_type = format!("0x1::coin::CoinInfo<{}>", token_address);
-        if let Ok(Some(resource)) = client.get_account_resource("0x1", &coin_info_type).await {
-            if let Value::Object(data) = &resource.data {
-                return Ok(TokenMetadata {
-                    address: token_address.to_string(),
-                    name: data
-                        .get("name")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_string(),
-                    symbol: data
-                        .get("symbol")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_string(),
-                    decimals: data.get("decimals").and_then(|v| v.as_u64()).unwrap_or(0) as u8,
-                    supply: data
-                        .get("supply")
-                        .and_then(|v| v.get("vec"))
-                        .and_then(|v| v.as_array())
-                        .and_then(|v| v.get(0))
-                        .and_then(|v| v.get("value"))
-                        .and_then(|v| v.as_str())
-                        .and_then(|s| s.parse().ok())
-                        .unwrap_or(0),
-                });
-            }
+        if let Ok(info) = client.get_coin_info(token_address).await {
+            return Ok(TokenMetadata {
+                address: token_address.to_string(),
+                name: info.name,
+                symbol: info.symbol,
+                decimals: info.decimals,
+                supply: info.supply.unwrap_or(0),
+            });
         }
         Ok(TokenMetadata {
             address: token_address.to_string(),
@@ -811,13 +1810,15 @@ impl DexAggregator {
     pub async fn get_top_prices_comparison(
         client: Arc<Aptos>,
     ) -> Result<Vec<TokenPriceComparison>, String> {
-        let popular_pairs = vec![
-            (USDC, APT), // USDC/APT
-            (USDT, APT), // USDT/APT
-            (THL, APT),  // THL/APT
-        ];
+        let network = client.network_config().clone();
+        // USDC/APT, USDT/APT and THL/APT where deployed
+        let popular_pairs = [network.usdc, network.usdt, network.thl]
+            .into_iter()
+            .flatten()
+            .map(|token| (token, network.apt.clone()));
         let mut comparisons = Vec::new();
         for (token_a, token_b) in popular_pairs {
+            let (token_a, token_b) = (token_a.as_str(), token_b.as_str());
             let mut prices = Vec::new();
             let amount_in = 1_000_000;
             if let Ok(quote) =
@@ -865,6 +1866,93 @@ pub struct DexSwapQuote {
     pub amount_out: u64,
     pub price: f64,
     pub dex_address: String,
+    /// percent, when the quote was computed from pool reserves
+    pub price_impact: Option<f64>,
+}
+
+impl DexSwapQuote {
+    /// Order quotes best first: higher `amount_out`, then lower price impact, then
+    /// dex name. An unknown price impact ranks after known values.
+    pub fn cmp_best(&self, other: &DexSwapQuote) -> std::cmp::Ordering {
+        use std::cmp::Ordering;
+        fn known_first<T: PartialOrd>(a: Option<T>, b: Option<T>) -> Ordering {
+            match (a, b) {
+                (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            }
+        }
+        other
+            .amount_out
+            .cmp(&self.amount_out)
+            .then_with(|| known_first(self.price_impact, other.price_impact))
+            .then_with(|| self.dex.cmp(&other.dex))
+    }
+}
+
+/// outcome of `DexAggregator::zap_in`
+#[derive(Debug, Clone)]
+pub struct ZapResult {
+    pub swap_dex: String,
+    /// dex the liquidity was added to
+    pub lp_dex: String,
+    /// part of the input that was swapped
+    pub swap_amount_in: u64,
+    /// input token added as liquidity
+    pub amount_a: u64,
+    /// swapped token added as liquidity
+    pub amount_b: u64,
+    pub swap: Value,
+    pub liquidity: Value,
+}
+
+/// pools `DexAggregator::zap_in` can add liquidity to
+const ZAP_LP_DEXES: [&str; 4] = ["Liquidswap", "PancakeSwap", "AnimeSwap", "Cellana"];
+
+/// dexes checked by `DexAggregator::get_token_price`
+const PRICE_DEXES: [&str; 5] = ["Liquidswap", "Thala", "PancakeSwap", "AnimeSwap", "Cellana"];
+
+/// decimals of APT and of the USDC / USDT coins `get_apt_price_in_stablecoins` quotes in
+const APT_DECIMALS: i32 = 8;
+const STABLECOIN_DECIMALS: i32 = 6;
+
+/// dexes whose reserves `find_arbitrage` compares
+const ARB_DEXES: [&str; 6] = [
+    "Liquidswap",
+    "AnimeSwap",
+    "Thala",
+    "PancakeSwap",
+    "Cellana",
+    "AuxExchange",
+];
+
+/// parts `find_split_route` allocates the input amount in
+const SPLIT_ROUTE_STEPS: u64 = 100;
+
+/// gas of one swap in octas when the node's estimate is unavailable
+const ARB_FALLBACK_GAS_OCTAS: u64 = 100 * 2000;
+
+/// cross-dex round trip, amounts are in `token_a`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArbOpportunity {
+    pub buy_dex: String,
+    pub sell_dex: String,
+    pub amount_in: u64,
+    /// `token_a` received after selling back
+    pub gross_out: u64,
+    /// `gross_out` minus `amount_in` and estimated gas
+    pub est_profit: u64,
+}
+
+/// swap split across DEX pools, see `DexAggregator::find_split_route`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplitRoute {
+    /// `(dex, amount_in, amount_out)` of each pool used
+    pub legs: Vec<(String, u64, u64)>,
+    pub amount_in: u64,
+    /// sum of the legs' `amount_out`
+    pub amount_out: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -877,121 +1965,199 @@ pub struct DexInfo {
     pub is_amm: bool,
 }
 
-/// dex event monitor
+/// dexes polled by `DexEventMonitor::start_monitoring_all_dexes`
+const MONITORED_DEXES: [&str; 6] = [
+    "Liquidswap",
+    "Thala",
+    "PancakeSwap",
+    "Cellana",
+    "AnimeSwap",
+    "AuxExchange",
+];
+
+/// Dex event monitor.
+///
+/// Polling of a dex pauses while it has no subscribers. `stop_dex` and `stop_all`
+/// abort the polling, a later subscription restarts it. Dropping the monitor aborts
+/// every polling task.
 pub struct DexEventMonitor {
     clients: HashMap<String, broadcast::Sender<EventData>>,
+    client: Option<Arc<Aptos>>,
+    poll_interval: Option<Duration>,
+    tasks: Mutex<HashMap<String, JoinHandle<()>>>,
+}
+
+impl Drop for DexEventMonitor {
+    fn drop(&mut self) {
+        self.stop_all();
+    }
+}
+
+/// aborts the polling tasks of a dex when dropped
+struct PollingTasks(Vec<JoinHandle<()>>);
+
+impl Drop for PollingTasks {
+    fn drop(&mut self) {
+        self.0.iter().for_each(|task| task.abort());
+    }
 }
 
 impl DexEventMonitor {
     pub fn new() -> Self {
         Self {
             clients: HashMap::new(),
+            client: None,
+            poll_interval: None,
+            tasks: Mutex::new(HashMap::new()),
         }
     }
+    /// Start polling every dex, `poll_interval` overrides the per dex default.
+    ///
+    /// Calling it again aborts the running polling and restarts it with the new
+    /// client and interval, existing subscriptions keep receiving.
     pub async fn start_monitoring_all_dexes(
         &mut self,
         client: Arc<Aptos>,
+        poll_interval: Option<Duration>,
     ) -> Result<(), String> {
-        let dexes = vec![
-            "Liquidswap",
-            "Thala",
-            "PancakeSwap",
-            "Cellana",
-            "AnimeSwap",
-            "AuxExchange",
-        ];
-        for dex_name in dexes {
-            let (sender, _) = broadcast::channel(1000);
-            self.clients.insert(dex_name.to_string(), sender);
+        self.stop_all();
+        self.client = Some(client);
+        self.poll_interval = poll_interval;
+        for dex_name in MONITORED_DEXES {
+            self.clients
+                .entry(dex_name.to_string())
+                .or_insert_with(|| broadcast::channel(1000).0);
+            self.ensure_task(dex_name);
         }
-        Self::start_dex_monitoring_task(
-            Arc::clone(&client),
-            "Liquidswap",
-            self.get_sender("Liquidswap"),
-        );
-        Self::start_dex_monitoring_task(Arc::clone(&client), "Thala", self.get_sender("Thala"));
-        Self::start_dex_monitoring_task(
-            Arc::clone(&client),
-            "PancakeSwap",
-            self.get_sender("PancakeSwap"),
-        );
-        Self::start_dex_monitoring_task(Arc::clone(&client), "Cellana", self.get_sender("Cellana"));
-        Self::start_dex_monitoring_task(
-            Arc::clone(&client),
-            "AnimeSwap",
-            self.get_sender("AnimeSwap"),
-        );
-        Self::start_dex_monitoring_task(
-            Arc::clone(&client),
-            "AuxExchange",
-            self.get_sender("AuxExchange"),
-        );
         Ok(())
     }
 
+    /// start the monitoring task of a dex unless it is running
+    fn ensure_task(&self, dex_name: &str) {
+        let (Some(client), Some(sender)) = (&self.client, self.get_sender(dex_name)) else {
+            return;
+        };
+        let mut tasks = self.tasks.lock().unwrap();
+        if tasks.get(dex_name).is_some_and(|task| !task.is_finished()) {
+            return;
+        }
+        let task = Self::start_dex_monitoring_task(
+            Arc::clone(client),
+            dex_name,
+            sender,
+            self.poll_interval,
+        );
+        tasks.insert(dex_name.to_string(), task);
+    }
+
     fn start_dex_monitoring_task(
         client: Arc<Aptos>,
         dex_name: &str,
-        sender: Option<broadcast::Sender<EventData>>,
-    ) {
-        if let Some(sender) = sender {
-            let client = Arc::clone(&client);
-            let dex_name = dex_name.to_string();
-            tokio::spawn(async move {
-                match dex_name.as_str() {
-                    "Liquidswap" => {
-                        let _ = Liquidswap::listen_events(client, sender, vec![]).await;
-                    }
-                    "Thala" => {
-                        let _ = Thala::listen_events(client, sender, vec![]).await;
-                    }
-                    "PancakeSwap" => {
-                        let filters = PancakeSwapEventFilters {
-                            min_swap_amount: Some(1000000000),
-                            include_cake_pairs: true,
-                            tracked_pairs: None,
-                        };
-                        let _ = PancakeSwap::listen_events(client, sender, filters).await;
-                    }
-                    "Cellana" => {
-                        let config = CellanaEventConfig {
-                            monitor_cell_pairs: true,
-                            min_swap_amount: 1000000000,
-                            monitor_farming: true,
-                            tracked_tokens: vec![],
-                        };
-                        let _ = Cellana::listen_events(client, sender, config).await;
-                    }
-                    "AnimeSwap" => {
-                        let filters = AnimeSwapEventFilters {
-                            min_swap_amount: Some(1000000000),
-                            tracked_tokens: None,
-                            min_liquidity_amount: Some(500000000),
-                        };
-                        let _ = AnimeSwap::listen_events(client, sender, filters).await;
-                    }
-                    "AuxExchange" => {
-                        let _ = AuxExchange::listen_events(client, sender, vec![]).await;
-                    }
-                    _ => {}
+        sender: broadcast::Sender<EventData>,
+        poll_interval: Option<Duration>,
+    ) -> JoinHandle<()> {
+        let dex_name = dex_name.to_string();
+        tokio::spawn(async move {
+            let invalidation = client
+                .reserve_cache()
+                .map(|cache| Arc::clone(cache).invalidate_on_events(&dex_name, sender.subscribe()));
+            let polling = match dex_name.as_str() {
+                "Liquidswap" => {
+                    let mut config = LiquidswapEventConfig::default();
+                    config.poll_interval = poll_interval.unwrap_or(config.poll_interval);
+                    Liquidswap::listen_events(client, sender, config).await
                 }
-            });
+                "Thala" => {
+                    let mut config = ThalaEventConfig::default();
+                    config.poll_interval = poll_interval.unwrap_or(config.poll_interval);
+                    Thala::listen_events(client, sender, config).await
+                }
+                "PancakeSwap" => {
+                    let mut filters = PancakeSwapEventFilters::default();
+                    filters.poll_interval = poll_interval.unwrap_or(filters.poll_interval);
+                    PancakeSwap::listen_events(client, sender, filters).await
+                }
+                "Cellana" => {
+                    let mut config = CellanaEventConfig::default();
+                    config.poll_interval = poll_interval.unwrap_or(config.poll_interval);
+                    Cellana::listen_events(client, sender, config).await
+                }
+                "AnimeSwap" => {
+                    let mut filters = AnimeSwapEventFilters::default();
+                    filters.poll_interval = poll_interval.unwrap_or(filters.poll_interval);
+                    AnimeSwap::listen_events(client, sender, filters).await
+                }
+                "AuxExchange" => {
+                    let mut config = AuxEventConfig::default();
+                    config.poll_interval = poll_interval.unwrap_or(config.poll_interval);
+                    AuxExchange::listen_events(client, sender, config).await
+                }
+                _ => Ok(vec![]),
+            };
+            // the polling tasks live as long as this task, aborting it drops the guard
+            let _polling = PollingTasks(
+                polling
+                    .unwrap_or_default()
+                    .into_iter()
+                    .chain(invalidation)
+                    .collect(),
+            );
+            std::future::pending::<()>().await
+        })
+    }
+
+    /// stop polling a dex, `false` if it was not being polled
+    pub fn stop_dex(&self, dex_name: &str) -> bool {
+        match self.tasks.lock().unwrap().remove(dex_name) {
+            Some(task) => {
+                task.abort();
+                true
+            }
+            None => false,
         }
     }
 
+    /// stop polling every dex
+    pub fn stop_all(&self) {
+        self.tasks
+            .lock()
+            .unwrap()
+            .drain()
+            .for_each(|(_, task)| task.abort());
+    }
+
+    /// whether the monitoring task of a dex is running, it may be paused
+    pub fn is_monitoring(&self, dex_name: &str) -> bool {
+        self.tasks
+            .lock()
+            .unwrap()
+            .get(dex_name)
+            .is_some_and(|task| !task.is_finished())
+    }
+
     fn get_sender(&self, dex_name: &str) -> Option<broadcast::Sender<EventData>> {
         self.clients.get(dex_name).cloned()
     }
 
+    /// subscribe to a dex, restarting its polling if it was stopped
     pub fn subscribe_to_dex(&self, dex_name: &str) -> Option<broadcast::Receiver<EventData>> {
-        self.clients.get(dex_name).map(|sender| sender.subscribe())
+        let receiver = self.clients.get(dex_name).map(|sender| sender.subscribe());
+        if receiver.is_some() {
+            self.ensure_task(dex_name);
+        }
+        receiver
     }
 
     pub fn get_all_receivers(&self) -> Vec<(String, broadcast::Receiver<EventData>)> {
-        self.clients
+        let receivers: Vec<_> = self
+            .clients
             .iter()
             .map(|(name, sender)| (name.clone(), sender.subscribe()))
-            .collect()
+            .collect();
+        receivers
+            .iter()
+            .for_each(|(name, _)| self.ensure_task(name));
+        receivers
     }
 
     pub fn publish_to_dex(&self, dex_name: &str, event: EventData) -> Result<(), String> {
@@ -1042,7 +2208,7 @@ impl DexAnalytics {
                 match volume_future {
                     Ok(volume) => Some((dex_name.to_string(), volume)),
                     Err(e) => {
-                        eprintln!("Failed to get volume for {}: {}", dex_name, e);
+                        log_warn!("Failed to get volume for {}: {}", dex_name, e);
                         None
                     }
                 }
@@ -1281,77 +2447,409 @@ impl DexAnalytics {
         liquidity_data.sort_by(|a, b| b.total_liquidity.cmp(&a.total_liquidity));
         Ok(liquidity_data)
     }
-}
-
-#[derive(Debug, Clone)]
-pub struct DexLiquidity {
-    pub dex: String,
-    pub token_a: String,
-    pub token_b: String,
-    pub reserve_a: u64,
-    pub reserve_b: u64,
-    pub total_liquidity: u64,
-}
 
-pub struct DexUtils;
-
-impl DexUtils {
-    pub fn calculate_price_impact(amount_in: u64, reserve_in: u64, reserve_out: u64) -> f64 {
-        if reserve_in == 0 || reserve_out == 0 {
-            return 0.0;
+    /// Time-weighted average price of `token_a` in `token_b` over the last `window_secs`,
+    /// sampled from the dex's recent swap events.
+    ///
+    /// Only swaps of the `token_a`/`token_b` pool that carry a timestamp are sampled,
+    /// each oriented by its direction so every sample is a `token_b` per `token_a`
+    /// price. Each price is weighted by how long it stayed the latest price. Thin pools
+    /// may produce very few samples, so check `sample_count` before trusting the result.
+    pub async fn get_twap(
+        client: Arc<Aptos>,
+        dex: &str,
+        token_a: &str,
+        token_b: &str,
+        window_secs: u64,
+    ) -> Result<TwapResult, String> {
+        let events = Self::get_dex_swap_events(client, dex).await?;
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|e| e.to_string())?
+            .as_secs();
+        let window_start = now.saturating_sub(window_secs);
+        let mut samples: Vec<(u64, f64)> = events
+            .iter()
+            .filter_map(|event| Self::pool_swap_sample(dex, event, token_a, token_b))
+            .filter(|(ts, _, _)| *ts >= window_start)
+            .map(|(ts, price, _)| (ts, price))
+            .collect();
+        if samples.is_empty() {
+            return Err(format!(
+                "no swap samples for {} in the last {}s",
+                dex, window_secs
+            ));
         }
-        let amount_out_before = reserve_out as f64 / reserve_in as f64 * amount_in as f64;
-        let amount_out_after =
-            DexAggregator::calculate_amm_output(amount_in, reserve_in, reserve_out) as f64;
-        if amount_out_before == 0.0 {
-            return 0.0;
+        samples.sort_by_key(|(ts, _)| *ts);
+        let sample_count = samples.len();
+        let mut weighted_sum = 0.0;
+        let mut total_weight = 0.0;
+        for (i, (ts, price)) in samples.iter().enumerate() {
+            let next_ts = samples.get(i + 1).map(|(next, _)| *next).unwrap_or(now);
+            let weight = next_ts.saturating_sub(*ts) as f64;
+            weighted_sum += price * weight;
+            total_weight += weight;
         }
-        ((amount_out_before - amount_out_after) / amount_out_before).abs() * 100.0
-    }
-
-    pub fn calculate_optimal_slippage(price_impact: f64) -> f64 {
-        if price_impact < 0.1 {
-            0.5
-        } else if price_impact < 1.0 {
-            1.0
+        // every sample landed on the current second, none has a duration yet
+        let twap = if total_weight > 0.0 {
+            weighted_sum / total_weight
         } else {
-            2.0
-        }
+            samples.iter().map(|(_, price)| price).sum::<f64>() / sample_count as f64
+        };
+        Ok(TwapResult {
+            dex: dex.to_string(),
+            token_a: token_a.to_string(),
+            token_b: token_b.to_string(),
+            twap,
+            sample_count,
+            window_secs,
+        })
     }
 
-    pub fn format_token_amount(amount: u64, decimals: u8) -> String {
-        let divisor = 10u64.pow(decimals as u32);
-        let whole = amount / divisor;
-        let fractional = amount % divisor;
-        if fractional == 0 {
-            format!("{}", whole)
-        } else {
-            format!(
-                "{}.{:0>width$}",
-                whole,
-                fractional,
-                width = decimals as usize
-            )
+    /// recent swap events of a dex
+    async fn get_dex_swap_events(client: Arc<Aptos>, dex: &str) -> Result<Vec<EventData>, String> {
+        match dex {
+            "Liquidswap" => Liquidswap::get_swap_events(client).await,
+            "Thala" => Thala::get_swap_events(client).await,
+            "AnimeSwap" => AnimeSwap::get_swap_events(client).await,
+            "PancakeSwap" => PancakeSwap::get_swap_events(client).await,
+            "Cellana" => Cellana::get_swap_events(client).await,
+            "AuxExchange" => AuxExchange::get_swap_events(client).await,
+            _ => Err(format!("Unsupported DEX: {}", dex)),
         }
     }
 
-    pub async fn validate_token_pair(
+    /// Fee APR of the `token_a`/`token_b` pool of a dex: the fees its recent swaps
+    /// paid over the last `window_hours`, divided by the pool's TVL and annualized.
+    ///
+    /// Volume and TVL are in `token_a`, the TVL being twice the `token_a` reserve as
+    /// both sides of the pool hold the same value. Only swaps naming the pool and
+    /// carrying a timestamp inside the window are counted, and only those in the
+    /// recent events page, so a busy pool's volume over a long window is
+    /// underestimated.
+    pub async fn estimate_pool_apr(
         client: Arc<Aptos>,
+        dex: &str,
         token_a: &str,
         token_b: &str,
-    ) -> Result<Vec<String>, String> {
-        let mut supported_dexes = Vec::new();
-        if Liquidswap::get_pool_info(Arc::clone(&client), token_a, token_b)
-            .await
-            .is_ok()
+        window_hours: u64,
+    ) -> Result<PoolApr, String> {
+        if window_hours == 0 {
+            return Err("window_hours must be positive".to_string());
+        }
+        let pool = DexAggregator::get_dex_pool(Arc::clone(&client), dex, token_a, token_b).await?;
+        let events = Self::get_dex_swap_events(client, dex).await?;
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|e| e.to_string())?
+            .as_secs();
+        let window_start = now.saturating_sub(window_hours * 3600);
+        let volume = events
+            .iter()
+            .filter_map(|event| Self::pool_swap_sample(dex, event, token_a, token_b))
+            .filter(|(ts, _, _)| *ts >= window_start)
+            .fold(0u64, |total, (_, _, amount)| total.saturating_add(amount));
+        Ok(PoolApr::new(volume, &pool, window_hours))
+    }
+
+    /// A swap of the `token_a`/`token_b` pool as `(ts, price of token_a in token_b,
+    /// token_a volume)`, `None` for swaps of other pools and swaps without a timestamp.
+    fn pool_swap_sample(
+        dex: &str,
+        event: &EventData,
+        token_a: &str,
+        token_b: &str,
+    ) -> Option<(u64, f64, u64)> {
+        let swap = NormalizedSwap::from_event(dex, event)?;
+        let (amount_a, amount_b) = if swap.token_in == token_a && swap.token_out == token_b {
+            (swap.amount_in, swap.amount_out)
+        } else if swap.token_in == token_b && swap.token_out == token_a {
+            (swap.amount_out, swap.amount_in)
+        } else {
+            return None;
+        };
+        if swap.ts == 0 || amount_a == 0 || amount_b == 0 {
+            return None;
+        }
+        Some((swap.ts, amount_b as f64 / amount_a as f64, amount_a))
+    }
+
+    /// Sample the node's gas unit price estimate `samples` times, `interval_secs`
+    /// apart, and smooth it with an exponential moving average.
+    ///
+    /// Failed samples are skipped, an error is returned only if every one fails.
+    pub async fn gas_price_ema(
+        client: Arc<Aptos>,
+        samples: usize,
+        interval_secs: u64,
+    ) -> Result<GasPriceEma, String> {
+        if samples == 0 {
+            return Err("gas price ema needs at least one sample".to_string());
+        }
+        let mut observed = Vec::with_capacity(samples);
+        let mut last_error = None;
+        for i in 0..samples {
+            if i > 0 && interval_secs > 0 {
+                tokio::time::sleep(std::time::Duration::from_secs(interval_secs)).await;
+            }
+            match client.get_gas_estimation().await {
+                Ok(estimation) => observed.push(estimation.gas_estimate),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        GasPriceEma::from_samples(observed)
+            .ok_or_else(|| format!("no gas price sample: {}", last_error.unwrap_or_default()))
+    }
+}
+
+/// smoothed gas unit price, in octas per gas unit
+#[derive(Debug, Clone, PartialEq)]
+pub struct GasPriceEma {
+    pub ema: f64,
+    pub min: u64,
+    pub max: u64,
+    /// observed prices, oldest first
+    pub samples: Vec<u64>,
+}
+
+impl GasPriceEma {
+    /// ema with smoothing `2 / (n + 1)`, seeded with the first sample
+    fn from_samples(samples: Vec<u64>) -> Option<Self> {
+        let first = *samples.first()?;
+        let alpha = 2.0 / (samples.len() as f64 + 1.0);
+        let ema = samples[1..].iter().fold(first as f64, |ema, price| {
+            alpha * *price as f64 + (1.0 - alpha) * ema
+        });
+        Some(GasPriceEma {
+            ema,
+            min: *samples.iter().min()?,
+            max: *samples.iter().max()?,
+            samples,
+        })
+    }
+
+    /// ema rounded up, for use as `gas_unit_price`
+    pub fn gas_unit_price(&self) -> u64 {
+        self.ema.ceil() as u64
+    }
+}
+
+/// fee yield of a pool, see `DexAnalytics::estimate_pool_apr`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolApr {
+    /// annualized fee yield, 0.12 is 12%
+    pub apr: f64,
+    /// swap volume over the window, in `token_a`
+    pub volume: u64,
+    /// pool value, in `token_a`
+    pub tvl: u64,
+    /// fraction of each swap paid as fee
+    pub fee_rate: f64,
+}
+
+impl PoolApr {
+    fn new(volume: u64, pool: &PoolInfo, window_hours: u64) -> Self {
+        let tvl = pool.reserve_a.saturating_mul(2);
+        let fee_rate = pool.fee_bps as f64 / 10_000.0;
+        let apr = if tvl == 0 || window_hours == 0 {
+            0.0
+        } else {
+            volume as f64 * fee_rate / tvl as f64 * (365.0 * 24.0 / window_hours as f64)
+        };
+        PoolApr {
+            apr,
+            volume,
+            tvl,
+            fee_rate,
+        }
+    }
+}
+
+/// time-weighted average price
+#[derive(Debug, Clone)]
+pub struct TwapResult {
+    pub dex: String,
+    pub token_a: String,
+    pub token_b: String,
+    pub twap: f64,
+    /// number of swap events used, low counts mean low confidence
+    pub sample_count: usize,
+    pub window_secs: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct DexLiquidity {
+    pub dex: String,
+    pub token_a: String,
+    pub token_b: String,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+    pub total_liquidity: u64,
+}
+
+pub struct DexUtils;
+
+impl DexUtils {
+    pub fn calculate_price_impact(amount_in: u64, reserve_in: u64, reserve_out: u64) -> f64 {
+        if reserve_in == 0 || reserve_out == 0 {
+            return 0.0;
+        }
+        let amount_out_before = reserve_out as f64 / reserve_in as f64 * amount_in as f64;
+        let amount_out_after =
+            DexAggregator::calculate_amm_output(amount_in, reserve_in, reserve_out) as f64;
+        if amount_out_before == 0.0 {
+            return 0.0;
+        }
+        ((amount_out_before - amount_out_after) / amount_out_before).abs() * 100.0
+    }
+
+    /// Constant product input needed to receive `amount_out`, with a fee in basis points.
+    ///
+    /// Rounds up, so swapping the result yields at least `amount_out`. `None` when the
+    /// pool cannot pay `amount_out` (`amount_out >= reserve_out`), for an empty pool or
+    /// a fee of 100% or more.
+    pub fn calculate_amount_in(
+        amount_out: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        fee_bps: u64,
+    ) -> Option<u64> {
+        if amount_out >= reserve_out || reserve_in == 0 || fee_bps >= 10_000 {
+            return None;
+        }
+        let numerator = (reserve_in as u128 * amount_out as u128).checked_mul(10_000)?;
+        let denominator = (reserve_out - amount_out) as u128 * (10_000 - fee_bps) as u128;
+        u64::try_from(numerator / denominator + 1).ok()
+    }
+
+    /// check that a slippage fraction is within `[0, 1]`
+    pub fn validate_slippage(slippage: f64) -> Result<(), String> {
+        if !slippage.is_finite() || !(0.0..=1.0).contains(&slippage) {
+            return Err(format!(
+                "invalid slippage {}: expected a fraction between 0 and 1",
+                slippage
+            ));
+        }
+        Ok(())
+    }
+
+    /// minimum accepted output for a quote with the given slippage fraction
+    pub fn min_amount_out(amount_out: u64, slippage: f64) -> u64 {
+        (amount_out as f64 * (1.0 - slippage)) as u64
+    }
+
+    /// minimum amounts accepted when adding `amount_x` / `amount_y` of liquidity,
+    /// after checking `slippage` is a fraction in `[0, 1]`
+    pub fn min_liquidity_amounts(
+        amount_x: u64,
+        amount_y: u64,
+        slippage: f64,
+    ) -> Result<(u64, u64), String> {
+        Self::validate_slippage(slippage)?;
+        Ok((
+            Self::min_amount_out(amount_x, slippage),
+            Self::min_amount_out(amount_y, slippage),
+        ))
+    }
+
+    pub fn calculate_optimal_slippage(price_impact: f64) -> f64 {
+        if price_impact < 0.1 {
+            0.5
+        } else if price_impact < 1.0 {
+            1.0
+        } else {
+            2.0
+        }
+    }
+
+    pub fn format_token_amount(amount: u64, decimals: u8) -> String {
+        let divisor = 10u64.pow(decimals as u32);
+        let whole = amount / divisor;
+        let fractional = amount % divisor;
+        if fractional == 0 {
+            format!("{}", whole)
+        } else {
+            format!(
+                "{}.{:0>width$}",
+                whole,
+                fractional,
+                width = decimals as usize
+            )
+        }
+    }
+
+    /// Format an on-chain amount with at most `max_frac_digits` fraction digits.
+    ///
+    /// Extra digits are truncated rather than rounded so a balance is never overstated,
+    /// trailing zeros are dropped.
+    pub fn format_token_amount_precision(amount: u64, decimals: u8, max_frac_digits: u8) -> String {
+        let divisor = 10u128.pow(decimals as u32);
+        let whole = amount as u128 / divisor;
+        let fractional = format!(
+            "{:0>width$}",
+            amount as u128 % divisor,
+            width = decimals as usize
+        );
+        let fractional = fractional[..decimals.min(max_frac_digits) as usize].trim_end_matches('0');
+        if fractional.is_empty() {
+            format!("{}", whole)
+        } else {
+            format!("{}.{}", whole, fractional)
+        }
+    }
+
+    /// Parse a human readable amount (`"1.5"`) into on-chain units.
+    ///
+    /// Rejects signs, exponents, more fraction digits than `decimals` and amounts
+    /// that overflow `u64`.
+    pub fn parse_token_amount(s: &str, decimals: u8) -> Result<u64, String> {
+        let s = s.trim();
+        let (whole, fractional) = s.split_once('.').unwrap_or((s, ""));
+        let is_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if (whole.is_empty() && fractional.is_empty())
+            || !is_digits(whole)
+            || !is_digits(fractional)
         {
+            return Err(format!("invalid token amount: {:?}", s));
+        }
+        if fractional.len() > decimals as usize {
+            return Err(format!(
+                "too many decimal places: {:?} allows {}",
+                s, decimals
+            ));
+        }
+        let overflow = || format!("token amount overflow: {:?}", s);
+        let scale = 10u128.checked_pow(decimals as u32).ok_or_else(overflow)?;
+        let whole: u128 = if whole.is_empty() {
+            0
+        } else {
+            whole.parse().map_err(|_| overflow())?
+        };
+        let fractional: u128 = if fractional.is_empty() {
+            0
+        } else {
+            format!("{:0<width$}", fractional, width = decimals as usize)
+                .parse()
+                .map_err(|_| overflow())?
+        };
+        let amount = whole
+            .checked_mul(scale)
+            .and_then(|v| v.checked_add(fractional))
+            .ok_or_else(overflow)?;
+        u64::try_from(amount).map_err(|_| overflow())
+    }
+
+    pub async fn validate_token_pair(
+        client: Arc<Aptos>,
+        token_a: &str,
+        token_b: &str,
+    ) -> Result<Vec<String>, String> {
+        let mut supported_dexes = Vec::new();
+        if Liquidswap::pool_exists(Arc::clone(&client), token_a, token_b).await == Ok(true) {
             supported_dexes.push("Liquidswap".to_string());
         }
 
-        if Thala::get_pool_info(Arc::clone(&client), token_a, token_b)
-            .await
-            .is_ok()
-        {
+        if Thala::pool_exists(Arc::clone(&client), token_a, token_b).await == Ok(true) {
             supported_dexes.push("Thala".to_string());
         }
 
@@ -1369,10 +2867,7 @@ impl DexUtils {
             supported_dexes.push("AnimeSwap".to_string());
         }
 
-        if Cellana::get_pool_info(Arc::clone(&client), token_a, token_b)
-            .await
-            .is_ok()
-        {
+        if Cellana::pool_exists(Arc::clone(&client), token_a, token_b).await == Ok(true) {
             supported_dexes.push("Cellana".to_string());
         }
 
@@ -1386,6 +2881,7 @@ impl Default for PancakeSwapEventFilters {
             min_swap_amount: Some(1000000000),
             include_cake_pairs: true,
             tracked_pairs: None,
+            poll_interval: Duration::from_secs(3),
         }
     }
 }
@@ -1397,6 +2893,7 @@ impl Default for CellanaEventConfig {
             min_swap_amount: 1000000000,
             monitor_farming: true,
             tracked_tokens: vec![],
+            poll_interval: Duration::from_secs(2),
         }
     }
 }
@@ -1407,6 +2904,1307 @@ impl Default for AnimeSwapEventFilters {
             min_swap_amount: Some(1000000000),
             tracked_tokens: None,
             min_liquidity_amount: Some(500000000),
+            poll_interval: Duration::from_secs(3),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        global::{
+            NetworkConfig,
+            mainnet::token_address::{USDC, USDT},
+        },
+        transport::MockTransport,
+    };
+    use serde_json::json;
+
+    fn event(event_type: &str, data: Value) -> EventData {
+        EventData {
+            event_type: event_type.to_string(),
+            event_data: data,
+            sequence_number: 1,
+            transaction_hash: "".to_string(),
+            block_height: 0,
+            timestamp_secs: 0,
+        }
+    }
+
+    #[test]
+    fn test_parse_liquidswap_flash_swap_event() {
+        use crate::dex::liquidswap::{FlashSwapEvent, LiquidswapEventParser};
+        let pool_event = event(
+            &format!(
+                "{}::liquidity_pool::FlashloanEvent<0x1::aptos_coin::AptosCoin, {}, 0x1::curves::Uncorrelated>",
+                LIQUIDSWAP_PROTOCOL_ADDRESS, USDC
+            ),
+            json!({"x_in": "1003000", "x_out": "1000000", "y_in": "0", "y_out": "0"}),
+        );
+        assert_eq!(
+            LiquidswapEventParser::parse_flash_swap_event(&pool_event),
+            Some(FlashSwapEvent {
+                borrower: "".to_string(),
+                amount_x: 1000000,
+                amount_y: 0,
+                fee: 3000,
+            })
+        );
+        let handle_event = event(
+            "0x1::liquidity_pool::EventsStore::flash_swap_events",
+            json!({"borrower": "0xb0b", "amount_x": "0", "amount_y": "500", "fee": "2"}),
+        );
+        let parsed = LiquidswapEventParser::parse_flash_swap_event(&handle_event).unwrap();
+        assert_eq!(parsed.borrower, "0xb0b");
+        assert_eq!((parsed.amount_x, parsed.amount_y, parsed.fee), (0, 500, 2));
+        let swap = event("0x1::liquidity_pool::SwapEvent", json!({}));
+        assert!(LiquidswapEventParser::parse_flash_swap_event(&swap).is_none());
+    }
+
+    #[test]
+    fn test_twap_samples_are_oriented_to_the_pool() {
+        let pancake_swap = |pair: &str, data: Value| {
+            event(
+                &format!(
+                    "{}::swap::SwapEvent<{}>",
+                    PANCAKESWAP_FACTORY_PROTOCOL_ADDRESS, pair
+                ),
+                data,
+            )
+        };
+        let pair = format!("{}, {}", APT, USDC);
+        let apt_in = pancake_swap(
+            &pair,
+            json!({"amount_x_in": "1000", "amount_y_in": "0", "amount_x_out": "0", "amount_y_out": "90", "timestamp": "100"}),
+        );
+        let usdc_in = pancake_swap(
+            &pair,
+            json!({"amount_x_in": "0", "amount_y_in": "50", "amount_x_out": "500", "amount_y_out": "0", "timestamp": "200"}),
+        );
+        let sample = |e: &EventData| DexAnalytics::pool_swap_sample("PancakeSwap", e, APT, USDC);
+        // both directions are a USDC per APT price
+        assert_eq!(sample(&apt_in), Some((100, 0.09, 1000)));
+        assert_eq!(sample(&usdc_in), Some((200, 0.1, 500)));
+        assert_eq!(
+            DexAnalytics::pool_swap_sample("PancakeSwap", &usdc_in, USDC, APT),
+            Some((200, 10.0, 50))
+        );
+        let other_pool = pancake_swap(
+            &format!("{}, 0x2::b::B", APT),
+            json!({"amount_x_in": "7", "amount_y_in": "0", "amount_x_out": "0", "amount_y_out": "7", "timestamp": "100"}),
+        );
+        let untimed = pancake_swap(
+            &pair,
+            json!({"amount_x_in": "1000", "amount_y_in": "0", "amount_x_out": "0", "amount_y_out": "90"}),
+        );
+        let untyped = event(
+            "0x7::pool::SwapEvent",
+            json!({"amount_in": "300", "amount_out": "20", "timestamp": "100"}),
+        );
+        assert_eq!(sample(&other_pool), None);
+        assert_eq!(sample(&untimed), None);
+        assert_eq!(sample(&untyped), None);
+    }
+
+    #[test]
+    fn test_pool_apr_counts_pair_volume_in_token_a() {
+        let pancake_swap = |pair: &str, data: Value| {
+            event(
+                &format!(
+                    "{}::swap::SwapEvent<{}>",
+                    PANCAKESWAP_FACTORY_PROTOCOL_ADDRESS, pair
+                ),
+                data,
+            )
+        };
+        let pair = format!("{}, {}", APT, USDC);
+        let apt_in = pancake_swap(
+            &pair,
+            json!({"amount_x_in": "1000", "amount_y_in": "0", "amount_x_out": "0", "amount_y_out": "90", "timestamp": "1700000000"}),
+        );
+        let usdc_in = pancake_swap(
+            &pair,
+            json!({"amount_x_in": "0", "amount_y_in": "50", "amount_x_out": "400", "amount_y_out": "0", "timestamp": "1700000000"}),
+        );
+        let other = pancake_swap(
+            &format!("{}, 0x2::b::B", APT),
+            json!({"amount_x_in": "7", "amount_y_in": "0", "amount_x_out": "0", "amount_y_out": "7", "timestamp": "1700000000"}),
+        );
+        let volume: Vec<Option<u64>> = [&apt_in, &usdc_in, &other]
+            .iter()
+            .map(|e| {
+                DexAnalytics::pool_swap_sample("PancakeSwap", e, APT, USDC)
+                    .map(|(_, _, volume)| volume)
+            })
+            .collect();
+        assert_eq!(volume, vec![Some(1000), Some(400), None]);
+        // swaps that don't name the pool or carry no timestamp are not counted
+        let thala = event(
+            "0x7::pool::SwapEvent",
+            json!({"amount_in": "300", "amount_out": "20", "timestamp": "1700000000"}),
+        );
+        assert_eq!(
+            DexAnalytics::pool_swap_sample("Thala", &thala, APT, USDC),
+            None
+        );
+        let untimed = pancake_swap(
+            &pair,
+            json!({"amount_x_in": "1000", "amount_y_in": "0", "amount_x_out": "0", "amount_y_out": "90"}),
+        );
+        assert_eq!(
+            DexAnalytics::pool_swap_sample("PancakeSwap", &untimed, APT, USDC),
+            None
+        );
+        let pool = PoolInfo {
+            reserve_a: 1_000_000,
+            reserve_b: 90_000,
+            fee_bps: 30,
+            total_lp_supply: None,
+        };
+        let apr = PoolApr::new(1_700, &pool, 24);
+        assert_eq!((apr.volume, apr.tvl), (1_700, 2_000_000));
+        assert!((apr.fee_rate - 0.003).abs() < 1e-12);
+        assert!((apr.apr - 1_700.0 * 0.003 / 2_000_000.0 * 365.0).abs() < 1e-12);
+        let empty = PoolInfo::default();
+        assert_eq!(PoolApr::new(1_700, &empty, 24).apr, 0.0);
+    }
+
+    #[test]
+    fn test_parse_pancakeswap_events() {
+        let swap = event(
+            &format!(
+                "{}::swap::SwapEvent<0x1::aptos_coin::AptosCoin, {}>",
+                PANCAKESWAP_FACTORY_PROTOCOL_ADDRESS, USDC
+            ),
+            json!({
+                "sender_address": "0xa11ce",
+                "amount_x_in": "0",
+                "amount_y_in": "2500000",
+                "amount_x_out": "100000000",
+                "amount_y_out": "0",
+                "timestamp": "1700000000"
+            }),
+        );
+        let normalized = NormalizedSwap::from_event("PancakeSwap", &swap).unwrap();
+        assert_eq!(normalized.sender, "0xa11ce");
+        assert_eq!(normalized.token_in, USDC);
+        assert_eq!(normalized.token_out, "0x1::aptos_coin::AptosCoin");
+        assert_eq!(normalized.amount_in, 2500000);
+        assert_eq!(normalized.amount_out, 100000000);
+        assert_eq!(normalized.ts, 1700000000);
+        let mint = event(
+            "0x1::swap::AddLiquidityEvent<0x1::aptos_coin::AptosCoin, 0x2::c::C>",
+            json!({"user": "0xa11ce", "amount_x": "10", "amount_y": "20", "liquidity": "14"}),
+        );
+        let mint = PancakeSwapEventParser::parse_mint_event(&mint).unwrap();
+        assert_eq!((mint.amount_a, mint.amount_b, mint.liquidity), (10, 20, 14));
+        assert_eq!(mint.token_b, "0x2::c::C");
+        let burn = event(
+            "0x1::swap::RemoveLiquidityEvent<0x1::a::A, 0x2::b::B>",
+            json!({"user": "0xa11ce", "amount_x": "5", "amount_y": "7", "liquidity": "6"}),
+        );
+        assert!(PancakeSwapEventParser::parse_mint_event(&burn).is_none());
+        assert_eq!(
+            PancakeSwapEventParser::parse_burn_event(&burn)
+                .unwrap()
+                .liquidity,
+            6
+        );
+    }
+
+    #[test]
+    fn test_parse_animeswap_events() {
+        let swap = event(
+            "0x16::AnimeSwapPoolV1::SwapEvent<0x1::aptos_coin::AptosCoin, 0x5::usdc::USDC>",
+            json!({
+                "amount_x_in": "300",
+                "amount_y_in": "0",
+                "amount_x_out": "0",
+                "amount_y_out": "290"
+            }),
+        );
+        let normalized = NormalizedSwap::from_event("AnimeSwap", &swap).unwrap();
+        assert_eq!(normalized.token_in, "0x1::aptos_coin::AptosCoin");
+        assert_eq!(normalized.token_out, "0x5::usdc::USDC");
+        assert_eq!((normalized.amount_in, normalized.amount_out), (300, 290));
+        assert_eq!(normalized.ts, 0);
+        let burn = event(
+            "0x16::AnimeSwapPoolV1::BurnEvent<0x1::a::A, 0x2::b::B>",
+            json!({"amount_x": "1", "amount_y": "2", "liquidity": "3"}),
+        );
+        let burn = AnimeSwapEventParser::parse_burn_event(&burn).unwrap();
+        assert_eq!((burn.amount_a, burn.amount_b, burn.liquidity), (1, 2, 3));
+        assert!(
+            AnimeSwapEventParser::parse_swap_event(&event("0x1::m::Other", json!({}))).is_none()
+        );
+    }
+
+    #[test]
+    fn test_normalize_liquidswap_and_thala_swaps_by_direction() {
+        let liquidswap = |data: Value| {
+            event(
+                &format!(
+                    "0x190d::liquidity_pool::SwapEvent<{}, {}, 0x190d::curves::Uncorrelated>",
+                    APT, USDC
+                ),
+                data,
+            )
+        };
+        let x_in = liquidswap(json!({"x_in": "1000", "x_out": "0", "y_in": "0", "y_out": "90"}));
+        let swap = NormalizedSwap::from_event("Liquidswap", &x_in).unwrap();
+        assert_eq!(
+            (swap.token_in.as_str(), swap.token_out.as_str()),
+            (APT, USDC)
+        );
+        assert_eq!((swap.amount_in, swap.amount_out), (1000, 90));
+        let y_in = liquidswap(json!({"x_in": "0", "x_out": "400", "y_in": "50", "y_out": "0"}));
+        let swap = NormalizedSwap::from_event("Liquidswap", &y_in).unwrap();
+        assert_eq!(
+            (swap.token_in.as_str(), swap.token_out.as_str()),
+            (USDC, APT)
+        );
+        assert_eq!((swap.amount_in, swap.amount_out), (50, 400));
+        // no per-side amounts, the direction is unknown
+        let untyped = liquidswap(json!({"amount_in": "1", "amount_out": "1"}));
+        assert!(NormalizedSwap::from_event("Liquidswap", &untyped).is_none());
+
+        let weighted = event(
+            &format!(
+                "0x48::weighted_pool::SwapEvent<{}, {}, 0x48::base_pool::Null, 0x48::base_pool::Null, \
+                 0x48::weighted_pool::Weight_50, 0x48::weighted_pool::Weight_50, \
+                 0x48::base_pool::Null, 0x48::base_pool::Null>",
+                APT, USDC
+            ),
+            json!({"idx_in": "1", "idx_out": "0", "amount_in": "50", "amount_out": "400"}),
+        );
+        let swap = NormalizedSwap::from_event("Thala", &weighted).unwrap();
+        assert_eq!(
+            (swap.token_in.as_str(), swap.token_out.as_str()),
+            (USDC, APT)
+        );
+        assert_eq!((swap.amount_in, swap.amount_out), (50, 400));
+        let v2 = event(
+            "0x7730::pool::SwapEvent",
+            json!({
+                "metadata": [{"inner": "0xa"}, {"inner": "0xb"}],
+                "idx_in": 0,
+                "idx_out": 1,
+                "amount_in": "7",
+                "amount_out": "6"
+            }),
+        );
+        let swap = NormalizedSwap::from_event("Thala", &v2).unwrap();
+        assert_eq!(
+            (swap.token_in.as_str(), swap.token_out.as_str()),
+            ("0xa", "0xb")
+        );
+        let pair = event(
+            &format!("0x48::amm::SwapEvent<{}, {}>", APT, USDC),
+            json!({"amount_x_in": "0", "amount_y_in": "50", "amount_x_out": "400", "amount_y_out": "0"}),
+        );
+        let swap = NormalizedSwap::from_event("Thala", &pair).unwrap();
+        assert_eq!((swap.token_in.as_str(), swap.amount_in), (USDC, 50));
+    }
+
+    #[test]
+    fn test_parse_aux_events() {
+        let swap = event(
+            &format!("{}::amm::SwapEvent", AUXSWAP_PROTOCOL_ADDRESS),
+            json!({
+                "sender": "0xb0b",
+                "timestamp": "1700000000123456",
+                "in_coin_type": "0x1::aptos_coin::AptosCoin",
+                "out_coin_type": "0x5::usdc::USDC",
+                "in_au": "1000",
+                "out_au": "990",
+                "fee_bps": "30"
+            }),
+        );
+        let normalized = NormalizedSwap::from_event("AuxExchange", &swap).unwrap();
+        assert_eq!(normalized.sender, "0xb0b");
+        assert_eq!((normalized.amount_in, normalized.amount_out), (1000, 990));
+        assert_eq!(normalized.ts, 1700000000);
+        let mint = event(
+            "0xbd::amm::AddLiquidityEvent",
+            json!({
+                "x_coin_type": "0x1::a::A",
+                "y_coin_type": "0x2::b::B",
+                "x_added_au": "100",
+                "y_added_au": "200",
+                "lp_minted_au": "141"
+            }),
+        );
+        let mint = AuxEventParser::parse_mint_event(&mint).unwrap();
+        assert_eq!(
+            (mint.amount_a, mint.amount_b, mint.liquidity),
+            (100, 200, 141)
+        );
+        assert_eq!(mint.token_a, "0x1::a::A");
+    }
+
+    #[test]
+    fn test_parse_cellana_events() {
+        let swap = event(
+            &format!(
+                "{}::liquidity_pool::SwapEvent",
+                CELLANASWAP_PROTOCOL_ADDRESS
+            ),
+            json!({
+                "pool": "0xpool",
+                "from_token": "0x1::aptos_coin::AptosCoin",
+                "to_token": "0x5::usdc::USDC",
+                "amount_in": "5000",
+                "amount_out": 4900
+            }),
+        );
+        let normalized = NormalizedSwap::from_event("Cellana", &swap).unwrap();
+        assert_eq!(normalized.token_in, "0x1::aptos_coin::AptosCoin");
+        assert_eq!((normalized.amount_in, normalized.amount_out), (5000, 4900));
+        let burn = event(
+            "0x4b::liquidity_pool::RemoveLiquidityEvent",
+            json!({"lp": "0xc0", "pool": "0xpool", "amount_lp": "9", "amount_1": "4", "amount_2": "8"}),
+        );
+        let burn = CellanaEventParser::parse_burn_event(&burn).unwrap();
+        assert_eq!(burn.provider, "0xc0");
+        assert_eq!((burn.amount_a, burn.amount_b, burn.liquidity), (4, 8, 9));
+        assert!(NormalizedSwap::from_event("Unknown", &swap).is_none());
+    }
+
+    #[test]
+    fn test_swap_quote_ties_are_deterministic() {
+        let quote = |dex: &str, amount_out: u64, price_impact: Option<f64>| DexSwapQuote {
+            dex: dex.to_string(),
+            amount_out,
+            price: amount_out as f64 / 1_000.0,
+            dex_address: String::new(),
+            price_impact,
+        };
+        let ranked = |mut quotes: Vec<DexSwapQuote>| {
+            quotes.sort_by(DexSwapQuote::cmp_best);
+            quotes.into_iter().map(|q| q.dex).collect::<Vec<_>>()
+        };
+        // equal output, the lower price impact wins whatever the arrival order
+        let tied = vec![
+            quote("Thala", 990, None),
+            quote("PancakeSwap", 990, Some(0.5)),
+            quote("AnimeSwap", 990, Some(0.2)),
+        ];
+        assert_eq!(ranked(tied.clone()), ["AnimeSwap", "PancakeSwap", "Thala"]);
+        assert_eq!(
+            ranked(tied.into_iter().rev().collect()),
+            ["AnimeSwap", "PancakeSwap", "Thala"]
+        );
+        // a higher output still beats a lower price impact
+        assert_eq!(
+            ranked(vec![
+                quote("AnimeSwap", 990, Some(0.2)),
+                quote("Cellana", 991, None)
+            ])[0],
+            "Cellana"
+        );
+        // then dex name
+        assert_eq!(
+            ranked(vec![
+                quote("Thala", 990, None),
+                quote("Cellana", 990, None),
+                quote("AuxExchange", 990, None),
+            ]),
+            ["AuxExchange", "Cellana", "Thala"]
+        );
+    }
+
+    #[test]
+    fn test_best_arbitrage_includes_price_impact_and_gas() {
+        let pool = |reserve_a, reserve_b| PoolInfo {
+            reserve_a,
+            reserve_b,
+            fee_bps: 30,
+            total_lp_supply: None,
+        };
+        // token_b is 10% cheaper on B
+        let pools = vec![
+            ("A".to_string(), pool(1_000_000_000, 1_000_000_000)),
+            ("B".to_string(), pool(1_000_000_000, 1_100_000_000)),
+        ];
+        let arb = DexAggregator::best_arbitrage(&pools, APT, USDC, 10_000_000, 0).unwrap();
+        assert_eq!((arb.buy_dex.as_str(), arb.sell_dex.as_str()), ("B", "A"));
+        assert_eq!(arb.amount_in, 10_000_000);
+        assert_eq!(arb.est_profit, arb.gross_out - 10_000_000);
+        // price impact eats the gap on a large trade
+        assert!(DexAggregator::best_arbitrage(&pools, APT, USDC, 500_000_000, 0).is_none());
+        let with_gas =
+            DexAggregator::best_arbitrage(&pools, APT, USDC, 10_000_000, 400_000).unwrap();
+        assert_eq!(with_gas.est_profit, arb.est_profit - 400_000);
+        let same = vec![
+            ("A".to_string(), pool(1_000_000, 1_000_000)),
+            ("B".to_string(), pool(1_000_000, 1_000_000)),
+        ];
+        assert!(DexAggregator::best_arbitrage(&same, APT, USDC, 1_000, 0).is_none());
+    }
+
+    #[test]
+    fn test_best_split_spreads_large_orders() {
+        let pool = |reserve_a, reserve_b| PoolInfo {
+            reserve_a,
+            reserve_b,
+            fee_bps: 30,
+            total_lp_supply: None,
+        };
+        let pools = vec![
+            ("A".to_string(), pool(1_000_000_000, 1_000_000_000)),
+            ("B".to_string(), pool(1_000_000_000, 1_000_000_000)),
+            ("C".to_string(), pool(100_000_000, 100_000_000)),
+        ];
+        let amount = 200_000_000;
+        let split = DexAggregator::best_split(&pools, amount, 3).unwrap();
+        let single =
+            DexAggregator::calculate_amm_output_with_fee(amount, 1_000_000_000, 1_000_000_000, 30);
+        assert!(split.legs.len() > 1);
+        assert!(split.amount_out > single);
+        assert_eq!(split.legs.iter().map(|(_, a, _)| a).sum::<u64>(), amount);
+        assert_eq!(
+            split.legs.iter().map(|(_, _, out)| out).sum::<u64>(),
+            split.amount_out
+        );
+        // the two deep pools share the order
+        let two = DexAggregator::best_split(&pools, amount, 2).unwrap();
+        let dexes: Vec<&str> = two.legs.iter().map(|(dex, _, _)| dex.as_str()).collect();
+        assert_eq!(dexes, vec!["A", "B"]);
+        let one = DexAggregator::best_split(&pools, amount, 1).unwrap();
+        assert_eq!(one.legs, vec![("A".to_string(), amount, single)]);
+        assert!(DexAggregator::best_split(&[], amount, 2).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_gas_price_ema_smooths_spike() {
+        let estimate = |price: u64| format!(r#"{{"gas_estimate":{}}}"#, price);
+        let transport = MockTransport::new()
+            .on_get("/estimate_gas_price", 200, &estimate(100))
+            .on_get("/estimate_gas_price", 500, "busy")
+            .on_get("/estimate_gas_price", 200, &estimate(400))
+            .on_get("/estimate_gas_price", 200, &estimate(100));
+        let client = Arc::new(Aptos::with_transport(
+            crate::AptosType::Mainnet,
+            Arc::new(transport),
+        ));
+        let ema = DexAnalytics::gas_price_ema(Arc::clone(&client), 4, 0)
+            .await
+            .unwrap();
+        assert_eq!(ema.samples, vec![100, 400, 100]);
+        assert_eq!((ema.min, ema.max), (100, 400));
+        // alpha = 0.5: 100 -> 250 -> 175
+        assert_eq!(ema.ema, 175.0);
+        assert_eq!(ema.gas_unit_price(), 175);
+        assert!(DexAnalytics::gas_price_ema(client, 0, 0).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_reserves_in_caller_order() {
+        let reserve = |resource_type: &str, fields: Value| {
+            json!({"type": resource_type, "data": fields}).to_string()
+        };
+        // AnimeSwap pool stored in canonical order, PancakeSwap pool stored reversed
+        let anime_type = format!(
+            "{}::swap::TokenPairReserve<{}, {}>",
+            ANIMESWAP_PROTOCOL_ADDRESS, APT, USDC
+        );
+        let pancake_type = format!(
+            "{}::swap::TokenPairReserve<{}, {}>",
+            PANCAKESWAP_FACTORY_PROTOCOL_ADDRESS, USDC, APT
+        );
+        let transport = MockTransport::new()
+            .on_get(
+                &anime_type,
+                200,
+                &reserve(
+                    &anime_type,
+                    json!({"reserve_a": "1000", "reserve_b": "8000"}),
+                ),
+            )
+            .on_get(
+                &pancake_type,
+                200,
+                &reserve(
+                    &pancake_type,
+                    json!({"reserve0": "8000", "reserve1": "1000"}),
+                ),
+            );
+        let client = Arc::new(Aptos::with_transport(
+            crate::AptosType::Mainnet,
+            Arc::new(transport),
+        ));
+        for (apt_usdc, usdc_apt) in [
+            (
+                AnimeSwap::get_reserves(Arc::clone(&client), APT, USDC).await,
+                AnimeSwap::get_reserves(Arc::clone(&client), USDC, APT).await,
+            ),
+            (
+                PancakeSwap::get_reserves(Arc::clone(&client), APT, USDC).await,
+                PancakeSwap::get_reserves(Arc::clone(&client), USDC, APT).await,
+            ),
+        ] {
+            let (apt, usdc) = apt_usdc.unwrap();
+            assert_eq!((apt, usdc), (1000, 8000));
+            let (usdc_rev, apt_rev) = usdc_apt.unwrap();
+            assert_eq!(usdc as f64 / apt as f64, usdc_rev as f64 / apt_rev as f64);
+        }
+        assert_eq!(
+            AnimeSwap::get_reserves(client, APT, USDT).await.unwrap(),
+            (0, 0)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_token_price_detailed_reports_failures() {
+        let anime_type = format!(
+            "{}::swap::TokenPairReserve<{}, {}>",
+            ANIMESWAP_PROTOCOL_ADDRESS, APT, USDC
+        );
+        let reserve = json!({"type": anime_type, "data": {"reserve_a": "1000000000", "reserve_b": "8000000000"}});
+        let transport = MockTransport::new()
+            .on_get("/accounts/", 503, "unavailable")
+            .on_post("/view", 503, "unavailable")
+            .on_get(&anime_type, 200, &reserve.to_string());
+        let client = Arc::new(Aptos::with_transport(
+            crate::AptosType::Mainnet,
+            Arc::new(transport),
+        ));
+        let report = DexAggregator::get_token_price_detailed(client, USDC).await;
+        let priced: Vec<&str> = report.prices.iter().map(|p| p.dex.as_str()).collect();
+        assert_eq!(priced, vec!["AnimeSwap"]);
+        assert!(report.prices[0].price > 0.0);
+        let failed: Vec<&str> = report
+            .failures
+            .iter()
+            .map(|(dex, _)| dex.as_str())
+            .collect();
+        assert_eq!(
+            failed,
+            vec!["Liquidswap", "Thala", "PancakeSwap", "Cellana"]
+        );
+        assert!(report.failures.iter().all(|(_, error)| !error.is_empty()));
+    }
+
+    #[test]
+    fn test_validate_slippage_bounds() {
+        assert!(DexUtils::validate_slippage(0.0).is_ok());
+        assert!(DexUtils::validate_slippage(0.005).is_ok());
+        assert!(DexUtils::validate_slippage(1.0).is_ok());
+        assert!(DexUtils::validate_slippage(-0.01).is_err());
+        assert!(DexUtils::validate_slippage(1.01).is_err());
+        assert!(DexUtils::validate_slippage(50.0).is_err());
+        assert!(DexUtils::validate_slippage(f64::NAN).is_err());
+        assert_eq!(DexUtils::min_amount_out(1_000, 0.0), 1_000);
+        assert_eq!(DexUtils::min_amount_out(1_000, 0.01), 990);
+        assert_eq!(DexUtils::min_amount_out(1_000, 1.0), 0);
+    }
+
+    #[test]
+    fn test_calculate_amount_in_inverts_amm_output() {
+        let pools = [
+            (1_000_000u64, 2_000_000u64),
+            (5_000_000_000, 3_000),
+            (1_000_000_000_000_000, 500_000_000_000_000),
+        ];
+        for (reserve_in, reserve_out) in pools {
+            for amount_out in [1, reserve_out / 1000, reserve_out / 3, reserve_out - 1] {
+                if amount_out == 0 {
+                    continue;
+                }
+                let amount_in =
+                    DexUtils::calculate_amount_in(amount_out, reserve_in, reserve_out, 30);
+                let Some(amount_in) = amount_in else {
+                    // more input than fits in a u64
+                    assert_eq!(amount_out, reserve_out - 1);
+                    continue;
+                };
+                assert!(
+                    DexAggregator::calculate_amm_output_with_fee(
+                        amount_in,
+                        reserve_in,
+                        reserve_out,
+                        30
+                    ) >= amount_out
+                );
+            }
+        }
+        // 30 bps matches the 0.3% fee of `calculate_amm_output`
+        let amount_in = DexUtils::calculate_amount_in(1_000, 1_000_000, 1_000_000, 30).unwrap();
+        assert!(DexAggregator::calculate_amm_output(amount_in, 1_000_000, 1_000_000) >= 1_000);
+        assert_eq!(
+            DexUtils::calculate_amount_in(100, 1_000, 1_000, 0),
+            Some(112)
+        );
+        assert_eq!(DexUtils::calculate_amount_in(1_000, 1_000, 1_000, 30), None);
+        assert_eq!(DexUtils::calculate_amount_in(2_000, 1_000, 1_000, 30), None);
+        assert_eq!(DexUtils::calculate_amount_in(10, 0, 1_000, 30), None);
+        assert_eq!(
+            DexUtils::calculate_amount_in(10, 1_000, 1_000, 10_000),
+            None
+        );
+    }
+
+    #[test]
+    fn test_min_liquidity_amounts() {
+        assert_eq!(
+            DexUtils::min_liquidity_amounts(1_000, 2_000, 0.0).unwrap(),
+            (1_000, 2_000)
+        );
+        assert_eq!(
+            DexUtils::min_liquidity_amounts(1_000, 2_000, 0.01).unwrap(),
+            (990, 1_980)
+        );
+        assert_eq!(
+            DexUtils::min_liquidity_amounts(1_000, 2_000, 1.0).unwrap(),
+            (0, 0)
+        );
+        assert!(DexUtils::min_liquidity_amounts(1_000, 2_000, 1.5).is_err());
+        assert!(DexUtils::min_liquidity_amounts(1_000, 2_000, -0.1).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_add_liquidity_rejects_slippage_before_submitting() {
+        let transport = Arc::new(MockTransport::new());
+        let client = Arc::new(crate::Aptos::with_transport(
+            crate::AptosType::Mainnet,
+            Arc::clone(&transport) as _,
+        ));
+        let wallet = Arc::new(Wallet::new().unwrap());
+        let results = [
+            Liquidswap::add_liquidity(
+                Arc::clone(&client),
+                Arc::clone(&wallet),
+                APT,
+                USDC,
+                1_000,
+                1_000,
+                1.5,
+            )
+            .await,
+            Thala::add_liquidity(
+                Arc::clone(&client),
+                Arc::clone(&wallet),
+                APT,
+                USDC,
+                1_000,
+                1_000,
+                1.5,
+            )
+            .await,
+            AnimeSwap::add_liquidity(
+                Arc::clone(&client),
+                Arc::clone(&wallet),
+                APT,
+                USDC,
+                1_000,
+                1_000,
+                -0.5,
+            )
+            .await,
+            Cellana::add_liquidity(client, wallet, APT, USDC, 1_000, 1_000, f64::NAN).await,
+        ];
+        for result in results {
+            assert!(result.unwrap_err().contains("invalid slippage"));
+        }
+        assert!(transport.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_exe_best_swap_rejects_slippage_before_quoting() {
+        let transport = Arc::new(MockTransport::new());
+        let client = Arc::new(Aptos::with_transport(
+            crate::AptosType::Mainnet,
+            transport.clone(),
+        ));
+        let wallet = Arc::new(Wallet::new().unwrap());
+        let result = DexAggregator::exe_best_swap(client, wallet, APT, USDC, 1_000, 50.0).await;
+        assert!(result.unwrap_err().contains("invalid slippage"));
+        assert!(transport.requests().is_empty());
+    }
+
+    #[test]
+    fn test_event_type_args_nested() {
+        assert_eq!(
+            event_type_args("0x1::m::E<0x1::a::A, 0x2::lp::LP<0x1::a::A, 0x3::b::B>>"),
+            vec!["0x1::a::A", "0x2::lp::LP<0x1::a::A, 0x3::b::B>"]
+        );
+        assert!(event_type_args("0x1::m::E").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_token_metadata_supply_above_u64() {
+        let transport = MockTransport::new().on_get(
+            "/resource/0x1::coin::CoinInfo",
+            200,
+            r#"{
+                "type": "0x1::coin::CoinInfo<0xbeef::eth::ETH>",
+                "data": {
+                    "name": "Ether",
+                    "symbol": "ETH",
+                    "decimals": 18,
+                    "supply": {"vec": [{"value": "120000000000000000000000000"}]}
+                }
+            }"#,
+        );
+        let client = Arc::new(Aptos::with_transport(
+            crate::AptosType::Mainnet,
+            Arc::new(transport),
+        ));
+        let metadata = DexAggregator::get_token_metadata(client, "0xbeef::eth::ETH")
+            .await
+            .unwrap();
+        assert!(metadata.supply > u64::MAX as u128);
+        assert_eq!(metadata.supply, 120_000_000_000_000_000_000_000_000);
+    }
+
+    #[test]
+    fn test_pool_info_lp_supply_above_u64() {
+        let pool = PoolInfo::from_resource(
+            &json!({"reserve_x": "10", "reserve_y": 20, "lp_supply": {"value": "36893488147419103232"}}),
+            "reserve_x",
+            "reserve_y",
+            None,
+            30,
+        )
+        .unwrap();
+        assert_eq!(pool.reserve_b, 20);
+        assert_eq!(pool.total_lp_supply, Some(1u128 << 65));
+    }
+
+    /// transport answering the account, chain, gas and router abi reads done before a
+    /// write
+    fn write_transport() -> Arc<MockTransport> {
+        let router = json!({
+            "bytecode": "0x",
+            "abi": {
+                "name": "router",
+                "exposed_functions": [
+                    {"name": "swap_exact_input", "params": ["&signer", "u64", "u64"]},
+                    {
+                        "name": "swap_exact_tokens_for_tokens",
+                        "params": ["&signer", "u64", "u64", "vector<0x1::string::String>", "address", "u64"]
+                    }
+                ]
+            }
+        });
+        Arc::new(
+            MockTransport::new()
+                .on_get(
+                    "/accounts/",
+                    200,
+                    r#"{"sequence_number":"3","authentication_key":"0x01"}"#,
+                )
+                .on_get("/module/router", 200, &router.to_string())
+                .on_get(
+                    "/v1/",
+                    200,
+                    r#"{"chain_id":1,"epoch":"1","ledger_version":"1","ledger_timestamp":"1","node_role":"full_node","block_height":"1"}"#,
+                )
+                .on_get(
+                    "/estimate_gas_price",
+                    200,
+                    r#"{"deprioritized_gas_estimate":100,"gas_estimate":100,"prioritized_gas_estimate":150}"#,
+                )
+                .on_post(
+                    "/transactions/simulate",
+                    200,
+                    r#"[{"version":"0","hash":"0x0","gas_used":"90","success":true,"vm_status":"Executed successfully","type":"user_transaction","sender":"0x1","sequence_number":"3","max_gas_amount":"180","gas_unit_price":"100","expiration_timestamp_secs":"1","payload":{"type":"entry_function_payload","function":"0x1::m::f","type_arguments":[],"arguments":[]},"signature":{"type":"ed25519_signature","public_key":"0x01","signature":"0x02"}}]"#,
+                ),
+        )
+    }
+
+    fn submitted_expiration(transport: &MockTransport) -> u64 {
+        let request = transport
+            .requests()
+            .into_iter()
+            .find(|r| r.method == "POST" && r.url.ends_with("/transactions"))
+            .unwrap();
+        request.body.unwrap()["transaction"]["expiration_timestamp_secs"]
+            .as_str()
+            .unwrap()
+            .parse()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_swap_deadline_sets_transaction_expiration() {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let transport = write_transport();
+        let client = Arc::new(Aptos::with_transport(
+            crate::AptosType::Mainnet,
+            transport.clone(),
+        ));
+        let wallet = Arc::new(Wallet::new().unwrap());
+        Liquidswap::swap_exact_input(
+            Arc::clone(&client),
+            Arc::clone(&wallet),
+            APT,
+            USDC,
+            1_000,
+            990,
+            120,
+        )
+        .await
+        .unwrap();
+        let expiration = submitted_expiration(&transport);
+        assert!((now + 120..=now + 125).contains(&expiration));
+
+        let transport = write_transport();
+        let client = Arc::new(Aptos::with_transport(
+            crate::AptosType::Mainnet,
+            transport.clone(),
+        ));
+        let address = wallet.address().to_string();
+        PancakeSwap::swap_exact_tokens_for_tokens(
+            Arc::clone(&client),
+            Arc::clone(&wallet),
+            1_000,
+            990,
+            vec![APT, USDC],
+            &address,
+            now + 60,
+        )
+        .await
+        .unwrap();
+        let expiration = submitted_expiration(&transport);
+        assert!((now + 60..=now + 65).contains(&expiration));
+
+        // a passed deadline is refused before anything is sent
+        let requests = transport.requests().len();
+        let err = PancakeSwap::swap_exact_tokens_for_tokens(
+            client,
+            wallet,
+            1_000,
+            990,
+            vec![APT, USDC],
+            &address,
+            now - 1,
+        )
+        .await
+        .unwrap_err();
+        assert!(err.contains("already passed"));
+        assert_eq!(transport.requests().len(), requests);
+    }
+
+    #[test]
+    fn test_parse_token_amount() {
+        assert_eq!(DexUtils::parse_token_amount("1.5", 8), Ok(150_000_000));
+        assert_eq!(DexUtils::parse_token_amount("0", 8), Ok(0));
+        assert_eq!(DexUtils::parse_token_amount("0.00000001", 8), Ok(1));
+        assert_eq!(DexUtils::parse_token_amount("42", 6), Ok(42_000_000));
+        assert_eq!(DexUtils::parse_token_amount(" .5 ", 2), Ok(50));
+        assert_eq!(DexUtils::parse_token_amount("7", 0), Ok(7));
+        assert_eq!(
+            DexUtils::parse_token_amount("18446744073709551615", 0),
+            Ok(u64::MAX)
+        );
+        assert!(DexUtils::parse_token_amount("0.000000001", 8).is_err());
+        assert!(DexUtils::parse_token_amount("184467440737.09551616", 8).is_err());
+        assert!(DexUtils::parse_token_amount("1e8", 8).is_err());
+        assert!(DexUtils::parse_token_amount("-1", 8).is_err());
+        assert!(DexUtils::parse_token_amount("1.2.3", 8).is_err());
+        assert!(DexUtils::parse_token_amount(".", 8).is_err());
+        assert!(DexUtils::parse_token_amount("", 8).is_err());
+    }
+
+    #[test]
+    fn test_format_token_amount_precision() {
+        assert_eq!(
+            DexUtils::format_token_amount_precision(150_000_000, 8, 4),
+            "1.5"
+        );
+        assert_eq!(
+            DexUtils::format_token_amount_precision(123_456_789, 8, 4),
+            "1.2345"
+        );
+        assert_eq!(DexUtils::format_token_amount_precision(1, 8, 4), "0");
+        assert_eq!(
+            DexUtils::format_token_amount_precision(1, 8, 8),
+            "0.00000001"
+        );
+        assert_eq!(DexUtils::format_token_amount_precision(5, 0, 4), "5");
+        assert_eq!(
+            DexUtils::format_token_amount_precision(u64::MAX, 18, 2),
+            "18.44"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dex_event_monitor_stop_and_resubscribe() {
+        let transport = Arc::new(MockTransport::new());
+        let client = Arc::new(Aptos::with_transport(
+            crate::AptosType::Mainnet,
+            Arc::clone(&transport) as _,
+        ));
+        let mut monitor = DexEventMonitor::new();
+        monitor
+            .start_monitoring_all_dexes(client, Some(std::time::Duration::from_millis(5)))
+            .await
+            .unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        // no subscribers, every dex is paused
+        assert!(transport.requests().is_empty());
+        assert!(MONITORED_DEXES.iter().all(|dex| monitor.is_monitoring(dex)));
+
+        assert!(monitor.stop_dex("Cellana"));
+        assert!(!monitor.stop_dex("Cellana"));
+        assert!(!monitor.is_monitoring("Cellana"));
+        let _receiver = monitor.subscribe_to_dex("Cellana").unwrap();
+        assert!(monitor.is_monitoring("Cellana"));
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        let polled = transport.requests().len();
+        assert!(polled > 0);
+        // repolled on the configured interval
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        assert!(transport.requests().len() > polled);
+
+        monitor.stop_all();
+        assert!(
+            MONITORED_DEXES
+                .iter()
+                .all(|dex| !monitor.is_monitoring(dex))
+        );
+
+        // dropping the monitor aborts its polling
+        let _receiver = monitor.subscribe_to_dex("Cellana").unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        drop(monitor);
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        let polled = transport.requests().len();
+        tokio::time::sleep(std::time::Duration::from_millis(30)).await;
+        assert_eq!(transport.requests().len(), polled);
+    }
+
+    #[tokio::test]
+    async fn test_dex_event_monitor_restart_replaces_polling() {
+        let client = |transport: &Arc<MockTransport>| {
+            Arc::new(Aptos::with_transport(
+                crate::AptosType::Mainnet,
+                Arc::clone(transport) as _,
+            ))
+        };
+        let (first, second) = (
+            Arc::new(MockTransport::new()),
+            Arc::new(MockTransport::new()),
+        );
+        let interval = Some(std::time::Duration::from_millis(5));
+        let mut monitor = DexEventMonitor::new();
+        monitor
+            .start_monitoring_all_dexes(client(&first), interval)
+            .await
+            .unwrap();
+        let receiver = monitor.subscribe_to_dex("Cellana").unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        assert!(!first.requests().is_empty());
+
+        monitor
+            .start_monitoring_all_dexes(client(&second), interval)
+            .await
+            .unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        // the first polling was aborted, the subscription moved to the new one
+        let polled = first.requests().len();
+        tokio::time::sleep(std::time::Duration::from_millis(30)).await;
+        assert_eq!(first.requests().len(), polled);
+        assert!(!second.requests().is_empty());
+        // the sender of the first start is kept
+        assert_eq!(monitor.clients["Cellana"].receiver_count(), 1);
+        drop(receiver);
+    }
+
+    #[test]
+    fn test_zap_swap_amount_follows_reserve_ratio() {
+        // balanced pool priced like the swap: an even split
+        assert_eq!(
+            DexAggregator::zap_swap_amount(1_000, 1_000_000, 2_000_000, 2.0, false),
+            500
+        );
+        // the pool holds relatively more of the output, so more is swapped
+        let swapped = DexAggregator::zap_swap_amount(1_000, 1_000_000, 6_000_000, 2.0, false);
+        assert_eq!(swapped, 750);
+        // swapping through the pool itself: a bit over half, to cover fee and impact
+        let swapped =
+            DexAggregator::zap_swap_amount(1_000_000, 1_000_000_000, 1_000_000_000, 1.0, true);
+        assert!((500_000..502_000).contains(&swapped));
+    }
+
+    #[tokio::test]
+    async fn test_validate_token_pair_checks_pool_existence() {
+        let thala = THALA_PROTOCOL_ADDRESS;
+        let pool_type = format!("{}::amm::Pool<{}, {}>", thala, APT, USDC);
+        let transport = Arc::new(MockTransport::new().on_get(
+            &format!("/accounts/{}/resource/{}", thala, pool_type),
+            200,
+            &json!({"type": pool_type, "data": {"reserve_x": "1", "reserve_y": "1"}}).to_string(),
+        ));
+        let client = Arc::new(Aptos::with_transport(
+            crate::AptosType::Mainnet,
+            Arc::clone(&transport) as _,
+        ));
+        let dexes = DexUtils::validate_token_pair(client, APT, USDC)
+            .await
+            .unwrap();
+        assert!(dexes.contains(&"Thala".to_string()));
+        // missing pools are not reported as supported
+        assert!(!dexes.contains(&"Liquidswap".to_string()));
+        assert!(!dexes.contains(&"Cellana".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_zap_in_rejects_token_outside_pool() {
+        let transport = Arc::new(MockTransport::new());
+        let client = Arc::new(Aptos::with_transport(
+            crate::AptosType::Mainnet,
+            Arc::clone(&transport) as _,
+        ));
+        let wallet = Arc::new(Wallet::new().unwrap());
+        let result = DexAggregator::zap_in(client, wallet, USDT, (APT, USDC), 1_000, 0.01).await;
+        assert!(result.unwrap_err().contains("is not a token"));
+        assert!(transport.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_token_price_usd() {
+        let transport = crate::oracle::tests::mock_pyth(MockTransport::new());
+        let client = Arc::new(crate::Aptos::with_transport(
+            crate::AptosType::Mainnet,
+            Arc::new(transport),
+        ));
+        let apt = DexAggregator::get_token_price_usd(Arc::clone(&client), APT)
+            .await
+            .unwrap();
+        assert_eq!((apt.price_apt, apt.dex), (1.0, None));
+        assert!((apt.price_usd - 8.5).abs() < 1e-9);
+        assert!((apt.confidence - 0.01).abs() < 1e-9);
+        // no pool quotes the token
+        let err = DexAggregator::get_token_price_usd(client, USDT)
+            .await
+            .unwrap_err();
+        assert!(err.contains("no DEX price"));
+    }
+
+    #[tokio::test]
+    async fn test_reserve_cache_serves_quotes_within_ttl() {
+        let pool_type = format!("{}::amm::Pool<{}, {}>", THALA_PROTOCOL_ADDRESS, APT, USDC);
+        let transport = Arc::new(MockTransport::new().on_get(
+            &pool_type,
+            200,
+            &json!({"type": pool_type, "data": {"reserve_x": "100000000000", "reserve_y": "8000000000"}})
+                .to_string(),
+        ));
+        let client = Arc::new(
+            Aptos::with_transport(crate::AptosType::Mainnet, Arc::clone(&transport) as _)
+                .with_reserve_cache(Duration::from_secs(60)),
+        );
+        let quote = |amount_in| {
+            DexAggregator::get_dex_quote(Arc::clone(&client), "Thala", APT, USDC, amount_in)
+        };
+        let first = quote(100_000_000).await.unwrap();
+        assert_eq!(first.amount_out, 7_968_055);
+        assert_eq!(transport.requests().len(), 1);
+        // another amount on the same pool is quoted from the cached reserves
+        let second = quote(200_000_000).await.unwrap();
+        assert!(second.amount_out > first.amount_out);
+        assert_eq!(transport.requests().len(), 1);
+
+        // any event of the dex drops its pools
+        let cache = Arc::clone(client.reserve_cache().unwrap());
+        let (sender, receiver) = broadcast::channel(8);
+        let invalidation = Arc::clone(&cache).invalidate_on_events("Thala", receiver);
+        sender
+            .send(event("0x1::amm::AddLiquidityEvent", json!({})))
+            .unwrap();
+        drop(sender);
+        invalidation.await.unwrap();
+        assert_eq!(cache.get("Thala", APT, USDC), None);
+        quote(100_000_000).await.unwrap();
+        assert_eq!(transport.requests().len(), 2);
+
+        cache.invalidate("Thala", USDC, APT);
+        assert_eq!(cache.get("Thala", APT, USDC), None);
+        let expired = ReserveCache::new(Duration::ZERO);
+        let generation = expired.generation("Thala", APT, USDC);
+        expired.insert(
+            "Thala",
+            APT,
+            USDC,
+            PoolInfo::from_reserves((1, 1)),
+            generation,
+        );
+        assert_eq!(expired.get("Thala", APT, USDC), None);
+    }
+
+    #[tokio::test]
+    async fn test_quotes_use_the_pool_fee() {
+        let pool_type = format!("{}::amm::Pool<{}, {}>", THALA_PROTOCOL_ADDRESS, APT, USDC);
+        let transport = Arc::new(
+            MockTransport::new().on_get(
+                &pool_type,
+                200,
+                &json!({"type": pool_type, "data": {
+                    "reserve_x": "100000000000",
+                    "reserve_y": "8000000000",
+                    "swap_fee_bps": "100"
+                }})
+                .to_string(),
+            ),
+        );
+        let client = Arc::new(Aptos::with_transport(
+            crate::AptosType::Mainnet,
+            Arc::clone(&transport) as _,
+        ));
+        let quote = DexAggregator::get_dex_quote(client, "Thala", APT, USDC, 100_000_000)
+            .await
+            .unwrap();
+        assert_eq!(
+            quote.amount_out,
+            DexAggregator::calculate_amm_output_with_fee(
+                100_000_000,
+                100_000_000_000,
+                8_000_000_000,
+                100
+            )
+        );
+        // below the 30 bps default
+        assert!(quote.amount_out < 7_968_055);
+    }
+
+    #[test]
+    fn test_reserve_cache_drops_late_writes_and_keeps_the_ema() {
+        let cache = ReserveCache::new(Duration::from_secs(60));
+        let pool = |reserve_a, reserve_b| PoolInfo::from_reserves((reserve_a, reserve_b));
+        cache.insert(
+            "Thala",
+            APT,
+            USDC,
+            pool(100, 1_000),
+            cache.generation("Thala", APT, USDC),
+        );
+        assert_eq!(
+            cache.ema_reserves("Thala", APT, USDC),
+            Some((100.0, 1_000.0))
+        );
+
+        // a fetch that started before a swap invalidated the pool must not be cached
+        let generation = cache.generation("Thala", APT, USDC);
+        let other_generation = cache.generation("Thala", APT, USDT);
+        cache.invalidate("Thala", USDC, APT);
+        cache.insert("Thala", APT, USDC, pool(200, 500), generation);
+        assert_eq!(cache.get("Thala", APT, USDC), None);
+        // other pools keep their entries and in-flight fetches
+        cache.insert("Thala", APT, USDT, pool(10, 20), other_generation);
+        assert_eq!(cache.get("Thala", APT, USDT), Some(pool(10, 20)));
+        let late = cache.generation("Thala", APT, USDT);
+        cache.invalidate_dex("Cellana");
+        cache.insert("Thala", APT, USDT, pool(11, 20), late);
+        assert_eq!(cache.get("Thala", APT, USDT), Some(pool(11, 20)));
+        cache.invalidate_dex("Thala");
+        cache.insert("Thala", APT, USDT, pool(12, 20), late);
+        assert_eq!(cache.get("Thala", APT, USDT), None);
+        assert_eq!(
+            cache.ema_reserves("Thala", APT, USDC),
+            Some((100.0, 1_000.0))
+        );
+
+        // the average survives the invalidation and moves by RESERVE_EMA_ALPHA
+        cache.insert(
+            "Thala",
+            APT,
+            USDC,
+            pool(200, 500),
+            cache.generation("Thala", APT, USDC),
+        );
+        assert_eq!(cache.get("Thala", APT, USDC), Some(pool(200, 500)));
+        let (ema_in, ema_out) = cache.ema_reserves("Thala", APT, USDC).unwrap();
+        assert!((ema_in - 120.0).abs() < 1e-9);
+        assert!((ema_out - 900.0).abs() < 1e-9);
+
+        cache.clear();
+        assert_eq!(cache.ema_reserves("Thala", APT, USDC), None);
+    }
+
+    #[tokio::test]
+    async fn test_get_apt_price_usd_is_cached() {
+        // 1000 APT against 8000 USDC
+        let anime_type = format!(
+            "{}::swap::TokenPairReserve<{}, {}>",
+            ANIMESWAP_PROTOCOL_ADDRESS, APT, USDC
+        );
+        let reserve = json!({"type": anime_type, "data": {"reserve_a": "100000000000", "reserve_b": "8000000000"}});
+        let transport = Arc::new(
+            MockTransport::new()
+                .on_get("/accounts/", 503, "unavailable")
+                .on_post("/view", 503, "unavailable")
+                .on_get(&anime_type, 200, &reserve.to_string())
+                .on_get(
+                    "/accounts/0xa/resource/0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>",
+                    200,
+                    r#"{"type":"0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>","data":{"coin":{"value":"250000000"},"frozen":false}}"#,
+                ),
+        );
+        let client = Aptos::with_transport(crate::AptosType::Mainnet, Arc::clone(&transport) as _);
+        let price = client.get_apt_price_usd().await.unwrap();
+        assert!((price - 8.0).abs() < 0.05, "{}", price);
+        let requests = transport.requests().len();
+        assert_eq!(client.clone().get_apt_price_usd().await.unwrap(), price);
+        let value = client.get_account_usd_value("0xa").await.unwrap();
+        assert!((value - 2.5 * price).abs() < 1e-9);
+        // the cached price is shared by clones, only the balance is read
+        assert_eq!(transport.requests().len(), requests + 1);
+
+        let devnet = Aptos::with_transport(crate::AptosType::Devnet, transport as _);
+        let err = devnet.get_apt_price_usd().await.unwrap_err();
+        assert!(err.contains("no DEX quotes APT"));
+    }
+
+    #[tokio::test]
+    async fn test_dex_addresses_follow_network_config() {
+        let transport = Arc::new(MockTransport::new());
+        let testnet = Arc::new(Aptos::with_transport(
+            crate::AptosType::Testnet,
+            Arc::clone(&transport) as _,
+        ));
+        assert_eq!(*testnet.network_config(), NetworkConfig::testnet());
+        let err = Liquidswap::get_pool_info(Arc::clone(&testnet), APT, USDC)
+            .await
+            .unwrap_err();
+        assert_eq!(err, "Liquidswap is not deployed on this network");
+        assert!(transport.requests().is_empty());
+
+        let custom = Arc::new(
+            Aptos::with_transport(crate::AptosType::Devnet, Arc::clone(&transport) as _)
+                .with_network_config(NetworkConfig {
+                    liquidswap: Some("0xabc".to_string()),
+                    ..NetworkConfig::devnet()
+                }),
+        );
+        Liquidswap::get_pool_info(custom, APT, USDC).await.unwrap();
+        let request = transport.requests().pop().unwrap();
+        assert!(
+            request
+                .url
+                .contains("/accounts/0xabc/resource/0xabc::liquidity_pool::")
+        );
+        assert!(NetworkConfig::mainnet().dex_address("Unknown").is_err());
+
+        // pair addresses derive from the network's factory
+        let mainnet = Aptos::with_transport(crate::AptosType::Mainnet, Arc::clone(&transport) as _);
+        let pancake = mainnet.clone().with_network_config(NetworkConfig {
+            pancakeswap: Some("0xfac".to_string()),
+            ..NetworkConfig::devnet()
+        });
+        assert_ne!(
+            PancakeSwap::get_pair_address(&mainnet, APT, USDC).unwrap(),
+            PancakeSwap::get_pair_address(&pancake, APT, USDC).unwrap()
+        );
+        assert_eq!(
+            PancakeSwap::get_pair_address(&pancake, APT, USDC),
+            PancakeSwap::get_pair_address(&pancake, USDC, APT)
+        );
+        assert!(PancakeSwap::get_pair_address(&testnet, APT, USDC).is_err());
+    }
+}
@@ -1,25 +1,34 @@
 /// The implementation module of pancakeswap complete interactive logic.
 use crate::{
     Aptos,
-    event::EventData,
-    global::mainnet::{
-        protocol_address::PANCAKESWAP_FACTORY_PROTOCOL_ADDRESS,
-        token_address::{APT, CAKE},
+    dex::{
+        NormalizedLiquidity, NormalizedSwap, canonical_order, event_kind_matches, event_string,
+        event_timestamp_secs, event_type_args, event_u64, resolve_xy_swap,
     },
-    types::ContractCall,
+    event::{EventBlockResolver, EventData},
+    types::{CallKind, ContractCall, MoveArgument},
     wallet::Wallet,
 };
 use serde_json::{Value, json};
 use sha2::{Digest, Sha256};
-use std::sync::Arc;
-use tokio::sync::broadcast;
+use std::{sync::Arc, time::Duration};
+use tokio::{sync::broadcast, task::JoinHandle};
+
+/// PancakeSwap factory address on the client's network
+fn protocol_address(client: &Aptos) -> Result<String, String> {
+    client
+        .network_config()
+        .dex_address("PancakeSwap")
+        .map(str::to_string)
+}
 
 pub struct PancakeSwap;
 
 impl PancakeSwap {
     /// get swap events
     pub async fn get_swap_events(client: Arc<Aptos>) -> Result<Vec<EventData>, String> {
-        let event_type = format!("{}::swap::SwapEvent", PANCAKESWAP_FACTORY_PROTOCOL_ADDRESS);
+        let protocol = protocol_address(&client)?;
+        let event_type = format!("{}::swap::SwapEvent", protocol);
         Self::get_recent_events(client, &event_type).await
     }
 
@@ -27,33 +36,11 @@ impl PancakeSwap {
         client: Arc<Aptos>,
         event_type: &str,
     ) -> Result<Vec<EventData>, String> {
-        let mut all_events = Vec::new();
-        let mut start_seq: Option<u64> = None;
-
+        let protocol = protocol_address(&client)?;
         let events = client
-            .get_account_event_vec(
-                PANCAKESWAP_FACTORY_PROTOCOL_ADDRESS,
-                event_type,
-                Some(100),
-                start_seq,
-            )
-            .await
-            .map_err(|e| e.to_string())?;
-
-        for event in events {
-            if let Ok(sequence) = event.sequence_number.parse::<u64>() {
-                let event_data = EventData {
-                    event_type: event.r#type.clone(),
-                    event_data: event.data.clone(),
-                    sequence_number: sequence,
-                    transaction_hash: "".to_string(),
-                    block_height: 0,
-                };
-                all_events.push(event_data);
-            }
-        }
-
-        Ok(all_events)
+            .get_all_account_events(&protocol, event_type, None, None)
+            .await?;
+        Ok(EventBlockResolver::new(client).events_data(&events).await)
     }
 
     /// add liquidity
@@ -69,19 +56,21 @@ impl PancakeSwap {
         to: &str,
         deadline: u64,
     ) -> Result<Value, String> {
+        let protocol = protocol_address(&client)?;
         let contract_call = ContractCall {
-            module_address: PANCAKESWAP_FACTORY_PROTOCOL_ADDRESS.to_string(),
+            module_address: protocol,
             module_name: "router".to_string(),
             function_name: "add_liquidity".to_string(),
             type_arguments: vec![coin_a.to_string(), coin_b.to_string()],
             arguments: vec![
-                json!(amount_a_desired.to_string()),
-                json!(amount_b_desired.to_string()),
-                json!(amount_a_min.to_string()),
-                json!(amount_b_min.to_string()),
-                json!(to),
-                json!(deadline.to_string()),
+                MoveArgument::U64(amount_a_desired),
+                MoveArgument::U64(amount_b_desired),
+                MoveArgument::U64(amount_a_min),
+                MoveArgument::U64(amount_b_min),
+                MoveArgument::Address(to.to_string()),
+                MoveArgument::U64(deadline),
             ],
+            kind: CallKind::Entry,
         };
         crate::contract::Contract::write(client, wallet, contract_call)
             .await
@@ -100,18 +89,20 @@ impl PancakeSwap {
         to: &str,
         deadline: u64,
     ) -> Result<Value, String> {
+        let protocol = protocol_address(&client)?;
         let contract_call = ContractCall {
-            module_address: PANCAKESWAP_FACTORY_PROTOCOL_ADDRESS.to_string(),
+            module_address: protocol,
             module_name: "router".to_string(),
             function_name: "remove_liquidity".to_string(),
             type_arguments: vec![coin_a.to_string(), coin_b.to_string()],
             arguments: vec![
-                json!(liquidity.to_string()),
-                json!(amount_a_min.to_string()),
-                json!(amount_b_min.to_string()),
-                json!(to),
-                json!(deadline.to_string()),
+                MoveArgument::U64(liquidity),
+                MoveArgument::U64(amount_a_min),
+                MoveArgument::U64(amount_b_min),
+                MoveArgument::Address(to.to_string()),
+                MoveArgument::U64(deadline),
             ],
+            kind: CallKind::Entry,
         };
         crate::contract::Contract::write(client, wallet, contract_call)
             .await
@@ -119,6 +110,9 @@ impl PancakeSwap {
     }
 
     /// swap exact tokens for tokens
+    ///
+    /// `deadline` - unix timestamp checked by the router, the transaction also expires then,
+    /// an error if it already passed
     pub async fn swap_exact_tokens_for_tokens(
         client: Arc<Aptos>,
         wallet: Arc<Wallet>,
@@ -128,69 +122,97 @@ impl PancakeSwap {
         to: &str,
         deadline: u64,
     ) -> Result<Value, String> {
+        let protocol = protocol_address(&client)?;
         let type_arguments: Vec<String> = path.iter().map(|s| s.to_string()).collect();
         let path_values: Vec<Value> = path.iter().map(|s| json!(s)).collect();
         let contract_call = ContractCall {
-            module_address: PANCAKESWAP_FACTORY_PROTOCOL_ADDRESS.to_string(),
+            module_address: protocol,
             module_name: "router".to_string(),
             function_name: "swap_exact_tokens_for_tokens".to_string(),
             type_arguments,
             arguments: vec![
-                json!(amount_in.to_string()),
-                json!(amount_out_min.to_string()),
-                json!(path_values),
-                json!(to),
-                json!(deadline.to_string()),
+                MoveArgument::U64(amount_in),
+                MoveArgument::U64(amount_out_min),
+                json!(path_values).into(),
+                MoveArgument::Address(to.to_string()),
+                MoveArgument::U64(deadline),
             ],
+            kind: CallKind::Entry,
         };
-        crate::contract::Contract::write(client, wallet, contract_call)
-            .await
-            .map(|result| json!(result))
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|e| e.to_string())?
+            .as_secs();
+        if deadline <= now {
+            return Err(format!("deadline {} has already passed", deadline));
+        }
+        crate::contract::Contract::write_with_expiration(
+            client,
+            wallet,
+            contract_call,
+            deadline - now,
+        )
+        .await
+        .map(|result| json!(result))
     }
 
-    /// get reserves
+    /// Get reserves in the caller's `(coin_a, coin_b)` order.
+    ///
+    /// Pairs are stored in canonical order, the reversed type arguments are tried
+    /// when that resource does not exist. `(0, 0)` if there is no pool.
     pub async fn get_reserves(
         client: Arc<Aptos>,
         coin_a: &str,
         coin_b: &str,
     ) -> Result<(u64, u64), String> {
-        let pair_address = Self::get_pair_address(coin_a, coin_b);
+        let (token_x, token_y, swapped) = canonical_order(coin_a, coin_b);
+        let reserves = match Self::get_stored_reserves(&client, token_x, token_y).await? {
+            Some(reserves) => Some((reserves, swapped)),
+            None => Self::get_stored_reserves(&client, token_y, token_x)
+                .await?
+                .map(|reserves| (reserves, !swapped)),
+        };
+        Ok(match reserves {
+            Some(((reserve_x, reserve_y), false)) => (reserve_x, reserve_y),
+            Some(((reserve_x, reserve_y), true)) => (reserve_y, reserve_x),
+            None => (0, 0),
+        })
+    }
+
+    /// reserves of the `TokenPairReserve<coin_x, coin_y>` resource, in that order
+    async fn get_stored_reserves(
+        client: &Aptos,
+        coin_x: &str,
+        coin_y: &str,
+    ) -> Result<Option<(u64, u64)>, String> {
+        let protocol = protocol_address(client)?;
         let resource_type = format!(
             "{}::swap::TokenPairReserve<{}, {}>",
-            PANCAKESWAP_FACTORY_PROTOCOL_ADDRESS, coin_a, coin_b
+            protocol, coin_x, coin_y
         );
-        match client
-            .get_account_resource(&pair_address, &resource_type)
-            .await
-        {
-            Ok(Some(resource)) => {
-                let reserve_a = resource
-                    .data
-                    .get("reserve0")
-                    .and_then(|v| v.as_str())
-                    .and_then(|s| s.parse().ok())
-                    .unwrap_or(0);
-                let reserve_b = resource
+        let resource = client
+            .get_account_resource(
+                &Self::get_pair_address(client, coin_x, coin_y)?,
+                &resource_type,
+            )
+            .await?;
+        Ok(resource.map(|resource| {
+            let reserve = |field: &str| {
+                resource
                     .data
-                    .get("reserve1")
+                    .get(field)
                     .and_then(|v| v.as_str())
                     .and_then(|s| s.parse().ok())
-                    .unwrap_or(0);
-                Ok((reserve_a, reserve_b))
-            }
-            Ok(None) => Ok((0, 0)),
-            Err(e) => Err(e),
-        }
+                    .unwrap_or(0)
+            };
+            (reserve("reserve0"), reserve("reserve1"))
+        }))
     }
 
-    /// get pair address
-    pub fn get_pair_address(coin_a: &str, coin_b: &str) -> String {
-        let (token_x, token_y) = if coin_a < coin_b {
-            (coin_a, coin_b)
-        } else {
-            (coin_b, coin_a)
-        };
-        let factory_address = PANCAKESWAP_FACTORY_PROTOCOL_ADDRESS;
+    /// pair address under the factory of the client's network
+    pub fn get_pair_address(client: &Aptos, coin_a: &str, coin_b: &str) -> Result<String, String> {
+        let (token_x, token_y, _) = canonical_order(coin_a, coin_b);
+        let factory_address = protocol_address(client)?;
         let salt = "pancake_swap_pair";
         let mut hasher = Sha256::new();
         hasher.update(factory_address.as_bytes());
@@ -206,24 +228,32 @@ impl PancakeSwap {
         for byte in addr_bytes {
             result.push_str(&format!("{:02x}", byte));
         }
-        result
+        Ok(result)
     }
 
     /// get cake price
     pub async fn get_cake_price(client: Arc<Aptos>) -> Result<f64, String> {
-        let (reserve_cake, reserve_apt) = Self::get_reserves(client, CAKE, APT).await?;
+        let network = client.network_config();
+        let cake = network
+            .cake
+            .clone()
+            .ok_or("CAKE is not deployed on this network")?;
+        let apt = network.apt.clone();
+        let (reserve_cake, reserve_apt) = Self::get_reserves(client, &cake, &apt).await?;
         if reserve_cake == 0 {
             return Ok(0.0);
         }
         Ok(reserve_apt as f64 / reserve_cake as f64)
     }
 
-    /// listen events
+    /// listen events, returns the polling tasks of the pair event handles
     pub async fn listen_events(
         client: Arc<Aptos>,
         event_sender: broadcast::Sender<EventData>,
         filters: PancakeSwapEventFilters,
-    ) -> Result<(), String> {
+    ) -> Result<Vec<JoinHandle<()>>, String> {
+        let protocol = protocol_address(&client)?;
+        let mut handles = Vec::new();
         let event_handles = vec![
             "swap_events".to_string(),
             "mint_events".to_string(),
@@ -232,34 +262,29 @@ impl PancakeSwap {
         ];
         for event_handle in event_handles {
             let client_clone = Arc::clone(&client);
+            let protocol = protocol.clone();
             let sender_clone = event_sender.clone();
+            let poll_interval = filters.poll_interval;
             let filters_clone = filters.clone();
-            tokio::spawn(async move {
+            handles.push(tokio::spawn(async move {
                 let mut last_sequence: Option<u64> = None;
                 loop {
-                    if let Ok(events) = client_clone
-                        .get_account_event_vec(
-                            PANCAKESWAP_FACTORY_PROTOCOL_ADDRESS,
-                            &event_handle,
-                            Some(100),
-                            last_sequence,
-                        )
-                        .await
+                    // paused while nobody is subscribed
+                    if sender_clone.receiver_count() > 0
+                        && let Ok(events) = client_clone
+                            .get_account_event_vec(
+                                &protocol,
+                                &event_handle,
+                                Some(100),
+                                last_sequence,
+                            )
+                            .await
                     {
+                        let mut resolver = EventBlockResolver::new(Arc::clone(&client_clone));
                         for event in events {
                             if let Ok(sequence) = event.sequence_number.parse::<u64>() {
                                 if last_sequence.map(|last| sequence > last).unwrap_or(true) {
-                                    let event_data = EventData {
-                                        event_type: event.r#type.clone(),
-                                        event_data: event.data.clone(),
-                                        sequence_number: sequence,
-                                        transaction_hash: "".to_string(),
-                                        block_height: client_clone
-                                            .get_chain_height()
-                                            .await
-                                            .unwrap_or(0)
-                                            as u64,
-                                    };
+                                    let event_data = resolver.event_data(&event, sequence).await;
 
                                     if PancakeSwapEventFilter::apply_filters(
                                         &event_data,
@@ -273,11 +298,11 @@ impl PancakeSwap {
                             }
                         }
                     }
-                    tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
+                    tokio::time::sleep(poll_interval).await;
                 }
-            });
+            }));
         }
-        Ok(())
+        Ok(handles)
     }
 }
 
@@ -287,6 +312,8 @@ pub struct PancakeSwapEventFilters {
     pub min_swap_amount: Option<u64>,
     pub include_cake_pairs: bool,
     pub tracked_pairs: Option<Vec<(String, String)>>,
+    /// delay between polls of each event handle
+    pub poll_interval: Duration,
 }
 
 ///  Pancake Swap Event Filter
@@ -362,3 +389,83 @@ impl PancakeSwapEventFilter {
         true
     }
 }
+
+/// Pancake Swap Event Parser
+pub struct PancakeSwapEventParser;
+
+impl PancakeSwapEventParser {
+    pub fn parse_swap_event(event_data: &EventData) -> Option<NormalizedSwap> {
+        if !event_kind_matches(&event_data.event_type, &["swap_events", "SwapEvent"]) {
+            return None;
+        }
+        let data = &event_data.event_data;
+        let (token_x, token_y) = Self::pair_tokens(event_data);
+        let (token_in, token_out, amount_in, amount_out) = resolve_xy_swap(
+            data,
+            &["amount0_in", "amount_x_in"],
+            &["amount1_in", "amount_y_in"],
+            &["amount0_out", "amount_x_out"],
+            &["amount1_out", "amount_y_out"],
+            token_x,
+            token_y,
+        )?;
+        Some(NormalizedSwap {
+            dex: "PancakeSwap".to_string(),
+            sender: event_string(data, &["sender_address", "sender", "user"]).unwrap_or_default(),
+            token_in,
+            token_out,
+            amount_in,
+            amount_out,
+            ts: event_timestamp_secs(event_data).unwrap_or(0),
+        })
+    }
+
+    pub fn parse_mint_event(event_data: &EventData) -> Option<NormalizedLiquidity> {
+        if !event_kind_matches(
+            &event_data.event_type,
+            &["mint_events", "AddLiquidityEvent", "MintEvent"],
+        ) {
+            return None;
+        }
+        Self::parse_liquidity_event(event_data)
+    }
+
+    pub fn parse_burn_event(event_data: &EventData) -> Option<NormalizedLiquidity> {
+        if !event_kind_matches(
+            &event_data.event_type,
+            &["burn_events", "RemoveLiquidityEvent", "BurnEvent"],
+        ) {
+            return None;
+        }
+        Self::parse_liquidity_event(event_data)
+    }
+
+    fn parse_liquidity_event(event_data: &EventData) -> Option<NormalizedLiquidity> {
+        let data = &event_data.event_data;
+        let (token_a, token_b) = Self::pair_tokens(event_data);
+        Some(NormalizedLiquidity {
+            dex: "PancakeSwap".to_string(),
+            provider: event_string(data, &["user", "sender", "sender_address"]).unwrap_or_default(),
+            token_a,
+            token_b,
+            amount_a: event_u64(data, &["amount0", "amount_x"])?,
+            amount_b: event_u64(data, &["amount1", "amount_y"])?,
+            liquidity: event_u64(data, &["liquidity"]).unwrap_or(0),
+            ts: event_timestamp_secs(event_data).unwrap_or(0),
+        })
+    }
+
+    /// pair tokens from the event data, or from the event type arguments
+    fn pair_tokens(event_data: &EventData) -> (String, String) {
+        let data = &event_data.event_data;
+        let mut type_args = event_type_args(&event_data.event_type).into_iter();
+        (
+            event_string(data, &["token0"])
+                .or_else(|| type_args.next())
+                .unwrap_or_default(),
+            event_string(data, &["token1"])
+                .or_else(|| type_args.next())
+                .unwrap_or_default(),
+        )
+    }
+}
@@ -1,21 +1,33 @@
 /// The implementation module of Thala complete interactive logic.
 use crate::{
     Aptos,
-    event::EventData,
-    global::mainnet::{protocol_address::THALA_PROTOCOL_ADDRESS, token_address::THL},
-    types::ContractCall,
+    dex::{
+        DexUtils, NormalizedSwap, PoolInfo, event_kind_matches, event_string, event_timestamp_secs,
+        event_type_args, event_u64, resolve_xy_swap,
+    },
+    event::{EventBlockResolver, EventData},
+    types::{CallKind, ContractCall, MoveArgument},
     wallet::Wallet,
 };
 use serde_json::{Value, json};
-use std::sync::Arc;
-use tokio::sync::broadcast;
+use std::{sync::Arc, time::Duration};
+use tokio::{sync::broadcast, task::JoinHandle};
+
+/// Thala address on the client's network
+fn protocol_address(client: &Aptos) -> Result<String, String> {
+    client
+        .network_config()
+        .dex_address("Thala")
+        .map(str::to_string)
+}
 
 pub struct Thala;
 
 impl Thala {
     /// get swap events
     pub async fn get_swap_events(client: Arc<Aptos>) -> Result<Vec<EventData>, String> {
-        let event_type = format!("{}::amm::SwapEvent", THALA_PROTOCOL_ADDRESS);
+        let protocol = protocol_address(&client)?;
+        let event_type = format!("{}::amm::SwapEvent", protocol);
         Self::get_recent_events(client, &event_type).await
     }
 
@@ -23,28 +35,14 @@ impl Thala {
         client: Arc<Aptos>,
         event_type: &str,
     ) -> Result<Vec<EventData>, String> {
-        let mut all_events = Vec::new();
-        let mut start_seq: Option<u64> = None;
+        let protocol = protocol_address(&client)?;
         let events = client
-            .get_account_event_vec(THALA_PROTOCOL_ADDRESS, event_type, Some(100), start_seq)
-            .await
-            .map_err(|e| e.to_string())?;
-        for event in events {
-            if let Ok(sequence) = event.sequence_number.parse::<u64>() {
-                let event_data = EventData {
-                    event_type: event.r#type.clone(),
-                    event_data: event.data.clone(),
-                    sequence_number: sequence,
-                    transaction_hash: "".to_string(),
-                    block_height: 0,
-                };
-                all_events.push(event_data);
-            }
-        }
-        Ok(all_events)
+            .get_all_account_events(&protocol, event_type, None, None)
+            .await?;
+        Ok(EventBlockResolver::new(client).events_data(&events).await)
     }
 
-    /// add liquidity
+    /// add liquidity, `slippage` is a fraction in `[0, 1]`
     pub async fn add_liquidity(
         client: Arc<Aptos>,
         wallet: Arc<Wallet>,
@@ -54,19 +52,21 @@ impl Thala {
         amount_y: u64,
         slippage: f64,
     ) -> Result<Value, String> {
-        let min_amount_x = (amount_x as f64 * (1.0 - slippage)) as u64;
-        let min_amount_y = (amount_y as f64 * (1.0 - slippage)) as u64;
+        let protocol = protocol_address(&client)?;
+        let (min_amount_x, min_amount_y) =
+            DexUtils::min_liquidity_amounts(amount_x, amount_y, slippage)?;
         let contract_call = ContractCall {
-            module_address: THALA_PROTOCOL_ADDRESS.to_string(),
+            module_address: protocol,
             module_name: "amm".to_string(),
             function_name: "add_liquidity".to_string(),
             type_arguments: vec![coin_x.to_string(), coin_y.to_string()],
             arguments: vec![
-                json!(amount_x.to_string()),
-                json!(amount_y.to_string()),
-                json!(min_amount_x.to_string()),
-                json!(min_amount_y.to_string()),
+                MoveArgument::U64(amount_x),
+                MoveArgument::U64(amount_y),
+                MoveArgument::U64(min_amount_x),
+                MoveArgument::U64(min_amount_y),
             ],
+            kind: CallKind::Entry,
         };
         crate::contract::Contract::write(client, wallet, contract_call)
             .await
@@ -83,16 +83,18 @@ impl Thala {
         min_amount_x: u64,
         min_amount_y: u64,
     ) -> Result<Value, String> {
+        let protocol = protocol_address(&client)?;
         let contract_call = ContractCall {
-            module_address: THALA_PROTOCOL_ADDRESS.to_string(),
+            module_address: protocol,
             module_name: "amm".to_string(),
             function_name: "remove_liquidity".to_string(),
             type_arguments: vec![coin_x.to_string(), coin_y.to_string()],
             arguments: vec![
-                json!(liquidity_amount.to_string()),
-                json!(min_amount_x.to_string()),
-                json!(min_amount_y.to_string()),
+                MoveArgument::U64(liquidity_amount),
+                MoveArgument::U64(min_amount_x),
+                MoveArgument::U64(min_amount_y),
             ],
+            kind: CallKind::Entry,
         };
         crate::contract::Contract::write(client, wallet, contract_call)
             .await
@@ -100,6 +102,7 @@ impl Thala {
     }
 
     /// swap exact input
+    /// `deadline_secs` - seconds until the transaction expires
     pub async fn swap_exact_input(
         client: Arc<Aptos>,
         wallet: Arc<Wallet>,
@@ -107,23 +110,32 @@ impl Thala {
         to_coin: &str,
         amount_in: u64,
         min_amount_out: u64,
+        deadline_secs: u64,
     ) -> Result<Value, String> {
+        let protocol = protocol_address(&client)?;
         let contract_call = ContractCall {
-            module_address: THALA_PROTOCOL_ADDRESS.to_string(),
+            module_address: protocol,
             module_name: "router".to_string(),
             function_name: "swap_exact_input".to_string(),
             type_arguments: vec![from_coin.to_string(), to_coin.to_string()],
             arguments: vec![
-                json!(amount_in.to_string()),
-                json!(min_amount_out.to_string()),
+                MoveArgument::U64(amount_in),
+                MoveArgument::U64(min_amount_out),
             ],
+            kind: CallKind::Entry,
         };
-        crate::contract::Contract::write(client, wallet, contract_call)
-            .await
-            .map(|result| json!(result))
+        crate::contract::Contract::write_with_expiration(
+            client,
+            wallet,
+            contract_call,
+            deadline_secs,
+        )
+        .await
+        .map(|result| json!(result))
     }
 
     /// swap exact output
+    /// `deadline_secs` - seconds until the transaction expires
     pub async fn swap_exact_output(
         client: Arc<Aptos>,
         wallet: Arc<Wallet>,
@@ -131,20 +143,28 @@ impl Thala {
         to_coin: &str,
         amount_out: u64,
         max_amount_in: u64,
+        deadline_secs: u64,
     ) -> Result<Value, String> {
+        let protocol = protocol_address(&client)?;
         let contract_call = ContractCall {
-            module_address: THALA_PROTOCOL_ADDRESS.to_string(),
+            module_address: protocol,
             module_name: "router".to_string(),
             function_name: "swap_exact_output".to_string(),
             type_arguments: vec![from_coin.to_string(), to_coin.to_string()],
             arguments: vec![
-                json!(amount_out.to_string()),
-                json!(max_amount_in.to_string()),
+                MoveArgument::U64(amount_out),
+                MoveArgument::U64(max_amount_in),
             ],
+            kind: CallKind::Entry,
         };
-        crate::contract::Contract::write(client, wallet, contract_call)
-            .await
-            .map(|result| json!(result))
+        crate::contract::Contract::write_with_expiration(
+            client,
+            wallet,
+            contract_call,
+            deadline_secs,
+        )
+        .await
+        .map(|result| json!(result))
     }
 
     /// get pool info
@@ -153,21 +173,47 @@ impl Thala {
         coin_x: &str,
         coin_y: &str,
     ) -> Result<Value, String> {
-        let resource_type = format!(
-            "{}::amm::Pool<{}, {}>",
-            THALA_PROTOCOL_ADDRESS, coin_x, coin_y
-        );
+        let protocol = protocol_address(&client)?;
+        let resource_type = format!("{}::amm::Pool<{}, {}>", protocol, coin_x, coin_y);
         client
-            .get_account_resource(THALA_PROTOCOL_ADDRESS, &resource_type)
+            .get_account_resource(&protocol, &resource_type)
             .await
             .map(|opt| opt.map(|r| r.data).unwrap_or(Value::Null))
             .map_err(|e| e.to_string())
     }
 
+    /// whether the pool of `coin_x` and `coin_y` exists, without fetching it
+    pub async fn pool_exists(
+        client: Arc<Aptos>,
+        coin_x: &str,
+        coin_y: &str,
+    ) -> Result<bool, String> {
+        let protocol = protocol_address(&client)?;
+        let resource_type = format!("{}::amm::Pool<{}, {}>", protocol, coin_x, coin_y);
+        client
+            .account_resource_exists(&protocol, &resource_type)
+            .await
+    }
+
+    /// get pool info mapped into the normalized `PoolInfo`
+    pub async fn get_pool_info_typed(
+        client: Arc<Aptos>,
+        coin_x: &str,
+        coin_y: &str,
+    ) -> Result<PoolInfo, String> {
+        let data = Self::get_pool_info(client, coin_x, coin_y).await?;
+        PoolInfo::from_resource(&data, "reserve_x", "reserve_y", Some("swap_fee_bps"), 30)
+    }
+
     /// get thl price
     pub async fn get_thl_price(client: Arc<Aptos>) -> Result<f64, String> {
-        let apt_coin = "0x1::aptos_coin::AptosCoin";
-        Self::get_price(client, THL, apt_coin, 100000000).await // 1 THL
+        let network = client.network_config();
+        let thl = network
+            .thl
+            .clone()
+            .ok_or("THL is not deployed on this network")?;
+        let apt_coin = network.apt.clone();
+        Self::get_price(client, &thl, &apt_coin, 100000000).await // 1 THL
     }
 
     /// 获取价格
@@ -200,42 +246,39 @@ impl Thala {
         }
     }
 
-    /// listen events
+    /// listen events, returns the polling task of each event type
     pub async fn listen_events(
         client: Arc<Aptos>,
         event_sender: broadcast::Sender<EventData>,
-        event_types: Vec<ThalaEventType>,
-    ) -> Result<(), String> {
-        for event_type in event_types {
+        config: ThalaEventConfig,
+    ) -> Result<Vec<JoinHandle<()>>, String> {
+        let protocol = protocol_address(&client)?;
+        let mut handles = Vec::new();
+        for event_type in config.event_types {
             let client_clone = Arc::clone(&client);
+            let protocol = protocol.clone();
             let sender_clone = event_sender.clone();
+            let poll_interval = config.poll_interval;
             let event_handle = event_type.get_event_handle();
-            tokio::spawn(async move {
+            handles.push(tokio::spawn(async move {
                 let mut last_sequence: Option<u64> = None;
                 loop {
-                    if let Ok(events) = client_clone
-                        .get_account_event_vec(
-                            THALA_PROTOCOL_ADDRESS,
-                            &event_handle,
-                            Some(100),
-                            last_sequence,
-                        )
-                        .await
+                    // paused while nobody is subscribed
+                    if sender_clone.receiver_count() > 0
+                        && let Ok(events) = client_clone
+                            .get_account_event_vec(
+                                &protocol,
+                                &event_handle,
+                                Some(100),
+                                last_sequence,
+                            )
+                            .await
                     {
+                        let mut resolver = EventBlockResolver::new(Arc::clone(&client_clone));
                         for event in events {
                             if let Ok(sequence) = event.sequence_number.parse::<u64>() {
                                 if last_sequence.map(|last| sequence > last).unwrap_or(true) {
-                                    let event_data = EventData {
-                                        event_type: event.r#type.clone(),
-                                        event_data: event.data.clone(),
-                                        sequence_number: sequence,
-                                        transaction_hash: "".to_string(),
-                                        block_height: client_clone
-                                            .get_chain_height()
-                                            .await
-                                            .unwrap_or(0)
-                                            as u64,
-                                    };
+                                    let event_data = resolver.event_data(&event, sequence).await;
 
                                     if let Some(filtered_event) =
                                         event_type.filter_event(&event_data)
@@ -248,15 +291,32 @@ impl Thala {
                             }
                         }
                     }
-                    tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+                    tokio::time::sleep(poll_interval).await;
                 }
-            });
+            }));
+        }
+        Ok(handles)
+    }
+}
+
+/// thala event listening config
+#[derive(Debug, Clone)]
+pub struct ThalaEventConfig {
+    pub event_types: Vec<ThalaEventType>,
+    pub poll_interval: Duration,
+}
+
+impl Default for ThalaEventConfig {
+    fn default() -> Self {
+        Self {
+            event_types: vec![],
+            poll_interval: Duration::from_secs(2),
         }
-        Ok(())
     }
 }
 
 /// thala event type
+#[derive(Debug, Clone)]
 pub enum ThalaEventType {
     SwapEvent,
     MintEvent,
@@ -307,6 +367,64 @@ impl ThalaEventType {
 pub struct ThalaEventParser;
 
 impl ThalaEventParser {
+    /// Normalize a swap event, `{ idx_in, idx_out, amount_in, amount_out }` indexing into
+    /// the pool's assets: the `metadata` objects of a v2 pool, or else the asset type
+    /// arguments of the event, skipping the `Null` padding and the weight types. Pair
+    /// events with per-side amounts (`x_in`, `y_out`, ..) are read in the direction the
+    /// amounts flowed.
+    pub fn parse_swap_event(event_data: &EventData) -> Option<NormalizedSwap> {
+        if !event_kind_matches(&event_data.event_type, &["swap_events", "SwapEvent"]) {
+            return None;
+        }
+        let data = &event_data.event_data;
+        let assets: Vec<String> = match data.get("metadata").and_then(|v| v.as_array()) {
+            Some(metadata) => metadata
+                .iter()
+                .filter_map(|m| m.get("inner").unwrap_or(m).as_str().map(|s| s.to_string()))
+                .collect(),
+            None => event_type_args(&event_data.event_type)
+                .into_iter()
+                .filter(|arg| !arg.ends_with("::Null") && !arg.contains("::Weight_"))
+                .collect(),
+        };
+        let (token_in, token_out, amount_in, amount_out) =
+            match (event_u64(data, &["idx_in"]), event_u64(data, &["idx_out"])) {
+                (Some(idx_in), Some(idx_out)) => (
+                    assets.get(idx_in as usize)?.clone(),
+                    assets.get(idx_out as usize)?.clone(),
+                    event_u64(data, &["amount_in"])?,
+                    event_u64(data, &["amount_out"])?,
+                ),
+                _ => {
+                    let mut assets = assets.into_iter();
+                    resolve_xy_swap(
+                        data,
+                        &["x_in", "amount_x_in"],
+                        &["y_in", "amount_y_in"],
+                        &["x_out", "amount_x_out"],
+                        &["y_out", "amount_y_out"],
+                        assets
+                            .next()
+                            .or_else(|| event_string(data, &["coin_x"]))
+                            .unwrap_or_default(),
+                        assets
+                            .next()
+                            .or_else(|| event_string(data, &["coin_y"]))
+                            .unwrap_or_default(),
+                    )?
+                }
+            };
+        Some(NormalizedSwap {
+            dex: "Thala".to_string(),
+            sender: event_string(data, &["sender", "user"]).unwrap_or_default(),
+            token_in,
+            token_out,
+            amount_in,
+            amount_out,
+            ts: event_timestamp_secs(event_data).unwrap_or(0),
+        })
+    }
+
     /// parse staking event
     pub fn parse_staking_event(event_data: &EventData) -> Option<ThalaStakingEvent> {
         if !event_data.event_type.contains("staking_events") {
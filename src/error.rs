@@ -0,0 +1,62 @@
+use crate::{
+    throttle::{CIRCUIT_BREAKER_OPEN, SERVICE_UNAVAILABLE},
+    transport::HttpResponse,
+};
+use std::fmt;
+
+/// aptos client error
+#[derive(Debug, Clone, PartialEq)]
+pub enum AptosError {
+    /// the node answered 404, e.g. an account that was never funded
+    NotFound(String),
+    /// the node answered with another non-success status
+    Api { status: u16, message: String },
+    /// the request could not be sent or the response could not be read
+    Transport(String),
+    /// the response could not be parsed
+    Parse(String),
+    /// the client's circuit breaker is open, see `Aptos::with_circuit_breaker`
+    ServiceUnavailable(String),
+}
+
+impl AptosError {
+    /// classify a non-success response by its status and `error_code`
+    pub(crate) fn from_response(response: HttpResponse) -> Self {
+        let error: Option<serde_json::Value> = response.json().ok();
+        let error_code = error.as_ref().and_then(|e| e["error_code"].as_str());
+        match response.status {
+            404 => AptosError::NotFound(response.body),
+            503 if error_code == Some(CIRCUIT_BREAKER_OPEN) => AptosError::ServiceUnavailable(
+                error
+                    .as_ref()
+                    .and_then(|e| e["message"].as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+            ),
+            status => AptosError::Api {
+                status,
+                message: response.body,
+            },
+        }
+    }
+}
+
+impl fmt::Display for AptosError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AptosError::NotFound(msg) => write!(f, "not found: {}", msg),
+            AptosError::Api { message, .. } => write!(f, "api error: {}", message),
+            AptosError::Transport(msg) => write!(f, "transport error: {}", msg),
+            AptosError::Parse(msg) => write!(f, "parse error: {}", msg),
+            AptosError::ServiceUnavailable(msg) => write!(f, "{}: {}", SERVICE_UNAVAILABLE, msg),
+        }
+    }
+}
+
+impl std::error::Error for AptosError {}
+
+impl From<AptosError> for String {
+    fn from(error: AptosError) -> Self {
+        error.to_string()
+    }
+}
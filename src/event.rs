@@ -1,4 +1,11 @@
-use crate::{Aptos, types::Event};
+use crate::{
+    Aptos,
+    block::Block,
+    global::NetworkConfig,
+    log::{log_error, log_warn},
+    types::Event,
+};
+use serde::{Deserialize, Deserializer, de::DeserializeOwned};
 use serde_json::Value;
 use std::{collections::HashMap, sync::Arc, time::Duration};
 use tokio::sync::broadcast;
@@ -12,7 +19,90 @@ pub struct EventData {
     pub event_data: Value,
     pub sequence_number: u64,
     pub transaction_hash: String,
+    /// height of the block the event was emitted in
     pub block_height: u64,
+    /// timestamp of that block, in seconds
+    pub timestamp_secs: u64,
+}
+
+/// Resolves fetched events to their transaction and block.
+///
+/// Each block is fetched once and then serves every event of the batch emitted in
+/// it, so create one resolver per poll batch.
+pub struct EventBlockResolver {
+    client: Arc<Aptos>,
+    blocks: Vec<Block>,
+}
+
+impl EventBlockResolver {
+    pub fn new(client: Arc<Aptos>) -> Self {
+        Self {
+            client,
+            blocks: Vec::new(),
+        }
+    }
+
+    /// block containing the transaction at `version`
+    async fn block_for(&mut self, version: u64) -> Option<&Block> {
+        let contains = |block: &Block| {
+            block
+                .first_version
+                .parse::<u64>()
+                .is_ok_and(|first| first <= version)
+                && block
+                    .last_version
+                    .parse::<u64>()
+                    .is_ok_and(|last| version <= last)
+        };
+        let index = match self.blocks.iter().position(contains) {
+            Some(index) => index,
+            None => {
+                let block = self.client.get_block_by_version(version, true).await.ok()?;
+                self.blocks.push(block);
+                self.blocks.len() - 1
+            }
+        };
+        self.blocks.get(index)
+    }
+
+    /// `EventData` of a fetched event, the chain fields are left empty when the
+    /// event has no version or its block can't be fetched
+    pub async fn event_data(&mut self, event: &Event, sequence_number: u64) -> EventData {
+        let mut event_data = EventData {
+            event_type: event.r#type.clone(),
+            event_data: event.data.clone(),
+            sequence_number,
+            transaction_hash: "".to_string(),
+            block_height: 0,
+            timestamp_secs: 0,
+        };
+        let Some(version) = event.version.as_ref().and_then(|v| v.parse::<u64>().ok()) else {
+            return event_data;
+        };
+        if let Some(block) = self.block_for(version).await {
+            event_data.block_height = block.block_height.parse().unwrap_or(0);
+            event_data.timestamp_secs = block.timestamp.parse::<u64>().unwrap_or(0) / 1_000_000;
+            event_data.transaction_hash = block
+                .transactions
+                .iter()
+                .flatten()
+                .find(|txn| txn.version == version.to_string())
+                .map(|txn| txn.hash.clone())
+                .unwrap_or_default();
+        }
+        event_data
+    }
+
+    /// `EventData` of each event with a valid sequence number, in order
+    pub async fn events_data(&mut self, events: &[Event]) -> Vec<EventData> {
+        let mut events_data = Vec::with_capacity(events.len());
+        for event in events {
+            if let Ok(sequence) = event.sequence_number.parse::<u64>() {
+                events_data.push(self.event_data(event, sequence).await);
+            }
+        }
+        events_data
+    }
 }
 
 impl EventHandler {
@@ -30,6 +120,7 @@ impl EventHandler {
                 .await
             {
                 Ok(events) => {
+                    let mut resolver = EventBlockResolver::new(Arc::clone(&client));
                     for event in events {
                         let sequence_number = match event.sequence_number.parse::<u64>() {
                             Ok(seq) => seq,
@@ -40,20 +131,14 @@ impl EventHandler {
                             .map(|last| sequence_number > last)
                             .unwrap_or(true)
                         {
-                            let event_data = EventData {
-                                event_type: event.r#type.clone(),
-                                event_data: event.data.clone(),
-                                sequence_number,
-                                transaction_hash: "hash".to_string(),
-                                block_height: client.get_chain_height().await.unwrap() as u64,
-                            };
+                            let event_data = resolver.event_data(&event, sequence_number).await;
                             let _ = event_sender.send(event_data);
                             last_sequence = Some(sequence_number);
                         }
                     }
                 }
                 Err(e) => {
-                    eprintln!("Error fetching events: {}", e);
+                    log_warn!("Error fetching events: {}", e);
                 }
             }
             tokio::time::sleep(Duration::from_secs(2)).await;
@@ -74,6 +159,7 @@ impl EventHandler {
                 .await
             {
                 Ok(events) => {
+                    let mut resolver = EventBlockResolver::new(Arc::clone(&client));
                     for event in events {
                         let sequence_number = match event.sequence_number.parse::<u64>() {
                             Ok(seq) => seq,
@@ -84,22 +170,14 @@ impl EventHandler {
                             .unwrap_or(true)
                         {
                             // Get transaction information
-                            let transaction_hash = "hash".to_string();
-                            let block_height = client.get_chain_height().await.unwrap() as u64;
-                            let event_data = EventData {
-                                event_type: event.r#type.clone(),
-                                event_data: event.data.clone(),
-                                sequence_number,
-                                transaction_hash,
-                                block_height,
-                            };
+                            let event_data = resolver.event_data(&event, sequence_number).await;
                             let _ = event_sender.send(event_data);
                             last_sequence = Some(sequence_number);
                         }
                     }
                 }
                 Err(e) => {
-                    eprintln!("Error fetching events: {}", e);
+                    log_warn!("Error fetching events: {}", e);
                 }
             }
 
@@ -177,6 +255,7 @@ impl EventSubscriptionManager {
         event: Event,
         transaction_hash: String,
         block_height: u64,
+        timestamp_secs: u64,
     ) -> Result<(), String> {
         let sequence_number = match event.sequence_number.parse::<u64>() {
             Ok(seq) => seq,
@@ -188,6 +267,7 @@ impl EventSubscriptionManager {
             sequence_number,
             transaction_hash,
             block_height,
+            timestamp_secs,
         };
         self.publish_event(event_key, event_data)
     }
@@ -202,6 +282,7 @@ impl EventUtils {
         event: Event,
         transaction_hash: String,
         block_height: u64,
+        timestamp_secs: u64,
     ) -> Result<EventData, String> {
         let sequence_number = event
             .sequence_number
@@ -213,6 +294,7 @@ impl EventUtils {
             sequence_number,
             transaction_hash,
             block_height,
+            timestamp_secs,
         })
     }
 
@@ -233,8 +315,248 @@ impl EventUtils {
     {
         for event in events {
             if let Err(e) = processor(event) {
-                eprintln!("Error processing event: {}", e);
+                log_error!("Error processing event: {}", e);
             }
         }
     }
 }
+
+/// Decode the data of an event into a user provided struct.
+///
+/// Move encodes u64/u128 values as json strings, annotate such fields with
+/// `#[serde(deserialize_with = "aptos_network_sdk::event::string_or_u64")]`.
+pub fn decode<T: DeserializeOwned>(event: &Event) -> Result<T, String> {
+    serde_json::from_value(event.data.clone())
+        .map_err(|e| format!("decode {} error: {}", event.r#type, e))
+}
+
+/// deserialize a u64 that may be encoded as a json string or number
+pub fn string_or_u64<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Value::deserialize(deserializer)? {
+        Value::String(s) => s.parse().map_err(serde::de::Error::custom),
+        Value::Number(n) => n
+            .as_u64()
+            .ok_or_else(|| serde::de::Error::custom("invalid u64")),
+        Value::Null => Ok(0),
+        other => Err(serde::de::Error::custom(format!(
+            "expected u64, found {}",
+            other
+        ))),
+    }
+}
+
+/// 0x1::coin::DepositEvent
+#[derive(Debug, Clone, Deserialize)]
+pub struct CoinDepositEvent {
+    #[serde(deserialize_with = "string_or_u64")]
+    pub amount: u64,
+}
+
+/// 0x1::coin::WithdrawEvent
+#[derive(Debug, Clone, Deserialize)]
+pub struct CoinWithdrawEvent {
+    #[serde(deserialize_with = "string_or_u64")]
+    pub amount: u64,
+}
+
+/// built-in decoded events
+#[derive(Debug, Clone)]
+pub enum DecodedEvent {
+    CoinDeposit(CoinDepositEvent),
+    CoinWithdraw(CoinWithdrawEvent),
+    #[cfg(feature = "dex")]
+    LiquidswapSwap(crate::dex::liquidswap::LiquidswapSwapEvent),
+    /// the event type has no registered decoder
+    Unknown(Value),
+}
+
+/// event decoder
+pub type EventDecoder = fn(&Event) -> Result<DecodedEvent, String>;
+
+/// Registry mapping event type strings (without type arguments) to decoders.
+pub struct EventRegistry {
+    decoders: HashMap<String, EventDecoder>,
+}
+
+impl Default for EventRegistry {
+    fn default() -> Self {
+        Self::for_network(&NetworkConfig::default())
+    }
+}
+
+impl EventRegistry {
+    /// registry with the built-in decoders for mainnet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// registry with the built-in decoders for the protocols of `network`
+    pub fn for_network(network: &NetworkConfig) -> Self {
+        let mut registry = Self {
+            decoders: HashMap::new(),
+        };
+        registry.register("0x1::coin::DepositEvent", |event| {
+            decode(event).map(DecodedEvent::CoinDeposit)
+        });
+        registry.register("0x1::coin::WithdrawEvent", |event| {
+            decode(event).map(DecodedEvent::CoinWithdraw)
+        });
+        #[cfg(feature = "dex")]
+        if let Some(liquidswap) = &network.liquidswap {
+            registry.register(
+                &format!("{}::liquidity_pool::SwapEvent", liquidswap),
+                |event| decode(event).map(DecodedEvent::LiquidswapSwap),
+            );
+            registry.register(&format!("{}::router::SwapEvent", liquidswap), |event| {
+                decode(event).map(DecodedEvent::LiquidswapSwap)
+            });
+        }
+        #[cfg(not(feature = "dex"))]
+        let _ = network;
+        registry
+    }
+
+    /// registry with the built-in decoders for the client's network
+    pub fn for_client(client: &Aptos) -> Self {
+        Self::for_network(client.network_config())
+    }
+
+    /// register a decoder, replacing any existing one for the same type
+    pub fn register(&mut self, event_type: &str, decoder: EventDecoder) {
+        self.decoders
+            .insert(Self::base_type(event_type).to_string(), decoder);
+    }
+
+    /// decode an event, unregistered types are returned as `DecodedEvent::Unknown`
+    pub fn decode(&self, event: &Event) -> Result<DecodedEvent, String> {
+        match self.decoders.get(Self::base_type(&event.r#type)) {
+            Some(decoder) => decoder(event),
+            None => Ok(DecodedEvent::Unknown(event.data.clone())),
+        }
+    }
+
+    /// strip type arguments, `0x1::coin::CoinStore<T>` -> `0x1::coin::CoinStore`
+    fn base_type(event_type: &str) -> &str {
+        event_type.split('<').next().unwrap_or(event_type).trim()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(event_type: &str, data: Value) -> Event {
+        Event {
+            guid: Value::Null,
+            sequence_number: "0".to_string(),
+            r#type: event_type.to_string(),
+            data,
+            version: None,
+        }
+    }
+
+    #[cfg(feature = "dex")]
+    #[test]
+    fn test_decode_liquidswap_swap_event() {
+        use crate::global::mainnet::protocol_address::LIQUIDSWAP_PROTOCOL_ADDRESS;
+        let event = event(
+            &format!(
+                "{}::liquidity_pool::SwapEvent<0x1::aptos_coin::AptosCoin, 0x1::a::B>",
+                LIQUIDSWAP_PROTOCOL_ADDRESS
+            ),
+            json!({"sender": "0xabc", "amount_in": "100", "amount_out": 95}),
+        );
+        match EventRegistry::new().decode(&event).unwrap() {
+            DecodedEvent::LiquidswapSwap(swap) => {
+                assert_eq!(swap.sender, "0xabc");
+                assert_eq!(swap.amount_in, 100);
+                assert_eq!(swap.amount_out, 95);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        // other networks decode the swaps of their own deployment only
+        let devnet = EventRegistry::for_network(&NetworkConfig::devnet());
+        assert!(matches!(
+            devnet.decode(&event),
+            Ok(DecodedEvent::Unknown(_))
+        ));
+        let custom = EventRegistry::for_network(&NetworkConfig {
+            liquidswap: Some("0xabc".to_string()),
+            ..NetworkConfig::devnet()
+        });
+        let custom_event = self::event(
+            "0xabc::router::SwapEvent<0x1::aptos_coin::AptosCoin, 0x1::a::B>",
+            event.data.clone(),
+        );
+        assert!(matches!(
+            custom.decode(&custom_event),
+            Ok(DecodedEvent::LiquidswapSwap(_))
+        ));
+        assert!(matches!(
+            custom.decode(&event),
+            Ok(DecodedEvent::Unknown(_))
+        ));
+    }
+
+    #[test]
+    fn test_decode_unknown_and_invalid() {
+        let registry = EventRegistry::new();
+        let unknown = event("0x1::foo::Bar", json!({"x": 1}));
+        assert!(matches!(
+            registry.decode(&unknown),
+            Ok(DecodedEvent::Unknown(_))
+        ));
+        let invalid = event("0x1::coin::DepositEvent", json!({"amount": "abc"}));
+        assert!(registry.decode(&invalid).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_event_block_resolver_fetches_each_block_once() {
+        use crate::{AptosType, transport::MockTransport};
+        let checkpoint = |version: &str, hash: &str| {
+            json!({
+                "version": version, "hash": hash, "state_checkpoint_hash": null,
+                "success": true, "type": "state_checkpoint_transaction"
+            })
+        };
+        let block = json!({
+            "block_height": "5",
+            "block_hash": "0x05",
+            "block_timestamp": "1700000000123456",
+            "first_version": "50",
+            "last_version": "52",
+            "transactions": [checkpoint("50", "0x50"), checkpoint("51", "0x51"), checkpoint("52", "0x52")]
+        });
+        let transport =
+            Arc::new(MockTransport::new().on_get("/blocks/by_version/51", 200, &block.to_string()));
+        let client = Arc::new(Aptos::with_transport(
+            AptosType::Mainnet,
+            Arc::clone(&transport) as _,
+        ));
+        let mut resolver = EventBlockResolver::new(client);
+        let mut first = event("0x1::coin::DepositEvent", json!({}));
+        first.version = Some("51".to_string());
+        let mut second = first.clone();
+        second.version = Some("52".to_string());
+
+        let first = resolver.event_data(&first, 1).await;
+        assert_eq!(first.transaction_hash, "0x51");
+        assert_eq!((first.block_height, first.timestamp_secs), (5, 1700000000));
+        let second = resolver.event_data(&second, 2).await;
+        assert_eq!(second.transaction_hash, "0x52");
+        assert_eq!(second.block_height, 5);
+        assert_eq!(transport.requests().len(), 1);
+
+        let unversioned = resolver
+            .event_data(&event("0x1::coin::DepositEvent", json!({})), 3)
+            .await;
+        assert_eq!(
+            (unversioned.block_height, unversioned.timestamp_secs),
+            (0, 0)
+        );
+    }
+}
@@ -0,0 +1,335 @@
+/// Fungible assets (`0x1::fungible_asset`), addressed by their metadata object.
+///
+/// Balances sit in primary stores, one object per owner and asset at an address
+/// derived from both. Transfers create the recipient's primary store when missing.
+///
+/// The mint and burn refs of an asset only exist inside the module that created it,
+/// so `create`, `mint` and `burn` call entry functions of that issuer module. `mint`
+/// and `burn` follow the aptos `managed_fungible_asset` example.
+use crate::{
+    Aptos,
+    contract::Contract,
+    global::mainnet::sys_address::X_1,
+    types::{ContractCall, ContractWriteResult, ViewRequest},
+    wallet::Wallet,
+};
+use serde_json::{Value, json};
+use sha3::{Digest, Sha3_256};
+use std::sync::Arc;
+
+/// type argument of the framework functions taking an `Object<Metadata>`
+pub const METADATA_TYPE: &str = "0x1::fungible_asset::Metadata";
+
+/// scheme byte of `object::create_user_derived_object_address`
+const OBJECT_DERIVED_SCHEME: u8 = 0xfc;
+
+/// metadata object of a fungible asset
+#[derive(Debug, Clone, PartialEq)]
+pub struct FungibleAssetMetadata {
+    pub address: String,
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub icon_uri: String,
+    pub project_uri: String,
+    /// `None` when the supply is not tracked
+    pub supply: Option<u128>,
+    /// `None` for an unlimited supply
+    pub maximum: Option<u128>,
+}
+
+/// parameters of a new fungible asset
+#[derive(Debug, Clone, Default)]
+pub struct FungibleAssetParams {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    /// `None` for an unlimited supply
+    pub max_supply: Option<u128>,
+    pub icon_uri: String,
+    pub project_uri: String,
+}
+
+pub struct FungibleAsset;
+
+impl FungibleAsset {
+    /// Create a fungible asset through `issuer_module`, e.g. `0xabc::managed_fungible_asset`.
+    ///
+    /// The module's `create(name, symbol, decimals, max_supply, icon_uri, project_uri)`
+    /// entry function creates a primary store enabled asset and keeps its refs.
+    pub async fn create(
+        client: Arc<Aptos>,
+        wallet: Arc<Wallet>,
+        issuer_module: &str,
+        params: FungibleAssetParams,
+    ) -> Result<ContractWriteResult, String> {
+        let max_supply = match params.max_supply {
+            Some(max_supply) => json!({ "vec": [max_supply.to_string()] }),
+            None => json!({ "vec": [] }),
+        };
+        let call = Self::issuer_call(
+            issuer_module,
+            "create",
+            vec![
+                json!(params.name),
+                json!(params.symbol),
+                json!(params.decimals),
+                max_supply,
+                json!(params.icon_uri),
+                json!(params.project_uri),
+            ],
+        )?;
+        Contract::write(client, wallet, call).await
+    }
+
+    /// mint `amount` of `metadata` into the primary store of `recipient`
+    pub async fn mint(
+        client: Arc<Aptos>,
+        wallet: Arc<Wallet>,
+        issuer_module: &str,
+        metadata: &str,
+        recipient: &str,
+        amount: u64,
+    ) -> Result<ContractWriteResult, String> {
+        let call = Self::issuer_call(
+            issuer_module,
+            "mint_to_primary_stores",
+            vec![
+                json!(metadata),
+                json!([recipient]),
+                json!([amount.to_string()]),
+            ],
+        )?;
+        Contract::write(client, wallet, call).await
+    }
+
+    /// burn `amount` of `metadata` from the primary store of `owner`
+    pub async fn burn(
+        client: Arc<Aptos>,
+        wallet: Arc<Wallet>,
+        issuer_module: &str,
+        metadata: &str,
+        owner: &str,
+        amount: u64,
+    ) -> Result<ContractWriteResult, String> {
+        let call = Self::issuer_call(
+            issuer_module,
+            "burn_from_primary_stores",
+            vec![json!(metadata), json!([owner]), json!([amount.to_string()])],
+        )?;
+        Contract::write(client, wallet, call).await
+    }
+
+    /// `0x1::primary_fungible_store::transfer`, creating the recipient's store if needed
+    pub async fn transfer(
+        client: Arc<Aptos>,
+        wallet: Arc<Wallet>,
+        metadata: &str,
+        recipient: &str,
+        amount: u64,
+    ) -> Result<ContractWriteResult, String> {
+        Contract::write(
+            client,
+            wallet,
+            Self::build_transfer_call(metadata, recipient, amount),
+        )
+        .await
+    }
+
+    pub fn build_transfer_call(metadata: &str, recipient: &str, amount: u64) -> ContractCall {
+        ContractCall::entry(
+            X_1,
+            "primary_fungible_store",
+            "transfer",
+            vec![METADATA_TYPE.to_string()],
+            vec![json!(metadata), json!(recipient), json!(amount.to_string())],
+        )
+    }
+
+    /// primary store balance of `owner`, 0 without a store
+    pub async fn balance(client: Arc<Aptos>, owner: &str, metadata: &str) -> Result<u64, String> {
+        let result = client
+            .view(
+                &ViewRequest::new("0x1::primary_fungible_store::balance")
+                    .type_arg(METADATA_TYPE)
+                    .arg(owner)
+                    .arg(metadata),
+                None,
+            )
+            .await?;
+        let balance = result
+            .first()
+            .ok_or("primary_fungible_store::balance returned no value")?;
+        Aptos::parse_u128(Some(balance))
+            .and_then(|balance| u64::try_from(balance).ok())
+            .ok_or_else(|| format!("invalid balance: {}", balance))
+    }
+
+    /// metadata, supply and maximum supply of an asset
+    pub async fn metadata(
+        client: Arc<Aptos>,
+        metadata: &str,
+    ) -> Result<FungibleAssetMetadata, String> {
+        let data = client
+            .get_account_resource(metadata, METADATA_TYPE)
+            .await?
+            .map(|resource| resource.data)
+            .ok_or_else(|| format!("fungible asset metadata not found: {}", metadata))?;
+        let (supply, maximum) = match client
+            .get_account_resource(metadata, "0x1::fungible_asset::ConcurrentSupply")
+            .await?
+        {
+            Some(resource) => (
+                Aptos::parse_coin_supply(&resource.data),
+                Aptos::parse_coin_supply(&resource.data["current"]["max_value"])
+                    .filter(|max| *max != u128::MAX),
+            ),
+            None => match client
+                .get_account_resource(metadata, "0x1::fungible_asset::Supply")
+                .await?
+            {
+                Some(resource) => (
+                    Aptos::parse_coin_supply(&resource.data),
+                    Aptos::parse_coin_supply(&resource.data["maximum"]),
+                ),
+                None => (None, None),
+            },
+        };
+        let text = |field: &str| data[field].as_str().unwrap_or_default().to_string();
+        Ok(FungibleAssetMetadata {
+            address: metadata.to_string(),
+            name: text("name"),
+            symbol: text("symbol"),
+            decimals: data["decimals"].as_u64().unwrap_or(0) as u8,
+            icon_uri: text("icon_uri"),
+            project_uri: text("project_uri"),
+            supply,
+            maximum,
+        })
+    }
+
+    /// whether `owner` already has a primary store for `metadata`
+    pub async fn primary_store_exists(
+        client: Arc<Aptos>,
+        owner: &str,
+        metadata: &str,
+    ) -> Result<bool, String> {
+        client.primary_store_exists(owner, metadata).await
+    }
+
+    /// Address of the primary store of `owner` for `metadata`, whether or not it exists.
+    ///
+    /// Same derivation as `object::create_user_derived_object_address`:
+    /// `sha3_256(owner | metadata | 0xfc)`.
+    pub fn primary_store_address(owner: &str, metadata: &str) -> Result<String, String> {
+        let mut hasher = Sha3_256::new();
+        hasher.update(address_bytes(owner)?);
+        hasher.update(address_bytes(metadata)?);
+        hasher.update([OBJECT_DERIVED_SCHEME]);
+        Ok(format!("0x{}", hex::encode(hasher.finalize())))
+    }
+
+    fn issuer_call(
+        issuer_module: &str,
+        function_name: &str,
+        arguments: Vec<Value>,
+    ) -> Result<ContractCall, String> {
+        let (module_address, module_name) = issuer_module
+            .split_once("::")
+            .ok_or_else(|| format!("invalid issuer module: {}", issuer_module))?;
+        Ok(ContractCall::entry(
+            module_address,
+            module_name,
+            function_name,
+            vec![],
+            arguments,
+        ))
+    }
+}
+
+/// 32 bytes of an address, short forms such as `0xa` are zero padded
+fn address_bytes(address: &str) -> Result<[u8; 32], String> {
+    let hex_part = address.trim_start_matches("0x");
+    if hex_part.is_empty() || hex_part.len() > 64 {
+        return Err(format!("Invalid address: {}", address));
+    }
+    let bytes = hex::decode(format!("{:0>64}", hex_part))
+        .map_err(|_| format!("Invalid address: {}", address))?;
+    bytes
+        .try_into()
+        .map_err(|_| format!("Invalid address: {}", address))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AptosType, transport::MockTransport};
+
+    #[test]
+    fn test_primary_store_address() {
+        let store = FungibleAsset::primary_store_address("0xa", "0xa").unwrap();
+        let mut expected = vec![0u8; 31];
+        expected.push(0x0a);
+        let mut hasher = Sha3_256::new();
+        hasher.update(&expected);
+        hasher.update(&expected);
+        hasher.update([0xfc]);
+        assert_eq!(store, format!("0x{}", hex::encode(hasher.finalize())));
+        assert_ne!(
+            store,
+            FungibleAsset::primary_store_address("0xb", "0xa").unwrap()
+        );
+        assert!(FungibleAsset::primary_store_address("0xzz", "0xa").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_balance_and_metadata() {
+        let transport = MockTransport::new()
+            .on_post("/view", 200, r#"["2500"]"#)
+            .on_get(
+                "/accounts/0xfa/resource/0x1::fungible_asset::Metadata",
+                200,
+                r#"{"type":"0x1::fungible_asset::Metadata","data":{"name":"Fungible","symbol":"FA","decimals":8,"icon_uri":"https://fa.test/icon.png","project_uri":"https://fa.test"}}"#,
+            )
+            .on_get(
+                "/accounts/0xfa/resource/0x1::fungible_asset::Supply",
+                200,
+                r#"{"type":"0x1::fungible_asset::Supply","data":{"current":"1000","maximum":{"vec":["5000"]}}}"#,
+            );
+        let client = Arc::new(Aptos::with_transport(
+            AptosType::Mainnet,
+            Arc::new(transport),
+        ));
+        assert_eq!(
+            FungibleAsset::balance(Arc::clone(&client), "0xa", "0xfa")
+                .await
+                .unwrap(),
+            2500
+        );
+        let metadata = FungibleAsset::metadata(client, "0xfa").await.unwrap();
+        assert_eq!(metadata.symbol, "FA");
+        assert_eq!(metadata.icon_uri, "https://fa.test/icon.png");
+        assert_eq!(
+            (metadata.supply, metadata.maximum),
+            (Some(1000), Some(5000))
+        );
+    }
+
+    #[test]
+    fn test_transfer_and_issuer_calls() {
+        let call = FungibleAsset::build_transfer_call("0xfa", "0xb", 7);
+        assert_eq!(call.function_name, "transfer");
+        assert_eq!(call.type_arguments, vec![METADATA_TYPE]);
+        assert_eq!(
+            call.arguments,
+            vec![json!("0xfa"), json!("0xb"), json!("7")]
+        );
+        let mint =
+            FungibleAsset::issuer_call("0xabc::managed_fungible_asset", "mint", vec![]).unwrap();
+        assert_eq!(
+            (mint.module_address.as_str(), mint.module_name.as_str()),
+            ("0xabc", "managed_fungible_asset")
+        );
+        assert!(FungibleAsset::issuer_call("0xabc", "mint", vec![]).is_err());
+    }
+}
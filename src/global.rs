@@ -4,6 +4,10 @@ pub mod rpc {
     pub const APTOS_MAINNET_URL: &str = "https://fullnode.mainnet.aptoslabs.com/v1";
     pub const APTOS_TESTNET_URL: &str = "https://fullnode.testnet.aptoslabs.com/v1";
     pub const APTOS_DEVNET_URL: &str = "https://fullnode.devnet.aptoslabs.com/v1";
+    /// aptos indexer graphql url
+    pub const APTOS_MAINNET_INDEXER_URL: &str = "https://api.mainnet.aptoslabs.com/v1/graphql";
+    pub const APTOS_TESTNET_INDEXER_URL: &str = "https://api.testnet.aptoslabs.com/v1/graphql";
+    pub const APTOS_DEVNET_INDEXER_URL: &str = "https://api.devnet.aptoslabs.com/v1/graphql";
 }
 pub mod mainnet {
     /// system reserved address.
@@ -71,6 +75,12 @@ pub mod mainnet {
         // cellana swap protocol address
         pub const CELLANASWAP_PROTOCOL_ADDRESS: &str =
             "0x9b5a27d3e7c7c8f7f313f43e4bdc00d8b652b0c5e0e0e0e0e0e0e0e0e0e0e0e0";
+        // aptos name service router address
+        pub const ANS_ROUTER_PROTOCOL_ADDRESS: &str =
+            "0x867ed1f6bf916171b1de3ee92849b8978b7d1b9e0a8cc982a3d19d535dfd9c0c";
+        // pyth price oracle address
+        pub const PYTH_PROTOCOL_ADDRESS: &str =
+            "0x7e783b349d3e89cf5931af376ebeadbfab855b3fa239b7ada8f5a92fbea6b387";
     }
     pub mod nft_market {
         pub const TOPAZ: &'static str =
@@ -90,6 +100,15 @@ pub mod mainnet {
         pub const WAPAL: &'static str =
             "0x2a0c6a5d8e4f7b3c1d9e8a7b6c5d4e3f2a1b0c9d8e7f6a5b4c3d2e1f0a9b8c7";
     }
+    /// pyth price feed ids
+    pub mod price_feed {
+        pub const APT_USD: &str =
+            "0x03ae4db29ed4ae33d323568895aa00337e658e348b37509f5372ae51f0af00d5";
+        pub const USDC_USD: &str =
+            "0xeaa020c61cc479712813461ce153894a96a6c00b21ed0cfc2798d1f9a9e9c94a";
+        pub const USDT_USD: &str =
+            "0x2b89b9dc8fdf9f34709a5b106b472f0f39bb6ca9ce04b0fd7f2e971688e2e53b";
+    }
     pub mod token_address {
         pub const APT: &str = "0x1::aptos_coin::AptosCoin";
         pub const USDC: &str =
@@ -104,3 +123,227 @@ pub mod mainnet {
             "0x7fd500c11216f0fe3095d0c4b8aa4d64a4e2e04f83758462f2b127255643615::thl_coin::THL";
     }
 }
+
+/// DEX names accepted by `NetworkConfig::dex_address`
+const DEX_NAMES: &[&str] = &[
+    "Liquidswap",
+    "Thala",
+    "PancakeSwap",
+    "AnimeSwap",
+    "AuxExchange",
+    "Cellana",
+];
+
+/// Protocol, token and market addresses of one network.
+///
+/// Every `Aptos` client carries the config of its network, DEX and token helpers
+/// read addresses from it. `None` marks a protocol that is not deployed there.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkConfig {
+    pub liquidswap: Option<String>,
+    pub thala: Option<String>,
+    pub pancakeswap: Option<String>,
+    pub animeswap: Option<String>,
+    pub auxswap: Option<String>,
+    pub cellana: Option<String>,
+    pub ans_router: Option<String>,
+    pub pyth: Option<String>,
+    pub apt: String,
+    pub usdc: Option<String>,
+    pub usdt: Option<String>,
+    pub wormhole_usdc: Option<String>,
+    pub cake: Option<String>,
+    pub thl: Option<String>,
+    /// `(name, address)` of the nft markets
+    pub nft_markets: Vec<(String, String)>,
+}
+
+impl NetworkConfig {
+    pub fn mainnet() -> Self {
+        use mainnet::{nft_market, protocol_address::*, token_address};
+        let some = |address: &str| Some(address.to_string());
+        NetworkConfig {
+            liquidswap: some(LIQUIDSWAP_PROTOCOL_ADDRESS),
+            thala: some(THALA_PROTOCOL_ADDRESS),
+            pancakeswap: some(PANCAKESWAP_FACTORY_PROTOCOL_ADDRESS),
+            animeswap: some(ANIMESWAP_PROTOCOL_ADDRESS),
+            auxswap: some(AUXSWAP_PROTOCOL_ADDRESS),
+            cellana: some(CELLANASWAP_PROTOCOL_ADDRESS),
+            ans_router: some(ANS_ROUTER_PROTOCOL_ADDRESS),
+            pyth: some(PYTH_PROTOCOL_ADDRESS),
+            apt: token_address::APT.to_string(),
+            usdc: some(token_address::USDC),
+            usdt: some(token_address::USDT),
+            wormhole_usdc: some(token_address::WORMHOLE_USDC),
+            cake: some(token_address::CAKE),
+            thl: some(token_address::THL),
+            nft_markets: [
+                ("Topaz", nft_market::TOPAZ),
+                ("Souffl3", nft_market::SOUFFL3),
+                ("BlueMove", nft_market::BLUEMOVE),
+                ("Mercato", nft_market::MERCATO),
+                ("AUX", nft_market::AUX_EXCHANGE),
+                ("PancakeSwap", nft_market::PANCAKE_SWAP_NFT),
+                ("Tradeport", nft_market::TRADEPORT),
+                ("Wapal", nft_market::WAPAL),
+            ]
+            .into_iter()
+            .map(|(name, address)| (name.to_string(), address.to_string()))
+            .collect(),
+        }
+    }
+
+    /// testnet names and pyth, no DEX or bridged token deployments
+    pub fn testnet() -> Self {
+        NetworkConfig {
+            ans_router: Some(
+                "0x5f8fd2347449685cf41d4db97926ec3a096eaf381332be4f1318ad4d16a8497c".to_string(),
+            ),
+            pyth: Some(mainnet::protocol_address::PYTH_PROTOCOL_ADDRESS.to_string()),
+            ..Self::devnet()
+        }
+    }
+
+    /// framework only
+    pub fn devnet() -> Self {
+        NetworkConfig {
+            liquidswap: None,
+            thala: None,
+            pancakeswap: None,
+            animeswap: None,
+            auxswap: None,
+            cellana: None,
+            ans_router: None,
+            pyth: None,
+            apt: mainnet::token_address::APT.to_string(),
+            usdc: None,
+            usdt: None,
+            wormhole_usdc: None,
+            cake: None,
+            thl: None,
+            nft_markets: vec![],
+        }
+    }
+
+    pub fn for_network(network: &crate::AptosType) -> Self {
+        match network {
+            crate::AptosType::Mainnet => Self::mainnet(),
+            crate::AptosType::Testnet => Self::testnet(),
+            crate::AptosType::Devnet => Self::devnet(),
+        }
+    }
+
+    /// address of a DEX by its `DexAggregator` name, e.g. `"Liquidswap"`
+    pub fn dex_address(&self, dex_name: &str) -> Result<&str, String> {
+        let address = match dex_name {
+            "Liquidswap" => &self.liquidswap,
+            "Thala" => &self.thala,
+            "PancakeSwap" => &self.pancakeswap,
+            "AnimeSwap" => &self.animeswap,
+            "AuxExchange" => &self.auxswap,
+            "Cellana" => &self.cellana,
+            _ => return Err(format!("unknown dex: {}", dex_name)),
+        };
+        address
+            .as_deref()
+            .ok_or_else(|| format!("{} is not deployed on this network", dex_name))
+    }
+
+    /// DEX protocol address by name, case insensitive, e.g. `"liquidswap"`,
+    /// `None` for an unknown name or a DEX not deployed on this network
+    pub fn resolve_dex(&self, name: &str) -> Option<&str> {
+        DEX_NAMES
+            .iter()
+            .find(|known| known.eq_ignore_ascii_case(name))
+            .and_then(|known| self.dex_address(known).ok())
+    }
+
+    /// tokens deployed on this network as `(symbol, address)`
+    pub fn token_symbols(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("APT", Some(&self.apt)),
+            ("USDC", self.usdc.as_ref()),
+            ("USDT", self.usdt.as_ref()),
+            ("whUSDC", self.wormhole_usdc.as_ref()),
+            ("CAKE", self.cake.as_ref()),
+            ("THL", self.thl.as_ref()),
+        ]
+        .into_iter()
+        .filter_map(|(symbol, address)| Some((symbol, address?.as_str())))
+        .collect()
+    }
+
+    /// token address by symbol, case insensitive, e.g. `"usdc"`
+    pub fn resolve_token_symbol(&self, symbol: &str) -> Option<&str> {
+        self.token_symbols()
+            .into_iter()
+            .find(|(known, _)| known.eq_ignore_ascii_case(symbol))
+            .map(|(_, address)| address)
+    }
+
+    /// nft market address by name
+    pub fn nft_market(&self, name: &str) -> Option<&str> {
+        self.nft_markets
+            .iter()
+            .find(|(market, _)| market == name)
+            .map(|(_, address)| address.as_str())
+    }
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self::mainnet()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_token_symbol_and_dex() {
+        let config = NetworkConfig::mainnet();
+        assert_eq!(
+            config.resolve_token_symbol("APT"),
+            Some(mainnet::token_address::APT)
+        );
+        assert_eq!(
+            config.resolve_token_symbol("usdc"),
+            Some(mainnet::token_address::USDC)
+        );
+        assert_eq!(
+            config.resolve_token_symbol("WHUSDC"),
+            Some(mainnet::token_address::WORMHOLE_USDC)
+        );
+        assert_eq!(config.resolve_token_symbol("DOGE"), None);
+        assert_eq!(config.resolve_token_symbol(""), None);
+        assert_eq!(config.token_symbols().len(), 6);
+        assert_eq!(
+            config.token_symbols()[0],
+            ("APT", "0x1::aptos_coin::AptosCoin")
+        );
+        assert_eq!(
+            config.resolve_dex("liquidswap"),
+            Some(mainnet::protocol_address::LIQUIDSWAP_PROTOCOL_ADDRESS)
+        );
+        assert_eq!(config.resolve_dex("Uniswap"), None);
+        // other networks only list what is deployed there
+        let devnet = NetworkConfig::devnet();
+        assert_eq!(
+            devnet.token_symbols(),
+            vec![("APT", mainnet::token_address::APT)]
+        );
+        assert_eq!(devnet.resolve_token_symbol("USDC"), None);
+        assert_eq!(devnet.resolve_dex("Liquidswap"), None);
+        let custom = NetworkConfig {
+            usdc: Some("0xabc::usdc::USDC".to_string()),
+            cellana: Some("0xce11".to_string()),
+            ..NetworkConfig::devnet()
+        };
+        assert_eq!(
+            custom.resolve_token_symbol("usdc"),
+            Some("0xabc::usdc::USDC")
+        );
+        assert_eq!(custom.resolve_dex("CELLANA"), Some("0xce11"));
+    }
+}
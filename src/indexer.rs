@@ -0,0 +1,203 @@
+/// Aptos indexer graphql client.
+///
+/// The indexer follows the chain through processors, each one tracking the last
+/// ledger version it has processed. Its data typically lags the fullnode by a few
+/// seconds and by minutes under load, so a read right after a write may not see
+/// it yet; use `wait_for_version` with the version of the committed transaction and
+/// the processors behind the tables the read queries.
+use crate::{
+    AptosType,
+    global::rpc::{APTOS_DEVNET_INDEXER_URL, APTOS_MAINNET_INDEXER_URL, APTOS_TESTNET_INDEXER_URL},
+    transport::{HttpTransport, ReqwestTransport},
+};
+use serde_json::{Value, json};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// delay between processor status polls
+const INDEXER_POLL_DELAY_TIME: u64 = 500;
+
+/// processor of the `events` table, read by `Aptos::get_module_events`
+pub const EVENTS_PROCESSOR: &str = "events_processor";
+/// processor of the `current_objects` table, read by `Aptos::get_owned_objects`
+pub const OBJECTS_PROCESSOR: &str = "objects_processor";
+
+#[derive(Debug, Clone)]
+pub struct Indexer {
+    transport: Arc<dyn HttpTransport>,
+    url: String,
+}
+
+impl Indexer {
+    pub fn new(network: AptosType) -> Self {
+        Self::with_transport(network, Arc::new(ReqwestTransport::new()))
+    }
+
+    /// create an indexer client on a custom transport
+    pub fn with_transport(network: AptosType, transport: Arc<dyn HttpTransport>) -> Self {
+        let url = match network {
+            AptosType::Mainnet => APTOS_MAINNET_INDEXER_URL,
+            AptosType::Testnet => APTOS_TESTNET_INDEXER_URL,
+            AptosType::Devnet => APTOS_DEVNET_INDEXER_URL,
+        };
+        Self::from_url(url, transport)
+    }
+
+    /// create an indexer client for a self hosted or third party endpoint
+    pub fn from_url(url: &str, transport: Arc<dyn HttpTransport>) -> Self {
+        Indexer {
+            transport,
+            url: url.to_string(),
+        }
+    }
+
+    /// run a graphql query, returning its `data`
+    pub async fn query(&self, query: &str, variables: Value) -> Result<Value, String> {
+        let body = json!({ "query": query, "variables": variables });
+        let response = self.transport.post(&self.url, &body).await?;
+        if !response.is_success() {
+            let error_msg = response.body;
+            return Err(format!("indexer error: {}", error_msg));
+        }
+        let mut result: Value = response.json()?;
+        if let Some(errors) = result.get("errors").and_then(|e| e.as_array()) {
+            let messages: Vec<&str> = errors
+                .iter()
+                .filter_map(|e| e.get("message").and_then(|m| m.as_str()))
+                .collect();
+            return Err(format!("indexer query failed: {}", messages.join("; ")));
+        }
+        Ok(result["data"].take())
+    }
+
+    /// last processed ledger version of each indexer processor
+    pub async fn get_processor_status(&self) -> Result<HashMap<String, u64>, String> {
+        let data = self
+            .query(
+                "query ProcessorStatus { processor_status { processor last_success_version } }",
+                json!({}),
+            )
+            .await?;
+        let statuses = data["processor_status"]
+            .as_array()
+            .ok_or_else(|| "processor status missing".to_string())?;
+        Ok(statuses
+            .iter()
+            .filter_map(|status| {
+                let processor = status["processor"].as_str()?;
+                let version = match &status["last_success_version"] {
+                    Value::Number(n) => n.as_u64()?,
+                    Value::String(s) => s.parse().ok()?,
+                    _ => return None,
+                };
+                Some((processor.to_string(), version))
+            })
+            .collect())
+    }
+
+    /// Wait until `processors`, those behind the tables a read depends on (e.g.
+    /// `OBJECTS_PROCESSOR`), have processed `version`. An empty list waits for every
+    /// processor, a stalled processor the read does not use then holds it up.
+    ///
+    /// Returns the processor status once caught up, an error after `timeout_secs`.
+    pub async fn wait_for_version(
+        &self,
+        version: u64,
+        processors: &[&str],
+        timeout_secs: u64,
+    ) -> Result<HashMap<String, u64>, String> {
+        let start = Instant::now();
+        let timeout = Duration::from_secs(timeout_secs);
+        loop {
+            let status = self.get_processor_status().await?;
+            let processed = if processors.is_empty() {
+                status.values().min().copied()
+            } else {
+                processors
+                    .iter()
+                    .map(|processor| status.get(*processor).copied())
+                    .collect::<Option<Vec<u64>>>()
+                    .and_then(|versions| versions.into_iter().min())
+            };
+            if processed.is_some_and(|processed| processed >= version) {
+                return Ok(status);
+            }
+            if start.elapsed() >= timeout {
+                return Err(format!(
+                    "indexer did not reach version {} within {}s, at {:?}",
+                    version, timeout_secs, processed
+                ));
+            }
+            tokio::time::sleep(Duration::from_millis(INDEXER_POLL_DELAY_TIME)).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{HttpResponse, MockTransport};
+
+    fn status_body(versions: &[(&str, u64)]) -> String {
+        let statuses: Vec<Value> = versions
+            .iter()
+            .map(|(processor, version)| {
+                json!({"processor": processor, "last_success_version": version})
+            })
+            .collect();
+        json!({"data": {"processor_status": statuses}}).to_string()
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_version_polls_until_caught_up() {
+        let transport = Arc::new(
+            MockTransport::new()
+                .on_post(
+                    "/graphql",
+                    200,
+                    &status_body(&[("coin_processor", 90), ("token_v2_processor", 120)]),
+                )
+                .on_post(
+                    "/graphql",
+                    200,
+                    &status_body(&[("coin_processor", 130), ("token_v2_processor", 125)]),
+                ),
+        );
+        let indexer = Indexer::with_transport(AptosType::Mainnet, transport.clone());
+        let status = indexer.get_processor_status().await.unwrap();
+        assert_eq!(status["coin_processor"], 90);
+        let status = indexer.wait_for_version(100, &[], 5).await.unwrap();
+        assert_eq!(status["token_v2_processor"], 125);
+        // only the listed processors count, the lagging one is ignored
+        let status = indexer
+            .wait_for_version(130, &["coin_processor"], 5)
+            .await
+            .unwrap();
+        assert_eq!(status["coin_processor"], 130);
+        assert!(indexer.wait_for_version(130, &[], 0).await.is_err());
+        // an unknown processor never catches up
+        assert!(
+            indexer
+                .wait_for_version(100, &[OBJECTS_PROCESSOR], 0)
+                .await
+                .is_err()
+        );
+        let body = transport.requests()[0].body.clone().unwrap();
+        assert!(body["query"].as_str().unwrap().contains("processor_status"));
+    }
+
+    #[tokio::test]
+    async fn test_query_surfaces_graphql_errors() {
+        let transport = Arc::new(MockTransport::new().on(
+            "POST",
+            "/graphql",
+            HttpResponse::new(200, r#"{"errors":[{"message":"field not found"}]}"#),
+        ));
+        let indexer = Indexer::from_url("http://localhost:8090/v1/graphql", transport);
+        let err = indexer.query("{ nope }", json!({})).await.unwrap_err();
+        assert!(err.contains("field not found"));
+    }
+}
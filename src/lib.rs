@@ -1,32 +1,119 @@
+pub mod ans;
 pub mod block;
+#[cfg(feature = "bridge")]
 pub mod bridge;
 pub mod contract;
+#[cfg(feature = "dex")]
 pub mod dex;
+pub mod error;
 pub mod event;
+pub mod fungible_asset;
 pub mod global;
+#[cfg(feature = "indexer")]
+pub mod indexer;
+mod log;
+mod move_value;
 pub mod multicall;
+pub mod multisig;
+#[cfg(feature = "nft")]
 pub mod nft;
+#[cfg(feature = "nft")]
 pub mod nft_market;
+pub mod oracle;
+pub mod pool;
+pub mod prelude;
+pub mod signing;
+#[cfg(feature = "staking")]
 pub mod staking;
+pub mod throttle;
 pub mod token;
 pub mod tool;
 pub mod trade;
+pub mod transport;
 pub mod types;
 pub mod wallet;
+pub mod watcher;
 
+/// mainnet protocol addresses, e.g. `protocols::LIQUIDSWAP_PROTOCOL_ADDRESS`
+pub use global::mainnet::protocol_address as protocols;
+/// mainnet token addresses, e.g. `tokens::APT`
+pub use global::mainnet::token_address as tokens;
+
+#[cfg(feature = "indexer")]
+use crate::indexer::Indexer;
 use crate::{
+    ans::AnsCache,
     block::Block,
-    global::rpc::{APTOS_DEVNET_URL, APTOS_MAINNET_URL, APTOS_TESTNET_URL},
-    trade::TransactionInfo,
+    error::AptosError,
+    global::{
+        NetworkConfig,
+        rpc::{APTOS_DEVNET_URL, APTOS_MAINNET_URL, APTOS_TESTNET_URL},
+    },
+    throttle::{ThrottleMetrics, ThrottledTransport},
+    trade::{TransactionInfo, TransactionType},
+    transport::{HttpTransport, ReqwestTransport},
     types::*,
+    wallet::Wallet,
 };
-use reqwest::Client;
+#[cfg(feature = "indexer")]
+use futures::TryStreamExt;
+use futures::{Stream, StreamExt};
+use serde::{Serialize, de::DeserializeOwned};
 use serde_json::Value;
-use std::time::Duration;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock},
+    time::Duration,
+};
+
+/// how long `Aptos::get_apt_price_usd` reuses a price
+#[cfg(feature = "dex")]
+pub const APT_PRICE_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// content type of bcs encoded signed transactions
+const SIGNED_TRANSACTION_BCS_CONTENT_TYPE: &str = "application/x.aptos.signed_transaction+bcs";
 
 /// waiting transaction delay time
 const WAITING_TRANSACTION_DELAY_TIME: u64 = 500;
 
+/// consecutive failed polls `Aptos::waiting_transaction` tolerates, a 404 is not a failure
+const WAITING_TRANSACTION_MAX_ERRORS: u32 = 5;
+
+/// ledger info polling interval of `Aptos::wait_for_next_epoch` once the epoch is due
+const EPOCH_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// polling interval of `Aptos::wait_for_next_epoch` when the epoch schedule is unknown
+const EPOCH_FALLBACK_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// resources fetched per page by `get_account_resource_vec`
+const RESOURCE_PAGE_LIMIT: u64 = 1000;
+
+/// events fetched per page by `get_all_account_events`
+const EVENT_PAGE_LIMIT: u64 = 100;
+
+/// events returned by `get_all_account_events` when no maximum is given
+pub const DEFAULT_MAX_EVENTS: u64 = 1000;
+
+/// latest transactions of an account searched by `detect_account_scheme`
+pub const ACCOUNT_SCHEME_SCAN_LIMIT: u64 = 25;
+
+/// objects listed by `get_owned_objects` when no limit is given
+#[cfg(feature = "indexer")]
+pub const DEFAULT_OWNED_OBJECTS_LIMIT: u64 = 100;
+
+/// object types looked up on the node at a time by `get_owned_objects`
+#[cfg(feature = "indexer")]
+const OBJECT_LOOKUP_CONCURRENCY: usize = 8;
+
+/// resources that name the kind of an object, preferred over its other resources
+#[cfg(feature = "indexer")]
+const PRIMARY_OBJECT_TYPES: [&str; 4] = [
+    "0x4::token::Token",
+    "0x4::collection::Collection",
+    "0x1::fungible_asset::FungibleStore",
+    "0x1::fungible_asset::Metadata",
+];
+
 /// client type
 #[derive(Debug, Clone)]
 pub enum AptosType {
@@ -35,23 +122,170 @@ pub enum AptosType {
     Devnet,
 }
 
+/// a transaction submitted through `Aptos::submit_transaction_idempotent`
+#[derive(Debug, Clone)]
+struct SubmittedTxn {
+    hash: String,
+    expiration_timestamp_secs: Option<u64>,
+}
+
+/// one lookup of a transaction hash
+#[derive(Debug)]
+struct TxPoll {
+    status: TxStatus,
+    /// the committed transaction
+    transaction: Option<TransactionInfo>,
+    /// expiration of a pending transaction
+    expiration_timestamp_secs: Option<u64>,
+    /// ledger timestamp of the answering node, from `x-aptos-ledger-timestamp`
+    ledger_timestamp_secs: Option<u64>,
+}
+
+/// Aptos node client.
+///
+/// Clones share the transport and caches. The client and the wallets are `Send + Sync`,
+/// so they can be moved into spawned tasks behind an `Arc`:
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use aptos_network_sdk::{Aptos, AptosType, wallet::Wallet};
+///
+/// # async fn example() -> Result<(), String> {
+/// let client = Arc::new(Aptos::new(AptosType::Mainnet));
+/// let wallet = Arc::new(Wallet::new()?);
+/// let balance = tokio::spawn({
+///     let client = Arc::clone(&client);
+///     async move { client.get_account_balance(wallet.address()).await }
+/// });
+/// println!("balance: {}", balance.await.unwrap()?);
+/// # Ok(())
+/// # }
+/// ```
 #[derive(Debug, Clone)]
 pub struct Aptos {
-    client: Client,
+    transport: Arc<dyn HttpTransport>,
     base_url: String,
+    /// installed by `with_rate_limit` / `with_circuit_breaker`, shared by clones
+    throttle: Option<Arc<ThrottledTransport>>,
+    ans_cache: Arc<AnsCache>,
+    /// chain id reported by the node, learned on first use
+    chain_id: Arc<OnceLock<u8>>,
+    /// protocol and token addresses of the client's network
+    network: Arc<NetworkConfig>,
+    /// hash and expiration of the transactions submitted through
+    /// `submit_transaction_idempotent`, by sender and sequence number
+    submitted: Arc<Mutex<HashMap<(String, u64), SubmittedTxn>>>,
+    /// gas schedule and the epoch it was read in
+    gas_schedule: Arc<Mutex<Option<(u64, GasSchedule)>>>,
+    /// last DEX-derived APT price in USD
+    #[cfg(feature = "dex")]
+    apt_price: Arc<Mutex<Option<(std::time::Instant, f64)>>>,
+    /// pool reserves reused by DEX quotes, set by `with_reserve_cache`
+    #[cfg(feature = "dex")]
+    reserve_cache: Option<Arc<dex::ReserveCache>>,
+    /// indexer used for queries the node cannot answer, set by `with_indexer`
+    #[cfg(feature = "indexer")]
+    indexer: Option<Arc<Indexer>>,
 }
 
 impl Aptos {
     pub fn new(network: AptosType) -> Self {
+        Self::with_transport(network, Arc::new(ReqwestTransport::new()))
+    }
+
+    /// create a client on a custom transport, e.g. `transport::MockTransport` in tests
+    pub fn with_transport(network: AptosType, transport: Arc<dyn HttpTransport>) -> Self {
         let base_url = match network {
             AptosType::Mainnet => APTOS_MAINNET_URL.to_string(),
             AptosType::Testnet => APTOS_TESTNET_URL.to_string(),
             AptosType::Devnet => APTOS_DEVNET_URL.to_string(),
         };
+        Self::with_base_url(base_url, transport)
+            .with_network_config(NetworkConfig::for_network(&network))
+    }
+
+    /// client on a node url ending in `/v1`
+    pub(crate) fn with_base_url(base_url: String, transport: Arc<dyn HttpTransport>) -> Self {
         Aptos {
-            client: Client::new(),
+            transport,
             base_url,
+            throttle: None,
+            ans_cache: Arc::new(AnsCache::default()),
+            chain_id: Arc::new(OnceLock::new()),
+            network: Arc::new(NetworkConfig::mainnet()),
+            submitted: Arc::new(Mutex::new(HashMap::new())),
+            gas_schedule: Arc::new(Mutex::new(None)),
+            #[cfg(feature = "dex")]
+            apt_price: Arc::new(Mutex::new(None)),
+            #[cfg(feature = "dex")]
+            reserve_cache: None,
+            #[cfg(feature = "indexer")]
+            indexer: None,
+        }
+    }
+
+    /// use `config` for protocol and token addresses, e.g. on a custom network
+    pub fn with_network_config(mut self, config: NetworkConfig) -> Self {
+        self.network = Arc::new(config);
+        self
+    }
+
+    /// read module events and other indexed data through `indexer`
+    #[cfg(feature = "indexer")]
+    pub fn with_indexer(mut self, indexer: Indexer) -> Self {
+        self.indexer = Some(Arc::new(indexer));
+        self
+    }
+
+    /// Reuse pool reserves fetched by `DexAggregator` quotes for up to `ttl`.
+    ///
+    /// A quote may be up to `ttl` stale. A `DexEventMonitor` started on this client
+    /// drops a pool's reserves as soon as it observes an event of that pool, so with
+    /// a monitor the staleness is bounded by its poll interval instead.
+    #[cfg(feature = "dex")]
+    pub fn with_reserve_cache(mut self, ttl: Duration) -> Self {
+        self.reserve_cache = Some(Arc::new(dex::ReserveCache::new(ttl)));
+        self
+    }
+
+    /// reserve cache installed by `with_reserve_cache`
+    #[cfg(feature = "dex")]
+    pub fn reserve_cache(&self) -> Option<&Arc<dex::ReserveCache>> {
+        self.reserve_cache.as_ref()
+    }
+
+    /// protocol and token addresses of the client's network
+    pub fn network_config(&self) -> &NetworkConfig {
+        &self.network
+    }
+
+    /// gate all outbound requests through a token bucket of `requests_per_sec`
+    pub fn with_rate_limit(mut self, requests_per_sec: f64) -> Self {
+        self.throttle().set_rate_limit(requests_per_sec);
+        self
+    }
+
+    /// after `failure_threshold` consecutive failures, reject requests with
+    /// `AptosError::ServiceUnavailable` for `cooldown`
+    pub fn with_circuit_breaker(mut self, failure_threshold: u32, cooldown: Duration) -> Self {
+        self.throttle()
+            .set_circuit_breaker(failure_threshold, cooldown);
+        self
+    }
+
+    /// rate limiter and circuit breaker state, `None` if neither is configured
+    pub fn throttle_metrics(&self) -> Option<ThrottleMetrics> {
+        self.throttle.as_ref().map(|throttle| throttle.metrics())
+    }
+
+    fn throttle(&mut self) -> Arc<ThrottledTransport> {
+        if let Some(throttle) = &self.throttle {
+            return Arc::clone(throttle);
         }
+        let throttle = Arc::new(ThrottledTransport::new(Arc::clone(&self.transport)));
+        self.transport = Arc::clone(&throttle) as Arc<dyn HttpTransport>;
+        self.throttle = Some(Arc::clone(&throttle));
+        throttle
     }
 
     /// get chain height
@@ -66,29 +300,284 @@ impl Aptos {
         Ok(chain_info.ledger_version.parse::<u64>().unwrap_or(0))
     }
 
-    /// get account info
-    pub async fn get_account_info(&self, address: &str) -> Result<AccountInfo, String> {
-        let url: String = format!("{}/accounts/{}", self.base_url, address);
-        let response = self.client.get(&url).send().await.unwrap();
-        if !response.status().is_success() {
-            let error_msg = response.text().await.unwrap();
-            return Err(format!("api error: {}", error_msg).to_string());
+    /// current epoch, from the ledger info
+    pub async fn get_epoch(&self) -> Result<u64, String> {
+        let chain_info = self.get_chain_info().await?;
+        chain_info
+            .epoch
+            .parse::<u64>()
+            .map_err(|e| format!("invalid epoch {}: {}", chain_info.epoch, e))
+    }
+
+    /// Wait until the chain moves past the current epoch and return the new epoch.
+    ///
+    /// Epochs last hours on mainnet, so this sleeps until the expected end of the
+    /// epoch, `last_reconfiguration_time + epoch_interval` in ledger time, and only
+    /// then polls the ledger info every `EPOCH_POLL_INTERVAL`. Without a readable
+    /// schedule it polls every `EPOCH_FALLBACK_POLL_INTERVAL`. Staking rewards and
+    /// validator set changes take effect at the epoch boundary.
+    pub async fn wait_for_next_epoch(&self, timeout_secs: u64) -> Result<u64, String> {
+        let start = std::time::Instant::now();
+        let timeout = Duration::from_secs(timeout_secs);
+        let chain_info = self.get_chain_info().await?;
+        let epoch = chain_info
+            .epoch
+            .parse::<u64>()
+            .map_err(|e| format!("invalid epoch {}: {}", chain_info.epoch, e))?;
+        let mut ledger_timestamp = chain_info.ledger_timestamp.parse::<u64>().ok();
+        let epoch_end = self.expected_epoch_end().await;
+        while start.elapsed() < timeout {
+            let delay = match (epoch_end, ledger_timestamp) {
+                (Some(end), Some(now)) => {
+                    Duration::from_micros(end.saturating_sub(now)).max(EPOCH_POLL_INTERVAL)
+                }
+                _ => EPOCH_FALLBACK_POLL_INTERVAL,
+            };
+            tokio::time::sleep(delay.min(timeout.saturating_sub(start.elapsed()))).await;
+            if let Ok(chain_info) = self.get_chain_info().await {
+                match chain_info.epoch.parse::<u64>() {
+                    Ok(current) if current > epoch => return Ok(current),
+                    _ => ledger_timestamp = chain_info.ledger_timestamp.parse().ok(),
+                }
+            }
         }
+        Err(format!(
+            "epoch {} did not end within {}s",
+            epoch, timeout_secs
+        ))
+    }
 
-        let account_info: AccountInfo = response.json().await.unwrap();
+    /// ledger timestamp in microseconds at which the current epoch is due to end
+    async fn expected_epoch_end(&self) -> Option<u64> {
+        let (configuration, block) = futures::join!(
+            self.get_account_resource("0x1", "0x1::reconfiguration::Configuration"),
+            self.get_account_resource("0x1", "0x1::block::BlockResource"),
+        );
+        let field = |resource: Option<Resource>, name: &str| {
+            resource?.data[name].as_str()?.parse::<u64>().ok()
+        };
+        let last_reconfiguration = field(configuration.ok()?, "last_reconfiguration_time")?;
+        let epoch_interval = field(block.ok()?, "epoch_interval")?;
+        last_reconfiguration.checked_add(epoch_interval)
+    }
+
+    /// get account info, `AptosError::NotFound` if the account does not exist on chain
+    pub async fn get_account_info(&self, address: &str) -> Result<AccountInfo, AptosError> {
+        let url: String = format!("{}/accounts/{}", self.base_url, address);
+        let response = self
+            .transport
+            .get(&url)
+            .await
+            .map_err(AptosError::Transport)?;
+        if !response.is_success() {
+            return Err(AptosError::from_response(response));
+        }
+        let account_info: AccountInfo = response.json().map_err(AptosError::Parse)?;
         Ok(account_info)
     }
 
-    /// get account resources vec
+    /// get the authentication key of an account, it differs from the address once
+    /// the key has been rotated
+    pub async fn get_authentication_key(&self, address: &str) -> Result<String, AptosError> {
+        Ok(self.get_account_info(address).await?.authentication_key)
+    }
+
+    /// Detect the signing scheme of an account.
+    ///
+    /// The authentication key is only a hash of the key, so the scheme is read from
+    /// the signature of the newest of the account's last `ACCOUNT_SCHEME_SCAN_LIMIT`
+    /// transactions signed by the key the authentication key is derived from. A key
+    /// rotation is signed by the old key, so the transactions before it do not count.
+    /// `AccountScheme::Unknown` if no such transaction is found, e.g. the account has
+    /// not sent one since its last rotation.
+    pub async fn detect_account_scheme(&self, address: &str) -> Result<AccountScheme, String> {
+        if self
+            .get_account_resource(address, "0x1::multisig_account::MultisigAccount")
+            .await?
+            .is_some()
+        {
+            return Ok(AccountScheme::MultisigAccount);
+        }
+        let account = self.get_account_info(address).await?;
+        let sequence_number = account
+            .sequence_number
+            .parse::<u64>()
+            .map_err(|e| format!("invalid sequence number: {}", e))?;
+        if sequence_number == 0 {
+            return Ok(AccountScheme::Unknown);
+        }
+        let start = sequence_number.saturating_sub(ACCOUNT_SCHEME_SCAN_LIMIT);
+        let transactions = self
+            .get_account_transaction_vec(address, Some(sequence_number - start), Some(start))
+            .await?;
+        let signed_by_current_key = transactions.iter().rev().find_map(|txn| {
+            let TransactionType::UserTransaction(user_txn) = &txn.transaction_type else {
+                return None;
+            };
+            AccountScheme::authentication_key(&user_txn.signature)
+                .is_some_and(|key| key.eq_ignore_ascii_case(&account.authentication_key))
+                .then(|| AccountScheme::from_signature(&user_txn.signature))
+        });
+        Ok(signed_by_current_key.unwrap_or(AccountScheme::Unknown))
+    }
+
+    /// get account info for many addresses, at most `concurrency` requests in flight.
+    /// each address keeps its own result, one failing lookup does not fail the batch
+    pub async fn batch_get_account_info(
+        &self,
+        addresses: &[String],
+        concurrency: usize,
+    ) -> HashMap<String, Result<AccountInfo, AptosError>> {
+        futures::stream::iter(addresses.iter().cloned())
+            .map(|address| async move {
+                let result = self.get_account_info(&address).await;
+                (address, result)
+            })
+            .buffer_unordered(concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    /// get all account resources, following the pagination cursor
     pub async fn get_account_resource_vec(&self, address: &str) -> Result<Vec<Resource>, String> {
-        let url = format!("{}/accounts/{}/resources", self.base_url, address);
-        let response = self.client.get(&url).send().await.unwrap();
-        if !response.status().is_success() {
-            let error_msg = response.text().await.unwrap();
+        let mut resources = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let (page, next) = self
+                .get_account_resources_page(address, RESOURCE_PAGE_LIMIT, cursor.as_deref())
+                .await?;
+            resources.extend(page);
+            match next {
+                Some(next) => cursor = Some(next),
+                None => return Ok(resources),
+            }
+        }
+    }
+
+    /// Objects owned by `address`, newest first, listed through the indexer set with
+    /// `with_indexer`.
+    ///
+    /// Returns up to `limit` objects (`DEFAULT_OWNED_OBJECTS_LIMIT` when `None`) after
+    /// skipping `offset`. The type of each object is read from its resources on the
+    /// node, a few objects at a time.
+    #[cfg(feature = "indexer")]
+    pub async fn get_owned_objects(
+        &self,
+        address: &str,
+        limit: Option<u64>,
+        offset: u64,
+    ) -> Result<Vec<ObjectInfo>, String> {
+        let indexer = self
+            .indexer
+            .as_ref()
+            .ok_or("listing owned objects requires an indexer, see with_indexer")?;
+        let data = indexer
+            .query(
+                "query OwnedObjects($owner: String, $limit: Int, $offset: Int) {
+                    current_objects(
+                        where: {owner_address: {_eq: $owner}, is_deleted: {_eq: false}}
+                        order_by: [{last_transaction_version: desc}, {object_address: asc}]
+                        limit: $limit
+                        offset: $offset
+                    ) {
+                        object_address
+                        owner_address
+                    }
+                }",
+                serde_json::json!({
+                    "owner": address,
+                    "limit": limit.unwrap_or(DEFAULT_OWNED_OBJECTS_LIMIT),
+                    "offset": offset,
+                }),
+            )
+            .await?;
+        let rows = data["current_objects"]
+            .as_array()
+            .ok_or("missing current_objects")?;
+        let objects: Vec<(String, String)> = rows
+            .iter()
+            .filter_map(|row| {
+                Some((
+                    row["object_address"].as_str()?.to_string(),
+                    row["owner_address"].as_str()?.to_string(),
+                ))
+            })
+            .collect();
+        futures::stream::iter(objects)
+            .map(|(object_address, owner)| async move {
+                let resources = self.get_account_resource_vec(&object_address).await?;
+                Ok::<_, String>(ObjectInfo {
+                    r#type: object_type(&resources),
+                    object_address,
+                    owner,
+                })
+            })
+            .buffered(OBJECT_LOOKUP_CONCURRENCY)
+            .try_collect()
+            .await
+    }
+
+    /// Up to `limit` objects owned by `address` whose type is `object_type`, generic
+    /// instances included, scanning `get_owned_objects` page by page.
+    #[cfg(feature = "indexer")]
+    pub async fn get_owned_objects_of_type(
+        &self,
+        address: &str,
+        object_type: &str,
+        limit: Option<u64>,
+    ) -> Result<Vec<ObjectInfo>, String> {
+        let limit = limit.unwrap_or(DEFAULT_OWNED_OBJECTS_LIMIT) as usize;
+        let mut matches = Vec::new();
+        let mut offset = 0;
+        while matches.len() < limit {
+            let page = self
+                .get_owned_objects(address, Some(DEFAULT_OWNED_OBJECTS_LIMIT), offset)
+                .await?;
+            let last_page = (page.len() as u64) < DEFAULT_OWNED_OBJECTS_LIMIT;
+            offset += page.len() as u64;
+            matches.extend(page.into_iter().filter(|object| {
+                object.r#type == object_type
+                    || object
+                        .r#type
+                        .strip_prefix(object_type)
+                        .is_some_and(|generics| generics.starts_with('<'))
+            }));
+            if last_page {
+                break;
+            }
+        }
+        matches.truncate(limit);
+        Ok(matches)
+    }
+
+    /// Get one page of account resources.
+    ///
+    /// Returns the resources and the cursor of the next page, taken from the
+    /// `x-aptos-cursor` header, `None` on the last page.
+    pub async fn get_account_resources_page(
+        &self,
+        address: &str,
+        limit: u64,
+        cursor: Option<&str>,
+    ) -> Result<(Vec<Resource>, Option<String>), String> {
+        let mut url = format!(
+            "{}/accounts/{}/resources?limit={}",
+            self.base_url, address, limit
+        );
+        if let Some(cursor) = cursor {
+            url.push_str(&format!("&start={}", cursor));
+        }
+        let response = self.transport.get(&url).await?;
+        if !response.is_success() {
+            let error_msg = response.body;
             return Err(format!("api error: {}", error_msg).to_string());
         }
-        let resources: Vec<Resource> = response.json().await.unwrap();
-        Ok(resources)
+        let resources: Vec<Resource> = response.json()?;
+        let next = response
+            .header("x-aptos-cursor")
+            .filter(|cursor| !cursor.is_empty())
+            .map(|cursor| cursor.to_string());
+        Ok((resources, next))
     }
 
     /// get account resource
@@ -99,35 +588,129 @@ impl Aptos {
     ) -> Result<Option<Resource>, String> {
         let url = format!(
             "{}/accounts/{}/resource/{}",
-            self.base_url, address, resource_type
+            self.base_url,
+            address,
+            transport::encode_path_segment(resource_type)
         );
-        let response = self.client.get(&url).send().await.unwrap();
+        let response = self.transport.get(&url).await?;
 
-        if response.status() == 404 {
+        if response.status == 404 {
             return Ok(None);
         }
 
-        if !response.status().is_success() {
-            let error_msg = response.text().await.unwrap();
+        if !response.is_success() {
+            let error_msg = response.body;
             return Err(format!("api error: {}", error_msg).to_string());
         }
 
-        let resource: Resource = response.json().await.unwrap();
+        let resource: Resource = response.json()?;
         Ok(Some(resource))
     }
 
+    /// Whether `address` holds `resource_type`, for existence checks such as coin
+    /// registration or pool existence.
+    ///
+    /// Sends a head request so the resource is not transferred, nodes answering it
+    /// with 405 are asked with a get whose body is ignored.
+    pub async fn account_resource_exists(
+        &self,
+        address: &str,
+        resource_type: &str,
+    ) -> Result<bool, String> {
+        let url = format!(
+            "{}/accounts/{}/resource/{}",
+            self.base_url,
+            address,
+            transport::encode_path_segment(resource_type)
+        );
+        let mut response = self.transport.head(&url).await?;
+        if response.status == 405 {
+            response = self.transport.get(&url).await?;
+        }
+        match response.status {
+            404 => Ok(false),
+            _ if response.is_success() => Ok(true),
+            status => Err(format!("api error: status {} {}", status, response.body)),
+        }
+    }
+
+    /// get account resource with its data deserialized into `T`, e.g. `types::CoinStore`
+    pub async fn get_account_resource_typed<T: DeserializeOwned>(
+        &self,
+        address: &str,
+        resource_type: &str,
+    ) -> Result<Option<T>, String> {
+        match self.get_account_resource(address, resource_type).await? {
+            Some(resource) => serde_json::from_value(resource.data)
+                .map(Some)
+                .map_err(|e| format!("resource parsing error: {}", e)),
+            None => Ok(None),
+        }
+    }
+
     /// get account module vec
     pub async fn get_account_module_vec(&self, address: &str) -> Result<Vec<Module>, String> {
         let url = format!("{}/accounts/{}/modules", self.base_url, address);
-        let response = self.client.get(&url).send().await.unwrap();
-        if !response.status().is_success() {
-            let error_msg = response.text().await.unwrap();
+        let response = self.transport.get(&url).await?;
+        if !response.is_success() {
+            let error_msg = response.body;
             return Err(format!("api error: {}", error_msg).to_string());
         }
-        let modules: Vec<Module> = response.json().await.unwrap();
+        let modules: Vec<Module> = response.json()?;
         Ok(modules)
     }
 
+    /// Get one page of account modules.
+    ///
+    /// `start` is the cursor of the previous page. Returns the modules and the cursor
+    /// of the next page, taken from the `x-aptos-cursor` header, `None` on the last
+    /// page.
+    pub async fn get_account_module_page(
+        &self,
+        address: &str,
+        limit: Option<u64>,
+        start: Option<&str>,
+    ) -> Result<(Vec<Module>, Option<String>), String> {
+        let limit = limit.unwrap_or(25);
+        let mut url = format!(
+            "{}/accounts/{}/modules?limit={}",
+            self.base_url, address, limit
+        );
+        if let Some(start) = start {
+            url.push_str(&format!("&start={}", start));
+        }
+        let response = self.transport.get(&url).await?;
+        if !response.is_success() {
+            let error_msg = response.body;
+            return Err(format!("api error: {}", error_msg).to_string());
+        }
+        let modules: Vec<Module> = response.json()?;
+        let next = response
+            .header("x-aptos-cursor")
+            .filter(|cursor| !cursor.is_empty())
+            .map(|cursor| cursor.to_string());
+        Ok((modules, next))
+    }
+
+    /// get account modules whose name contains `name_substr` (case insensitive)
+    pub async fn get_modules_matching(
+        &self,
+        address: &str,
+        name_substr: &str,
+    ) -> Result<Vec<Module>, String> {
+        let name_substr = name_substr.to_lowercase();
+        let modules = self.get_account_module_vec(address).await?;
+        Ok(modules
+            .into_iter()
+            .filter(|module| {
+                module
+                    .name()
+                    .map(|name| name.to_lowercase().contains(&name_substr))
+                    .unwrap_or(false)
+            })
+            .collect())
+    }
+
     /// get account module
     pub async fn get_account_module(
         &self,
@@ -138,67 +721,376 @@ impl Aptos {
             "{}/accounts/{}/module/{}",
             self.base_url, address, module_name
         );
-        let response = self.client.get(&url).send().await.unwrap();
-        if response.status() == 404 {
+        let response = self.transport.get(&url).await?;
+        if response.status == 404 {
             return Ok(None);
         }
-        if !response.status().is_success() {
-            let error_msg = response.text().await.unwrap();
+        if !response.is_success() {
+            let error_msg = response.body;
             return Err(format!("api error: {}", error_msg).to_string());
         }
-        let module: Module = response.json().await.unwrap();
+        let module: Module = response.json()?;
         Ok(Some(module))
     }
 
-    /// submit transaction
+    /// get the abi of one function of a module, `None` if the module or function
+    /// does not exist
+    pub async fn get_function_abi(
+        &self,
+        address: &str,
+        module_name: &str,
+        function_name: &str,
+    ) -> Result<Option<Value>, String> {
+        let module = match self.get_account_module(address, module_name).await? {
+            Some(module) => module,
+            None => return Ok(None),
+        };
+        Ok(module
+            .abi
+            .as_ref()
+            .and_then(|abi| abi.get("exposed_functions"))
+            .and_then(|functions| functions.as_array())
+            .and_then(|functions| {
+                functions
+                    .iter()
+                    .find(|function| function["name"].as_str() == Some(function_name))
+            })
+            .cloned())
+    }
+
+    /// parameter types of an entry function without its leading signers, framework
+    /// functions the sdk builds are known and skip the abi lookup
+    pub async fn entry_function_params(&self, function: &str) -> Result<Vec<String>, String> {
+        if let Some(params) = signing::framework_params(function) {
+            return Ok(params);
+        }
+        let parts: Vec<&str> = function.split("::").collect();
+        if parts.len() != 3 {
+            return Err(format!("invalid function: {}", function));
+        }
+        let abi = self
+            .get_function_abi(parts[0], parts[1], parts[2])
+            .await?
+            .ok_or_else(|| format!("function not found: {}", function))?;
+        Ok(signing::entry_function_params(&abi))
+    }
+
+    /// BCS of a json raw transaction, arguments are encoded by the parameter types of
+    /// the function it calls
+    pub async fn raw_transaction_bcs(&self, raw_txn: &Value) -> Result<Vec<u8>, String> {
+        let params = match signing::payload_function(&raw_txn["payload"]) {
+            Some(function) => self.entry_function_params(function).await?,
+            None => vec![],
+        };
+        signing::raw_transaction_bcs(raw_txn, &params)
+    }
+
+    /// BCS of a json raw transaction calling `call`, typed arguments are encoded by
+    /// their own type and the abi is only read for `Json` ones
+    pub async fn contract_call_transaction_bcs(
+        &self,
+        raw_txn: &Value,
+        call: &ContractCall,
+    ) -> Result<Vec<u8>, String> {
+        let params = if call
+            .arguments
+            .iter()
+            .any(|argument| matches!(argument, MoveArgument::Json(_)))
+        {
+            Some(self.entry_function_params(&call.function_id()).await?)
+        } else {
+            None
+        };
+        signing::contract_call_transaction_bcs(raw_txn, &call.arguments, params.as_deref())
+    }
+
+    /// Simulate many raw transactions from `wallet`, e.g. candidate swap routes, at
+    /// most `concurrency` simulations in flight. Results are in input order and a
+    /// failing simulation does not fail the others.
+    pub async fn simulate_batch(
+        &self,
+        wallet: Arc<Wallet>,
+        raw_txns: Vec<Value>,
+        concurrency: usize,
+    ) -> Vec<SimulationResult> {
+        futures::stream::iter(raw_txns)
+            .map(|raw_txn| {
+                let wallet = Arc::clone(&wallet);
+                async move {
+                    let signed_txn =
+                        trade::Trade::create_signed_transaction_tx(wallet, raw_txn, vec![0u8; 64])?;
+                    self.simulate_transaction(&signed_txn).await
+                }
+            })
+            .buffered(concurrency.max(1))
+            .map(SimulationResult::from)
+            .collect()
+            .await
+    }
+
+    /// Submit a json encoded transaction, `submit_bcs_transaction` is preferred when
+    /// the transaction can be bcs encoded.
+    ///
+    /// A transaction carrying a `chain_id`, at its top level or in its `transaction`,
+    /// is rejected with a "chain id mismatch" error unless it matches the node's.
     pub async fn submit_transaction(&self, txn_payload: &Value) -> Result<TransactionInfo, String> {
+        let txn_chain_id = txn_payload
+            .get("chain_id")
+            .or_else(|| txn_payload.get("transaction")?.get("chain_id"))
+            .and_then(|v| v.as_u64().or_else(|| v.as_str()?.parse().ok()));
+        if let Some(txn_chain_id) = txn_chain_id {
+            let chain_id = self.chain_id().await?;
+            if txn_chain_id != chain_id as u64 {
+                return Err(format!(
+                    "chain id mismatch: transaction is for chain {} but the node is on chain {}",
+                    txn_chain_id, chain_id
+                ));
+            }
+        }
         let url = format!("{}/transactions", self.base_url);
-        let response = self
-            .client
-            .post(&url)
-            .header("Content-Type", "application/json")
-            .json(txn_payload)
-            .send()
-            .await
-            .unwrap();
-        if !response.status().is_success() {
-            let error_msg = response.text().await.unwrap();
+        let response = self.transport.post(&url, txn_payload).await?;
+        if !response.is_success() {
+            let error_msg = response.body;
             return Err(format!("transaction submit failed: {}", error_msg).to_string());
         }
-        let transaction: TransactionInfo = response.json().await.unwrap();
+        let transaction: TransactionInfo = response.json()?;
         Ok(transaction)
     }
 
+    /// Submit a bcs encoded signed transaction, e.g. from
+    /// `Trade::create_signed_transaction_bcs`.
+    ///
+    /// This is the preferred submission path: the node skips json parsing and the
+    /// bytes are exactly what was signed, while `submit_transaction` has the node
+    /// re-encode the json, where loosely typed arguments can be read differently.
+    pub async fn submit_bcs_transaction(
+        &self,
+        signed_txn_bytes: &[u8],
+    ) -> Result<TransactionInfo, String> {
+        let url = format!("{}/transactions", self.base_url);
+        let response = self
+            .transport
+            .post_bytes(&url, SIGNED_TRANSACTION_BCS_CONTENT_TYPE, signed_txn_bytes)
+            .await?;
+        if !response.is_success() {
+            return Err(format!("transaction submit failed: {}", response.body));
+        }
+        response.json()
+    }
+
+    /// Submit a signed transaction unless one with the same sender and sequence number
+    /// is already pending or committed, for retrying after a timeout.
+    ///
+    /// Pending transactions are only known when they were submitted through this
+    /// client, committed ones are looked up on chain. An ed25519 transaction's hash is
+    /// computed and recorded before it is submitted, so a submit whose response was
+    /// lost is still found on retry. Records are dropped once their transaction is
+    /// seen committed or has expired.
+    pub async fn submit_transaction_idempotent(
+        &self,
+        txn_payload: &Value,
+    ) -> Result<TxSubmission, String> {
+        let transaction = txn_payload.get("transaction").unwrap_or(txn_payload);
+        let sender = transaction["sender"]
+            .as_str()
+            .ok_or("transaction has no sender")?
+            .to_lowercase();
+        let sequence_number = match &transaction["sequence_number"] {
+            Value::String(s) => s.parse::<u64>().ok(),
+            other => other.as_u64(),
+        }
+        .ok_or("transaction has no sequence number")?;
+        let expiration = match &transaction["expiration_timestamp_secs"] {
+            Value::String(s) => s.parse::<u64>().ok(),
+            other => other.as_u64(),
+        };
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|e| e.to_string())?
+            .as_secs();
+        let key = (sender.clone(), sequence_number);
+        let known_hash = {
+            let mut submitted = self.submitted.lock().unwrap();
+            submitted.retain(|_, txn| txn.expiration_timestamp_secs.is_none_or(|secs| secs > now));
+            submitted.get(&key).map(|txn| txn.hash.clone())
+        };
+        if let Some(hash) = known_hash {
+            match self.fetch_transaction_status(&hash).await? {
+                TxPoll {
+                    status: TxStatus::Pending,
+                    ..
+                } => return Ok(TxSubmission::Pending { hash }),
+                TxPoll {
+                    transaction: Some(committed),
+                    ..
+                } => {
+                    self.submitted.lock().unwrap().remove(&key);
+                    return Ok(TxSubmission::Committed(committed));
+                }
+                _ => {}
+            }
+        }
+        let url = format!(
+            "{}/accounts/{}/transactions?limit=1&start={}",
+            self.base_url, sender, sequence_number
+        );
+        let response = self.transport.get(&url).await?;
+        if response.is_success() {
+            let committed: Vec<TransactionInfo> = response.json()?;
+            if let Some(existing) = committed.into_iter().find(|transaction| {
+                transaction
+                    .get_sequence_number()
+                    .is_some_and(|number| number == sequence_number)
+            }) {
+                self.submitted.lock().unwrap().remove(&key);
+                return Ok(TxSubmission::Committed(existing));
+            }
+        } else if response.status != 404 {
+            return Err(format!("api error: {}", response.body));
+        }
+        if let Ok(hash) = self.signed_transaction_hash(txn_payload).await {
+            self.submitted.lock().unwrap().insert(
+                key.clone(),
+                SubmittedTxn {
+                    hash,
+                    expiration_timestamp_secs: expiration,
+                },
+            );
+        }
+        let submitted = self.submit_transaction(txn_payload).await?;
+        self.submitted.lock().unwrap().insert(
+            key,
+            SubmittedTxn {
+                hash: submitted.hash.clone(),
+                expiration_timestamp_secs: expiration,
+            },
+        );
+        Ok(TxSubmission::Submitted(submitted))
+    }
+
+    /// Hash the node gives a signed json transaction, computed before submitting it.
+    /// Only single signer ed25519 transactions are supported.
+    pub async fn signed_transaction_hash(&self, signed_txn: &Value) -> Result<String, String> {
+        let signature = &signed_txn["signature"];
+        if signature["type"] != "ed25519_signature" {
+            return Err(format!("unsupported signature type: {}", signature["type"]));
+        }
+        let signature_bytes = |field: &str| {
+            let hex_str = signature[field]
+                .as_str()
+                .ok_or_else(|| format!("signature has no {}", field))?;
+            hex::decode(hex_str.trim_start_matches("0x"))
+                .map_err(|e| format!("invalid {}: {}", field, e))
+        };
+        let public_key = signature_bytes("public_key")?;
+        let signature = signature_bytes("signature")?;
+        let raw_txn_bcs = self.raw_transaction_bcs(&signed_txn["transaction"]).await?;
+        Ok(signing::ed25519_transaction_hash(
+            &raw_txn_bcs,
+            &public_key,
+            &signature,
+        ))
+    }
+
+    /// Simulate a signed transaction without committing it.
+    ///
+    /// The signature must not be valid, the node rejects simulating signed
+    /// transactions. Gas unit price and max gas amount are estimated by the node.
+    pub async fn simulate_transaction(
+        &self,
+        signed_txn: &Value,
+    ) -> Result<TransactionInfo, String> {
+        let url = format!(
+            "{}/transactions/simulate?estimate_gas_unit_price=true&estimate_max_gas_amount=true",
+            self.base_url
+        );
+        let response = self.transport.post(&url, signed_txn).await?;
+        if !response.is_success() {
+            let error_msg = response.body;
+            return Err(format!("transaction simulation failed: {}", error_msg));
+        }
+        let mut transactions: Vec<TransactionInfo> = response.json()?;
+        if transactions.is_empty() {
+            return Err("transaction simulation returned no result".to_string());
+        }
+        Ok(transactions.remove(0))
+    }
+
     /// get transaction info
     pub async fn get_transaction_info_by_hash(
         &self,
         tx_hash: &str,
     ) -> Result<TransactionInfo, String> {
         let url = format!("{}/transactions/by_hash/{}", self.base_url, tx_hash);
-        let response = self.client.get(&url).send().await.unwrap();
-        if !response.status().is_success() {
-            let error_msg = response.text().await.unwrap();
+        let response = self.transport.get(&url).await?;
+        if !response.is_success() {
+            let error_msg = response.body;
             return Err(format!("api error: {}", error_msg).to_string());
         }
         let transaction: TransactionInfo = response
             .json()
-            .await
             .map_err(|e| format!("transaction parsing error: {:?}", e))?;
         Ok(transaction)
     }
 
+    /// Status of a transaction hash.
+    ///
+    /// The node answers 404 for hashes it does not know and a `pending_transaction`
+    /// for hashes still in its mempool.
+    pub async fn get_transaction_status(&self, tx_hash: &str) -> Result<TxStatus, String> {
+        Ok(self.fetch_transaction_status(tx_hash).await?.status)
+    }
+
+    /// status with the committed transaction
+    async fn fetch_transaction_status(&self, tx_hash: &str) -> Result<TxPoll, String> {
+        let url = format!("{}/transactions/by_hash/{}", self.base_url, tx_hash);
+        let response = self.transport.get(&url).await?;
+        let mut poll = TxPoll {
+            status: TxStatus::NotFound,
+            transaction: None,
+            expiration_timestamp_secs: None,
+            ledger_timestamp_secs: response
+                .header("x-aptos-ledger-timestamp")
+                .and_then(|usecs| usecs.parse::<u64>().ok())
+                .map(|usecs| usecs / 1_000_000),
+        };
+        if response.status == 404 {
+            return Ok(poll);
+        }
+        if !response.is_success() {
+            return Err(format!("api error: {}", response.body));
+        }
+        let value: Value = response.json()?;
+        if value["type"] == "pending_transaction" {
+            poll.status = TxStatus::Pending;
+            poll.expiration_timestamp_secs = value["expiration_timestamp_secs"]
+                .as_str()
+                .and_then(|secs| secs.parse().ok());
+            return Ok(poll);
+        }
+        let transaction: TransactionInfo = serde_json::from_value(value)
+            .map_err(|e| format!("transaction parsing error: {:?}", e))?;
+        poll.status = if transaction.success {
+            TxStatus::Success
+        } else {
+            TxStatus::Failed(transaction.vm_status.clone())
+        };
+        poll.transaction = Some(transaction);
+        Ok(poll)
+    }
+
     /// get transaction by version
     pub async fn get_transaction_info_by_version(
         &self,
         version: u64,
     ) -> Result<TransactionInfo, String> {
         let url = format!("{}/transactions/by_version/{}", self.base_url, version);
-        let response = self.client.get(&url).send().await.unwrap();
-        if !response.status().is_success() {
-            let error_msg = response.text().await.unwrap();
+        let response = self.transport.get(&url).await?;
+        if !response.is_success() {
+            let error_msg = response.body;
             return Err(format!("api error: {}", error_msg).to_string());
         }
-        let transaction: TransactionInfo = response.json().await.unwrap();
+        let transaction: TransactionInfo = response.json()?;
         Ok(transaction)
     }
 
@@ -217,52 +1109,131 @@ impl Aptos {
         if let Some(start) = start {
             url.push_str(&format!("&start={}", start));
         }
-        let response = self.client.get(&url).send().await.unwrap();
-        if !response.status().is_success() {
-            let error_msg = response.text().await.unwrap();
+        let response = self.transport.get(&url).await?;
+        if !response.is_success() {
+            let error_msg = response.body;
             return Err(format!("api error: {}", error_msg).to_string());
         }
-        let transactions: Vec<TransactionInfo> = response.json().await.unwrap();
+        let transactions: Vec<TransactionInfo> = response.json()?;
         Ok(transactions)
     }
 
     /// get chain info
     pub async fn get_chain_info(&self) -> Result<ChainInfo, String> {
         let url = format!("{}/", self.base_url);
-        let response = self.client.get(&url).send().await.unwrap();
-        if !response.status().is_success() {
-            let error_msg = response.text().await.unwrap();
+        let response = self.transport.get(&url).await?;
+        if !response.is_success() {
+            let error_msg = response.body;
             return Err(format!("api error: {}", error_msg).to_string());
         }
-        let ledger_info: ChainInfo = response.json().await.unwrap();
+        let ledger_info: ChainInfo = response.json()?;
+        let _ = self.chain_id.set(ledger_info.chain_id);
         Ok(ledger_info)
     }
 
-    /// get block by height
-    pub async fn get_block_by_height(&self, height: u64) -> Result<Block, String> {
-        let url = format!("{}/blocks/by_height/{}", self.base_url, height);
-        let response = self.client.get(&url).send().await.unwrap();
-        if !response.status().is_success() {
-            let error_msg = response.text().await.unwrap();
+    /// chain id of the connected network, fetched once and then cached
+    pub async fn chain_id(&self) -> Result<u8, String> {
+        match self.chain_id.get() {
+            Some(chain_id) => Ok(*chain_id),
+            None => Ok(self.get_chain_info().await?.chain_id),
+        }
+    }
+
+    /// Check the node's health and ledger state.
+    ///
+    /// Only an unreachable ledger info endpoint is an error, a failing `/-/healthy`
+    /// check is reported as `healthy: false`.
+    pub async fn health(&self) -> Result<HealthStatus, String> {
+        let healthy = self
+            .transport
+            .get(&format!("{}/-/healthy", self.base_url))
+            .await
+            .is_ok_and(|response| response.is_success());
+        let chain_info = self.get_chain_info().await?;
+        Ok(HealthStatus {
+            healthy,
+            chain_id: chain_info.chain_id,
+            epoch: chain_info.epoch.parse().unwrap_or(0),
+            ledger_version: chain_info.ledger_version.parse().unwrap_or(0),
+            oldest_ledger_version: chain_info.oldest_ledger_version.parse().unwrap_or(0),
+            ledger_timestamp: chain_info.ledger_timestamp.parse().unwrap_or(0),
+            node_role: chain_info.node_role,
+            git_hash: chain_info.git_hash,
+        })
+    }
+
+    /// whether the node's latest ledger timestamp is within `max_lag_secs` of now
+    pub async fn is_synced(&self, max_lag_secs: u64) -> Result<bool, String> {
+        let chain_info = self.get_chain_info().await?;
+        let ledger_timestamp = chain_info
+            .ledger_timestamp
+            .parse::<u64>()
+            .map_err(|e| format!("invalid ledger timestamp: {}", e))?;
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|e| e.to_string())?
+            .as_secs();
+        Ok(now.saturating_sub(ledger_timestamp / 1_000_000) <= max_lag_secs)
+    }
+
+    /// get block by height, `with_transactions` embeds the block's transactions
+    pub async fn get_block_by_height(
+        &self,
+        height: u64,
+        with_transactions: bool,
+    ) -> Result<Block, String> {
+        let url = format!(
+            "{}/blocks/by_height/{}?with_transactions={}",
+            self.base_url, height, with_transactions
+        );
+        let response = self.transport.get(&url).await?;
+        if !response.is_success() {
+            let error_msg = response.body;
             return Err(format!("api error: {}", error_msg).to_string());
         }
-        let block: Block = response.json().await.unwrap();
+        let block: Block = response.json()?;
         Ok(block)
     }
 
-    /// get block by version
-    pub async fn get_block_by_version(&self, version: u64) -> Result<Block, String> {
-        let url = format!("{}/blocks/by_version/{}", self.base_url, version);
-        let response = self.client.get(&url).send().await.unwrap();
-        if !response.status().is_success() {
-            let error_msg = response.text().await.unwrap();
+    /// get block by version, `with_transactions` embeds the block's transactions
+    pub async fn get_block_by_version(
+        &self,
+        version: u64,
+        with_transactions: bool,
+    ) -> Result<Block, String> {
+        let url = format!(
+            "{}/blocks/by_version/{}?with_transactions={}",
+            self.base_url, version, with_transactions
+        );
+        let response = self.transport.get(&url).await?;
+        if !response.is_success() {
+            let error_msg = response.body;
             return Err(format!("api error: {}", error_msg).to_string());
         }
-        let block: Block = response.json().await.unwrap();
+        let block: Block = response.json()?;
         Ok(block)
     }
 
-    /// get account event vec
+    /// Walk blocks `from_height..=to_height` in order, with their transactions.
+    ///
+    /// Blocks are fetched lazily one at a time as the stream is polled.
+    pub fn blocks(
+        &self,
+        from_height: u64,
+        to_height: u64,
+    ) -> impl Stream<Item = Result<Block, String>> + '_ {
+        futures::stream::unfold(from_height, move |height| async move {
+            if height > to_height {
+                return None;
+            }
+            let block = self.get_block_by_height(height, true).await;
+            Some((block, height + 1))
+        })
+    }
+
+    /// Get account event vec, `event_type` is an event handle and its field, e.g.
+    /// `0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>/deposit_events`, or a
+    /// creation number.
     pub async fn get_account_event_vec(
         &self,
         address: &str,
@@ -271,155 +1242,770 @@ impl Aptos {
         start: Option<u64>,
     ) -> Result<Vec<Event>, String> {
         let limit = limit.unwrap_or(25);
+        let event_path = match event_type.rsplit_once('/') {
+            Some((handle, field)) => format!(
+                "{}/{}",
+                transport::encode_path_segment(handle),
+                transport::encode_path_segment(field)
+            ),
+            None => transport::encode_path_segment(event_type),
+        };
         let mut url = format!(
             "{}/accounts/{}/events/{}?limit={}",
-            self.base_url, address, event_type, limit
+            self.base_url, address, event_path, limit
         );
         if let Some(start) = start {
             url.push_str(&format!("&start={}", start));
         }
-        let response = self.client.get(&url).send().await.unwrap();
-        if !response.status().is_success() {
-            let error_msg = response.text().await.unwrap();
+        let response = self.transport.get(&url).await?;
+        if !response.is_success() {
+            let error_msg = response.body;
             return Err(format!("api error: {}", error_msg).to_string());
         }
-        let events: Vec<Event> = response.json().await.unwrap();
+        let events: Vec<Event> = response.json()?;
         Ok(events)
     }
 
-    /// get table item
-    pub async fn get_table_item(
+    /// Page through the events of an event handle, following the sequence number
+    /// cursor until a short page or `max` events, `DEFAULT_MAX_EVENTS` when `None`.
+    ///
+    /// Starts at sequence number `since_seq`, or when `None` at the most recent `max`
+    /// events.
+    pub async fn get_all_account_events(
         &self,
-        table_handle: &str,
-        key_type: &str,
-        value_type: &str,
-        key: &Value,
-    ) -> Result<Value, String> {
-        let url = format!("{}/tables/{}/item", self.base_url, table_handle);
-        let request = TableRequest {
-            key_type: key_type.to_string(),
-            value_type: value_type.to_string(),
-            key: key.clone(),
+        address: &str,
+        event_type: &str,
+        since_seq: Option<u64>,
+        max: Option<u64>,
+    ) -> Result<Vec<Event>, String> {
+        let max = max.unwrap_or(DEFAULT_MAX_EVENTS);
+        let mut start = match since_seq {
+            Some(since_seq) => since_seq,
+            None => {
+                // the node returns the latest events when no start is given
+                let latest = self
+                    .get_account_event_vec(address, event_type, Some(1), None)
+                    .await?;
+                let Some(newest) = latest
+                    .last()
+                    .and_then(|event| event.sequence_number.parse::<u64>().ok())
+                else {
+                    return Ok(latest);
+                };
+                (newest + 1).saturating_sub(max)
+            }
         };
-        let response = self
-            .client
-            .post(&url)
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
-            .await
-            .unwrap();
-        if !response.status().is_success() {
-            let error_msg = response.text().await.unwrap();
-            return Err(format!("api error: {}", error_msg).to_string());
+        let mut all_events = Vec::new();
+        loop {
+            let limit = max
+                .saturating_sub(all_events.len() as u64)
+                .min(EVENT_PAGE_LIMIT);
+            if limit == 0 {
+                break;
+            }
+            let events = self
+                .get_account_event_vec(address, event_type, Some(limit), Some(start))
+                .await?;
+            let page_len = events.len() as u64;
+            let Some(last_sequence) = events
+                .last()
+                .and_then(|event| event.sequence_number.parse::<u64>().ok())
+            else {
+                all_events.extend(events);
+                break;
+            };
+            all_events.extend(events);
+            if page_len < limit {
+                break;
+            }
+            start = last_sequence + 1;
         }
-        let value: Value = response.json().await.unwrap();
-        Ok(value)
+        Ok(all_events)
     }
 
-    /// view function
-    pub async fn view(&self, view_request: &ViewRequest) -> Result<Vec<Value>, String> {
-        let url = format!("{}/view", self.base_url);
-        let response = self
-            .client
-            .post(&url)
-            .header("Content-Type", "application/json")
-            .json(view_request)
-            .send()
-            .await
-            .unwrap();
-        if !response.status().is_success() {
-            let error_msg = response.text().await.unwrap();
-            return Err(format!("api error: {}", error_msg).to_string());
-        }
-        let result: Vec<Value> = response.json().await.unwrap();
-        Ok(result)
-    }
+    /// Module events of `event_type`, e.g. `0xabc::pool::SwapEvent`, emitted with
+    /// `0x1::event::emit` instead of an account event handle.
+    ///
+    /// Returns up to `limit` events from ledger version `start` on, or the latest ones
+    /// when `None`, oldest first. Read from the indexer set with `with_indexer`; without
+    /// one the node, which has no index by event type, is searched and only the events
+    /// of the `limit` transactions from `start` are found.
+    pub async fn get_module_events(
+        &self,
+        event_type: &str,
+        limit: Option<u64>,
+        start: Option<u64>,
+    ) -> Result<Vec<Event>, String> {
+        let limit = limit.unwrap_or(25);
+        #[cfg(feature = "indexer")]
+        if let Some(indexer) = &self.indexer {
+            return Self::get_indexed_module_events(indexer, event_type, limit, start).await;
+        }
+        let mut url = format!("{}/transactions?limit={}", self.base_url, limit);
+        if let Some(start) = start {
+            url.push_str(&format!("&start={}", start));
+        }
+        let response = self.transport.get(&url).await?;
+        if !response.is_success() {
+            let error_msg = response.body;
+            return Err(format!("api error: {}", error_msg).to_string());
+        }
+        let transactions: Vec<TransactionInfo> = response.json()?;
+        Ok(transactions
+            .into_iter()
+            .flat_map(|transaction| {
+                let version = transaction.version;
+                transaction
+                    .events
+                    .into_iter()
+                    .filter(|event| event.r#type == event_type)
+                    .map(move |event| Event {
+                        guid: serde_json::to_value(&event.guid).unwrap_or_default(),
+                        sequence_number: event.sequence_number,
+                        r#type: event.r#type,
+                        data: event.data,
+                        version: Some(version.clone()),
+                    })
+            })
+            .collect())
+    }
+
+    #[cfg(feature = "indexer")]
+    async fn get_indexed_module_events(
+        indexer: &Indexer,
+        event_type: &str,
+        limit: u64,
+        start: Option<u64>,
+    ) -> Result<Vec<Event>, String> {
+        let (filter, order) = match start {
+            Some(start) => (
+                serde_json::json!({
+                    "type": {"_eq": event_type},
+                    "transaction_version": {"_gte": start}
+                }),
+                "asc",
+            ),
+            None => (serde_json::json!({"type": {"_eq": event_type}}), "desc"),
+        };
+        let data = indexer
+            .query(
+                "query ModuleEvents($where: events_bool_exp, $order_by: [events_order_by!], $limit: Int) {
+                    events(where: $where, order_by: $order_by, limit: $limit) {
+                        account_address
+                        creation_number
+                        sequence_number
+                        type
+                        data
+                        transaction_version
+                    }
+                }",
+                serde_json::json!({
+                    "where": filter,
+                    "order_by": [{"transaction_version": order}, {"event_index": order}],
+                    "limit": limit,
+                }),
+            )
+            .await?;
+        let rows = data["events"].as_array().ok_or("missing events")?;
+        let text = |value: &Value| match value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        let mut events: Vec<Event> = rows
+            .iter()
+            .map(|row| Event {
+                guid: serde_json::json!({
+                    "creation_number": text(&row["creation_number"]),
+                    "account_address": text(&row["account_address"]),
+                }),
+                sequence_number: text(&row["sequence_number"]),
+                r#type: text(&row["type"]),
+                data: row["data"].clone(),
+                version: Some(text(&row["transaction_version"])),
+            })
+            .collect();
+        if start.is_none() {
+            events.reverse();
+        }
+        Ok(events)
+    }
+
+    /// get table item, `None` when the item does not exist or was deleted
+    pub async fn get_table_item(
+        &self,
+        table_handle: &str,
+        key_type: &str,
+        value_type: &str,
+        key: &Value,
+    ) -> Result<Option<Value>, String> {
+        let request = TableRequest {
+            key_type: key_type.to_string(),
+            value_type: value_type.to_string(),
+            key: key.clone(),
+        };
+        self.post_table_item(table_handle, &request).await
+    }
+
+    /// Bytes of a `vector<u8>` table item, e.g. a protocol table storing BCS encoded
+    /// state. The api returns the bytes as a `0x` hex string, base64 is accepted as
+    /// well. `None` when the item does not exist or was deleted.
+    pub async fn get_table_item_raw(
+        &self,
+        table_handle: &str,
+        key_type: &str,
+        key: &Value,
+    ) -> Result<Option<Vec<u8>>, String> {
+        let value = self
+            .get_table_item(table_handle, key_type, "vector<u8>", key)
+            .await?;
+        let Some(value) = value else {
+            return Ok(None);
+        };
+        let encoded = value
+            .as_str()
+            .ok_or_else(|| format!("table item is not a byte string: {}", value))?;
+        let bytes = match encoded.strip_prefix("0x") {
+            Some(hex_bytes) => hex::decode(hex_bytes).map_err(|e| format!("invalid hex: {}", e))?,
+            None => {
+                use base64::Engine as _;
+                base64::engine::general_purpose::STANDARD
+                    .decode(encoded)
+                    .map_err(|e| format!("invalid base64: {}", e))?
+            }
+        };
+        Ok(Some(bytes))
+    }
+
+    /// post a table item request, mapping a 404 `table_item_not_found` to `None`
+    async fn post_table_item(
+        &self,
+        table_handle: &str,
+        request: &TableRequest,
+    ) -> Result<Option<Value>, String> {
+        let url = format!("{}/tables/{}/item", self.base_url, table_handle);
+        let body = serde_json::to_value(request).map_err(|e| e.to_string())?;
+        let response = self.transport.post(&url, &body).await?;
+        let not_found = response.status == 404
+            && response
+                .json::<Value>()
+                .is_ok_and(|error| error["error_code"] == "table_item_not_found");
+        if not_found {
+            return Ok(None);
+        }
+        if !response.is_success() {
+            return Err(format!("api error: {}", response.body));
+        }
+        response.json().map(Some)
+    }
+
+    /// Get a table item with a typed key and value.
+    ///
+    /// `key_type` / `value_type` default to the move type inferred from `K` / `V`
+    /// (integers, bool, `String` as `0x1::string::String`), and must be given for
+    /// anything else. Errors when the item does not exist.
+    pub async fn get_table_item_typed<K: Serialize, V: DeserializeOwned>(
+        &self,
+        table_handle: &str,
+        key: K,
+        key_type: Option<&str>,
+        value_type: Option<&str>,
+    ) -> Result<V, String> {
+        self.get_table_item_typed_opt(table_handle, key, key_type, value_type)
+            .await?
+            .ok_or_else(|| format!("table item not found: {}", table_handle))
+    }
+
+    /// like `get_table_item_typed`, returning `None` for a missing item
+    pub async fn get_table_item_typed_opt<K: Serialize, V: DeserializeOwned>(
+        &self,
+        table_handle: &str,
+        key: K,
+        key_type: Option<&str>,
+        value_type: Option<&str>,
+    ) -> Result<Option<V>, String> {
+        let key_type = match key_type {
+            Some(t) => t.to_string(),
+            None => infer_move_type::<K>()
+                .ok_or_else(|| format!("cannot infer key type: {}", std::any::type_name::<K>()))?,
+        };
+        let value_type = match value_type {
+            Some(t) => t.to_string(),
+            None => infer_move_type::<V>().ok_or_else(|| {
+                format!("cannot infer value type: {}", std::any::type_name::<V>())
+            })?,
+        };
+        let mut key = serde_json::to_value(key).map_err(|e| e.to_string())?;
+        // the api expects 64 bit and wider integers as strings
+        if key.is_number() && matches!(key_type.as_str(), "u64" | "u128" | "u256") {
+            key = Value::String(key.to_string());
+        }
+        let request = TableRequest {
+            key_type,
+            value_type,
+            key,
+        };
+        match self.post_table_item(table_handle, &request).await? {
+            Some(value) => decode_move_value(value).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// view function, at `ledger_version` when given, otherwise at the latest version
+    pub async fn view(
+        &self,
+        view_request: &ViewRequest,
+        ledger_version: Option<u64>,
+    ) -> Result<Vec<Value>, String> {
+        let mut url = format!("{}/view", self.base_url);
+        if let Some(version) = ledger_version {
+            url.push_str(&format!("?ledger_version={}", version));
+        }
+        let body = serde_json::to_value(view_request).map_err(|e| e.to_string())?;
+        let response = self.transport.post(&url, &body).await?;
+        if !response.is_success() {
+            let error_msg = response.body;
+            return Err(format!("api error: {}", error_msg).to_string());
+        }
+        let result: Vec<Value> = response.json()?;
+        Ok(result)
+    }
+
+    /// View function decoded into `T`, the whole output at once, so a function
+    /// returning `(u64, u64)` decodes into `(u64, u64)` and a single return value
+    /// into a 1-tuple. Integers encoded as json strings are parsed at any depth.
+    pub async fn view_typed<T: DeserializeOwned>(
+        &self,
+        view_request: &ViewRequest,
+        ledger_version: Option<u64>,
+    ) -> Result<T, String> {
+        let result = self.view(view_request, ledger_version).await?;
+        decode_move_value(Value::Array(result))
+            .map_err(|e| format!("{} output: {}", view_request.function, e))
+    }
 
     /// estimate gas price
     pub async fn estimate_gas_price(&self) -> Result<u64, String> {
+        let gas_estimation = self.get_gas_estimation().await?;
+        Ok(gas_estimation.gas_estimate * 2000)
+    }
+
+    /// Gas parameters from `0x1::gas_schedule::GasScheduleV2`.
+    ///
+    /// The schedule only changes at epoch boundaries, so it is cached for the epoch
+    /// it was read in and each call costs one ledger info request.
+    pub async fn get_gas_schedule(&self) -> Result<GasSchedule, String> {
+        let epoch = self.get_epoch().await?;
+        let cached = self.gas_schedule.lock().unwrap().clone();
+        if let Some((_, schedule)) = cached.filter(|(cached_epoch, _)| *cached_epoch == epoch) {
+            return Ok(schedule);
+        }
+        let resource = self
+            .get_account_resource("0x1", "0x1::gas_schedule::GasScheduleV2")
+            .await?
+            .ok_or("gas schedule not found")?;
+        let schedule = GasSchedule::from_resource(&resource.data)?;
+        *self.gas_schedule.lock().unwrap() = Some((epoch, schedule.clone()));
+        Ok(schedule)
+    }
+
+    /// get the node's gas unit price estimates
+    pub async fn get_gas_estimation(&self) -> Result<GasEstimation, String> {
         let url = format!("{}/estimate_gas_price", self.base_url);
-        let response = self.client.get(&url).send().await.unwrap();
-        if !response.status().is_success() {
-            let error_msg = response.text().await.unwrap();
+        let response = self.transport.get(&url).await?;
+        if !response.is_success() {
+            let error_msg = response.body;
             return Err(format!("api error: {}", error_msg).to_string());
         }
-        let gas_estimation: GasEstimation = response.json().await.unwrap();
-        Ok(gas_estimation.gas_estimate * 2000)
+        let gas_estimation: GasEstimation = response.json()?;
+        Ok(gas_estimation)
     }
 
     /// get account balance
     pub async fn get_account_balance(&self, address: &str) -> Result<u64, String> {
-        let resources = self.get_account_resource_vec(address).await.unwrap();
-        for resource in resources {
-            if resource.r#type == "0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>" {
-                if let Some(data) = resource.data.as_object() {
-                    if let Some(coin) = data.get("coin") {
-                        if let Some(value) = coin.get("value") {
-                            return if let Some(balance) = value.as_str() {
-                                Ok(balance.parse().unwrap_or(0))
-                            } else if let Some(balance) = value.as_u64() {
-                                Ok(balance)
-                            } else {
-                                Ok(0)
-                            };
-                        }
-                    }
-                }
-            }
-        }
-        Ok(0)
+        self.get_token_balance(address, "0x1::aptos_coin::AptosCoin")
+            .await
     }
+
     /// get token balance
     pub async fn get_token_balance(&self, address: &str, token_type: &str) -> Result<u64, String> {
         let resource_type = format!("0x1::coin::CoinStore<{}>", token_type);
-        if let Some(resource) = self
-            .get_account_resource(address, &resource_type)
+        let coin_store: Option<CoinStore> = self
+            .get_account_resource_typed(address, &resource_type)
+            .await?;
+        Ok(coin_store.map(|store| store.coin.value).unwrap_or(0))
+    }
+
+    /// Whether `address` has a store for `token_type`, frozen or not, see
+    /// `get_store_status` to tell a frozen store apart.
+    pub async fn is_registered(&self, address: &str, token_type: &str) -> Result<bool, String> {
+        Ok(self.get_store_status(address, token_type).await? != StoreStatus::Unregistered)
+    }
+
+    /// Store of `address` for `token_type`: missing, registered or frozen.
+    ///
+    /// `token_type` is either a coin type (`0x1::aptos_coin::AptosCoin`), checked for a
+    /// `0x1::coin::CoinStore<T>` resource or a primary store of its paired fungible asset,
+    /// or a fungible asset metadata address, checked for a primary store.
+    pub async fn get_store_status(
+        &self,
+        address: &str,
+        token_type: &str,
+    ) -> Result<StoreStatus, String> {
+        if token_type.contains("::") {
+            let resource_type = format!("0x1::coin::CoinStore<{}>", token_type);
+            let coin_store: Option<CoinStore> = self
+                .get_account_resource_typed(address, &resource_type)
+                .await?;
+            if let Some(coin_store) = coin_store {
+                return Ok(if coin_store.frozen {
+                    StoreStatus::Frozen
+                } else {
+                    StoreStatus::Registered
+                });
+            }
+            // coins migrated to fungible assets live in the paired primary store
+            let metadata = self.paired_metadata(token_type).await;
+            match metadata {
+                Some(metadata) => self.primary_store_status(address, &metadata).await,
+                None => Ok(StoreStatus::Unregistered),
+            }
+        } else {
+            self.primary_store_status(address, token_type).await
+        }
+    }
+
+    /// Whether the store holding `token_type` for `address` is frozen, transfers in
+    /// and out of a frozen store abort.
+    ///
+    /// `token_type` is a coin type or a fungible asset metadata address, as for
+    /// `is_registered`. An account without a store is not frozen.
+    pub async fn is_coin_store_frozen(
+        &self,
+        address: &str,
+        token_type: &str,
+    ) -> Result<bool, String> {
+        if token_type.contains("::") {
+            let resource_type = format!("0x1::coin::CoinStore<{}>", token_type);
+            let coin_store: Option<CoinStore> = self
+                .get_account_resource_typed(address, &resource_type)
+                .await?;
+            if let Some(coin_store) = coin_store {
+                return Ok(coin_store.frozen);
+            }
+            match self.paired_metadata(token_type).await {
+                Some(metadata) => self.primary_store_frozen(address, &metadata).await,
+                None => Ok(false),
+            }
+        } else {
+            self.primary_store_frozen(address, token_type).await
+        }
+    }
+
+    /// fungible asset metadata address paired with a coin type
+    async fn paired_metadata(&self, coin_type: &str) -> Option<String> {
+        let paired = self
+            .view(
+                &ViewRequest::new("0x1::coin::paired_metadata").type_arg(coin_type),
+                None,
+            )
             .await
-            .unwrap()
+            .ok()?;
+        paired
+            .first()
+            .and_then(|v| v.get("vec"))
+            .and_then(|v| v.as_array())
+            .and_then(|v| v.first())
+            .and_then(|v| v.get("inner"))
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+    }
+
+    /// Get coin metadata.
+    ///
+    /// `token_type` is a coin type, read from `0x1::coin::CoinInfo<T>` at the coin's
+    /// address, or a fungible asset metadata address. Coins without a `CoinInfo`
+    /// fall back to the metadata of their paired fungible asset.
+    pub async fn get_coin_info(&self, token_type: &str) -> Result<CoinInfo, String> {
+        if let Some((coin_address, _)) = token_type.split_once("::") {
+            let resource_type = format!("0x1::coin::CoinInfo<{}>", token_type);
+            if let Some(resource) = self
+                .get_account_resource(coin_address, &resource_type)
+                .await?
+            {
+                let data = &resource.data;
+                let supply = match data.get("supply").and_then(Self::parse_coin_supply) {
+                    Some(supply) => Some(supply),
+                    None => self.coin_supply_view(token_type).await,
+                };
+                return Ok(CoinInfo {
+                    name: data["name"].as_str().unwrap_or_default().to_string(),
+                    symbol: data["symbol"].as_str().unwrap_or_default().to_string(),
+                    decimals: data["decimals"].as_u64().unwrap_or(0) as u8,
+                    supply,
+                });
+            }
+            match self.paired_metadata(token_type).await {
+                Some(metadata) => self.get_fungible_asset_info(&metadata).await,
+                None => Err(format!("coin info not found: {}", token_type)),
+            }
+        } else {
+            self.get_fungible_asset_info(token_type).await
+        }
+    }
+
+    /// Current supply of a coin type or fungible asset metadata address, see `get_coin_info`.
+    ///
+    /// `None` when the supply is not tracked or cannot be read.
+    pub async fn get_token_supply(&self, token_type: &str) -> Result<Option<u128>, String> {
+        Ok(self.get_coin_info(token_type).await?.supply)
+    }
+
+    /// metadata and supply of a fungible asset metadata object
+    async fn get_fungible_asset_info(&self, metadata: &str) -> Result<CoinInfo, String> {
+        let data = self
+            .get_account_resource(metadata, "0x1::fungible_asset::Metadata")
+            .await?
+            .map(|r| r.data)
+            .ok_or_else(|| format!("coin info not found: {}", metadata))?;
+        let supply = match self
+            .get_account_resource(metadata, "0x1::fungible_asset::ConcurrentSupply")
+            .await?
         {
-            if let Some(data) = resource.data.as_object() {
-                if let Some(coin) = data.get("coin") {
-                    if let Some(value) = coin.get("value") {
-                        return if let Some(balance) = value.as_str() {
-                            Ok(balance.parse().unwrap_or(0))
-                        } else if let Some(balance) = value.as_u64() {
-                            Ok(balance)
-                        } else {
-                            Ok(0)
-                        };
-                    }
+            Some(resource) => Self::parse_coin_supply(&resource.data),
+            None => self
+                .get_account_resource(metadata, "0x1::fungible_asset::Supply")
+                .await?
+                .and_then(|resource| Self::parse_coin_supply(&resource.data)),
+        };
+        Ok(CoinInfo {
+            name: data["name"].as_str().unwrap_or_default().to_string(),
+            symbol: data["symbol"].as_str().unwrap_or_default().to_string(),
+            decimals: data["decimals"].as_u64().unwrap_or(0) as u8,
+            supply,
+        })
+    }
+
+    /// `0x1::coin::supply<T>`, for coins whose supply sits in an aggregator
+    async fn coin_supply_view(&self, coin_type: &str) -> Option<u128> {
+        let result = self
+            .view(
+                &ViewRequest::new("0x1::coin::supply").type_arg(coin_type),
+                None,
+            )
+            .await
+            .ok()?;
+        Self::parse_coin_supply(result.first()?)
+    }
+
+    /// Supply of a coin or fungible asset from any of its on-chain shapes.
+    ///
+    /// Accepts `CoinInfo.supply` as `Option<OptionalAggregator>` or `Option<Integer>`,
+    /// the `Option<u128>` returned by `0x1::coin::supply`, the data of the fungible asset
+    /// `Supply` / `ConcurrentSupply` resources and plain integers. `None` when the supply
+    /// is not tracked or sits in an aggregator, whose value is not stored in the resource.
+    pub fn parse_coin_supply(value: &Value) -> Option<u128> {
+        match value {
+            Value::String(_) | Value::Number(_) => Self::parse_u128(Some(value)),
+            Value::Object(fields) => {
+                let inner = ["vec", "current", "integer"]
+                    .iter()
+                    .find_map(|field| fields.get(*field));
+                match inner {
+                    Some(Value::Array(items)) => Self::parse_coin_supply(items.first()?),
+                    Some(inner) => Self::parse_coin_supply(inner),
+                    None => Self::parse_u128(fields.get("value")),
                 }
             }
+            _ => None,
         }
-        Ok(0)
     }
-    /// waiting transaction
+
+    pub(crate) fn parse_u128(value: Option<&Value>) -> Option<u128> {
+        match value? {
+            Value::String(s) => s.parse().ok(),
+            Value::Number(n) => n.as_u64().map(u128::from),
+            _ => None,
+        }
+    }
+
+    /// whether the fungible asset primary store of `address` exists
+    pub(crate) async fn primary_store_exists(
+        &self,
+        address: &str,
+        metadata: &str,
+    ) -> Result<bool, String> {
+        let result = self
+            .view(
+                &ViewRequest {
+                    function: "0x1::primary_fungible_store::primary_store_exists".to_string(),
+                    type_arguments: vec!["0x1::fungible_asset::Metadata".to_string()],
+                    arguments: vec![Value::from(address), Value::from(metadata)],
+                },
+                None,
+            )
+            .await?;
+        Ok(result.first().and_then(|v| v.as_bool()).unwrap_or(false))
+    }
+
+    async fn primary_store_frozen(&self, address: &str, metadata: &str) -> Result<bool, String> {
+        let result = self
+            .view(
+                &ViewRequest {
+                    function: "0x1::primary_fungible_store::is_frozen".to_string(),
+                    type_arguments: vec!["0x1::fungible_asset::Metadata".to_string()],
+                    arguments: vec![Value::from(address), Value::from(metadata)],
+                },
+                None,
+            )
+            .await?;
+        Ok(result.first().and_then(|v| v.as_bool()).unwrap_or(false))
+    }
+
+    async fn primary_store_status(
+        &self,
+        address: &str,
+        metadata: &str,
+    ) -> Result<StoreStatus, String> {
+        if !self.primary_store_exists(address, metadata).await? {
+            Ok(StoreStatus::Unregistered)
+        } else if self.primary_store_frozen(address, metadata).await? {
+            Ok(StoreStatus::Frozen)
+        } else {
+            Ok(StoreStatus::Registered)
+        }
+    }
+
+    /// Wait until a transaction is committed, successfully or not.
+    ///
+    /// Unknown hashes keep being polled until the timeout, the node may not have seen
+    /// the transaction yet, e.g. a load balanced node behind the one it was submitted
+    /// to, but the timeout error tells a hash that was never found apart from one
+    /// still pending. Once the node's ledger time passes the transaction's expiration
+    /// and the hash is still unknown, it can no longer be committed and the wait ends
+    /// early. The expiration is known for transactions submitted through
+    /// `submit_transaction_idempotent` or seen pending, see
+    /// `waiting_transaction_with_expiration` for others. Transport and api errors are
+    /// retried at most `WAITING_TRANSACTION_MAX_ERRORS` times in a row.
     pub async fn waiting_transaction(
         &self,
         txn_hash: &str,
         timeout_secs: u64,
+    ) -> Result<TransactionInfo, String> {
+        let expiration = self
+            .submitted
+            .lock()
+            .unwrap()
+            .values()
+            .find(|txn| txn.hash == txn_hash)
+            .and_then(|txn| txn.expiration_timestamp_secs);
+        self.wait_for_transaction(txn_hash, expiration, timeout_secs)
+            .await
+    }
+
+    /// `waiting_transaction` for a transaction expiring at `expiration_timestamp_secs`
+    pub async fn waiting_transaction_with_expiration(
+        &self,
+        txn_hash: &str,
+        expiration_timestamp_secs: u64,
+        timeout_secs: u64,
+    ) -> Result<TransactionInfo, String> {
+        self.wait_for_transaction(txn_hash, Some(expiration_timestamp_secs), timeout_secs)
+            .await
+    }
+
+    async fn wait_for_transaction(
+        &self,
+        txn_hash: &str,
+        mut expiration: Option<u64>,
+        timeout_secs: u64,
     ) -> Result<TransactionInfo, String> {
         let start = std::time::Instant::now();
         let timeout = Duration::from_secs(timeout_secs);
+        let mut last_status = TxStatus::NotFound;
+        let mut errors = 0;
         while start.elapsed() < timeout {
-            match self.get_transaction_info_by_hash(txn_hash).await {
-                Ok(txn) => {
-                    // transaction completed
-                    return Ok(txn);
+            match self.fetch_transaction_status(txn_hash).await {
+                Ok(TxPoll {
+                    transaction: Some(txn),
+                    ..
+                }) => return Ok(txn),
+                Ok(poll) => {
+                    expiration = expiration.or(poll.expiration_timestamp_secs);
+                    if let (TxStatus::NotFound, Some(expiration), Some(ledger_secs)) =
+                        (&poll.status, expiration, poll.ledger_timestamp_secs)
+                        && ledger_secs >= expiration
+                    {
+                        return Err(format!(
+                            "transaction expired without being committed tx:{:?}\nexpiration:{}",
+                            txn_hash, expiration
+                        ));
+                    }
+                    last_status = poll.status;
+                    errors = 0;
                 }
                 Err(e) => {
-                    // during transaction processing, delay accessing the transaction status again.
-                    tokio::time::sleep(Duration::from_millis(WAITING_TRANSACTION_DELAY_TIME)).await;
+                    errors += 1;
+                    if errors >= WAITING_TRANSACTION_MAX_ERRORS {
+                        return Err(format!(
+                            "waiting for transaction {} failed {} times in a row: {}",
+                            txn_hash, errors, e
+                        ));
+                    }
                 }
             }
+            // during transaction processing, delay accessing the transaction status again.
+            tokio::time::sleep(Duration::from_millis(WAITING_TRANSACTION_DELAY_TIME)).await;
+        }
+        if last_status == TxStatus::NotFound {
+            return Err(format!(
+                "transaction not found tx:{:?}\ntime:{:?}",
+                txn_hash, timeout_secs
+            ));
         }
         Err(format!(
             "Transaction timeout tx:{:?}\ntime:{:?}",
             txn_hash, timeout_secs
-        )
-        .to_string())
+        ))
+    }
+    /// Wait until a transaction is committed and `min_confirmations` more versions
+    /// are committed on top of it, i.e. `ledger_version - version >= min_confirmations`.
+    ///
+    /// Aptos transactions are final once committed. Both the transaction and the
+    /// ledger version are read from this client's node, so the margin does not check
+    /// the transaction against another node. With 0 confirmations this is
+    /// `waiting_transaction`. Failed transactions are returned once confirmed like
+    /// successful ones, `timeout_secs` covers the whole wait.
+    pub async fn wait_for_finality(
+        &self,
+        txn_hash: &str,
+        min_confirmations: u64,
+        timeout_secs: u64,
+    ) -> Result<TransactionInfo, String> {
+        let start = std::time::Instant::now();
+        let txn = self.waiting_transaction(txn_hash, timeout_secs).await?;
+        if min_confirmations == 0 {
+            return Ok(txn);
+        }
+        let version = txn
+            .version
+            .parse::<u64>()
+            .map_err(|e| format!("invalid transaction version {}: {}", txn.version, e))?;
+        let mut confirmations = 0;
+        while start.elapsed() < Duration::from_secs(timeout_secs) {
+            if let Ok(ledger_version) = self.get_ledger_version().await {
+                confirmations = ledger_version.saturating_sub(version);
+                if confirmations >= min_confirmations {
+                    return Ok(txn);
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(WAITING_TRANSACTION_DELAY_TIME)).await;
+        }
+        Err(format!(
+            "transaction {} has {} of {} confirmations after {}s",
+            txn_hash, confirmations, min_confirmations, timeout_secs
+        ))
     }
+
     /// determine whether the transaction is successful
     pub async fn is_transaction_successful(&self, txn_hash: &str) -> Result<bool, String> {
         match self.get_transaction_info_by_hash(txn_hash).await {
@@ -434,33 +2020,1219 @@ impl Aptos {
             Err(e) => Err(e),
         }
     }
-    /// get account sequence number
-    pub async fn get_account_sequence_number(&self, address: &str) -> Result<u64, String> {
-        match self.get_account_info(address).await {
-            Ok(info) => Ok(info.sequence_number.parse::<u64>().unwrap()),
-            Err(e) => Err(e),
+
+    /// APT price in USD, cached for `APT_PRICE_CACHE_TTL`.
+    ///
+    /// Taken from the deepest APT/USDC or APT/USDT DEX pool with the stablecoin at $1,
+    /// so it is DEX-derived and not oracle-grade. Use `oracle::Oracle::get_apt_usd_price`
+    /// for the pyth price.
+    #[cfg(feature = "dex")]
+    pub async fn get_apt_price_usd(&self) -> Result<f64, String> {
+        let cached = *self.apt_price.lock().unwrap();
+        if let Some((_, price)) =
+            cached.filter(|(fetched_at, _)| fetched_at.elapsed() < APT_PRICE_CACHE_TTL)
+        {
+            return Ok(price);
         }
+        let price =
+            dex::DexAggregator::get_apt_price_in_stablecoins(Arc::new(self.clone())).await?;
+        *self.apt_price.lock().unwrap() = Some((std::time::Instant::now(), price));
+        Ok(price)
+    }
+
+    /// USD value of an account's APT balance, see `get_apt_price_usd`
+    #[cfg(feature = "dex")]
+    pub async fn get_account_usd_value(&self, address: &str) -> Result<f64, String> {
+        let (balance, price) = futures::try_join!(
+            self.get_apt_balance_by_account(address),
+            self.get_apt_price_usd()
+        )?;
+        Ok(balance * price)
+    }
+
+    /// get account sequence number
+    pub async fn get_account_sequence_number(&self, address: &str) -> Result<u64, AptosError> {
+        let info = self.get_account_info(address).await?;
+        info.sequence_number.parse::<u64>().map_err(|e| {
+            AptosError::Parse(format!(
+                "invalid sequence number {}: {}",
+                info.sequence_number, e
+            ))
+        })
     }
     /// account exists
-    pub async fn account_exists(&self, address: &str) -> Result<bool, String> {
+    pub async fn account_exists(&self, address: &str) -> Result<bool, AptosError> {
         match self.get_account_info(address).await {
             Ok(_) => Ok(true),
-            Err(e) => {
-                if e.to_string().contains("Account not found") {
-                    Ok(false)
-                } else {
-                    Err(e)
-                }
-            }
+            Err(AptosError::NotFound(_)) => Ok(false),
+            Err(e) => Err(e),
         }
     }
 }
 
+/// move type name for primitive rust types
+pub(crate) fn infer_move_type<T>() -> Option<String> {
+    let name = std::any::type_name::<T>();
+    let move_type = match name {
+        "bool" | "u8" | "u16" | "u32" | "u64" | "u128" => name,
+        "alloc::string::String" | "&str" => "0x1::string::String",
+        _ => return None,
+    };
+    Some(move_type.to_string())
+}
+
+/// type of an object from its resources: a known primary resource, otherwise the
+/// first resource outside `0x1::object`
+#[cfg(feature = "indexer")]
+fn object_type(resources: &[Resource]) -> String {
+    let types = || resources.iter().map(|resource| resource.r#type.as_str());
+    types()
+        .find(|r#type| PRIMARY_OBJECT_TYPES.contains(r#type))
+        .or_else(|| types().find(|r#type| !r#type.starts_with("0x1::object::")))
+        .or_else(|| types().next())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Deserialize a move value, also accepting integers encoded as json strings at any
+/// depth, e.g. a `(u64, u64)` from `["1", "2"]`.
+pub(crate) fn decode_move_value<T: DeserializeOwned>(value: Value) -> Result<T, String> {
+    match T::deserialize(move_value::MoveDeserializer(value.clone())) {
+        Ok(decoded) => Ok(decoded),
+        Err(e) => match &value {
+            Value::String(s) => {
+                serde_json::from_str(s).map_err(|_| format!("decode move value error: {}", e))
+            }
+            _ => Err(format!("decode move value error: {}", e)),
+        },
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::transport::MockTransport;
+    use serde_json::json;
     use std::sync::Arc;
 
+    fn mock_client(transport: MockTransport) -> (Aptos, Arc<MockTransport>) {
+        let transport = Arc::new(transport);
+        (
+            Aptos::with_transport(AptosType::Mainnet, transport.clone()),
+            transport,
+        )
+    }
+
+    #[test]
+    fn test_client_and_wallets_are_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Aptos>();
+        assert_send_sync::<wallet::Wallet>();
+        assert_send_sync::<wallet::MultiEd25519Wallet>();
+        assert_send_sync::<wallet::SingleKeyWallet>();
+    }
+
+    #[tokio::test]
+    async fn test_mock_get_account_info() {
+        let (client, _) = mock_client(MockTransport::new().on_get(
+            "/accounts/0x1",
+            200,
+            r#"{"sequence_number":"7","authentication_key":"0x01"}"#,
+        ));
+        let info = client.get_account_info("0x1").await.unwrap();
+        assert_eq!(info.sequence_number, "7");
+        assert_eq!(client.get_account_sequence_number("0x1").await.unwrap(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_get_account_resource_encodes_type() {
+        let pool = "0x1::pool::Pool<0x1::aptos_coin::AptosCoin, 0xc::usdc::USDC>";
+        let (client, transport) = mock_client(MockTransport::new().on_get(
+            &format!("/accounts/0xa/resource/{}", pool),
+            200,
+            &json!({"type": pool, "data": {"reserve_x": "10"}}).to_string(),
+        ));
+        let resource = client.get_account_resource("0xa", pool).await.unwrap();
+        assert_eq!(resource.unwrap().data["reserve_x"], "10");
+        assert!(transport.requests()[0].url.ends_with(
+            "/resource/0x1::pool::Pool%3C0x1::aptos_coin::AptosCoin%2C%200xc::usdc::USDC%3E"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_account_event_vec_encodes_handle() {
+        let handle = "0x1::pool::Pool<0x1::aptos_coin::AptosCoin, 0xc::usdc::USDC>/swap_events";
+        let (client, transport) = mock_client(MockTransport::new().on_get(
+            &format!("/accounts/0xa/events/{}", handle),
+            200,
+            "[]",
+        ));
+        client
+            .get_account_event_vec("0xa", handle, Some(5), Some(3))
+            .await
+            .unwrap();
+        assert!(transport.requests()[0].url.ends_with(
+            "/events/0x1::pool::Pool%3C0x1::aptos_coin::AptosCoin%2C%200xc::usdc::USDC%3E/swap_events?limit=5&start=3"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_detect_account_scheme() {
+        let user_txn = |sequence_number: u64, signature: &Value| {
+            json!({
+                "version": "9", "hash": "0x09", "state_checkpoint_hash": null, "success": true,
+                "type": "user_transaction", "sender": "0xa", "sequence_number": sequence_number.to_string(),
+                "payload": {"type": "entry_function_payload", "function": "0x1::coin::transfer", "type_arguments": [], "arguments": []},
+                "signature": signature
+            })
+        };
+        let auth_key = |signature: &Value| {
+            AccountScheme::authentication_key(&serde_json::from_value(signature.clone()).unwrap())
+                .unwrap()
+        };
+        let account = |sequence_number: u64, authentication_key: &str| {
+            json!({"sequence_number": sequence_number.to_string(), "authentication_key": authentication_key})
+                .to_string()
+        };
+        let ed25519 = json!({"type": "ed25519_signature", "public_key": format!("0x{}", "11".repeat(32)), "signature": "0x2"});
+        let multi = json!({"type": "multi_ed25519_signature", "public_keys": [format!("0x{}", "22".repeat(32)), format!("0x{}", "33".repeat(32))], "signatures": ["0x3"], "threshold": 1});
+        let sponsored = json!({"type": "fee_payer_signature", "sender": {"type": "single_key_signature", "public_key": format!("0x0020{}", "44".repeat(32)), "signature": "0x2"}});
+        let (client, transport) = mock_client(
+            MockTransport::new()
+                // rotated from ed25519 to multi ed25519, then sent with the new key
+                .on_get("/accounts/0xa", 200, &account(2, &auth_key(&multi)))
+                .on_get(
+                    "/accounts/0xa/transactions?limit=2&start=0",
+                    200,
+                    &json!([user_txn(0, &ed25519), user_txn(1, &multi)]).to_string(),
+                )
+                .on_get("/accounts/0xb", 200, &account(1, &auth_key(&sponsored)))
+                .on_get(
+                    "/accounts/0xb/transactions?limit=1&start=0",
+                    200,
+                    &json!([user_txn(0, &sponsored)]).to_string(),
+                )
+                .on_get("/accounts/0xc", 200, &account(0, "0xc"))
+                // the rotation is the last transaction, signed by the old key
+                .on_get("/accounts/0xe", 200, &account(1, &auth_key(&multi)))
+                .on_get(
+                    "/accounts/0xe/transactions?limit=1&start=0",
+                    200,
+                    &json!([user_txn(0, &ed25519)]).to_string(),
+                )
+                .on_get(
+                    "/resource/0x1::multisig_account::MultisigAccount",
+                    404,
+                    "{}",
+                )
+                .on_get(
+                    "/accounts/0xd/resource/0x1::multisig_account::MultisigAccount",
+                    200,
+                    r#"{"type":"0x1::multisig_account::MultisigAccount","data":{}}"#,
+                ),
+        );
+        assert_eq!(
+            client.get_authentication_key("0xa").await.unwrap(),
+            auth_key(&multi)
+        );
+        assert_eq!(
+            client.detect_account_scheme("0xa").await.unwrap(),
+            AccountScheme::MultiEd25519
+        );
+        assert_eq!(
+            client.detect_account_scheme("0xb").await.unwrap(),
+            AccountScheme::SingleKey
+        );
+        assert_eq!(
+            client.detect_account_scheme("0xc").await.unwrap(),
+            AccountScheme::Unknown
+        );
+        assert_eq!(
+            client.detect_account_scheme("0xe").await.unwrap(),
+            AccountScheme::Unknown
+        );
+        let requests_before = transport.requests().len();
+        assert_eq!(
+            client.detect_account_scheme("0xd").await.unwrap(),
+            AccountScheme::MultisigAccount
+        );
+        assert_eq!(transport.requests().len(), requests_before + 1);
+
+        // ed25519 authentication key: sha3_256(public key || 0x00)
+        let mut preimage = vec![0x11u8; 32];
+        preimage.push(0);
+        assert_eq!(
+            auth_key(&ed25519),
+            format!(
+                "0x{}",
+                hex::encode(<sha3::Sha3_256 as sha3::Digest>::digest(&preimage))
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_health_and_sync_status() {
+        let now_usecs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_micros();
+        let ledger_info = |timestamp: u128| {
+            json!({
+                "chain_id": 1, "epoch": "9", "ledger_version": "5000", "oldest_ledger_version": "100",
+                "ledger_timestamp": timestamp.to_string(), "node_role": "full_node",
+                "block_height": "700", "oldest_block_height": "10", "git_hash": "abc123"
+            })
+            .to_string()
+        };
+        let (client, _) = mock_client(
+            MockTransport::new()
+                .on_get("/-/healthy", 200, r#"{"message":"aptos-node:ok"}"#)
+                .on_get("/v1/", 200, &ledger_info(now_usecs))
+                .on_get("/v1/", 200, &ledger_info(now_usecs))
+                .on_get("/v1/", 200, &ledger_info(now_usecs - 120_000_000)),
+        );
+        let health = client.health().await.unwrap();
+        assert!(health.healthy);
+        assert_eq!((health.epoch, health.ledger_version), (9, 5000));
+        assert_eq!(health.oldest_ledger_version, 100);
+        assert_eq!(health.git_hash.as_deref(), Some("abc123"));
+        assert!(client.is_synced(10).await.unwrap());
+        assert!(!client.is_synced(10).await.unwrap());
+
+        let (unhealthy, _) = mock_client(
+            MockTransport::new()
+                .on_get("/-/healthy", 503, "unhealthy")
+                .on_get("/v1/", 200, &ledger_info(now_usecs)),
+        );
+        assert!(!unhealthy.health().await.unwrap().healthy);
+    }
+
+    #[tokio::test]
+    async fn test_frozen_store_cannot_receive() {
+        let (client, _) = mock_client(MockTransport::new().on_get(
+            "/resource/0x1::coin::CoinStore<0xc::usdc::USDC>",
+            200,
+            r#"{"type":"0x1::coin::CoinStore<0xc::usdc::USDC>","data":{"coin":{"value":"5"},"frozen":true}}"#,
+        ));
+        assert!(
+            client
+                .is_coin_store_frozen("0xa", "0xc::usdc::USDC")
+                .await
+                .unwrap()
+        );
+        assert!(
+            client
+                .is_registered("0xa", "0xc::usdc::USDC")
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            client
+                .get_store_status("0xa", "0xc::usdc::USDC")
+                .await
+                .unwrap(),
+            StoreStatus::Frozen
+        );
+        assert!(!StoreStatus::Frozen.can_receive());
+
+        // fungible asset: primary_store_exists, then is_frozen
+        let (client, transport) = mock_client(
+            MockTransport::new()
+                .on_post("/view", 200, "[true]")
+                .on_post("/view", 200, "[true]")
+                .on_post("/view", 200, "[false]"),
+        );
+        assert_eq!(
+            client.get_store_status("0xa", "0xbeef").await.unwrap(),
+            StoreStatus::Frozen
+        );
+        assert!(!client.is_coin_store_frozen("0xa", "0xbeef").await.unwrap());
+        let requests = transport.requests();
+        assert_eq!(
+            requests[1].body.as_ref().unwrap()["function"],
+            "0x1::primary_fungible_store::is_frozen"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_all_account_events_pages() {
+        let page = |sequences: std::ops::Range<u64>| {
+            let events: Vec<Value> = sequences
+                .map(|seq| {
+                    json!({
+                        "guid": {"creation_number": "0", "account_address": "0x1"},
+                        "sequence_number": seq.to_string(), "type": "0x1::m::E", "data": {}
+                    })
+                })
+                .collect();
+            Value::from(events).to_string()
+        };
+        let (client, transport) = mock_client(
+            MockTransport::new()
+                .on_get("/events/h?limit=100&start=0", 200, &page(0..100))
+                .on_get("/events/h?limit=100&start=100", 200, &page(100..130))
+                .on_get("/events/h?limit=20&start=100", 200, &page(100..120))
+                .on_get("/events/h?limit=1", 200, &page(129..130))
+                .on_get("/events/h?limit=30&start=100", 200, &page(100..130)),
+        );
+        let all = client
+            .get_all_account_events("0x1", "h", Some(0), None)
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 130);
+        assert_eq!(all.last().unwrap().sequence_number, "129");
+        let capped = client
+            .get_all_account_events("0x1", "h", Some(0), Some(120))
+            .await
+            .unwrap();
+        assert_eq!(capped.len(), 120);
+        assert_eq!(transport.requests().len(), 4);
+        // the most recent events are paged from the latest sequence number back
+        let recent = client
+            .get_all_account_events("0x1", "h", None, Some(30))
+            .await
+            .unwrap();
+        assert_eq!(recent.len(), 30);
+        assert_eq!(recent[0].sequence_number, "100");
+        assert_eq!(recent.last().unwrap().sequence_number, "129");
+    }
+
+    #[tokio::test]
+    async fn test_submit_rejects_chain_id_mismatch() {
+        let (client, transport) = mock_client(
+            MockTransport::new()
+                .on_get(
+                    "/v1/",
+                    200,
+                    r#"{"chain_id":2,"epoch":"1","ledger_version":"1","ledger_timestamp":"1","node_role":"full_node","block_height":"1"}"#,
+                )
+                .on_post("/transactions", 400, "invalid signature"),
+        );
+        let mainnet_txn = json!({"transaction": {"sender": "0xa", "chain_id": 1}, "signature": {}});
+        let error = client.submit_transaction(&mainnet_txn).await.unwrap_err();
+        assert!(error.contains("chain id mismatch"));
+        assert_eq!(client.chain_id().await.unwrap(), 2);
+        let testnet_txn = json!({"transaction": {"sender": "0xa", "chain_id": 2}, "signature": {}});
+        // passes the check and reaches the node
+        let submitted = client.submit_transaction(&testnet_txn).await;
+        assert!(!submitted.unwrap_err().contains("chain id mismatch"));
+        // the chain id is fetched once
+        let chain_info_requests = transport
+            .requests()
+            .iter()
+            .filter(|r| r.method == "GET")
+            .count();
+        assert_eq!(chain_info_requests, 1);
+    }
+
+    #[tokio::test]
+    async fn test_submit_transaction_idempotent() {
+        let committed = json!([{
+            "version": "9", "hash": "0xlanded", "state_checkpoint_hash": null,
+            "success": true, "vm_status": "Executed successfully",
+            "type": "user_transaction", "sender": "0xa", "sequence_number": "2",
+            "payload": {"type": "entry_function_payload", "function": "0x1::coin::transfer", "type_arguments": [], "arguments": []},
+            "signature": {"type": "ed25519_signature", "public_key": "0x1", "signature": "0x2"}
+        }]);
+        let (client, transport) = mock_client(
+            MockTransport::new()
+                .on_get(
+                    "/accounts/0xa/transactions?limit=1&start=2",
+                    200,
+                    &committed.to_string(),
+                )
+                .on_get("/accounts/0xa/transactions?limit=1&start=3", 200, "[]")
+                .on_post("/transactions", 400, "invalid signature"),
+        );
+        let retried =
+            json!({"transaction": {"sender": "0xA", "sequence_number": "2"}, "signature": {}});
+        let submission = client
+            .submit_transaction_idempotent(&retried)
+            .await
+            .unwrap();
+        assert!(matches!(submission, TxSubmission::Committed(_)));
+        assert_eq!(submission.hash(), "0xlanded");
+        assert!(transport.requests().iter().all(|r| r.method == "GET"));
+
+        let fresh =
+            json!({"transaction": {"sender": "0xa", "sequence_number": 3}, "signature": {}});
+        let error = client
+            .submit_transaction_idempotent(&fresh)
+            .await
+            .unwrap_err();
+        assert!(error.contains("transaction submit failed"));
+        assert!(
+            client
+                .submit_transaction_idempotent(&json!({}))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_idempotent_submit_finds_a_lost_submission() {
+        let wallet = Arc::new(wallet::Wallet::new().unwrap());
+        let signed = |sequence_number: u64, expiration: u64| {
+            let raw_txn = json!({
+                "sender": wallet.address(),
+                "sequence_number": sequence_number.to_string(),
+                "max_gas_amount": "2000",
+                "gas_unit_price": "100",
+                "expiration_timestamp_secs": expiration.to_string(),
+                "payload": {
+                    "type": "entry_function_payload",
+                    "function": "0x1::aptos_account::transfer",
+                    "type_arguments": [],
+                    "arguments": ["0xb", "1"]
+                },
+                "chain_id": 1
+            });
+            let bcs = signing::raw_transaction_bcs(
+                &raw_txn,
+                &signing::framework_params("0x1::aptos_account::transfer").unwrap(),
+            )
+            .unwrap();
+            let signature = wallet.sign(&signing::signing_message(&bcs)).unwrap();
+            trade::Trade::create_signed_transaction_tx(Arc::clone(&wallet), raw_txn, signature)
+                .unwrap()
+        };
+        let live = signed(3, u64::MAX / 2);
+        let (offline, _) = mock_client(MockTransport::new());
+        let hash = offline.signed_transaction_hash(&live).await.unwrap();
+        let (client, transport) = mock_client(
+            MockTransport::new()
+                .on_get(
+                    "/v1/",
+                    200,
+                    r#"{"chain_id":1,"epoch":"1","ledger_version":"1","ledger_timestamp":"1","node_role":"full_node","block_height":"1"}"#,
+                )
+                .on_get("/transactions?limit=1&start=", 200, "[]")
+                .on_get(
+                    &format!("/transactions/by_hash/{}", hash),
+                    200,
+                    &json!({"type": "pending_transaction", "hash": hash, "sender": wallet.address(), "sequence_number": "3", "max_gas_amount": "2000", "gas_unit_price": "100", "expiration_timestamp_secs": "1", "payload": {}, "signature": {}}).to_string(),
+                )
+                .on_post("/transactions", 504, "gateway timeout"),
+        );
+        let posts = || {
+            transport
+                .requests()
+                .iter()
+                .filter(|r| r.method == "POST")
+                .count()
+        };
+        // the node accepted it but the response was lost
+        assert!(client.submit_transaction_idempotent(&live).await.is_err());
+        let retried = client.submit_transaction_idempotent(&live).await.unwrap();
+        assert!(matches!(retried, TxSubmission::Pending { .. }));
+        assert_eq!(retried.hash(), hash);
+        assert_eq!(posts(), 1);
+
+        // an expired record is dropped and the transaction submitted again
+        let expired = signed(4, 1);
+        assert!(
+            client
+                .submit_transaction_idempotent(&expired)
+                .await
+                .is_err()
+        );
+        assert!(
+            client
+                .submit_transaction_idempotent(&expired)
+                .await
+                .is_err()
+        );
+        assert_eq!(posts(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_transaction_status() {
+        let committed = |hash: &str, success: bool, vm_status: &str| {
+            json!({
+                "version": "9", "hash": hash, "state_checkpoint_hash": null,
+                "success": success, "vm_status": vm_status,
+                "type": "user_transaction", "sender": "0xa", "sequence_number": "2",
+                "payload": {"type": "entry_function_payload", "function": "0x1::coin::transfer", "type_arguments": [], "arguments": []},
+                "signature": {"type": "ed25519_signature", "public_key": "0x1", "signature": "0x2"}
+            })
+            .to_string()
+        };
+        let (client, _) = mock_client(
+            MockTransport::new()
+                .on_get(
+                    "/transactions/by_hash/0xpending",
+                    200,
+                    r#"{"type":"pending_transaction","hash":"0xpending","sender":"0xa","sequence_number":"2","max_gas_amount":"2000","gas_unit_price":"100","expiration_timestamp_secs":"1","payload":{},"signature":{}}"#,
+                )
+                .on_get(
+                    "/transactions/by_hash/0xdone",
+                    200,
+                    &committed("0xdone", true, "Executed successfully"),
+                )
+                .on_get(
+                    "/transactions/by_hash/0xaborted",
+                    200,
+                    &committed("0xaborted", false, "Move abort: EINSUFFICIENT_BALANCE"),
+                )
+                .on_get("/transactions/by_hash/0xbroken", 500, "internal error"),
+        );
+        let status = |hash: &'static str| {
+            let client = client.clone();
+            async move { client.get_transaction_status(hash).await }
+        };
+        assert_eq!(status("0xmissing").await.unwrap(), TxStatus::NotFound);
+        assert_eq!(status("0xpending").await.unwrap(), TxStatus::Pending);
+        assert_eq!(status("0xdone").await.unwrap(), TxStatus::Success);
+        assert_eq!(
+            status("0xaborted").await.unwrap(),
+            TxStatus::Failed("Move abort: EINSUFFICIENT_BALANCE".to_string())
+        );
+        assert!(status("0xbroken").await.is_err());
+        assert!(!TxStatus::Pending.is_committed());
+
+        // committed failures are returned, not waited on
+        let aborted = client.waiting_transaction("0xaborted", 1).await.unwrap();
+        assert!(!aborted.success);
+        let missing = client
+            .waiting_transaction("0xmissing", 1)
+            .await
+            .unwrap_err();
+        assert!(missing.starts_with("transaction not found"));
+        let pending = client
+            .waiting_transaction("0xpending", 1)
+            .await
+            .unwrap_err();
+        assert!(pending.starts_with("Transaction timeout"));
+        let broken = client
+            .waiting_transaction("0xbroken", 30)
+            .await
+            .unwrap_err();
+        assert!(broken.contains("failed 5 times in a row: api error: internal error"));
+    }
+
+    #[tokio::test]
+    async fn test_waiting_transaction_tolerates_lagging_node() {
+        let not_found = r#"{"message":"not found","error_code":"transaction_not_found"}"#;
+        let (client, transport) = mock_client(
+            MockTransport::new()
+                .on_get("/transactions/by_hash/0xlag", 404, not_found)
+                .on_get("/transactions/by_hash/0xlag", 404, not_found)
+                .on_get("/transactions/by_hash/0xlag", 503, "unavailable")
+                .on_get("/transactions/by_hash/0xlag", 404, not_found)
+                .on_get(
+                    "/transactions/by_hash/0xlag",
+                    200,
+                    r#"{"version":"9","hash":"0xlag","state_checkpoint_hash":null,"success":true,"type":"user_transaction","sender":"0xa","sequence_number":"2","payload":{"type":"entry_function_payload","function":"0x1::coin::transfer","type_arguments":[],"arguments":[]},"signature":{"type":"ed25519_signature","public_key":"0x1","signature":"0x2"}}"#,
+                ),
+        );
+        let txn = client.waiting_transaction("0xlag", 10).await.unwrap();
+        assert_eq!(txn.hash, "0xlag");
+        assert_eq!(transport.requests().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_waiting_transaction_stops_after_expiration() {
+        let not_found = |ledger_secs: u64| {
+            transport::HttpResponse::new(
+                404,
+                r#"{"message":"not found","error_code":"transaction_not_found"}"#,
+            )
+            .with_header(
+                "x-aptos-ledger-timestamp",
+                &(ledger_secs * 1_000_000).to_string(),
+            )
+        };
+        let (client, transport) = mock_client(
+            MockTransport::new()
+                .on("GET", "/transactions/by_hash/0xlost", not_found(99))
+                .on("GET", "/transactions/by_hash/0xlost", not_found(100))
+                .on(
+                    "GET",
+                    "/transactions/by_hash/0xdropped",
+                    transport::HttpResponse::new(
+                        200,
+                        r#"{"type":"pending_transaction","hash":"0xdropped","sender":"0xa","sequence_number":"2","max_gas_amount":"2000","gas_unit_price":"100","expiration_timestamp_secs":"100","payload":{},"signature":{}}"#,
+                    ),
+                )
+                .on("GET", "/transactions/by_hash/0xdropped", not_found(101)),
+        );
+        let started = std::time::Instant::now();
+        let err = client
+            .waiting_transaction_with_expiration("0xlost", 100, 30)
+            .await
+            .unwrap_err();
+        assert!(err.starts_with("transaction expired without being committed"));
+        assert_eq!(transport.requests().len(), 2);
+        // the expiration of a transaction seen pending is remembered
+        let err = client
+            .waiting_transaction("0xdropped", 30)
+            .await
+            .unwrap_err();
+        assert!(err.starts_with("transaction expired without being committed"));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_simulate_batch_keeps_input_order() {
+        let simulated = |gas_used: u64, success: bool, vm_status: &str| {
+            json!([{
+                "version": "0", "hash": "0x0", "state_checkpoint_hash": null,
+                "gas_used": gas_used.to_string(), "success": success, "vm_status": vm_status,
+                "type": "user_transaction", "sender": "0xa", "sequence_number": "2",
+                "payload": {"type": "entry_function_payload", "function": "0x1::router::swap", "type_arguments": [], "arguments": []},
+                "signature": {"type": "ed25519_signature", "public_key": "0x1", "signature": "0x2"}
+            }])
+            .to_string()
+        };
+        let (client, transport) = mock_client(
+            MockTransport::new()
+                .on_post(
+                    "/transactions/simulate",
+                    200,
+                    &simulated(900, true, "Executed successfully"),
+                )
+                .on_post(
+                    "/transactions/simulate",
+                    200,
+                    &simulated(40, false, "Move abort: E_OUTPUT_TOO_LOW"),
+                )
+                .on_post("/transactions/simulate", 400, "invalid transaction")
+                .on_post(
+                    "/transactions/simulate",
+                    200,
+                    &simulated(700, true, "Executed successfully"),
+                ),
+        );
+        let wallet = Arc::new(Wallet::new().unwrap());
+        let routes = ["liquidswap", "thala", "pancake", "cellana"];
+        let candidates = routes
+            .iter()
+            .map(|route| {
+                json!({
+                    "sender": wallet.address(),
+                    "sequence_number": "2",
+                    "payload": {"type": "entry_function_payload", "function": format!("0x1::{}::swap", route), "type_arguments": [], "arguments": []}
+                })
+            })
+            .collect();
+        let results = client.simulate_batch(wallet, candidates, 2).await;
+        let outcomes: Vec<(bool, u64)> = results.iter().map(|r| (r.success, r.gas_used)).collect();
+        assert_eq!(
+            outcomes,
+            vec![(true, 900), (false, 40), (false, 0), (true, 700)]
+        );
+        assert_eq!(results[1].vm_status, "Move abort: E_OUTPUT_TOO_LOW");
+        assert!(
+            results[2]
+                .error
+                .as_ref()
+                .unwrap()
+                .contains("invalid transaction")
+        );
+        assert!(results[2].transaction.is_none());
+        let submitted: Vec<String> = transport
+            .requests()
+            .iter()
+            .map(|r| r.body.as_ref().unwrap()["transaction"]["payload"]["function"].to_string())
+            .collect();
+        assert_eq!(submitted.len(), 4);
+        for (route, function) in routes.iter().zip(&submitted) {
+            assert!(function.contains(route));
+        }
+        assert!(
+            client
+                .simulate_batch(Arc::new(Wallet::new().unwrap()), vec![], 4)
+                .await
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_gas_schedule_cached_per_epoch() {
+        let chain_info = |epoch: u64| {
+            json!({"chain_id": 1, "epoch": epoch.to_string(), "ledger_version": "1", "ledger_timestamp": "1", "node_role": "full_node", "block_height": "1"})
+                .to_string()
+        };
+        let schedule = json!({
+            "type": "0x1::gas_schedule::GasScheduleV2",
+            "data": {
+                "feature_version": "12",
+                "entries": [
+                    {"key": "instr.nop", "val": "36"},
+                    {"key": "txn.storage_fee_per_state_byte", "val": "40"},
+                    {"key": "txn.max_execution_gas", "val": "920000000"}
+                ]
+            }
+        });
+        let (client, transport) = mock_client(
+            MockTransport::new()
+                .on_get("/v1/", 200, &chain_info(5))
+                .on_get("/v1/", 200, &chain_info(5))
+                .on_get("/v1/", 200, &chain_info(6))
+                .on_get(
+                    "/accounts/0x1/resource/0x1::gas_schedule::GasScheduleV2",
+                    200,
+                    &schedule.to_string(),
+                ),
+        );
+        let gas_schedule = client.get_gas_schedule().await.unwrap();
+        assert_eq!(gas_schedule.feature_version, 12);
+        assert_eq!(gas_schedule.entries.len(), 3);
+        assert_eq!(gas_schedule.get("txn.storage_fee_per_state_byte"), Some(40));
+        assert_eq!(gas_schedule.get("txn.max_execution_gas"), Some(920_000_000));
+        assert_eq!(gas_schedule.get("instr.unknown"), None);
+        let schedule_reads = || {
+            transport
+                .requests()
+                .iter()
+                .filter(|r| r.url.contains("GasScheduleV2"))
+                .count()
+        };
+        assert_eq!(client.get_gas_schedule().await.unwrap(), gas_schedule);
+        assert_eq!(schedule_reads(), 1);
+        // a new epoch reads the schedule again
+        client.get_gas_schedule().await.unwrap();
+        assert_eq!(schedule_reads(), 2);
+        assert!(GasSchedule::from_resource(&json!({"entries": []})).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_account_resource_exists() {
+        let (client, _) = mock_client(
+            MockTransport::new()
+                .on_get(
+                    "/accounts/0x1/resource/0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>",
+                    200,
+                    r#"{"type":"0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>","data":{}}"#,
+                )
+                .on_get("/accounts/0xbroken/resource/", 500, "internal error"),
+        );
+        assert!(
+            client
+                .account_resource_exists("0x1", "0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>")
+                .await
+                .unwrap()
+        );
+        assert!(
+            !client
+                .account_resource_exists("0x2", "0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>")
+                .await
+                .unwrap()
+        );
+        assert!(
+            client
+                .account_resource_exists("0xbroken", "0x1::account::Account")
+                .await
+                .is_err()
+        );
+        let (client, transport) = mock_client(
+            MockTransport::new()
+                .on(
+                    "HEAD",
+                    "/accounts/0x3/resource/",
+                    transport::HttpResponse::new(405, ""),
+                )
+                .on_get("/accounts/0x3/resource/", 200, "{}"),
+        );
+        assert!(
+            client
+                .account_resource_exists("0x3", "0x1::account::Account")
+                .await
+                .unwrap()
+        );
+        let methods: Vec<_> = transport.requests().into_iter().map(|r| r.method).collect();
+        assert_eq!(methods, ["HEAD", "GET"]);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_next_epoch() {
+        let chain_info = |epoch: u64| {
+            json!({"chain_id": 1, "epoch": epoch.to_string(), "ledger_version": "1", "ledger_timestamp": "1", "node_role": "full_node", "block_height": "1"})
+                .to_string()
+        };
+        let (client, transport) = mock_client(
+            MockTransport::new()
+                .on_get("/v1/", 200, &chain_info(7))
+                .on_get("/v1/", 200, &chain_info(7))
+                .on_get("/v1/", 200, &chain_info(8))
+                // the epoch started at 0.5s and lasts 2s, so it is due 2.5s after the ledger time
+                .on_get(
+                    "/resource/0x1::reconfiguration::Configuration",
+                    200,
+                    r#"{"type":"0x1::reconfiguration::Configuration","data":{"epoch":"7","last_reconfiguration_time":"500000"}}"#,
+                )
+                .on_get(
+                    "/resource/0x1::block::BlockResource",
+                    200,
+                    r#"{"type":"0x1::block::BlockResource","data":{"epoch_interval":"2000000","height":"1"}}"#,
+                ),
+        );
+        assert_eq!(client.get_epoch().await.unwrap(), 7);
+        let started = std::time::Instant::now();
+        assert_eq!(client.wait_for_next_epoch(10).await.unwrap(), 8);
+        // slept until the expected end instead of polling every second
+        assert!(started.elapsed() >= Duration::from_millis(2000));
+        let ledger_reads = transport
+            .requests()
+            .iter()
+            .filter(|r| r.url.ends_with("/v1/"))
+            .count();
+        assert_eq!(ledger_reads, 3);
+        // the epoch stays at 8
+        let err = client.wait_for_next_epoch(1).await.unwrap_err();
+        assert!(err.contains("epoch 8 did not end within 1s"));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_finality() {
+        let chain_info = |ledger_version: u64| {
+            json!({"chain_id": 1, "epoch": "1", "ledger_version": ledger_version.to_string(), "ledger_timestamp": "1", "node_role": "full_node", "block_height": "1"})
+                .to_string()
+        };
+        let (client, transport) = mock_client(
+            MockTransport::new()
+                .on_get(
+                    "/transactions/by_hash/0xdone",
+                    200,
+                    r#"{"version":"9","hash":"0xdone","state_checkpoint_hash":null,"success":true,"type":"user_transaction","sender":"0xa","sequence_number":"2","payload":{"type":"entry_function_payload","function":"0x1::coin::transfer","type_arguments":[],"arguments":[]},"signature":{"type":"ed25519_signature","public_key":"0x1","signature":"0x2"}}"#,
+                )
+                .on_get("/v1/", 200, &chain_info(10))
+                .on_get("/v1/", 200, &chain_info(12)),
+        );
+        let txn = client.wait_for_finality("0xdone", 3, 5).await.unwrap();
+        assert_eq!(txn.version, "9");
+        let ledger_reads = transport
+            .requests()
+            .iter()
+            .filter(|r| r.url.ends_with("/v1/"))
+            .count();
+        assert_eq!(ledger_reads, 2);
+        // 0 confirmations does not read the ledger
+        client.wait_for_finality("0xdone", 0, 5).await.unwrap();
+        assert_eq!(transport.requests().len(), 4);
+        let err = client
+            .wait_for_finality("0xdone", 100, 1)
+            .await
+            .unwrap_err();
+        assert!(err.contains("3 of 100 confirmations"));
+    }
+
+    #[tokio::test]
+    async fn test_never_funded_account() {
+        let address = "0x8f2a6c4e1b3d5f7092a4c6e8b0d2f4a6c8e0b2d4f6a8c0e2b4d6f8a0c2e4b6d8";
+        let (client, _) = mock_client(MockTransport::new().on_get(
+            &format!("/accounts/{}", address),
+            404,
+            &format!(
+                r#"{{"message":"Account not found by Address({}) and Ledger version(1)","error_code":"account_not_found","vm_error_code":null}}"#,
+                address
+            ),
+        ));
+        assert!(matches!(
+            client.get_account_info(address).await,
+            Err(AptosError::NotFound(_))
+        ));
+        assert!(!client.account_exists(address).await.unwrap());
+        assert!(matches!(
+            client.get_account_sequence_number(address).await,
+            Err(AptosError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_batch_get_account_info_keeps_per_address_errors() {
+        let (client, transport) = mock_client(
+            MockTransport::new()
+                .on_get(
+                    "/accounts/0xa",
+                    200,
+                    r#"{"sequence_number":"1","authentication_key":"0x0a"}"#,
+                )
+                .on_get(
+                    "/accounts/0xb",
+                    200,
+                    r#"{"sequence_number":"2","authentication_key":"0x0b"}"#,
+                )
+                .on_get("/accounts/0xc", 500, "boom"),
+        );
+        let addresses: Vec<String> = ["0xa", "0xb", "0xc", "0xd"]
+            .iter()
+            .map(|a| a.to_string())
+            .collect();
+        let results = client.batch_get_account_info(&addresses, 2).await;
+        assert_eq!(results.len(), 4);
+        assert_eq!(results["0xa"].as_ref().unwrap().sequence_number, "1");
+        assert_eq!(results["0xb"].as_ref().unwrap().sequence_number, "2");
+        assert!(matches!(
+            results["0xc"],
+            Err(AptosError::Api { status: 500, .. })
+        ));
+        assert!(matches!(results["0xd"], Err(AptosError::NotFound(_))));
+        assert_eq!(transport.requests().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_get_coin_info_coin_and_fungible_asset() {
+        let (client, _) = mock_client(
+            MockTransport::new()
+                .on_get(
+                    "/accounts/0xbeef/resource/0x1::coin::CoinInfo",
+                    200,
+                    r#"{"type":"0x1::coin::CoinInfo<0xbeef::eth::ETH>","data":{
+                        "name":"Ether","symbol":"ETH","decimals":8,
+                        "supply":{"vec":[{"aggregator":{"vec":[]},"integer":{"vec":[{"limit":"340282366920938463463374607431768211455","value":"21000000"}]}}]}
+                    }}"#,
+                )
+                .on_get(
+                    "/accounts/0xfa/resource/0x1::fungible_asset::Metadata",
+                    200,
+                    r#"{"type":"0x1::fungible_asset::Metadata","data":{"name":"Tether USD","symbol":"USDt","decimals":6,"icon_uri":"","project_uri":""}}"#,
+                )
+                .on_get(
+                    "/accounts/0xfa/resource/0x1::fungible_asset::ConcurrentSupply",
+                    200,
+                    r#"{"type":"0x1::fungible_asset::ConcurrentSupply","data":{"current":{"max_value":"340282366920938463463374607431768211455","value":"36893488147419103232"}}}"#,
+                ),
+        );
+        let coin = client.get_coin_info("0xbeef::eth::ETH").await.unwrap();
+        assert_eq!(
+            coin,
+            CoinInfo {
+                name: "Ether".to_string(),
+                symbol: "ETH".to_string(),
+                decimals: 8,
+                supply: Some(21_000_000),
+            }
+        );
+        let fa = client.get_coin_info("0xfa").await.unwrap();
+        assert_eq!((fa.symbol.as_str(), fa.decimals), ("USDt", 6));
+        assert_eq!(fa.supply, Some(36_893_488_147_419_103_232));
+        assert!(client.get_coin_info("0xdead::x::X").await.is_err());
+    }
+
+    #[test]
+    fn test_parse_coin_supply_shapes() {
+        let parse = |value: Value| Aptos::parse_coin_supply(&value);
+        // Option<OptionalAggregator> with an integer
+        assert_eq!(
+            parse(
+                json!({"vec": [{"aggregator": {"vec": []}, "integer": {"vec": [{"limit": "340282366920938463463374607431768211455", "value": "21000000"}]}}]})
+            ),
+            Some(21_000_000)
+        );
+        // Option<OptionalAggregator> with a parallelizable aggregator
+        assert_eq!(
+            parse(
+                json!({"vec": [{"aggregator": {"vec": [{"handle": "0x1", "key": "0x2", "limit": "100"}]}, "integer": {"vec": []}}]})
+            ),
+            None
+        );
+        // Option<Integer>
+        assert_eq!(parse(json!({"vec": [{"value": "7"}]})), Some(7));
+        // Option<u128> from 0x1::coin::supply
+        assert_eq!(
+            parse(json!({"vec": ["36893488147419103232"]})),
+            Some(1u128 << 65)
+        );
+        // fungible asset ConcurrentSupply and Supply
+        assert_eq!(
+            parse(json!({"current": {"max_value": "1000", "value": "12"}})),
+            Some(12)
+        );
+        assert_eq!(
+            parse(json!({"current": "15", "maximum": {"vec": ["100"]}})),
+            Some(15)
+        );
+        // direct values
+        assert_eq!(parse(json!("9")), Some(9));
+        assert_eq!(parse(json!(9)), Some(9));
+        // untracked or unknown
+        assert_eq!(parse(json!({"vec": []})), None);
+        assert_eq!(parse(json!({"handle": "0x1"})), None);
+        assert_eq!(parse(json!(null)), None);
+        assert_eq!(parse(json!("not a number")), None);
+    }
+
+    #[tokio::test]
+    async fn test_get_token_supply() {
+        let (client, _) = mock_client(
+            MockTransport::new()
+                .on_get(
+                    "/accounts/0xbeef/resource/0x1::coin::CoinInfo",
+                    200,
+                    r#"{"type":"0x1::coin::CoinInfo<0xbeef::eth::ETH>","data":{"name":"Ether","symbol":"ETH","decimals":8,"supply":{"vec":[{"value":"42"}]}}}"#,
+                )
+                .on_get(
+                    "/accounts/0xfa/resource/0x1::fungible_asset::Metadata",
+                    200,
+                    r#"{"type":"0x1::fungible_asset::Metadata","data":{"name":"Asset","symbol":"FA","decimals":6,"icon_uri":"","project_uri":""}}"#,
+                )
+                .on_get(
+                    "/accounts/0xfa/resource/0x1::fungible_asset::Supply",
+                    200,
+                    r#"{"type":"0x1::fungible_asset::Supply","data":{"current":"5","maximum":{"vec":[]}}}"#,
+                ),
+        );
+        assert_eq!(
+            client.get_token_supply("0xbeef::eth::ETH").await.unwrap(),
+            Some(42)
+        );
+        assert_eq!(client.get_token_supply("0xfa").await.unwrap(), Some(5));
+    }
+
+    #[tokio::test]
+    async fn test_account_exists_propagates_api_errors() {
+        let (client, _) = mock_client(MockTransport::new().on_get("/accounts/0x1", 500, "boom"));
+        assert!(matches!(
+            client.account_exists("0x1").await,
+            Err(AptosError::Api { status: 500, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_account_resource_vec_follows_cursor() {
+        let resource = |name: &str| format!(r#"{{"type":"0x1::{}","data":{{}}}}"#, name);
+        let (client, transport) = mock_client(
+            MockTransport::new()
+                .on(
+                    "GET",
+                    "/accounts/0x1/resources?limit=1000",
+                    transport::HttpResponse::new(
+                        200,
+                        &format!("[{},{}]", resource("a::A"), resource("b::B")),
+                    )
+                    .with_header(
+                        "X-Aptos-Cursor",
+                        "0x0000000000000000000000000000000000000000000000000000000000000001",
+                    ),
+                )
+                .on_get(
+                    "&start=0x0000000000000000000000000000000000000000000000000000000000000001",
+                    200,
+                    &format!("[{}]", resource("c::C")),
+                ),
+        );
+        let resources = client.get_account_resource_vec("0x1").await.unwrap();
+        let types: Vec<&str> = resources.iter().map(|r| r.r#type.as_str()).collect();
+        assert_eq!(types, vec!["0x1::a::A", "0x1::b::B", "0x1::c::C"]);
+        assert_eq!(transport.requests().len(), 2);
+        let (page, next) = client
+            .get_account_resources_page("0x1", 1000, None)
+            .await
+            .unwrap();
+        assert_eq!(page.len(), 2);
+        assert!(next.unwrap().ends_with("01"));
+    }
+
+    #[tokio::test]
+    async fn test_get_account_module_page_returns_cursor() {
+        let module = |name: &str| format!(r#"{{"bytecode":"0x","abi":{{"name":"{}"}}}}"#, name);
+        let (client, _) = mock_client(
+            MockTransport::new()
+                .on(
+                    "GET",
+                    "/accounts/0x1/modules?limit=2",
+                    transport::HttpResponse::new(
+                        200,
+                        &format!("[{},{}]", module("a"), module("b")),
+                    )
+                    .with_header("X-Aptos-Cursor", "0x02"),
+                )
+                .on_get(
+                    "/accounts/0x1/modules?limit=2&start=0x02",
+                    200,
+                    &format!("[{}]", module("c")),
+                ),
+        );
+        let (page, next) = client
+            .get_account_module_page("0x1", Some(2), None)
+            .await
+            .unwrap();
+        assert_eq!(page.len(), 2);
+        assert_eq!(next.as_deref(), Some("0x02"));
+        let (page, next) = client
+            .get_account_module_page("0x1", Some(2), next.as_deref())
+            .await
+            .unwrap();
+        assert_eq!(page[0].name(), Some("c"));
+        assert_eq!(next, None);
+    }
+
+    #[tokio::test]
+    async fn test_mock_resource_not_found() {
+        let (client, _) = mock_client(MockTransport::new());
+        let resource = client
+            .get_account_resource("0x1", "0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>")
+            .await
+            .unwrap();
+        assert!(resource.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_mock_view_posts_request() {
+        let (client, transport) =
+            mock_client(MockTransport::new().on_post("/view", 200, r#"["42"]"#));
+        let result = client
+            .view(
+                &ViewRequest {
+                    function: "0x1::coin::balance".to_string(),
+                    type_arguments: vec!["0x1::aptos_coin::AptosCoin".to_string()],
+                    arguments: vec![json!("0x1")],
+                },
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(result, vec![json!("42")]);
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(
+            requests[0].body.as_ref().unwrap()["function"],
+            "0x1::coin::balance"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_view_typed_tuples() {
+        let (client, _) = mock_client(
+            MockTransport::new()
+                .on_post("/view", 200, r#"["42"]"#)
+                .on_post("/view", 200, r#"["1000","2500"]"#)
+                .on_post(
+                    "/view",
+                    200,
+                    r#"[["0xa","0xb"],"340282366920938463463374607431768211455",true]"#,
+                )
+                .on_post("/view", 200, r#"["1","2"]"#),
+        );
+        let request = ViewRequest {
+            function: "0x1::pool::reserves".to_string(),
+            type_arguments: vec![],
+            arguments: vec![],
+        };
+        let (balance,): (u64,) = client.view_typed(&request, None).await.unwrap();
+        assert_eq!(balance, 42);
+        let reserves: (u64, u64) = client.view_typed(&request, None).await.unwrap();
+        assert_eq!(reserves, (1000, 2500));
+        let (owners, supply, frozen): (Vec<String>, u128, bool) =
+            client.view_typed(&request, None).await.unwrap();
+        assert_eq!(owners, vec!["0xa", "0xb"]);
+        assert_eq!(supply, u128::MAX);
+        assert!(frozen);
+        let err = client
+            .view_typed::<(u64, u64, u64)>(&request, None)
+            .await
+            .unwrap_err();
+        assert!(err.starts_with("0x1::pool::reserves output"));
+        let read = types::ContractReadResult {
+            success: true,
+            data: json!(["7", ["8", "9"]]),
+            error: None,
+        };
+        assert_eq!(read.decode::<(u8, Vec<u64>)>().unwrap(), (7, vec![8, 9]));
+    }
+
     #[tokio::test]
     async fn test_get_specific_transaction() {
         let client = Aptos::new(AptosType::Mainnet);
@@ -477,4 +3249,318 @@ mod tests {
             }
         }
     }
+
+    #[tokio::test]
+    async fn test_get_table_item_typed_supply_table() {
+        // the APT supply is kept in an aggregator table keyed by address
+        let handle = "0x1b854694ae746cdbd8d44186ca4929b2b337df21d1c74633be19b2710552fdca";
+        let key = "0x619dc29a0aac8fa146714058e8dd6d2d0f3bdf5f6331907bf91f3acd81e6935";
+        let (client, transport) = mock_client(MockTransport::new().on_post(
+            &format!("/tables/{}/item", handle),
+            200,
+            r#""18446744073709551616""#,
+        ));
+        let supply: u128 = client
+            .get_table_item_typed(handle, key, Some("address"), None)
+            .await
+            .unwrap();
+        assert_eq!(supply, 1u128 << 64);
+        let body = transport.requests()[0].body.clone().unwrap();
+        assert_eq!(body["key_type"], "address");
+        assert_eq!(body["value_type"], "u128");
+        assert_eq!(body["key"], key);
+    }
+
+    #[tokio::test]
+    async fn test_get_table_item_typed_missing_item() {
+        let (client, transport) = mock_client(MockTransport::new().on_post(
+            "/tables/0x1/item",
+            404,
+            r#"{"message":"Table Item not found","error_code":"table_item_not_found"}"#,
+        ));
+        let item: Option<u64> = client
+            .get_table_item_typed_opt("0x1", 7u64, None, None)
+            .await
+            .unwrap();
+        assert!(item.is_none());
+        assert_eq!(transport.requests()[0].body.as_ref().unwrap()["key"], "7");
+        let result: Result<u64, String> =
+            client.get_table_item_typed("0x1", 7u64, None, None).await;
+        assert!(result.is_err());
+        let result: Result<Value, String> =
+            client.get_table_item_typed("0x1", 7u64, None, None).await;
+        assert!(result.unwrap_err().contains("cannot infer value type"));
+    }
+
+    #[tokio::test]
+    async fn test_get_table_item_missing_and_raw() {
+        let not_found = r#"{"message":"Table Item not found","error_code":"table_item_not_found"}"#;
+        let (client, transport) = mock_client(
+            MockTransport::new()
+                .on_post("/tables/0xa/item", 200, r#"{"owner":"0x1","amount":"5"}"#)
+                .on_post("/tables/0xb/item", 404, not_found)
+                .on_post("/tables/0xc/item", 200, r#""0x0102ff""#)
+                .on_post("/tables/0xd/item", 200, r#""AQL/""#)
+                .on_post("/tables/0xe/item", 200, r#"{"owner":"0x1"}"#)
+                .on_post("/tables/0xf/item", 500, not_found)
+                .on_post(
+                    "/tables/0x10/item",
+                    404,
+                    r#"{"message":"Resource not found","error_code":"resource_not_found"}"#,
+                ),
+        );
+        let key = json!("0x1");
+        let item = client
+            .get_table_item("0xa", "address", "0x1::pool::Position", &key)
+            .await
+            .unwrap();
+        assert_eq!(item.unwrap()["amount"], "5");
+        // a missing or deleted item is not an error
+        let missing = client
+            .get_table_item("0xb", "address", "0x1::pool::Position", &key)
+            .await
+            .unwrap();
+        assert!(missing.is_none());
+        assert!(
+            client
+                .get_table_item_raw("0xb", "address", &key)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let hex_bytes = client
+            .get_table_item_raw("0xc", "address", &key)
+            .await
+            .unwrap();
+        assert_eq!(hex_bytes, Some(vec![1, 2, 255]));
+        let base64_bytes = client
+            .get_table_item_raw("0xd", "address", &key)
+            .await
+            .unwrap();
+        assert_eq!(base64_bytes, Some(vec![1, 2, 255]));
+        let body = transport.requests()[3].body.clone().unwrap();
+        assert_eq!(body["value_type"], "vector<u8>");
+        assert!(
+            client
+                .get_table_item_raw("0xe", "address", &key)
+                .await
+                .is_err()
+        );
+        // the error code only counts on a 404, and a 404 only with that code
+        for table in ["0xf", "0x10"] {
+            assert!(
+                client
+                    .get_table_item(table, "address", "0x1::pool::Position", &key)
+                    .await
+                    .is_err()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_typed_coin_store_balance() {
+        let (client, _) = mock_client(MockTransport::new().on_get(
+            "/accounts/0x1/resource/0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>",
+            200,
+            r#"{
+                "type": "0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>",
+                "data": {
+                    "coin": {"value": "123456"},
+                    "deposit_events": {"counter": "2", "guid": {"id": {"addr": "0x1", "creation_num": "2"}}},
+                    "withdraw_events": {"counter": "1", "guid": {"id": {"addr": "0x1", "creation_num": "3"}}},
+                    "frozen": false
+                }
+            }"#,
+        ));
+        let store: CoinStore = client
+            .get_account_resource_typed("0x1", "0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(store.coin.value, 123456);
+        assert_eq!(store.deposit_events.counter, 2);
+        assert_eq!(store.withdraw_events.guid.id.creation_num, "3");
+        assert_eq!(client.get_account_balance("0x1").await.unwrap(), 123456);
+        assert_eq!(client.get_account_balance("0x2").await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_view_balance_at_ledger_version() {
+        let (client, transport) = mock_client(MockTransport::new().on_post(
+            "/view?ledger_version=123456",
+            200,
+            r#"["5000"]"#,
+        ));
+        let request = ViewRequest::new("0x1::coin::balance")
+            .type_arg("0x1::aptos_coin::AptosCoin")
+            .arg("0x1");
+        let result = client.view(&request, Some(123456)).await.unwrap();
+        assert_eq!(result, vec![json!("5000")]);
+        let requests = transport.requests();
+        assert!(requests[0].url.ends_with("/view?ledger_version=123456"));
+        assert_eq!(
+            requests[0].body,
+            Some(json!({
+                "function": "0x1::coin::balance",
+                "type_arguments": ["0x1::aptos_coin::AptosCoin"],
+                "arguments": ["0x1"]
+            }))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_module_events_from_node() {
+        let swap = "0xd::pool::SwapEvent";
+        let transactions = json!([{
+            "version": "40", "hash": "0x1", "state_checkpoint_hash": null,
+            "success": true, "vm_status": "Executed successfully",
+            "type": "user_transaction", "sender": "0xa", "sequence_number": "2",
+            "payload": {"type": "entry_function_payload", "function": "0xd::router::swap", "type_arguments": [], "arguments": []},
+            "signature": {"type": "ed25519_signature", "public_key": "0x1", "signature": "0x2"},
+            "events": [
+                {"guid": {"creation_number": "0", "account_address": "0x0"}, "sequence_number": "0", "type": swap, "data": {"amount_in": "5"}},
+                {"guid": {"creation_number": "3", "account_address": "0xa"}, "sequence_number": "1", "type": "0x1::coin::WithdrawEvent", "data": {"amount": "5"}}
+            ]
+        }]);
+        let (client, transport) = mock_client(MockTransport::new().on_get(
+            "/transactions?limit=10&start=40",
+            200,
+            &transactions.to_string(),
+        ));
+        let events = client
+            .get_module_events(swap, Some(10), Some(40))
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data["amount_in"], "5");
+        assert_eq!(events[0].version.as_deref(), Some("40"));
+        assert_eq!(events[0].guid["account_address"], "0x0");
+        assert!(
+            transport.requests()[0]
+                .url
+                .ends_with("/v1/transactions?limit=10&start=40")
+        );
+    }
+
+    #[cfg(feature = "indexer")]
+    #[tokio::test]
+    async fn test_get_module_events_from_indexer() {
+        let swap = "0xd::pool::SwapEvent";
+        let transport = Arc::new(MockTransport::new().on_post(
+            "/graphql",
+            200,
+            &json!({"data": {"events": [
+                {"account_address": "0x0", "creation_number": 0, "sequence_number": 0, "type": swap, "data": {"amount_in": "7"}, "transaction_version": 52},
+                {"account_address": "0x0", "creation_number": 0, "sequence_number": 0, "type": swap, "data": {"amount_in": "6"}, "transaction_version": 51}
+            ]}})
+            .to_string(),
+        ));
+        let client = Aptos::with_transport(AptosType::Mainnet, transport.clone()).with_indexer(
+            Indexer::from_url("https://indexer.test/v1/graphql", transport.clone()),
+        );
+        let events = client.get_module_events(swap, None, None).await.unwrap();
+        let versions: Vec<_> = events.iter().filter_map(|e| e.version.as_deref()).collect();
+        assert_eq!(versions, vec!["51", "52"]);
+        assert_eq!(events[0].sequence_number, "0");
+        let body = transport.requests()[0].body.clone().unwrap();
+        assert_eq!(body["variables"]["where"]["type"]["_eq"], swap);
+        assert_eq!(body["variables"]["limit"], 25);
+        assert_eq!(
+            body["variables"]["order_by"][0]["transaction_version"],
+            "desc"
+        );
+
+        client
+            .get_module_events(swap, Some(2), Some(51))
+            .await
+            .unwrap();
+        let body = transport.requests()[1].body.clone().unwrap();
+        assert_eq!(
+            body["variables"]["where"]["transaction_version"]["_gte"],
+            51
+        );
+        assert_eq!(
+            body["variables"]["order_by"][0]["transaction_version"],
+            "asc"
+        );
+        assert!(transport.requests().iter().all(|r| r.method == "POST"));
+    }
+
+    #[cfg(feature = "indexer")]
+    #[tokio::test]
+    async fn test_get_owned_objects() {
+        let transport = Arc::new(
+            MockTransport::new()
+                .on_post(
+                    "/graphql",
+                    200,
+                    &json!({"data": {"current_objects": [
+                        {"object_address": "0xo1", "owner_address": "0xa"},
+                        {"object_address": "0xo2", "owner_address": "0xa"}
+                    ]}})
+                    .to_string(),
+                )
+                .on_get(
+                    "/accounts/0xo1/resources",
+                    200,
+                    r#"[{"type":"0x1::object::ObjectCore","data":{}},{"type":"0x4::property_map::PropertyMap","data":{}},{"type":"0x4::token::Token","data":{}}]"#,
+                )
+                .on_get(
+                    "/accounts/0xo2/resources",
+                    200,
+                    r#"[{"type":"0x1::object::ObjectCore","data":{}},{"type":"0xabc::vault::Vault<0x1::aptos_coin::AptosCoin>","data":{}}]"#,
+                ),
+        );
+        let without_indexer = Aptos::with_transport(AptosType::Mainnet, transport.clone());
+        assert!(
+            without_indexer
+                .get_owned_objects("0xa", None, 0)
+                .await
+                .unwrap_err()
+                .contains("requires an indexer")
+        );
+        let client = Aptos::with_transport(AptosType::Mainnet, transport.clone()).with_indexer(
+            Indexer::from_url("https://indexer.test/v1/graphql", transport.clone()),
+        );
+        let objects = client.get_owned_objects("0xa", Some(2), 4).await.unwrap();
+        assert_eq!(
+            objects,
+            vec![
+                ObjectInfo {
+                    object_address: "0xo1".to_string(),
+                    r#type: "0x4::token::Token".to_string(),
+                    owner: "0xa".to_string(),
+                },
+                ObjectInfo {
+                    object_address: "0xo2".to_string(),
+                    r#type: "0xabc::vault::Vault<0x1::aptos_coin::AptosCoin>".to_string(),
+                    owner: "0xa".to_string(),
+                },
+            ]
+        );
+        let body = transport.requests()[0].body.clone().unwrap();
+        assert_eq!(body["variables"]["owner"], "0xa");
+        assert_eq!(body["variables"]["limit"], 2);
+        assert_eq!(body["variables"]["offset"], 4);
+        let vaults = client
+            .get_owned_objects_of_type("0xa", "0xabc::vault::Vault", None)
+            .await
+            .unwrap();
+        assert_eq!(vaults.len(), 1);
+        assert_eq!(vaults[0].object_address, "0xo2");
+        let requests = transport.requests();
+        let query = requests.iter().rfind(|r| r.method == "POST").unwrap();
+        assert_eq!(
+            query.body.as_ref().unwrap()["variables"]["limit"],
+            DEFAULT_OWNED_OBJECTS_LIMIT
+        );
+        assert!(
+            client
+                .get_owned_objects_of_type("0xa", "0xabc::vault::V", None)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
 }
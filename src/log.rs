@@ -0,0 +1,53 @@
+/// Diagnostics, forwarded to `tracing` with the `tracing` feature and printed to
+/// stderr without it.
+macro_rules! log_warn {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        ::tracing::warn!($($arg)*);
+        #[cfg(not(feature = "tracing"))]
+        eprintln!($($arg)*);
+    }};
+}
+
+macro_rules! log_error {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        ::tracing::error!($($arg)*);
+        #[cfg(not(feature = "tracing"))]
+        eprintln!($($arg)*);
+    }};
+}
+
+pub(crate) use {log_error, log_warn};
+
+use crate::transport::HttpResponse;
+
+/// Run an http request inside an `rpc` span with its method and url, logging the
+/// status and latency once it completes.
+#[cfg(feature = "tracing")]
+pub(crate) async fn traced(
+    method: &str,
+    url: &str,
+    request: impl Future<Output = Result<HttpResponse, String>>,
+) -> Result<HttpResponse, String> {
+    use tracing::Instrument;
+    let span = tracing::debug_span!("rpc", method, url);
+    let started = std::time::Instant::now();
+    let result = request.instrument(span.clone()).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    let _entered = span.enter();
+    match &result {
+        Ok(response) => tracing::debug!(status = response.status, latency_ms, "rpc response"),
+        Err(e) => tracing::warn!(error = %e, latency_ms, "rpc failed"),
+    }
+    result
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) async fn traced(
+    _method: &str,
+    _url: &str,
+    request: impl Future<Output = Result<HttpResponse, String>>,
+) -> Result<HttpResponse, String> {
+    request.await
+}
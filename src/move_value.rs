@@ -0,0 +1,209 @@
+/// Serde deserializer over move values returned by the api.
+///
+/// The api encodes `u64`, `u128` and `u256` as json strings, `MoveDeserializer`
+/// parses integers from strings at any depth, so view outputs decode straight into
+/// tuples, vectors and structs, e.g. `["1", "2"]` into `(u64, u64)`.
+use serde::{
+    Deserializer,
+    de::{DeserializeSeed, Error, MapAccess, SeqAccess, Visitor},
+    forward_to_deserialize_any,
+};
+use serde_json::{Map, Value};
+
+/// deserializer accepting integers encoded as json strings
+pub(crate) struct MoveDeserializer(pub(crate) Value);
+
+macro_rules! deserialize_integer {
+    ($($method:ident => $visit:ident($ty:ty)),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                match self.0 {
+                    Value::String(s) => {
+                        let integer = s.parse::<$ty>().map_err(|e| {
+                            Self::Error::custom(format!("invalid {} {:?}: {}", stringify!($ty), s, e))
+                        })?;
+                        visitor.$visit(integer)
+                    }
+                    value => value.$method(visitor),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for MoveDeserializer {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::Array(values) => visit_array(values, visitor),
+            Value::Object(map) => visit_object(map, visitor),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    deserialize_integer! {
+        deserialize_i8 => visit_i8(i8),
+        deserialize_i16 => visit_i16(i16),
+        deserialize_i32 => visit_i32(i32),
+        deserialize_i64 => visit_i64(i64),
+        deserialize_i128 => visit_i128(i128),
+        deserialize_u8 => visit_u8(u8),
+        deserialize_u16 => visit_u16(u16),
+        deserialize_u32 => visit_u32(u32),
+        deserialize_u64 => visit_u64(u64),
+        deserialize_u128 => visit_u128(u128),
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::Null => visitor.visit_none(),
+            value => visitor.visit_some(MoveDeserializer(value)),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.0.deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool f32 f64 char str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+fn visit_array<'de, V: Visitor<'de>>(
+    values: Vec<Value>,
+    visitor: V,
+) -> Result<V::Value, serde_json::Error> {
+    let len = values.len();
+    let mut seq = MoveSeq(values.into_iter());
+    let decoded = visitor.visit_seq(&mut seq)?;
+    if seq.0.len() > 0 {
+        return Err(serde_json::Error::invalid_length(
+            len,
+            &"fewer elements in array",
+        ));
+    }
+    Ok(decoded)
+}
+
+fn visit_object<'de, V: Visitor<'de>>(
+    map: Map<String, Value>,
+    visitor: V,
+) -> Result<V::Value, serde_json::Error> {
+    visitor.visit_map(MoveMap {
+        entries: map.into_iter(),
+        value: None,
+    })
+}
+
+struct MoveSeq(std::vec::IntoIter<Value>);
+
+impl<'de> SeqAccess<'de> for MoveSeq {
+    type Error = serde_json::Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error> {
+        self.0
+            .next()
+            .map(|value| seed.deserialize(MoveDeserializer(value)))
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+struct MoveMap {
+    entries: serde_json::map::IntoIter,
+    value: Option<Value>,
+}
+
+impl<'de> MapAccess<'de> for MoveMap {
+    type Error = serde_json::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(MoveDeserializer(Value::String(key)))
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| Self::Error::custom("map value requested before its key"))?;
+        seed.deserialize(MoveDeserializer(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    fn decode<T: for<'de> Deserialize<'de>>(value: Value) -> Result<T, serde_json::Error> {
+        T::deserialize(MoveDeserializer(value))
+    }
+
+    #[test]
+    fn test_nested_string_integers() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Pool {
+            reserves: (u64, u128),
+            fees: Vec<u16>,
+            name: String,
+            owner: Option<String>,
+        }
+        let pool: Pool = decode(json!({
+            "reserves": ["10", "340282366920938463463374607431768211455"],
+            "fees": ["30", 5],
+            "name": "42",
+            "owner": null,
+        }))
+        .unwrap();
+        assert_eq!(
+            pool,
+            Pool {
+                reserves: (10, u128::MAX),
+                fees: vec![30, 5],
+                name: "42".to_string(),
+                owner: None,
+            }
+        );
+        let nested: Vec<Vec<u64>> = decode(json!([["1", "2"], [], ["3"]])).unwrap();
+        assert_eq!(nested, vec![vec![1, 2], vec![], vec![3]]);
+        assert!(decode::<u64>(json!("-1")).is_err());
+        assert!(decode::<(u64, u64)>(json!(["1", "2", "3"])).is_err());
+        assert!(decode::<(u64, u64)>(json!(["1"])).is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{trade::BatchTradeHandle, types::ContractCall, wallet::Wallet, Aptos};
+use crate::{trade::BatchTradeHandle, types::{ContractCall, MoveArgument}, wallet::Wallet, Aptos};
 use futures::future::join_all;
 use serde_json::{Value, json};
 use std::{collections::HashMap, sync::Arc};
@@ -40,7 +40,7 @@ impl MultiContractCall {
             if let (Some(dep_field), Some(prev_result)) = (dependency, &previous_result) {
                 if let Some(dep_value) = prev_result.get(&dep_field) {
                     // Adding dependent values ​​to parameters
-                    final_call.arguments.push(dep_value.clone());
+                    final_call.arguments.push(dep_value.clone().into());
                 }
             }
             match crate::contract::Contract::write(
@@ -112,7 +112,7 @@ impl MultiCallUtils {
         base_calls
             .into_iter()
             .map(|mut call| {
-                call.arguments.extend(common_args.clone());
+                call.arguments.extend(common_args.iter().cloned().map(MoveArgument::from));
                 call
             })
             .collect()
@@ -0,0 +1,333 @@
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+use crate::{
+    Aptos,
+    contract::Contract,
+    trade::Trade,
+    types::{CallKind, ContractCall, ContractWriteResult, MoveArgument, ViewRequest},
+    wallet::Wallet,
+};
+
+/// multisig account framework module
+pub const MULTISIG_ACCOUNT: &str = "0x1::multisig_account";
+
+/// `0x1::multisig_account` flow: create, propose, approve or reject, execute.
+pub struct MultisigAccount;
+
+impl MultisigAccount {
+    fn call(function_name: &str, arguments: Vec<Value>) -> ContractCall {
+        ContractCall {
+            module_address: "0x1".to_string(),
+            module_name: "multisig_account".to_string(),
+            function_name: function_name.to_string(),
+            type_arguments: vec![],
+            arguments: arguments.into_iter().map(MoveArgument::from).collect(),
+            kind: CallKind::Entry,
+        }
+    }
+
+    /// create a multisig account owned by the wallet and `additional_owners`
+    pub async fn create_with_owners(
+        client: Arc<Aptos>,
+        wallet: Arc<Wallet>,
+        additional_owners: &[&str],
+        num_signatures_required: u64,
+    ) -> Result<ContractWriteResult, String> {
+        let call = Self::call(
+            "create_with_owners",
+            vec![
+                json!(additional_owners),
+                json!(num_signatures_required.to_string()),
+                json!(Vec::<String>::new()),
+                json!(Vec::<String>::new()),
+            ],
+        );
+        Contract::write(client, wallet, call).await
+    }
+
+    /// propose a transaction, `payload` is the bcs encoded multisig transaction payload
+    pub async fn propose(
+        client: Arc<Aptos>,
+        wallet: Arc<Wallet>,
+        multisig_address: &str,
+        payload: &[u8],
+    ) -> Result<ContractWriteResult, String> {
+        let call = Self::call(
+            "create_transaction",
+            vec![
+                json!(multisig_address),
+                json!(format!("0x{}", hex::encode(payload))),
+            ],
+        );
+        Contract::write(client, wallet, call).await
+    }
+
+    /// propose a transaction by its payload hash only
+    pub async fn propose_hash(
+        client: Arc<Aptos>,
+        wallet: Arc<Wallet>,
+        multisig_address: &str,
+        payload_hash: &[u8],
+    ) -> Result<ContractWriteResult, String> {
+        let call = Self::call(
+            "create_transaction_with_hash",
+            vec![
+                json!(multisig_address),
+                json!(format!("0x{}", hex::encode(payload_hash))),
+            ],
+        );
+        Contract::write(client, wallet, call).await
+    }
+
+    /// approve a pending transaction
+    pub async fn approve(
+        client: Arc<Aptos>,
+        wallet: Arc<Wallet>,
+        multisig_address: &str,
+        sequence_number: u64,
+    ) -> Result<ContractWriteResult, String> {
+        let call = Self::call(
+            "approve_transaction",
+            vec![json!(multisig_address), json!(sequence_number.to_string())],
+        );
+        Contract::write(client, wallet, call).await
+    }
+
+    /// reject a pending transaction
+    pub async fn reject(
+        client: Arc<Aptos>,
+        wallet: Arc<Wallet>,
+        multisig_address: &str,
+        sequence_number: u64,
+    ) -> Result<ContractWriteResult, String> {
+        let call = Self::call(
+            "reject_transaction",
+            vec![json!(multisig_address), json!(sequence_number.to_string())],
+        );
+        Contract::write(client, wallet, call).await
+    }
+
+    /// remove the next pending transaction once it has enough rejections
+    pub async fn execute_rejected(
+        client: Arc<Aptos>,
+        wallet: Arc<Wallet>,
+        multisig_address: &str,
+    ) -> Result<ContractWriteResult, String> {
+        let call = Self::call(
+            "execute_rejected_transaction",
+            vec![json!(multisig_address)],
+        );
+        Contract::write(client, wallet, call).await
+    }
+
+    /// build the `multisig_payload` executing `call` on behalf of the multisig account
+    pub fn create_execute_payload(multisig_address: &str, call: &ContractCall) -> Value {
+        json!({
+            "type": "multisig_payload",
+            "multisig_address": multisig_address,
+            "transaction_payload": call.entry_function_payload()
+        })
+    }
+
+    /// execute the next approved transaction, the wallet must be an owner
+    pub async fn execute(
+        client: Arc<Aptos>,
+        wallet: Arc<Wallet>,
+        multisig_address: &str,
+        call: &ContractCall,
+    ) -> Result<ContractWriteResult, String> {
+        let mut raw_txn = Trade::create_entry_function_tx(
+            Arc::clone(&client),
+            Arc::clone(&wallet),
+            Self::create_execute_payload(multisig_address, call),
+            30,
+        )
+        .await?;
+        Trade::estimate_gas(Arc::clone(&client), Arc::clone(&wallet), &mut raw_txn).await?;
+        let signed_txn = Trade::sign_contract_call(&client, wallet, raw_txn, call).await?;
+        Contract::submit_and_wait(&client, &signed_txn).await
+    }
+
+    async fn view(client: &Aptos, function: &str, arguments: Vec<Value>) -> Result<Value, String> {
+        let result = client
+            .view(
+                &ViewRequest {
+                    function: format!("{}::{}", MULTISIG_ACCOUNT, function),
+                    type_arguments: vec![],
+                    arguments,
+                },
+                None,
+            )
+            .await?;
+        result
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("empty view result: {}", function))
+    }
+
+    fn as_u64(value: &Value) -> Result<u64, String> {
+        match value {
+            Value::String(s) => s.parse().map_err(|e| format!("parse u64 error: {}", e)),
+            Value::Number(n) => n.as_u64().ok_or_else(|| "invalid u64".to_string()),
+            _ => Err(format!("invalid u64: {}", value)),
+        }
+    }
+
+    /// get owner addresses
+    pub async fn owners(client: Arc<Aptos>, multisig_address: &str) -> Result<Vec<String>, String> {
+        let owners = Self::view(&client, "owners", vec![json!(multisig_address)]).await?;
+        serde_json::from_value(owners).map_err(|e| format!("parse owners error: {}", e))
+    }
+
+    /// get number of approvals required
+    pub async fn num_signatures_required(
+        client: Arc<Aptos>,
+        multisig_address: &str,
+    ) -> Result<u64, String> {
+        let value = Self::view(
+            &client,
+            "num_signatures_required",
+            vec![json!(multisig_address)],
+        )
+        .await?;
+        Self::as_u64(&value)
+    }
+
+    /// get sequence number the next proposal will get
+    pub async fn next_sequence_number(
+        client: Arc<Aptos>,
+        multisig_address: &str,
+    ) -> Result<u64, String> {
+        let value = Self::view(
+            &client,
+            "next_sequence_number",
+            vec![json!(multisig_address)],
+        )
+        .await?;
+        Self::as_u64(&value)
+    }
+
+    /// check whether a pending transaction has enough approvals to execute
+    pub async fn can_be_executed(
+        client: Arc<Aptos>,
+        multisig_address: &str,
+        sequence_number: u64,
+    ) -> Result<bool, String> {
+        let value = Self::view(
+            &client,
+            "can_be_executed",
+            vec![json!(multisig_address), json!(sequence_number.to_string())],
+        )
+        .await?;
+        value
+            .as_bool()
+            .ok_or_else(|| format!("invalid bool: {}", value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AptosType, transport::MockTransport};
+
+    #[test]
+    fn test_create_execute_payload() {
+        let call = ContractCall {
+            module_address: "0x1".to_string(),
+            module_name: "aptos_account".to_string(),
+            function_name: "transfer".to_string(),
+            type_arguments: vec![],
+            arguments: vec![json!("0xb0b").into(), json!("100").into()],
+            kind: CallKind::Entry,
+        };
+        let payload = MultisigAccount::create_execute_payload("0xa11ce", &call);
+        assert_eq!(payload["type"], "multisig_payload");
+        assert_eq!(payload["multisig_address"], "0xa11ce");
+        assert_eq!(
+            payload["transaction_payload"]["function"],
+            "0x1::aptos_account::transfer"
+        );
+        assert_eq!(payload["transaction_payload"]["arguments"][1], "100");
+    }
+
+    #[tokio::test]
+    async fn test_execute_signs_bcs_with_estimated_gas() {
+        let committed = r#"{"version":"9","hash":"0xe1","gas_used":"700","success":true,"vm_status":"Executed successfully","type":"user_transaction","sender":"0xa","sequence_number":"3","max_gas_amount":"1500","gas_unit_price":"120","expiration_timestamp_secs":"1","payload":{"type":"multisig_payload","multisig_address":"0xa11ce"},"signature":{"type":"ed25519_signature","public_key":"0x01","signature":"0x02"}}"#;
+        let transport = Arc::new(
+            MockTransport::new()
+                .on_get(
+                    "/accounts/",
+                    200,
+                    r#"{"sequence_number":"3","authentication_key":"0x01"}"#,
+                )
+                .on_get(
+                    "/estimate_gas_price",
+                    200,
+                    r#"{"deprioritized_gas_estimate":100,"gas_estimate":100,"prioritized_gas_estimate":150}"#,
+                )
+                .on_get(
+                    "/v1/",
+                    200,
+                    r#"{"chain_id":2,"epoch":"1","ledger_version":"9","ledger_timestamp":"1","node_role":"full_node","block_height":"1"}"#,
+                )
+                .on_post("/transactions/simulate", 200, &format!("[{}]", committed))
+                .on_post("/transactions", 202, committed)
+                .on_get("/transactions/by_hash/", 200, committed),
+        );
+        let client = Arc::new(Aptos::with_transport(AptosType::Testnet, transport.clone()));
+        let wallet = Arc::new(Wallet::new().unwrap());
+        let call = ContractCall::builder("0x1", "aptos_account", "transfer")
+            .arg_address("0xb0b")
+            .arg_u64(100)
+            .build();
+        let result =
+            MultisigAccount::execute(Arc::clone(&client), Arc::clone(&wallet), "0xa11ce", &call)
+                .await
+                .unwrap();
+        assert!(result.success);
+        let submitted = transport
+            .requests()
+            .into_iter()
+            .find(|r| r.method == "POST" && r.url.ends_with("/transactions"))
+            .unwrap()
+            .body
+            .unwrap();
+        let transaction = &submitted["transaction"];
+        assert_eq!(transaction["max_gas_amount"], "1500");
+        assert_eq!(transaction["gas_unit_price"], "120");
+        let raw_txn_bcs = client.raw_transaction_bcs(transaction).await.unwrap();
+        // multisig payload with an entry function
+        assert_eq!(raw_txn_bcs[40], 3);
+        let signature = hex::decode(submitted["signature"]["signature"].as_str().unwrap()).unwrap();
+        assert!(
+            wallet
+                .verify(&crate::signing::signing_message(&raw_txn_bcs), &signature)
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_multisig_views() {
+        let transport = MockTransport::new().on_post("/view", 200, r#"["3"]"#);
+        let client = Arc::new(Aptos::with_transport(
+            AptosType::Mainnet,
+            Arc::new(transport),
+        ));
+        assert_eq!(
+            MultisigAccount::num_signatures_required(Arc::clone(&client), "0xa11ce")
+                .await
+                .unwrap(),
+            3
+        );
+        let transport = MockTransport::new().on_post("/view", 200, r#"[["0x1","0x2"]]"#);
+        let client = Arc::new(Aptos::with_transport(
+            AptosType::Mainnet,
+            Arc::new(transport),
+        ));
+        assert_eq!(
+            MultisigAccount::owners(client, "0xa11ce").await.unwrap(),
+            vec!["0x1", "0x2"]
+        );
+    }
+}
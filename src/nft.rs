@@ -11,7 +11,7 @@ use crate::{
             },
         },
     },
-    types::ContractCall,
+    types::{CallKind, ContractCall, MoveArgument},
     wallet::Wallet,
 };
 use serde_json::{Value, json};
@@ -19,6 +19,24 @@ use std::sync::Arc;
 
 pub struct NFTManager;
 
+/// token royalty, `numerator / denominator` of a sale goes to `payee_address`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Royalty {
+    pub numerator: u64,
+    pub denominator: u64,
+    pub payee_address: String,
+}
+
+impl Royalty {
+    /// royalty owed on a sale at `price`
+    pub fn amount(&self, price: u64) -> u64 {
+        if self.denominator == 0 {
+            return 0;
+        }
+        (price as u128 * self.numerator as u128 / self.denominator as u128) as u64
+    }
+}
+
 impl NFTManager {
     /// create nft collection
     pub async fn create_nft_collection(
@@ -35,12 +53,13 @@ impl NFTManager {
             function_name: create_collection_script.to_string(),
             type_arguments: vec![],
             arguments: vec![
-                json!(name),
-                json!(description),
-                json!(uri),
-                json!(max_amount.unwrap_or(u64::MAX).to_string()),
-                json!(false), // mutable
+                MoveArgument::String(name.to_string()),
+                MoveArgument::String(description.to_string()),
+                MoveArgument::String(uri.to_string()),
+                MoveArgument::U64(max_amount.unwrap_or(u64::MAX)),
+                MoveArgument::Bool(false), // mutable
             ],
+            kind: CallKind::Entry,
         };
         crate::contract::Contract::write(client, wallet, contract_call)
             .await
@@ -64,19 +83,20 @@ impl NFTManager {
             function_name: create_token_script.to_string(),
             type_arguments: vec![],
             arguments: vec![
-                json!(collection),
-                json!(name),
-                json!(description),
-                json!(supply.to_string()),
-                json!(supply.to_string()), // max supply
-                json!(uri),
-                json!(wallet.address().map_err(|e| e.to_string())?), // royalty payee
-                json!(royalty_points_per_million.to_string()),
-                json!(0u64.to_string()),     // royalty denominator
-                json!(vec![] as Vec<Value>), // property keys
-                json!(vec![] as Vec<Value>), // property values
-                json!(vec![] as Vec<Value>), // property types
+                MoveArgument::String(collection.to_string()),
+                MoveArgument::String(name.to_string()),
+                MoveArgument::String(description.to_string()),
+                MoveArgument::U64(supply),
+                MoveArgument::U64(supply), // max supply
+                MoveArgument::String(uri.to_string()),
+                MoveArgument::Address(wallet.address().to_string()), // royalty payee
+                MoveArgument::U64(royalty_points_per_million),
+                MoveArgument::U64(0),               // royalty denominator
+                json!(vec![] as Vec<Value>).into(), // property keys
+                json!(vec![] as Vec<Value>).into(), // property values
+                json!(vec![] as Vec<Value>).into(), // property types
             ],
+            kind: CallKind::Entry,
         };
         crate::contract::Contract::write(client, wallet, contract_call)
             .await
@@ -96,10 +116,11 @@ impl NFTManager {
             function_name: transfer_script.to_string(),
             type_arguments: vec![],
             arguments: vec![
-                json!(recipient),
-                json!(token_id),
-                json!(1u64.to_string()), // amount
+                MoveArgument::Address(recipient.to_string()),
+                json!(token_id).into(),
+                MoveArgument::U64(1), // amount
             ],
+            kind: CallKind::Entry,
         };
         crate::contract::Contract::write(client, wallet, contract_call)
             .await
@@ -146,4 +167,78 @@ impl NFTManager {
             Ok(Value::Null)
         }
     }
+
+    /// Royalty of a token, `None` when it has none.
+    ///
+    /// `token_id` is either a `0x4` token object address, whose own royalty wins over
+    /// its collection's, or a `0x3` token id `creator::collection::name`.
+    pub async fn get_royalty(
+        client: Arc<Aptos>,
+        token_id: &str,
+    ) -> Result<Option<Royalty>, String> {
+        let parts: Vec<&str> = token_id.splitn(3, "::").collect();
+        if let [creator, collection, name] = parts[..] {
+            let Some(resource) = client.get_account_resource(creator, collections).await? else {
+                return Ok(None);
+            };
+            let handle = resource.data["token_data"]["handle"]
+                .as_str()
+                .ok_or("collections resource has no token_data table")?;
+            let token_data: Option<Value> = client
+                .get_table_item_typed_opt(
+                    handle,
+                    json!({"creator": creator, "collection": collection, "name": name}),
+                    Some("0x3::token::TokenDataId"),
+                    Some("0x3::token::TokenData"),
+                )
+                .await?;
+            return Ok(token_data.and_then(|data| {
+                let royalty = &data["royalty"];
+                Self::parse_royalty(
+                    &royalty["royalty_points_numerator"],
+                    &royalty["royalty_points_denominator"],
+                    &royalty["payee_address"],
+                )
+            }));
+        }
+        if let Some(royalty) = Self::get_object_royalty(&client, token_id).await? {
+            return Ok(Some(royalty));
+        }
+        let token = client
+            .get_account_resource(token_id, "0x4::token::Token")
+            .await?;
+        match token
+            .as_ref()
+            .and_then(|t| t.data["collection"]["inner"].as_str())
+        {
+            Some(collection) => Self::get_object_royalty(&client, collection).await,
+            None => Ok(None),
+        }
+    }
+
+    /// `0x4::royalty::Royalty` stored at an object
+    async fn get_object_royalty(client: &Aptos, address: &str) -> Result<Option<Royalty>, String> {
+        let resource = client
+            .get_account_resource(address, "0x4::royalty::Royalty")
+            .await?;
+        Ok(resource.and_then(|resource| {
+            Self::parse_royalty(
+                &resource.data["numerator"],
+                &resource.data["denominator"],
+                &resource.data["payee_address"],
+            )
+        }))
+    }
+
+    fn parse_royalty(numerator: &Value, denominator: &Value, payee: &Value) -> Option<Royalty> {
+        let number = |value: &Value| match value {
+            Value::String(s) => s.parse::<u64>().ok(),
+            other => other.as_u64(),
+        };
+        Some(Royalty {
+            numerator: number(numerator)?,
+            denominator: number(denominator)?,
+            payee_address: payee.as_str()?.to_string(),
+        })
+    }
 }
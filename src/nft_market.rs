@@ -2,11 +2,21 @@ use crate::global::mainnet::nft_market::{
     AUX_EXCHANGE, BLUEMOVE, MERCATO, PANCAKE_SWAP_NFT, SOUFFL3, TOPAZ, TRADEPORT, WAPAL,
 };
 // nft_marketplace.rs
-use crate::{Aptos, types::ContractCall, wallet::Wallet};
+use crate::{
+    Aptos,
+    indexer::Indexer,
+    nft::NFTManager,
+    types::{CallKind, ContractCall, MoveArgument},
+    wallet::Wallet,
+};
+use futures::future::join_all;
 use serde_json::{Value, json};
 use std::collections::HashMap;
 use std::sync::Arc;
 
+/// gas units assumed for a purchase by `estimate_total_purchase_cost`
+const PURCHASE_GAS_UNITS: u64 = 5_000;
+
 /// NFT marketplace aggregator manager
 pub struct NFTMarketplaceAggregator;
 
@@ -27,6 +37,22 @@ impl Marketplaces {
             WAPAL,
         ]
     }
+
+    /// Address of a marketplace by name, `None` for an unknown marketplace
+    pub fn address(marketplace_name: &str) -> Option<&'static str> {
+        let address = match marketplace_name {
+            "Topaz" => TOPAZ,
+            "Souffl3" => SOUFFL3,
+            "BlueMove" => BLUEMOVE,
+            "Mercato" => MERCATO,
+            "AUX" => AUX_EXCHANGE,
+            "PancakeSwap" => PANCAKE_SWAP_NFT,
+            "Tradeport" => TRADEPORT,
+            "Wapal" => WAPAL,
+            _ => return None,
+        };
+        Some(address)
+    }
 }
 
 /// NFT listing information
@@ -50,6 +76,17 @@ pub struct NFTOrderBook {
     pub floor_price: Option<u64>, // Floor price
 }
 
+/// All-in cost of buying a listing, in octas of the listing currency
+#[derive(Debug, Clone, PartialEq)]
+pub struct PurchaseCost {
+    pub price: u64,
+    pub marketplace_fee: u64,
+    pub royalty: u64,
+    /// gas fee in octas
+    pub est_gas: u64,
+    pub total: u64,
+}
+
 /// Transaction result
 #[derive(Debug, Clone)]
 pub struct NFTPurchaseResult {
@@ -79,6 +116,165 @@ impl NFTMarketplaceAggregator {
         Ok(all_listings)
     }
 
+    /// Search all listings of a collection across marketplaces, cheapest first.
+    ///
+    /// `collection_identifier` is the collection name or its object address, and is
+    /// matched against the listing resources of each marketplace.
+    pub async fn search_collection_listings(
+        client: Arc<Aptos>,
+        collection_identifier: &str,
+    ) -> Result<Vec<NFTListing>, String> {
+        let results = join_all(Marketplaces::all_markets().into_iter().map(|marketplace| {
+            Self::get_collection_listings(Arc::clone(&client), marketplace, collection_identifier)
+        }))
+        .await;
+        let mut all_listings: Vec<NFTListing> = results.into_iter().flatten().flatten().collect();
+        all_listings.sort_by(|a, b| {
+            a.price
+                .cmp(&b.price)
+                .then_with(|| a.listing_time.cmp(&b.listing_time))
+        });
+        Ok(all_listings)
+    }
+
+    /// Search the active listings of a collection id through the indexer, cheapest first
+    pub async fn search_collection_listings_indexed(
+        indexer: &Indexer,
+        collection_id: &str,
+    ) -> Result<Vec<NFTListing>, String> {
+        let data = indexer
+            .query(
+                "query CollectionListings($collection_id: String) {
+                    current_nft_marketplace_listings(
+                        where: {collection_id: {_eq: $collection_id}, is_deleted: {_eq: false}}
+                        order_by: {price: asc}
+                    ) {
+                        token_data_id
+                        price
+                        seller
+                        marketplace
+                        contract_address
+                        coin_type
+                        last_transaction_timestamp
+                    }
+                }",
+                json!({ "collection_id": collection_id }),
+            )
+            .await?;
+        let rows = data
+            .get("current_nft_marketplace_listings")
+            .and_then(|v| v.as_array())
+            .ok_or("missing current_nft_marketplace_listings")?;
+        let field = |row: &Value, name: &str| {
+            row.get(name)
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string()
+        };
+        let mut listings: Vec<NFTListing> = rows
+            .iter()
+            .map(|row| NFTListing {
+                token_id: field(row, "token_data_id"),
+                price: row
+                    .get("price")
+                    .and_then(|v| v.as_u64().or_else(|| v.as_str()?.parse().ok()))
+                    .unwrap_or(0),
+                marketplace: field(row, "contract_address"),
+                seller: field(row, "seller"),
+                listing_time: 0,
+                currency: row
+                    .get("coin_type")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("0x1::aptos_coin::AptosCoin")
+                    .to_string(),
+                marketplace_name: field(row, "marketplace"),
+            })
+            .collect();
+        listings.sort_by_key(|listing| listing.price);
+        Ok(listings)
+    }
+
+    /// Get the listings of a collection from a specific marketplace
+    async fn get_collection_listings(
+        client: Arc<Aptos>,
+        marketplace_address: &str,
+        collection_identifier: &str,
+    ) -> Result<Vec<NFTListing>, String> {
+        let marketplace_name = match marketplace_address {
+            TOPAZ => "Topaz",
+            SOUFFL3 => "Souffl3",
+            BLUEMOVE => "BlueMove",
+            MERCATO => "Mercato",
+            AUX_EXCHANGE => "AUX",
+            PANCAKE_SWAP_NFT => "PancakeSwap",
+            TRADEPORT => "Tradeport",
+            WAPAL => "Wapal",
+            _ => return Ok(vec![]),
+        };
+        let resources = client.get_account_resource_vec(marketplace_address).await?;
+        Ok(resources
+            .iter()
+            .filter(|resource| Self::resource_in_collection(&resource.data, collection_identifier))
+            .filter_map(|resource| {
+                let token_id = Self::resource_token_id(&resource.data);
+                match marketplace_address {
+                    TRADEPORT => Self::extract_tradeport_listing_from_resource(
+                        resource,
+                        &token_id,
+                        marketplace_name,
+                    ),
+                    WAPAL => Self::extract_wapal_listing_from_resource(
+                        resource,
+                        &token_id,
+                        marketplace_name,
+                    ),
+                    _ => Self::extract_listing_from_resource(resource, &token_id, marketplace_name),
+                }
+            })
+            .collect())
+    }
+
+    /// Whether listing resource data belongs to the collection
+    fn resource_in_collection(data: &Value, collection_identifier: &str) -> bool {
+        let token_data_id = data
+            .get("token_id")
+            .and_then(|id| id.get("token_data_id"))
+            .or_else(|| data.get("token_data_id"));
+        [
+            data.get("collection"),
+            data.get("collection_name"),
+            data.get("collection_id"),
+            token_data_id.and_then(|id| id.get("collection")),
+        ]
+        .into_iter()
+        .flatten()
+        .filter_map(|v| v.as_str())
+        .any(|collection| collection == collection_identifier)
+    }
+
+    /// Token id of listing resource data, `creator::collection::name` for token v1 ids
+    fn resource_token_id(data: &Value) -> String {
+        let token_data_id = data
+            .get("token_id")
+            .and_then(|id| id.get("token_data_id"))
+            .or_else(|| data.get("token_data_id"));
+        if let Some(id) = token_data_id.filter(|id| id.is_object()) {
+            let part = |name: &str| id.get(name).and_then(|v| v.as_str()).unwrap_or("");
+            return format!(
+                "{}::{}::{}",
+                part("creator"),
+                part("collection"),
+                part("name")
+            );
+        }
+        data.get("token_id")
+            .or(token_data_id)
+            .or_else(|| data.get("token_name"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string()
+    }
+
     /// Get NFT listings from specific marketplace
     async fn get_marketplace_listings(
         client: Arc<Aptos>,
@@ -419,6 +615,52 @@ impl NFTMarketplaceAggregator {
         None
     }
 
+    /// Build the order book of a token: its listings, floor price and best offer
+    pub async fn build_order_book(
+        client: Arc<Aptos>,
+        token_id: &str,
+    ) -> Result<NFTOrderBook, String> {
+        let listings = Self::search_nft_listings(Arc::clone(&client), token_id).await?;
+        let floor_price = listings.iter().map(|listing| listing.price).min();
+        let offers =
+            join_all(Marketplaces::all_markets().into_iter().map(|marketplace| {
+                Self::get_token_offers(Arc::clone(&client), marketplace, token_id)
+            }))
+            .await;
+        let best_offer = offers.into_iter().flatten().flatten().max();
+        Ok(NFTOrderBook {
+            token_id: token_id.to_string(),
+            listings,
+            best_offer,
+            floor_price,
+        })
+    }
+
+    /// Prices of the open offers on a token from a specific marketplace's bid/offer resources
+    async fn get_token_offers(
+        client: Arc<Aptos>,
+        marketplace_address: &str,
+        token_id: &str,
+    ) -> Result<Vec<u64>, String> {
+        let resources = client.get_account_resource_vec(marketplace_address).await?;
+        Ok(resources
+            .iter()
+            .filter(|resource| {
+                let resource_type = resource.r#type.to_lowercase();
+                resource_type.contains("offer") || resource_type.contains("bid")
+            })
+            .filter(|resource| Self::resource_token_id(&resource.data) == token_id)
+            .filter_map(|resource| {
+                ["price", "offer_price", "bid_price", "amount"]
+                    .iter()
+                    .find_map(|field| resource.data.get(*field))
+                    .and_then(|p| p.as_str())
+                    .and_then(|p| p.parse::<u64>().ok())
+                    .filter(|price| *price > 0)
+            })
+            .collect())
+    }
+
     /// Get best price (cross-market comparison)
     pub async fn get_best_price(
         client: Arc<Aptos>,
@@ -428,24 +670,52 @@ impl NFTMarketplaceAggregator {
         Ok(listings.into_iter().min_by_key(|listing| listing.price))
     }
 
-    /// Purchase NFT on specified marketplace
+    /// Estimate what buying `listing` costs: the price, the marketplace fee of
+    /// `fee_bps` basis points, the token royalty and the gas at the node's current
+    /// gas price. Marketplace fees are not published in a common on-chain format, so
+    /// the caller supplies the fee of the listing's marketplace.
+    pub async fn estimate_total_purchase_cost(
+        client: Arc<Aptos>,
+        listing: &NFTListing,
+        fee_bps: u64,
+    ) -> Result<PurchaseCost, String> {
+        let royalty = NFTManager::get_royalty(Arc::clone(&client), &listing.token_id).await?;
+        let gas_unit_price = client.get_gas_estimation().await?.gas_estimate;
+        Ok(Self::purchase_cost(
+            listing.price,
+            fee_bps,
+            royalty.map_or(0, |royalty| royalty.amount(listing.price)),
+            PURCHASE_GAS_UNITS * gas_unit_price,
+        ))
+    }
+
+    fn purchase_cost(price: u64, fee_bps: u64, royalty: u64, gas: u64) -> PurchaseCost {
+        let marketplace_fee = (price as u128 * fee_bps as u128 / 10_000) as u64;
+        PurchaseCost {
+            price,
+            marketplace_fee,
+            royalty,
+            est_gas: gas,
+            total: price + marketplace_fee + royalty + gas,
+        }
+    }
+
+    /// Purchase NFT on specified marketplace, `total_cost` is the price and the gas fee
+    /// paid. See `estimate_total_purchase_cost` for the fee and royalty on top.
     pub async fn purchase_nft(
         client: Arc<Aptos>,
         wallet: Arc<Wallet>,
         listing: &NFTListing,
     ) -> Result<NFTPurchaseResult, String> {
         let contract_call = Self::build_purchase_call(listing)?;
-
-        match crate::contract::Contract::write(client, wallet, contract_call).await {
-            Ok(result) => Ok(NFTPurchaseResult {
-                success: true,
-                transaction_hash: result.transaction_hash.clone(),
-                marketplace: listing.marketplace_name.clone(),
-                total_cost: listing.price,
-                gas_used: result.gas_used_as_u64(),
-            }),
-            Err(e) => Err(e),
-        }
+        let result = crate::contract::Contract::write(client, wallet, contract_call).await?;
+        Ok(NFTPurchaseResult {
+            success: true,
+            transaction_hash: result.transaction_hash.clone(),
+            marketplace: listing.marketplace_name.clone(),
+            total_cost: listing.price + result.fee_octas().unwrap_or(0),
+            gas_used: result.gas_used_as_u64(),
+        })
     }
 
     /// Build purchase call
@@ -468,7 +738,8 @@ impl NFTMarketplaceAggregator {
             module_name,
             function_name,
             type_arguments: vec![],
-            arguments,
+            arguments: arguments.into_iter().map(MoveArgument::from).collect(),
+            kind: CallKind::Entry,
         })
     }
 
@@ -560,6 +831,124 @@ impl NFTMarketplaceAggregator {
         )
     }
 
+    /// Place an offer on a token, escrowing `price`.
+    ///
+    /// Supported marketplaces: Wapal, Tradeport.
+    pub async fn make_offer(
+        client: Arc<Aptos>,
+        wallet: Arc<Wallet>,
+        token_id: &str,
+        price: u64,
+        market: &str,
+    ) -> Result<NFTPurchaseResult, String> {
+        let contract_call = Self::build_offer_call(token_id, price, market)?;
+        let result = crate::contract::Contract::write(client, wallet, contract_call).await?;
+        Ok(NFTPurchaseResult {
+            success: true,
+            transaction_hash: result.transaction_hash.clone(),
+            marketplace: market.to_string(),
+            total_cost: price,
+            gas_used: result.gas_used_as_u64(),
+        })
+    }
+
+    /// Cancel an offer placed with `make_offer`, refunding the escrow.
+    ///
+    /// Supported marketplaces: Wapal, Tradeport.
+    pub async fn cancel_offer(
+        client: Arc<Aptos>,
+        wallet: Arc<Wallet>,
+        token_id: &str,
+        market: &str,
+    ) -> Result<NFTPurchaseResult, String> {
+        let contract_call = Self::build_cancel_offer_call(token_id, market)?;
+        let result = crate::contract::Contract::write(client, wallet, contract_call).await?;
+        Ok(NFTPurchaseResult {
+            success: true,
+            transaction_hash: result.transaction_hash.clone(),
+            marketplace: market.to_string(),
+            total_cost: 0,
+            gas_used: result.gas_used_as_u64(),
+        })
+    }
+
+    /// Build offer call
+    fn build_offer_call(token_id: &str, price: u64, market: &str) -> Result<ContractCall, String> {
+        let (module_address, module_name, function_name, arguments) = match market {
+            "Wapal" => Self::build_wapal_offer_call(token_id, price),
+            "Tradeport" => Self::build_tradeport_offer_call(token_id, price),
+            _ => return Err("Unsupported marketplace for offers".to_string()),
+        };
+        Ok(ContractCall {
+            module_address,
+            module_name,
+            function_name,
+            type_arguments: vec![],
+            arguments: arguments.into_iter().map(MoveArgument::from).collect(),
+            kind: CallKind::Entry,
+        })
+    }
+
+    /// Build offer cancellation call
+    fn build_cancel_offer_call(token_id: &str, market: &str) -> Result<ContractCall, String> {
+        let (module_address, module_name, function_name, arguments) = match market {
+            "Wapal" => Self::build_wapal_cancel_offer_call(token_id),
+            "Tradeport" => Self::build_tradeport_cancel_offer_call(token_id),
+            _ => return Err("Unsupported marketplace for offers".to_string()),
+        };
+        Ok(ContractCall {
+            module_address,
+            module_name,
+            function_name,
+            type_arguments: vec![],
+            arguments: arguments.into_iter().map(MoveArgument::from).collect(),
+            kind: CallKind::Entry,
+        })
+    }
+
+    /// Build Wapal offer call
+    fn build_wapal_offer_call(token_id: &str, price: u64) -> (String, String, String, Vec<Value>) {
+        (
+            WAPAL.to_string(),
+            "offer".to_string(),
+            "make_offer".to_string(),
+            vec![json!(token_id), json!(price.to_string())],
+        )
+    }
+
+    /// Build Wapal offer cancellation call
+    fn build_wapal_cancel_offer_call(token_id: &str) -> (String, String, String, Vec<Value>) {
+        (
+            WAPAL.to_string(),
+            "offer".to_string(),
+            "cancel_offer".to_string(),
+            vec![json!(token_id)],
+        )
+    }
+
+    /// Build Tradeport offer call
+    fn build_tradeport_offer_call(
+        token_id: &str,
+        price: u64,
+    ) -> (String, String, String, Vec<Value>) {
+        (
+            TRADEPORT.to_string(),
+            "biddings".to_string(),
+            "token_bid".to_string(),
+            vec![json!(token_id), json!(price.to_string())],
+        )
+    }
+
+    /// Build Tradeport offer cancellation call
+    fn build_tradeport_cancel_offer_call(token_id: &str) -> (String, String, String, Vec<Value>) {
+        (
+            TRADEPORT.to_string(),
+            "biddings".to_string(),
+            "cancel_token_bid".to_string(),
+            vec![json!(token_id)],
+        )
+    }
+
     /// List NFT on multiple marketplaces
     pub async fn list_nft_on_markets(
         client: Arc<Aptos>,
@@ -652,7 +1041,8 @@ impl NFTMarketplaceAggregator {
             module_name,
             function_name,
             type_arguments: vec![],
-            arguments,
+            arguments: arguments.into_iter().map(MoveArgument::from).collect(),
+            kind: CallKind::Entry,
         })
     }
 
@@ -751,3 +1141,251 @@ impl NFTMarketUtils {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AptosType, transport::MockTransport};
+
+    #[tokio::test]
+    async fn test_search_collection_listings_sorted_by_price() {
+        let listing = |name: &str, collection: &str, price: &str| {
+            json!({
+                "type": format!("{}::listings::Listing", TOPAZ),
+                "data": {
+                    "token_id": {"token_data_id": {"creator": "0xc", "collection": collection, "name": name}},
+                    "price": price,
+                    "seller": "0x5e11e7"
+                }
+            })
+        };
+        let topaz = json!([
+            listing("Ape #2", "Apes", "900"),
+            listing("Cat #1", "Cats", "10"),
+            listing("Ape #7", "Apes", "300"),
+        ]);
+        let bluemove = json!([{
+            "type": format!("{}::Marketplace::Listing", BLUEMOVE),
+            "data": {"collection_name": "Apes", "token_name": "Ape #9", "price": "500"}
+        }]);
+        let transport = MockTransport::new()
+            .on_get(
+                &format!("/accounts/{}/resources", TOPAZ),
+                200,
+                &topaz.to_string(),
+            )
+            .on_get(
+                &format!("/accounts/{}/resources", BLUEMOVE),
+                200,
+                &bluemove.to_string(),
+            );
+        let client = Arc::new(Aptos::with_transport(
+            AptosType::Mainnet,
+            Arc::new(transport),
+        ));
+        let listings = NFTMarketplaceAggregator::search_collection_listings(client, "Apes")
+            .await
+            .unwrap();
+        let found: Vec<(&str, u64, &str)> = listings
+            .iter()
+            .map(|l| (l.token_id.as_str(), l.price, l.marketplace_name.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("0xc::Apes::Ape #7", 300, "Topaz"),
+                ("Ape #9", 500, "BlueMove"),
+                ("0xc::Apes::Ape #2", 900, "Topaz"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_build_order_book() {
+        let token =
+            json!({"token_data_id": {"creator": "0xc", "collection": "Apes", "name": "Ape #7"}});
+        let resources = json!([
+            {"type": format!("{}::listings::Listing", TOPAZ), "data": {"token_id": token, "price": "300"}},
+            {"type": format!("{}::bids::TokenBid", TOPAZ), "data": {"token_id": token, "price": "250"}},
+            {"type": format!("{}::bids::TokenBid", TOPAZ), "data": {"token_id": token, "price": "120"}},
+            {"type": format!("{}::bids::TokenBid", TOPAZ), "data": {"token_id": "other", "price": "999"}},
+        ]);
+        let transport = MockTransport::new().on_get(
+            &format!("/accounts/{}/resources", TOPAZ),
+            200,
+            &resources.to_string(),
+        );
+        let client = Arc::new(Aptos::with_transport(
+            AptosType::Mainnet,
+            Arc::new(transport),
+        ));
+        let book = NFTMarketplaceAggregator::build_order_book(client, "0xc::Apes::Ape #7")
+            .await
+            .unwrap();
+        assert_eq!(book.token_id, "0xc::Apes::Ape #7");
+        assert_eq!(book.listings.len(), 1);
+        assert_eq!(book.floor_price, Some(300));
+        assert_eq!(book.best_offer, Some(250));
+    }
+
+    #[tokio::test]
+    async fn test_estimate_total_purchase_cost() {
+        let transport = MockTransport::new()
+            .on_get(
+                "/estimate_gas_price",
+                200,
+                r#"{"deprioritized_gas_estimate":100,"gas_estimate":100,"prioritized_gas_estimate":150}"#,
+            )
+            // a token object without its own royalty falls back to its collection's
+            .on_get(
+                "/accounts/0xt0k3n/resource/0x4::token::Token",
+                200,
+                r#"{"type":"0x4::token::Token","data":{"collection":{"inner":"0xc011"},"name":"Ape #7"}}"#,
+            )
+            .on_get(
+                "/accounts/0xc011/resource/0x4::royalty::Royalty",
+                200,
+                r#"{"type":"0x4::royalty::Royalty","data":{"numerator":"5","denominator":"100","payee_address":"0xa"}}"#,
+            )
+            .on_get(
+                "/accounts/0xc/resource/0x3::token::Collections",
+                200,
+                r#"{"type":"0x3::token::Collections","data":{"token_data":{"handle":"0xh"}}}"#,
+            )
+            .on_post(
+                "/tables/0xh/item",
+                200,
+                r#"{"name":"Ape #2","royalty":{"royalty_points_numerator":"1","royalty_points_denominator":"10","payee_address":"0xb"}}"#,
+            );
+        let client = Arc::new(Aptos::with_transport(
+            AptosType::Mainnet,
+            Arc::new(transport),
+        ));
+        let listing = |token_id: &str, marketplace_name: &str| NFTListing {
+            token_id: token_id.to_string(),
+            price: 1_000_000,
+            marketplace: TOPAZ.to_string(),
+            seller: "0x5e11e7".to_string(),
+            listing_time: 0,
+            currency: "0x1::aptos_coin::AptosCoin".to_string(),
+            marketplace_name: marketplace_name.to_string(),
+        };
+        let cost = NFTMarketplaceAggregator::estimate_total_purchase_cost(
+            Arc::clone(&client),
+            &listing("0xt0k3n", "Topaz"),
+            100,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            cost,
+            PurchaseCost {
+                price: 1_000_000,
+                marketplace_fee: 10_000,
+                royalty: 50_000,
+                est_gas: 500_000,
+                total: 1_560_000,
+            }
+        );
+        let v1 = NFTMarketplaceAggregator::estimate_total_purchase_cost(
+            Arc::clone(&client),
+            &listing("0xc::Apes::Ape #2", "Wapal"),
+            200,
+        )
+        .await
+        .unwrap();
+        assert_eq!((v1.marketplace_fee, v1.royalty), (20_000, 100_000));
+        let no_royalty = NFTMarketplaceAggregator::estimate_total_purchase_cost(
+            client,
+            &listing("0xn0n3", "Topaz"),
+            0,
+        )
+        .await
+        .unwrap();
+        assert_eq!((no_royalty.marketplace_fee, no_royalty.royalty), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_purchase_reports_price_and_gas_paid() {
+        let marketplace = json!({
+            "bytecode": "0x",
+            "abi": {
+                "name": "marketplace",
+                "exposed_functions": [
+                    {"name": "purchase", "params": ["&signer", "0x1::string::String", "address", "u64"]}
+                ]
+            }
+        });
+        let committed = json!({
+            "version": "5", "hash": "0xb0u6h7", "state_checkpoint_hash": null,
+            "gas_used": "40", "success": true, "vm_status": "Executed successfully",
+            "type": "user_transaction", "sender": "0xa", "sequence_number": "3",
+            "max_gas_amount": "80", "gas_unit_price": "100", "expiration_timestamp_secs": "1",
+            "payload": {"type": "entry_function_payload", "function": "0x1::m::f", "type_arguments": [], "arguments": []},
+            "signature": {"type": "ed25519_signature", "public_key": "0x01", "signature": "0x02"}
+        });
+        let transport = MockTransport::new()
+            .on_get(
+                "/accounts/",
+                200,
+                r#"{"sequence_number":"3","authentication_key":"0x01"}"#,
+            )
+            .on_get("/module/marketplace", 200, &marketplace.to_string())
+            .on_get(
+                "/v1/",
+                200,
+                r#"{"chain_id":1,"epoch":"1","ledger_version":"5","ledger_timestamp":"1","node_role":"full_node","block_height":"1"}"#,
+            )
+            .on_get("/estimate_gas_price", 200, r#"{"gas_estimate":100}"#)
+            .on_post(
+                "/transactions/simulate",
+                200,
+                &json!([committed]).to_string(),
+            )
+            .on_post("/transactions", 202, &committed.to_string())
+            .on_get("/transactions/by_hash/", 200, &committed.to_string());
+        let transport = Arc::new(transport);
+        let client = Arc::new(Aptos::with_transport(
+            AptosType::Mainnet,
+            Arc::clone(&transport) as _,
+        ));
+        let listing = NFTListing {
+            token_id: "0xt0k3n".to_string(),
+            price: 1_000_000,
+            marketplace: TOPAZ.to_string(),
+            seller: "0x5e11e7".to_string(),
+            listing_time: 0,
+            currency: "0x1::aptos_coin::AptosCoin".to_string(),
+            marketplace_name: "Topaz".to_string(),
+        };
+        let wallet = Arc::new(Wallet::new().unwrap());
+        let result = NFTMarketplaceAggregator::purchase_nft(client, wallet, &listing)
+            .await
+            .unwrap();
+        assert_eq!(result.transaction_hash, "0xb0u6h7");
+        // the price and the gas fee paid, no estimate is read before the purchase
+        assert_eq!(result.total_cost, 1_000_000 + 40 * 100);
+        assert!(
+            transport
+                .requests()
+                .iter()
+                .all(|r| !r.url.contains("/resource"))
+        );
+    }
+
+    #[test]
+    fn test_build_offer_calls() {
+        let offer = NFTMarketplaceAggregator::build_offer_call("0xt0k3n", 150, "Wapal").unwrap();
+        assert_eq!(offer.module_address, WAPAL);
+        assert_eq!(offer.function_id(), format!("{}::offer::make_offer", WAPAL));
+        assert_eq!(offer.arguments, vec![json!("0xt0k3n"), json!("150")]);
+        let cancel =
+            NFTMarketplaceAggregator::build_cancel_offer_call("0xt0k3n", "Tradeport").unwrap();
+        assert_eq!(
+            cancel.function_id(),
+            format!("{}::biddings::cancel_token_bid", TRADEPORT)
+        );
+        assert!(NFTMarketplaceAggregator::build_offer_call("0xt0k3n", 150, "Topaz").is_err());
+        assert!(NFTMarketplaceAggregator::build_cancel_offer_call("0xt0k3n", "Topaz").is_err());
+    }
+}
@@ -0,0 +1,227 @@
+/// USD prices from the Pyth oracle on Aptos.
+///
+/// Prices are read from the latest price table kept by the Pyth package, no update
+/// is pushed, so a feed that nobody refreshed recently can be stale. Use
+/// `OracleConfig::max_age_secs` to reject old prices.
+use crate::{
+    Aptos,
+    global::{
+        NetworkConfig,
+        mainnet::{price_feed::APT_USD, protocol_address::PYTH_PROTOCOL_ADDRESS},
+    },
+};
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+/// where to read prices from
+#[derive(Debug, Clone)]
+pub struct OracleConfig {
+    /// address of the pyth package
+    pub pyth_address: String,
+    /// feed used to value APT in USD
+    pub apt_usd_feed_id: String,
+    /// reject prices published longer ago than this
+    pub max_age_secs: Option<u64>,
+}
+
+impl Default for OracleConfig {
+    fn default() -> Self {
+        OracleConfig {
+            pyth_address: PYTH_PROTOCOL_ADDRESS.to_string(),
+            apt_usd_feed_id: APT_USD.to_string(),
+            max_age_secs: None,
+        }
+    }
+}
+
+impl OracleConfig {
+    /// default config on the pyth deployment of `network`
+    pub fn from_network(network: &NetworkConfig) -> Result<Self, String> {
+        let pyth_address = network
+            .pyth
+            .clone()
+            .ok_or("Pyth is not deployed on this network")?;
+        Ok(OracleConfig {
+            pyth_address,
+            ..OracleConfig::default()
+        })
+    }
+}
+
+/// oracle price with its confidence interval, both already scaled by the exponent
+#[derive(Debug, Clone, PartialEq)]
+pub struct OraclePrice {
+    pub feed_id: String,
+    pub price: f64,
+    pub confidence: f64,
+    /// unix seconds
+    pub publish_time: u64,
+}
+
+pub struct Oracle;
+
+impl Oracle {
+    /// latest price of a pyth feed, e.g. `global::mainnet::price_feed::APT_USD`
+    pub async fn get_price(client: Arc<Aptos>, feed_id: &str) -> Result<OraclePrice, String> {
+        let config = OracleConfig::from_network(client.network_config())?;
+        Self::get_price_with_config(client, feed_id, &config).await
+    }
+
+    pub async fn get_price_with_config(
+        client: Arc<Aptos>,
+        feed_id: &str,
+        config: &OracleConfig,
+    ) -> Result<OraclePrice, String> {
+        let feed_id = normalize_feed_id(feed_id)?;
+        let resource_type = format!("{}::state::LatestPriceInfo", config.pyth_address);
+        let latest = client
+            .get_account_resource(&config.pyth_address, &resource_type)
+            .await?
+            .ok_or_else(|| format!("pyth price table not found at {}", config.pyth_address))?;
+        let handle = latest.data["info"]["handle"]
+            .as_str()
+            .ok_or("pyth price table has no handle")?;
+        let price_info = client
+            .get_table_item(
+                handle,
+                &format!("{}::price_identifier::PriceIdentifier", config.pyth_address),
+                &format!("{}::price_info::PriceInfo", config.pyth_address),
+                &json!({ "bytes": feed_id }),
+            )
+            .await
+            .map_err(|e| format!("price feed {} not found: {}", feed_id, e))?
+            .ok_or_else(|| format!("price feed {} not found", feed_id))?;
+        let price = parse_price(&feed_id, &price_info["price_feed"]["price"])?;
+        if let Some(max_age) = config.max_age_secs {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            let age = now.saturating_sub(price.publish_time);
+            if age > max_age {
+                return Err(format!(
+                    "price feed {} is stale: published {}s ago",
+                    feed_id, age
+                ));
+            }
+        }
+        Ok(price)
+    }
+
+    /// latest APT price in USD
+    pub async fn get_apt_usd_price(
+        client: Arc<Aptos>,
+        config: &OracleConfig,
+    ) -> Result<OraclePrice, String> {
+        Self::get_price_with_config(client, &config.apt_usd_feed_id, config).await
+    }
+}
+
+/// `0x` prefixed lowercase 32 byte hex
+fn normalize_feed_id(feed_id: &str) -> Result<String, String> {
+    let hex = feed_id.trim().trim_start_matches("0x").to_lowercase();
+    if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("invalid price feed id: {}", feed_id));
+    }
+    Ok(format!("0x{}", hex))
+}
+
+/// pyth `i64`, encoded as `{ negative, magnitude }`
+fn parse_i64(value: &Value) -> Option<i64> {
+    let magnitude = match &value["magnitude"] {
+        Value::String(s) => s.parse::<i64>().ok()?,
+        other => other.as_i64()?,
+    };
+    Some(if value["negative"].as_bool()? {
+        -magnitude
+    } else {
+        magnitude
+    })
+}
+
+fn parse_u64(value: &Value) -> Option<u64> {
+    match value {
+        Value::String(s) => s.parse().ok(),
+        other => other.as_u64(),
+    }
+}
+
+/// scale a pyth `Price` by its exponent
+fn parse_price(feed_id: &str, price: &Value) -> Result<OraclePrice, String> {
+    let invalid = || format!("invalid price data for feed {}", feed_id);
+    let raw = parse_i64(&price["price"]).ok_or_else(invalid)?;
+    let conf = parse_u64(&price["conf"]).ok_or_else(invalid)?;
+    let expo = parse_i64(&price["expo"]).ok_or_else(invalid)?;
+    let scale = 10f64.powi(expo as i32);
+    Ok(OraclePrice {
+        feed_id: feed_id.to_string(),
+        price: raw as f64 * scale,
+        confidence: conf as f64 * scale,
+        publish_time: parse_u64(&price["timestamp"]).ok_or_else(invalid)?,
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{AptosType, transport::MockTransport};
+
+    /// mock transport serving an APT/USD price of 8.5 +- 0.01
+    pub(crate) fn mock_pyth(transport: MockTransport) -> MockTransport {
+        transport
+            .on_get(
+                "::state::LatestPriceInfo",
+                200,
+                r#"{"type":"state::LatestPriceInfo","data":{"info":{"handle":"0xfeeds"}}}"#,
+            )
+            .on_post(
+                "/tables/0xfeeds/item",
+                200,
+                &json!({
+                    "attestation_time": "1700000000",
+                    "arrival_time": "1700000001",
+                    "price_feed": {
+                        "price_identifier": {"bytes": APT_USD},
+                        "price": {
+                            "price": {"negative": false, "magnitude": "850000000"},
+                            "conf": "1000000",
+                            "expo": {"negative": true, "magnitude": "8"},
+                            "timestamp": "1700000000"
+                        }
+                    }
+                })
+                .to_string(),
+            )
+    }
+
+    #[tokio::test]
+    async fn test_get_price_scales_by_exponent() {
+        let transport = Arc::new(mock_pyth(MockTransport::new()));
+        let client = Arc::new(Aptos::with_transport(
+            AptosType::Mainnet,
+            Arc::clone(&transport) as _,
+        ));
+        let price = Oracle::get_price(
+            Arc::clone(&client),
+            &APT_USD.to_uppercase().replace("0X", ""),
+        )
+        .await
+        .unwrap();
+        assert_eq!(price.feed_id, APT_USD);
+        assert!((price.price - 8.5).abs() < 1e-9);
+        assert!((price.confidence - 0.01).abs() < 1e-9);
+        assert_eq!(price.publish_time, 1_700_000_000);
+        let request = transport.requests().pop().unwrap();
+        assert_eq!(request.body.unwrap()["key"], json!({ "bytes": APT_USD }));
+
+        let stale = OracleConfig {
+            max_age_secs: Some(60),
+            ..OracleConfig::default()
+        };
+        let err = Oracle::get_apt_usd_price(Arc::clone(&client), &stale)
+            .await
+            .unwrap_err();
+        assert!(err.contains("stale"));
+        assert!(Oracle::get_price(client, "0x1234").await.is_err());
+    }
+}
@@ -0,0 +1,386 @@
+/// Failover across several fullnodes.
+///
+/// `AptosPool` dereferences to an `Aptos` client whose transport picks a node per
+/// request according to a `PoolPolicy`. A node that times out, fails to connect or
+/// answers with a 5xx is marked unhealthy for a cooldown and the request is retried
+/// on the next node.
+use crate::{
+    Aptos,
+    global::NetworkConfig,
+    transport::{HttpResponse, HttpTransport, ReqwestTransport},
+};
+use futures::future::BoxFuture;
+use serde_json::Value;
+use std::{
+    ops::Deref,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+/// how long a failed node is skipped by default
+pub const DEFAULT_NODE_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// default per request timeout
+pub const DEFAULT_NODE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// weight of the latest sample in a node's latency average
+const LATENCY_SMOOTHING: f64 = 0.3;
+
+/// node selection policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PoolPolicy {
+    /// rotate the first node tried across requests
+    #[default]
+    RoundRobin,
+    /// always start with the first healthy node in the configured order
+    FirstHealthy,
+    /// start with the healthy node with the lowest average latency
+    LowestLatency,
+}
+
+/// snapshot of a pool node
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeStatus {
+    pub url: String,
+    pub healthy: bool,
+    /// smoothed latency of successful requests, `None` before the first one
+    pub latency: Option<Duration>,
+}
+
+#[derive(Debug)]
+struct Node {
+    url: String,
+    unhealthy_until: Option<Instant>,
+    latency: Option<Duration>,
+}
+
+impl Node {
+    fn is_healthy(&self) -> bool {
+        self.unhealthy_until
+            .is_none_or(|until| Instant::now() >= until)
+    }
+
+    fn record_success(&mut self, elapsed: Duration) {
+        self.unhealthy_until = None;
+        self.latency = Some(match self.latency {
+            Some(latency) => {
+                latency.mul_f64(1.0 - LATENCY_SMOOTHING) + elapsed.mul_f64(LATENCY_SMOOTHING)
+            }
+            None => elapsed,
+        });
+    }
+}
+
+#[derive(Debug)]
+struct PoolConfig {
+    policy: PoolPolicy,
+    cooldown: Duration,
+    timeout: Duration,
+}
+
+/// Transport rewriting the client's base url to the selected node.
+#[derive(Debug)]
+struct FailoverTransport {
+    inner: Arc<dyn HttpTransport>,
+    /// base url of the wrapped `Aptos`, replaced by the node url on each request
+    base_url: String,
+    nodes: Mutex<Vec<Node>>,
+    config: Mutex<PoolConfig>,
+    next: AtomicUsize,
+}
+
+impl FailoverTransport {
+    /// node indexes in the order they should be tried, unhealthy nodes last
+    fn candidates(&self) -> Vec<usize> {
+        let nodes = self.nodes.lock().unwrap();
+        let policy = self.config.lock().unwrap().policy;
+        let count = nodes.len();
+        let mut order: Vec<usize> = match policy {
+            PoolPolicy::RoundRobin if count > 0 => {
+                let start = self.next.fetch_add(1, Ordering::Relaxed) % count;
+                (0..count).map(|i| (start + i) % count).collect()
+            }
+            _ => (0..count).collect(),
+        };
+        if policy == PoolPolicy::LowestLatency {
+            // unmeasured nodes sort first so they get measured
+            order.sort_by_key(|&i| nodes[i].latency.unwrap_or(Duration::ZERO));
+        }
+        // stable, so the policy order is kept within each group
+        order.sort_by_key(|&i| !nodes[i].is_healthy());
+        order
+    }
+
+    async fn send<'a, F>(&'a self, path: &'a str, request: F) -> Result<HttpResponse, String>
+    where
+        F: Fn(String) -> BoxFuture<'a, Result<HttpResponse, String>>,
+    {
+        let candidates = self.candidates();
+        if candidates.is_empty() {
+            return Err("no nodes configured".to_string());
+        }
+        let (cooldown, timeout) = {
+            let config = self.config.lock().unwrap();
+            (config.cooldown, config.timeout)
+        };
+        let mut last_result = Err("no node responded".to_string());
+        for index in candidates {
+            let url = format!("{}{}", self.nodes.lock().unwrap()[index].url, path);
+            let started = Instant::now();
+            let result = match tokio::time::timeout(timeout, request(url)).await {
+                Ok(result) => result,
+                Err(_) => Err(format!(
+                    "http error: timed out after {}ms",
+                    timeout.as_millis()
+                )),
+            };
+            let failed = match &result {
+                Ok(response) => response.status >= 500,
+                Err(_) => true,
+            };
+            let mut nodes = self.nodes.lock().unwrap();
+            if !failed {
+                nodes[index].record_success(started.elapsed());
+                return result;
+            }
+            nodes[index].unhealthy_until = Some(Instant::now() + cooldown);
+            last_result = result;
+        }
+        last_result
+    }
+
+    fn path<'a>(&self, url: &'a str) -> &'a str {
+        url.strip_prefix(self.base_url.as_str()).unwrap_or(url)
+    }
+}
+
+impl HttpTransport for FailoverTransport {
+    fn get<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<HttpResponse, String>> {
+        Box::pin(async move {
+            self.send(self.path(url), |url| {
+                Box::pin(async move { self.inner.get(&url).await })
+            })
+            .await
+        })
+    }
+
+    fn head<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<HttpResponse, String>> {
+        Box::pin(async move {
+            self.send(self.path(url), |url| {
+                Box::pin(async move { self.inner.head(&url).await })
+            })
+            .await
+        })
+    }
+
+    fn post<'a>(
+        &'a self,
+        url: &'a str,
+        body: &'a Value,
+    ) -> BoxFuture<'a, Result<HttpResponse, String>> {
+        Box::pin(async move {
+            self.send(self.path(url), |url| {
+                Box::pin(async move { self.inner.post(&url, body).await })
+            })
+            .await
+        })
+    }
+
+    fn post_bytes<'a>(
+        &'a self,
+        url: &'a str,
+        content_type: &'a str,
+        body: &'a [u8],
+    ) -> BoxFuture<'a, Result<HttpResponse, String>> {
+        Box::pin(async move {
+            self.send(self.path(url), |url| {
+                Box::pin(async move { self.inner.post_bytes(&url, content_type, body).await })
+            })
+            .await
+        })
+    }
+}
+
+/// Client spread over several fullnode urls, e.g.
+/// `https://fullnode.mainnet.aptoslabs.com/v1`.
+///
+/// Every `Aptos` method is available through `Deref`.
+#[derive(Debug, Clone)]
+pub struct AptosPool {
+    client: Aptos,
+    failover: Arc<FailoverTransport>,
+}
+
+impl AptosPool {
+    pub fn new(urls: Vec<String>) -> Self {
+        Self::with_transport(urls, Arc::new(ReqwestTransport::new()))
+    }
+
+    /// create a pool on a custom transport, e.g. `transport::MockTransport` in tests
+    pub fn with_transport(urls: Vec<String>, transport: Arc<dyn HttpTransport>) -> Self {
+        let nodes: Vec<Node> = urls
+            .iter()
+            .map(|url| Node {
+                url: url.trim_end_matches('/').to_string(),
+                unhealthy_until: None,
+                latency: None,
+            })
+            .collect();
+        let base_url = nodes
+            .first()
+            .map(|node| node.url.clone())
+            .unwrap_or_default();
+        let failover = Arc::new(FailoverTransport {
+            inner: transport,
+            base_url: base_url.clone(),
+            nodes: Mutex::new(nodes),
+            config: Mutex::new(PoolConfig {
+                policy: PoolPolicy::default(),
+                cooldown: DEFAULT_NODE_COOLDOWN,
+                timeout: DEFAULT_NODE_TIMEOUT,
+            }),
+            next: AtomicUsize::new(0),
+        });
+        AptosPool {
+            client: Aptos::with_base_url(base_url, Arc::clone(&failover) as _),
+            failover,
+        }
+    }
+
+    pub fn with_policy(self, policy: PoolPolicy) -> Self {
+        self.failover.config.lock().unwrap().policy = policy;
+        self
+    }
+
+    /// how long a failed node is skipped
+    pub fn with_cooldown(self, cooldown: Duration) -> Self {
+        self.failover.config.lock().unwrap().cooldown = cooldown;
+        self
+    }
+
+    /// per request timeout, a timed out request fails over to the next node
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        self.failover.config.lock().unwrap().timeout = timeout;
+        self
+    }
+
+    /// gate requests through a token bucket, shared by all nodes
+    pub fn with_rate_limit(mut self, requests_per_sec: f64) -> Self {
+        self.client = self.client.with_rate_limit(requests_per_sec);
+        self
+    }
+
+    /// protocol and token addresses of the network the nodes serve
+    pub fn with_network_config(mut self, config: NetworkConfig) -> Self {
+        self.client = self.client.with_network_config(config);
+        self
+    }
+
+    /// the underlying client
+    pub fn client(&self) -> &Aptos {
+        &self.client
+    }
+
+    /// health and latency of each node, in configured order
+    pub fn node_status(&self) -> Vec<NodeStatus> {
+        self.failover
+            .nodes
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|node| NodeStatus {
+                url: node.url.clone(),
+                healthy: node.is_healthy(),
+                latency: node.latency,
+            })
+            .collect()
+    }
+}
+
+impl Deref for AptosPool {
+    type Target = Aptos;
+
+    fn deref(&self) -> &Aptos {
+        &self.client
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::MockTransport;
+
+    const ACCOUNT: &str = r#"{"sequence_number":"3","authentication_key":"0xa"}"#;
+
+    fn urls() -> Vec<String> {
+        vec![
+            "https://node-a.test/v1".to_string(),
+            "https://node-b.test/v1/".to_string(),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_failover_marks_node_unhealthy() {
+        let transport = Arc::new(
+            MockTransport::new()
+                .on_get("node-a.test/v1/accounts/0xa", 503, "overloaded")
+                .on_get("node-b.test/v1/accounts/0xa", 200, ACCOUNT),
+        );
+        let pool = AptosPool::with_transport(urls(), Arc::clone(&transport) as _)
+            .with_policy(PoolPolicy::FirstHealthy);
+        let account = pool.get_account_info("0xa").await.unwrap();
+        assert_eq!(account.sequence_number, "3");
+        let status = pool.node_status();
+        assert!(!status[0].healthy);
+        assert!(status[1].healthy && status[1].latency.is_some());
+        assert_eq!(status[1].url, "https://node-b.test/v1");
+
+        // node a is skipped while cooling down
+        pool.get_account_info("0xa").await.unwrap();
+        let urls: Vec<String> = transport.requests().into_iter().map(|r| r.url).collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://node-a.test/v1/accounts/0xa",
+                "https://node-b.test/v1/accounts/0xa",
+                "https://node-b.test/v1/accounts/0xa",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_round_robin_and_client_errors_do_not_fail_over() {
+        let transport = Arc::new(
+            MockTransport::new()
+                .on_get("node-a.test/v1/accounts/0xa", 200, ACCOUNT)
+                .on_get("node-b.test/v1/accounts/0xa", 200, ACCOUNT),
+        );
+        let pool = AptosPool::with_transport(urls(), Arc::clone(&transport) as _);
+        pool.get_account_info("0xa").await.unwrap();
+        pool.get_account_info("0xa").await.unwrap();
+        // a 404 is an answer, not a node failure
+        assert!(pool.get_account_info("0xb").await.is_err());
+        let hosts: Vec<bool> = transport
+            .requests()
+            .iter()
+            .map(|r| r.url.contains("node-a"))
+            .collect();
+        assert_eq!(hosts, vec![true, false, true]);
+        assert!(pool.node_status().iter().all(|node| node.healthy));
+    }
+
+    #[tokio::test]
+    async fn test_all_nodes_failing_returns_last_response() {
+        let transport = MockTransport::new().on_get("/accounts/0xa", 502, "bad gateway");
+        let pool = AptosPool::with_transport(urls(), Arc::new(transport))
+            .with_cooldown(Duration::from_millis(20));
+        assert!(pool.get_account_info("0xa").await.is_err());
+        assert!(pool.node_status().iter().all(|node| !node.healthy));
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(pool.node_status().iter().all(|node| node.healthy));
+        assert!(AptosPool::new(vec![]).get_chain_info().await.is_err());
+    }
+}
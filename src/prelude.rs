@@ -0,0 +1,40 @@
+/// Commonly used types, for `use aptos_network_sdk::prelude::*;`.
+///
+/// Contains the client (`Aptos`, `AptosType`, `AptosError`), the wallets, the
+/// transaction, contract and token entry points (`Trade`, `Contract`,
+/// `TokenManager`, `FungibleAsset`, `DexAggregator`), the call and transaction types they take and
+/// return, and the mainnet `tokens` / `protocols` address constants, e.g.
+/// `tokens::APT`.
+pub use crate::{
+    Aptos, AptosType,
+    contract::{Contract, StopSignal},
+    error::AptosError,
+    event::EventData,
+    fungible_asset::FungibleAsset,
+    protocols,
+    token::TokenManager,
+    tokens,
+    trade::{Trade, TransactionInfo},
+    types::{
+        AccountScheme, CallKind, ContractCall, ContractReadResult, ContractWriteResult,
+        MoveArgument,
+    },
+    wallet::{MultiEd25519Wallet, SingleKeyWallet, Wallet},
+};
+
+#[cfg(feature = "dex")]
+pub use crate::dex::DexAggregator;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prelude_exports() {
+        assert_eq!(tokens::APT, "0x1::aptos_coin::AptosCoin");
+        assert!(protocols::LIQUIDSWAP_PROTOCOL_ADDRESS.starts_with("0x"));
+        let call = ContractCall::view("0x1", "coin", "balance", vec![], vec![]);
+        assert_eq!(call.kind, CallKind::View);
+        let _client = Aptos::new(AptosType::Mainnet);
+    }
+}
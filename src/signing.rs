@@ -0,0 +1,651 @@
+/// BCS of raw transactions and the messages their senders sign.
+///
+/// The node accepts json transactions but verifies signatures over
+/// `sha3_256(salt) || bcs(raw transaction)`. Entry function arguments are encoded by
+/// the move type of the parameter they are passed to, see
+/// `Aptos::entry_function_params`.
+use serde_json::Value;
+use sha3::{Digest, Sha3_256};
+
+use crate::types::MoveArgument;
+
+/// salt of single signer transactions
+pub const RAW_TRANSACTION_SALT: &[u8] = b"APTOS::RawTransaction";
+/// salt of multi agent and fee payer transactions
+pub const RAW_TRANSACTION_WITH_DATA_SALT: &[u8] = b"APTOS::RawTransactionWithData";
+/// salt of transaction hashes
+pub const TRANSACTION_SALT: &[u8] = b"APTOS::Transaction";
+
+/// parameters of framework entry functions the sdk builds, signed without an abi lookup
+const FRAMEWORK_PARAMS: &[(&str, &[&str])] = &[
+    ("aptos_account::transfer", &["address", "u64"]),
+    ("aptos_account::transfer_coins", &["address", "u64"]),
+    ("aptos_account::create_account", &["address"]),
+    ("coin::transfer", &["address", "u64"]),
+    (
+        "primary_fungible_store::transfer",
+        &["0x1::object::Object<T0>", "address", "u64"],
+    ),
+];
+
+/// `sha3_256(salt) || bcs`
+pub fn salted_message(salt: &[u8], bcs: &[u8]) -> Vec<u8> {
+    let mut message = Sha3_256::digest(salt).to_vec();
+    message.extend_from_slice(bcs);
+    message
+}
+
+/// message a single signer signs for a raw transaction
+pub fn signing_message(raw_txn_bcs: &[u8]) -> Vec<u8> {
+    salted_message(RAW_TRANSACTION_SALT, raw_txn_bcs)
+}
+
+/// Message the sender and the fee payer of a fee payer transaction sign, the BCS of
+/// `RawTransactionWithData::MultiAgentWithFeePayer { raw_txn, secondary_signer_addresses: [], fee_payer_address }`.
+pub fn fee_payer_signing_message(
+    raw_txn_bcs: &[u8],
+    fee_payer_address: &str,
+) -> Result<Vec<u8>, String> {
+    // variant index of MultiAgentWithFeePayer
+    let mut bcs = vec![1u8];
+    bcs.extend_from_slice(raw_txn_bcs);
+    // no secondary signers
+    bcs.push(0);
+    bcs.extend(address_bytes(fee_payer_address)?);
+    Ok(salted_message(RAW_TRANSACTION_WITH_DATA_SALT, &bcs))
+}
+
+/// Hash the node gives a single signer ed25519 transaction, known before submitting
+/// it: `sha3_256(sha3_256(salt) || 0 || bcs(SignedTransaction))`, the 0 being the
+/// `Transaction::UserTransaction` variant.
+pub fn ed25519_transaction_hash(raw_txn_bcs: &[u8], public_key: &[u8], signature: &[u8]) -> String {
+    let mut transaction = vec![0u8];
+    transaction.extend_from_slice(raw_txn_bcs);
+    // variant index of TransactionAuthenticator::Ed25519
+    transaction.push(0);
+    uleb128(public_key.len(), &mut transaction);
+    transaction.extend_from_slice(public_key);
+    uleb128(signature.len(), &mut transaction);
+    transaction.extend_from_slice(signature);
+    format!(
+        "0x{}",
+        hex::encode(Sha3_256::digest(salted_message(
+            TRANSACTION_SALT,
+            &transaction
+        )))
+    )
+}
+
+/// parameter types of a framework function the sdk builds, `None` for other functions
+pub fn framework_params(function: &str) -> Option<Vec<String>> {
+    let (address, name) = function.split_once("::")?;
+    if address_bytes(address).ok()? != address_bytes("0x1").ok()? {
+        return None;
+    }
+    FRAMEWORK_PARAMS
+        .iter()
+        .find(|(framework_name, _)| *framework_name == name)
+        .map(|(_, params)| params.iter().map(|p| p.to_string()).collect())
+}
+
+/// parameter types of an `exposed_functions` abi entry, without the leading signers
+pub fn entry_function_params(abi: &Value) -> Vec<String> {
+    abi["params"]
+        .as_array()
+        .map(|params| params.iter().filter_map(|p| p.as_str()).collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .skip_while(|p| *p == "signer" || *p == "&signer")
+        .map(str::to_string)
+        .collect()
+}
+
+/// entry function a json payload calls, `None` for a multisig payload given by hash
+pub fn payload_function(payload: &Value) -> Option<&str> {
+    payload_entry_function(payload)?["function"].as_str()
+}
+
+/// BCS of a json raw transaction, `params` are the parameter types of its entry function
+pub fn raw_transaction_bcs(raw_txn: &Value, params: &[String]) -> Result<Vec<u8>, String> {
+    let arguments: Vec<MoveArgument> = payload_entry_function(&raw_txn["payload"])
+        .and_then(|payload| payload["arguments"].as_array())
+        .map(|arguments| arguments.iter().cloned().map(MoveArgument::Json).collect())
+        .unwrap_or_default();
+    contract_call_transaction_bcs(raw_txn, &arguments, Some(params))
+}
+
+/// BCS of a json raw transaction calling a `ContractCall` with `arguments`, which
+/// replace the json ones of its payload. `params` are only needed for `Json` arguments.
+pub fn contract_call_transaction_bcs(
+    raw_txn: &Value,
+    arguments: &[MoveArgument],
+    params: Option<&[String]>,
+) -> Result<Vec<u8>, String> {
+    let mut bcs = address_bytes(
+        raw_txn["sender"]
+            .as_str()
+            .ok_or("raw transaction has no sender")?,
+    )?
+    .to_vec();
+    bcs.extend(u64_field(raw_txn, "sequence_number")?.to_le_bytes());
+    bcs.extend(payload_bcs(&raw_txn["payload"], arguments, params)?);
+    bcs.extend(u64_field(raw_txn, "max_gas_amount")?.to_le_bytes());
+    bcs.extend(u64_field(raw_txn, "gas_unit_price")?.to_le_bytes());
+    bcs.extend(u64_field(raw_txn, "expiration_timestamp_secs")?.to_le_bytes());
+    let chain_id = u64_field(raw_txn, "chain_id")?;
+    bcs.push(u8::try_from(chain_id).map_err(|_| format!("invalid chain id: {}", chain_id))?);
+    Ok(bcs)
+}
+
+/// entry function payload of a json payload, the inner one of a multisig payload
+fn payload_entry_function(payload: &Value) -> Option<&Value> {
+    match payload["type"].as_str()? {
+        "multisig_payload" => payload.get("transaction_payload"),
+        _ => Some(payload),
+    }
+}
+
+fn u64_field(raw_txn: &Value, field: &str) -> Result<u64, String> {
+    parse_unsigned(&raw_txn[field])
+        .and_then(|value| u64::try_from(value).ok())
+        .ok_or_else(|| format!("invalid {}: {}", field, raw_txn[field]))
+}
+
+/// BCS of a `TransactionPayload`
+fn payload_bcs(
+    payload: &Value,
+    arguments: &[MoveArgument],
+    params: Option<&[String]>,
+) -> Result<Vec<u8>, String> {
+    match payload["type"].as_str() {
+        Some("entry_function_payload") => {
+            // variant index of TransactionPayload::EntryFunction
+            let mut bcs = vec![2u8];
+            bcs.extend(entry_function_bcs(payload, arguments, params)?);
+            Ok(bcs)
+        }
+        Some("multisig_payload") => {
+            // variant index of TransactionPayload::Multisig
+            let mut bcs = vec![3u8];
+            bcs.extend(address_bytes(
+                payload["multisig_address"]
+                    .as_str()
+                    .ok_or("multisig payload has no multisig_address")?,
+            )?);
+            match &payload["transaction_payload"] {
+                Value::Null => bcs.push(0),
+                inner => {
+                    // Some, then MultisigTransactionPayload::EntryFunction
+                    bcs.extend([1u8, 0u8]);
+                    bcs.extend(entry_function_bcs(inner, arguments, params)?);
+                }
+            }
+            Ok(bcs)
+        }
+        other => Err(format!("unsupported payload type: {:?}", other)),
+    }
+}
+
+/// BCS of an `EntryFunction`
+fn entry_function_bcs(
+    payload: &Value,
+    arguments: &[MoveArgument],
+    params: Option<&[String]>,
+) -> Result<Vec<u8>, String> {
+    let function = payload["function"]
+        .as_str()
+        .ok_or("payload has no function")?;
+    let parts: Vec<&str> = function.split("::").collect();
+    if parts.len() != 3 {
+        return Err(format!("invalid function: {}", function));
+    }
+    let type_arguments: Vec<&str> = payload["type_arguments"]
+        .as_array()
+        .map(|types| types.iter().filter_map(|t| t.as_str()).collect())
+        .unwrap_or_default();
+    if let Some(params) = params
+        && arguments.len() != params.len()
+    {
+        return Err(format!(
+            "{} expects {} args, got {}",
+            function,
+            params.len(),
+            arguments.len()
+        ));
+    }
+    let mut bcs = address_bytes(parts[0])?.to_vec();
+    bcs.extend(string_bcs(parts[1]));
+    bcs.extend(string_bcs(parts[2]));
+    uleb128(type_arguments.len(), &mut bcs);
+    for type_argument in &type_arguments {
+        bcs.extend(type_tag_bcs(type_argument)?);
+    }
+    uleb128(arguments.len(), &mut bcs);
+    for (index, argument) in arguments.iter().enumerate() {
+        let param = params
+            .and_then(|params| params.get(index))
+            .map(String::as_str);
+        let encoded = move_argument_bcs(argument, param, &type_arguments)
+            .map_err(|e| format!("argument {}: {}", index, e))?;
+        uleb128(encoded.len(), &mut bcs);
+        bcs.extend(encoded);
+    }
+    Ok(bcs)
+}
+
+/// BCS of a `ContractCall` argument: typed arguments by their own type, `Json` ones as
+/// the parameter type `param`
+pub fn move_argument_bcs(
+    argument: &MoveArgument,
+    param: Option<&str>,
+    type_arguments: &[&str],
+) -> Result<Vec<u8>, String> {
+    match argument {
+        MoveArgument::U8(value) => Ok(vec![*value]),
+        MoveArgument::U64(value) => Ok(value.to_le_bytes().to_vec()),
+        MoveArgument::U128(value) => Ok(value.to_le_bytes().to_vec()),
+        MoveArgument::Bool(value) => Ok(vec![*value as u8]),
+        MoveArgument::Address(address) => Ok(address_bytes(address)?.to_vec()),
+        MoveArgument::String(value) => Ok(string_bcs(value)),
+        MoveArgument::Json(value) => {
+            let param = param.ok_or_else(|| format!("no parameter type for {}", value))?;
+            argument_bcs(value, param, type_arguments)
+        }
+    }
+}
+
+/// BCS of a json argument as move type `move_type`, generic parameters `T0`, `T1`..
+/// resolve to `type_arguments`
+pub fn argument_bcs(
+    value: &Value,
+    move_type: &str,
+    type_arguments: &[&str],
+) -> Result<Vec<u8>, String> {
+    let move_type = move_type.trim();
+    let invalid = || format!("invalid {}: {}", move_type, value);
+    let unsigned = |bytes: usize| -> Result<Vec<u8>, String> {
+        let value = parse_unsigned(value).ok_or_else(invalid)?;
+        if bytes < 16 && value >> (bytes * 8) != 0 {
+            return Err(invalid());
+        }
+        Ok(value.to_le_bytes()[..bytes].to_vec())
+    };
+    match move_type {
+        "bool" => match value {
+            Value::Bool(b) => Ok(vec![*b as u8]),
+            Value::String(s) if s == "true" || s == "false" => Ok(vec![(s == "true") as u8]),
+            _ => Err(invalid()),
+        },
+        "u8" => unsigned(1),
+        "u16" => unsigned(2),
+        "u32" => unsigned(4),
+        "u64" => unsigned(8),
+        "u128" => unsigned(16),
+        "u256" => u256_bytes(value).ok_or_else(invalid).map(|b| b.to_vec()),
+        "address" => Ok(address_bytes(value.as_str().ok_or_else(invalid)?)?.to_vec()),
+        "0x1::string::String" => Ok(string_bcs(value.as_str().ok_or_else(invalid)?)),
+        "vector<u8>" => {
+            let bytes = match value {
+                Value::String(s) => match s.strip_prefix("0x") {
+                    Some(hex_bytes) => hex::decode(hex_bytes).map_err(|_| invalid())?,
+                    None => s.as_bytes().to_vec(),
+                },
+                Value::Array(items) => items
+                    .iter()
+                    .map(|item| {
+                        parse_unsigned(item)
+                            .and_then(|b| u8::try_from(b).ok())
+                            .ok_or_else(invalid)
+                    })
+                    .collect::<Result<_, _>>()?,
+                _ => return Err(invalid()),
+            };
+            let mut bcs = Vec::new();
+            uleb128(bytes.len(), &mut bcs);
+            bcs.extend(bytes);
+            Ok(bcs)
+        }
+        _ if move_type.starts_with("vector<") && move_type.ends_with('>') => {
+            let inner = &move_type["vector<".len()..move_type.len() - 1];
+            let items = value.as_array().ok_or_else(invalid)?;
+            let mut bcs = Vec::new();
+            uleb128(items.len(), &mut bcs);
+            for item in items {
+                bcs.extend(argument_bcs(item, inner, type_arguments)?);
+            }
+            Ok(bcs)
+        }
+        _ if move_type.starts_with("0x1::object::Object<") => {
+            Ok(address_bytes(value.as_str().ok_or_else(invalid)?)?.to_vec())
+        }
+        _ if move_type.starts_with("0x1::option::Option<") && move_type.ends_with('>') => {
+            let inner = &move_type["0x1::option::Option<".len()..move_type.len() - 1];
+            // the api takes an option as a vector of zero or one element
+            let value = match value {
+                Value::Array(items) if items.len() <= 1 => items.first(),
+                Value::Array(_) => return Err(invalid()),
+                Value::Null => None,
+                value => Some(value),
+            };
+            match value {
+                Some(value) => {
+                    let mut bcs = vec![1u8];
+                    bcs.extend(argument_bcs(value, inner, type_arguments)?);
+                    Ok(bcs)
+                }
+                None => Ok(vec![0u8]),
+            }
+        }
+        _ => match move_type
+            .strip_prefix('T')
+            .and_then(|index| index.parse::<usize>().ok())
+        {
+            Some(index) => {
+                let resolved = type_arguments
+                    .get(index)
+                    .ok_or_else(|| format!("missing type argument {}", move_type))?;
+                argument_bcs(value, resolved, type_arguments)
+            }
+            None => Err(format!("cannot encode an argument of type {}", move_type)),
+        },
+    }
+}
+
+/// BCS of a `TypeTag`, e.g. `0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>`
+pub fn type_tag_bcs(type_tag: &str) -> Result<Vec<u8>, String> {
+    let type_tag = type_tag.trim();
+    let primitive = match type_tag {
+        "bool" => Some(0u8),
+        "u8" => Some(1),
+        "u64" => Some(2),
+        "u128" => Some(3),
+        "address" => Some(4),
+        "signer" => Some(5),
+        "u16" => Some(8),
+        "u32" => Some(9),
+        "u256" => Some(10),
+        _ => None,
+    };
+    if let Some(variant) = primitive {
+        return Ok(vec![variant]);
+    }
+    if let Some(inner) = type_tag
+        .strip_prefix("vector<")
+        .and_then(|rest| rest.strip_suffix('>'))
+    {
+        let mut bcs = vec![6u8];
+        bcs.extend(type_tag_bcs(inner)?);
+        return Ok(bcs);
+    }
+    let (path, generics) = match type_tag.find('<') {
+        Some(start) if type_tag.ends_with('>') => (
+            &type_tag[..start],
+            split_type_list(&type_tag[start + 1..type_tag.len() - 1]),
+        ),
+        Some(_) => return Err(format!("invalid type: {}", type_tag)),
+        None => (type_tag, vec![]),
+    };
+    let parts: Vec<&str> = path.split("::").collect();
+    if parts.len() != 3 {
+        return Err(format!("invalid type: {}", type_tag));
+    }
+    let mut bcs = vec![7u8];
+    bcs.extend(address_bytes(parts[0])?);
+    bcs.extend(string_bcs(parts[1]));
+    bcs.extend(string_bcs(parts[2]));
+    uleb128(generics.len(), &mut bcs);
+    for generic in generics {
+        bcs.extend(type_tag_bcs(generic)?);
+    }
+    Ok(bcs)
+}
+
+/// split `A, B<C, D>` at its top level commas
+fn split_type_list(types: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut start) = (0usize, 0usize);
+    for (index, c) in types.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                parts.push(types[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    if !types[start..].trim().is_empty() {
+        parts.push(types[start..].trim());
+    }
+    parts
+}
+
+/// 32 byte account address, short forms such as `0x1` are zero padded
+pub fn address_bytes(address: &str) -> Result<[u8; 32], String> {
+    let hex_part = address.trim().trim_start_matches("0x");
+    if hex_part.is_empty() || hex_part.len() > 64 {
+        return Err(format!("invalid address: {}", address));
+    }
+    let bytes = hex::decode(format!("{:0>64}", hex_part))
+        .map_err(|e| format!("invalid address {}: {}", address, e))?;
+    let mut result = [0u8; 32];
+    result.copy_from_slice(&bytes);
+    Ok(result)
+}
+
+fn string_bcs(value: &str) -> Vec<u8> {
+    let mut bcs = Vec::with_capacity(value.len() + 1);
+    uleb128(value.len(), &mut bcs);
+    bcs.extend_from_slice(value.as_bytes());
+    bcs
+}
+
+fn uleb128(mut value: usize, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// unsigned integer from a json number or decimal string
+fn parse_unsigned(value: &Value) -> Option<u128> {
+    match value {
+        Value::Number(n) => n.as_u64().map(u128::from),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+/// little endian u256 from a json number or decimal string
+fn u256_bytes(value: &Value) -> Option<[u8; 32]> {
+    let digits = match value {
+        Value::Number(n) => n.as_u64()?.to_string(),
+        Value::String(s) if !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) => s.clone(),
+        _ => return None,
+    };
+    let mut bytes = [0u8; 32];
+    for digit in digits.bytes() {
+        // bytes = bytes * 10 + digit
+        let mut carry = (digit - b'0') as u32;
+        for byte in bytes.iter_mut() {
+            let next = *byte as u32 * 10 + carry;
+            *byte = next as u8;
+            carry = next >> 8;
+        }
+        if carry != 0 {
+            return None;
+        }
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// `0x1::aptos_account::transfer(0x2, 1000)` from `0x1`, laid out field by field
+    fn transfer_raw_txn_bcs() -> Vec<u8> {
+        let mut expected = hex::decode(format!("{:0>64}", "1")).unwrap(); // sender
+        expected.extend(7u64.to_le_bytes()); // sequence number
+        expected.push(2); // TransactionPayload::EntryFunction
+        expected.extend(hex::decode(format!("{:0>64}", "1")).unwrap()); // module address
+        expected.extend(b"\x0daptos_account");
+        expected.extend(b"\x08transfer");
+        expected.push(0); // no type arguments
+        expected.push(2); // two arguments
+        expected.push(32);
+        expected.extend(hex::decode(format!("{:0>64}", "2")).unwrap());
+        expected.push(8);
+        expected.extend(1000u64.to_le_bytes());
+        expected.extend(2000u64.to_le_bytes()); // max gas amount
+        expected.extend(100u64.to_le_bytes()); // gas unit price
+        expected.extend(1_700_000_000u64.to_le_bytes()); // expiration
+        expected.push(2); // chain id
+        expected
+    }
+
+    fn transfer_raw_txn() -> Value {
+        json!({
+            "sender": "0x1",
+            "sequence_number": "7",
+            "max_gas_amount": "2000",
+            "gas_unit_price": "100",
+            "expiration_timestamp_secs": "1700000000",
+            "payload": {
+                "type": "entry_function_payload",
+                "function": "0x1::aptos_account::transfer",
+                "type_arguments": [],
+                "arguments": ["0x2", "1000"]
+            },
+            "chain_id": 2
+        })
+    }
+
+    #[test]
+    fn test_raw_transaction_bcs_layout() {
+        let params = framework_params("0x1::aptos_account::transfer").unwrap();
+        let bcs = raw_transaction_bcs(&transfer_raw_txn(), &params).unwrap();
+        assert_eq!(bcs, transfer_raw_txn_bcs());
+        // the prefix is sha3_256 of the salt, as in the aptos sdks
+        let message = signing_message(&bcs);
+        assert_eq!(
+            hex::encode(&message[..32]),
+            "b5e97db07fa0bd0e5598aa3643a9bc6f6693bddc1a9fec9e674a461eaa00b193"
+        );
+        assert_eq!(&message[32..], bcs.as_slice());
+    }
+
+    #[test]
+    fn test_fee_payer_signing_message_layout() {
+        let raw_txn_bcs = transfer_raw_txn_bcs();
+        let message = fee_payer_signing_message(&raw_txn_bcs, "0xfee").unwrap();
+        let mut expected =
+            hex::decode("5efa3c4f02f83a0f4b2d69fc95c607cc02825cc4e7be536ef0992df050d9e67c")
+                .unwrap();
+        // MultiAgentWithFeePayer tag comes before the raw transaction
+        expected.push(1);
+        expected.extend(&raw_txn_bcs);
+        expected.push(0); // no secondary signers
+        expected.extend(hex::decode(format!("{:0>64}", "fee")).unwrap());
+        assert_eq!(message, expected);
+    }
+
+    #[test]
+    fn test_ed25519_transaction_hash_layout() {
+        let raw_txn_bcs = transfer_raw_txn_bcs();
+        let (public_key, signature) = ([0x0au8; 32], [0x0bu8; 64]);
+        let mut transaction = Sha3_256::digest(TRANSACTION_SALT).to_vec();
+        transaction.push(0); // Transaction::UserTransaction
+        transaction.extend(&raw_txn_bcs);
+        transaction.push(0); // TransactionAuthenticator::Ed25519
+        transaction.push(32);
+        transaction.extend(public_key);
+        transaction.push(64);
+        transaction.extend(signature);
+        assert_eq!(
+            ed25519_transaction_hash(&raw_txn_bcs, &public_key, &signature),
+            format!("0x{}", hex::encode(Sha3_256::digest(&transaction)))
+        );
+    }
+
+    #[test]
+    fn test_type_tags_and_arguments() {
+        assert_eq!(type_tag_bcs("u64").unwrap(), vec![2]);
+        assert_eq!(type_tag_bcs("vector<u8>").unwrap(), vec![6, 1]);
+        let mut coin_store = vec![7];
+        coin_store.extend(address_bytes("0x1").unwrap());
+        coin_store.extend(b"\x04coin\x09CoinStore\x01\x07");
+        coin_store.extend(address_bytes("0x1").unwrap());
+        coin_store.extend(b"\x0aaptos_coin\x09AptosCoin\x00");
+        assert_eq!(
+            type_tag_bcs("0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>").unwrap(),
+            coin_store
+        );
+
+        assert_eq!(argument_bcs(&json!(7), "u8", &[]).unwrap(), vec![7]);
+        assert!(argument_bcs(&json!(256), "u8", &[]).is_err());
+        assert_eq!(argument_bcs(&json!("true"), "bool", &[]).unwrap(), vec![1]);
+        assert_eq!(
+            argument_bcs(&json!("1"), "u128", &[]).unwrap(),
+            1u128.to_le_bytes().to_vec()
+        );
+        let mut u256 = vec![0u8; 32];
+        u256[0] = 1;
+        u256[31] = 0x80;
+        let max_bit =
+            "57896044618658097711785492504343953926634992332820282019728792003956564819969";
+        assert_eq!(argument_bcs(&json!(max_bit), "u256", &[]).unwrap(), u256);
+        assert_eq!(
+            argument_bcs(&json!("hi"), "0x1::string::String", &[]).unwrap(),
+            b"\x02hi".to_vec()
+        );
+        assert_eq!(
+            argument_bcs(&json!("0x0102"), "vector<u8>", &[]).unwrap(),
+            vec![2, 1, 2]
+        );
+        assert_eq!(
+            argument_bcs(&json!(["1", "2"]), "vector<u64>", &[]).unwrap(),
+            [
+                vec![2],
+                1u64.to_le_bytes().to_vec(),
+                2u64.to_le_bytes().to_vec()
+            ]
+            .concat()
+        );
+        assert_eq!(
+            argument_bcs(&json!([]), "0x1::option::Option<u64>", &[]).unwrap(),
+            vec![0]
+        );
+        assert_eq!(argument_bcs(&json!("5"), "T0", &["u8"]).unwrap(), vec![5]);
+        assert!(argument_bcs(&json!({}), "0x1::pool::Config", &[]).is_err());
+    }
+
+    #[test]
+    fn test_typed_arguments_encode_by_their_own_type() {
+        let mut raw_txn = transfer_raw_txn();
+        raw_txn["payload"]["function"] = json!("0xcafe::pool::swap");
+        let typed = [
+            MoveArgument::U8(7),
+            MoveArgument::U128(1),
+            MoveArgument::String("hi".to_string()),
+        ];
+        // no parameter types needed for typed arguments
+        let bcs = contract_call_transaction_bcs(&raw_txn, &typed, None).unwrap();
+        let mut arguments = vec![3u8, 1, 7, 16];
+        arguments.extend(1u128.to_le_bytes());
+        arguments.extend(b"\x03\x02hi");
+        // the arguments follow the function name and the empty type argument list
+        let start = 32 + 8 + 1 + 32 + 5 + 5 + 1;
+        assert_eq!(&bcs[start..start + arguments.len()], arguments.as_slice());
+
+        // a json argument is encoded as its parameter type
+        let json_u8 = [MoveArgument::Json(json!("7"))];
+        assert!(contract_call_transaction_bcs(&raw_txn, &json_u8, None).is_err());
+        let params = vec!["u8".to_string()];
+        let bcs = contract_call_transaction_bcs(&raw_txn, &json_u8, Some(&params)).unwrap();
+        assert_eq!(&bcs[start..start + 3], &[1, 1, 7]);
+    }
+}
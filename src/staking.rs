@@ -1,4 +1,8 @@
-use crate::{Aptos, types::ContractCall, wallet::Wallet};
+use crate::{
+    Aptos,
+    types::{CallKind, ContractCall, MoveArgument},
+    wallet::Wallet,
+};
 use serde_json::{Value, json};
 use std::sync::Arc;
 
@@ -17,7 +21,8 @@ impl SystemStaking {
             module_name: "staking_contract".to_string(),
             function_name: "stake".to_string(),
             type_arguments: vec![],
-            arguments: vec![json!(amount.to_string())],
+            arguments: vec![MoveArgument::U64(amount)],
+            kind: CallKind::Entry,
         };
         crate::contract::Contract::write(client, wallet, contract_call)
             .await
@@ -35,7 +40,8 @@ impl SystemStaking {
             module_name: "staking_contract".to_string(),
             function_name: "unstake".to_string(),
             type_arguments: vec![],
-            arguments: vec![json!(amount.to_string())],
+            arguments: vec![MoveArgument::U64(amount)],
+            kind: CallKind::Entry,
         };
         crate::contract::Contract::write(client, wallet, contract_call)
             .await
@@ -50,6 +56,7 @@ impl SystemStaking {
             function_name: "claim_rewards".to_string(),
             type_arguments: vec![],
             arguments: vec![],
+            kind: CallKind::Entry,
         };
         crate::contract::Contract::write(client, wallet, contract_call)
             .await
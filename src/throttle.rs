@@ -0,0 +1,382 @@
+/// Client side backpressure: a token bucket rate limiter and a circuit breaker.
+///
+/// Both are applied by `ThrottledTransport`, which `Aptos::with_rate_limit` and
+/// `Aptos::with_circuit_breaker` install in front of the client's transport.
+use crate::transport::{HttpResponse, HttpTransport};
+use futures::future::BoxFuture;
+use serde_json::Value;
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// display prefix of `AptosError::ServiceUnavailable`
+pub const SERVICE_UNAVAILABLE: &str = "service unavailable";
+
+/// `error_code` of the 503 response returned while the circuit breaker is open
+pub const CIRCUIT_BREAKER_OPEN: &str = "circuit_breaker_open";
+
+/// circuit breaker state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    /// requests pass through
+    Closed,
+    /// requests are rejected until the cooldown ends
+    Open,
+    /// the cooldown ended, a single probe request decides whether the breaker closes
+    HalfOpen,
+}
+
+/// snapshot of the throttle for observability
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThrottleMetrics {
+    /// tokens left in the bucket, `None` without a rate limit
+    pub available_tokens: Option<f64>,
+    /// `None` without a circuit breaker
+    pub breaker_state: Option<BreakerState>,
+    pub consecutive_failures: u32,
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(requests_per_sec: f64) -> Self {
+        let capacity = requests_per_sec.max(1.0);
+        TokenBucket {
+            rate: requests_per_sec,
+            capacity,
+            tokens: capacity,
+            refilled_at: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.refilled_at = now;
+    }
+
+    /// take a token, or the time until one is available
+    fn try_take(&mut self) -> Result<(), Duration> {
+        self.refill();
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / self.rate))
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    /// when the half-open probe was let through, `None` when none is in flight
+    probe_started: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    fn state(&self) -> BreakerState {
+        match self.opened_at {
+            None => BreakerState::Closed,
+            Some(opened_at) if opened_at.elapsed() < self.cooldown => BreakerState::Open,
+            Some(_) => BreakerState::HalfOpen,
+        }
+    }
+
+    /// Let a request through, or the reason it is rejected.
+    ///
+    /// While half-open only one probe is in flight at a time. A probe that never
+    /// records its result, e.g. a dropped future, frees the slot after the cooldown.
+    fn admit(&mut self) -> Result<(), String> {
+        match self.state() {
+            BreakerState::Closed => Ok(()),
+            BreakerState::Open => {
+                let retry_in = self
+                    .cooldown
+                    .saturating_sub(self.opened_at.map_or(Duration::ZERO, |t| t.elapsed()));
+                Err(format!(
+                    "circuit breaker open after {} consecutive failures, retry in {}ms",
+                    self.consecutive_failures,
+                    retry_in.as_millis()
+                ))
+            }
+            BreakerState::HalfOpen => match self.probe_started {
+                Some(started) if started.elapsed() < self.cooldown => {
+                    Err("circuit breaker half-open, waiting for the probe request".to_string())
+                }
+                _ => {
+                    self.probe_started = Some(Instant::now());
+                    Ok(())
+                }
+            },
+        }
+    }
+
+    fn record(&mut self, success: bool) {
+        self.probe_started = None;
+        if success {
+            self.consecutive_failures = 0;
+            self.opened_at = None;
+            return;
+        }
+        self.consecutive_failures += 1;
+        if self.state() == BreakerState::HalfOpen
+            || self.consecutive_failures >= self.failure_threshold
+        {
+            self.opened_at = Some(Instant::now());
+        }
+    }
+}
+
+/// Transport applying the rate limit and circuit breaker to an inner transport.
+///
+/// Transport errors, 429 and 5xx responses count as failures, any other response
+/// resets the failure count. Rejected requests get a 503 response with the
+/// `CIRCUIT_BREAKER_OPEN` error code, without reaching the inner transport.
+#[derive(Debug)]
+pub struct ThrottledTransport {
+    inner: Arc<dyn HttpTransport>,
+    limiter: Mutex<Option<TokenBucket>>,
+    breaker: Mutex<Option<CircuitBreaker>>,
+}
+
+impl ThrottledTransport {
+    pub fn new(inner: Arc<dyn HttpTransport>) -> Self {
+        ThrottledTransport {
+            inner,
+            limiter: Mutex::new(None),
+            breaker: Mutex::new(None),
+        }
+    }
+
+    /// allow `requests_per_sec` requests a second, with bursts of the same size
+    pub fn set_rate_limit(&self, requests_per_sec: f64) {
+        *self.limiter.lock().unwrap() =
+            (requests_per_sec > 0.0).then(|| TokenBucket::new(requests_per_sec));
+    }
+
+    /// reject requests for `cooldown` after `failure_threshold` consecutive failures
+    pub fn set_circuit_breaker(&self, failure_threshold: u32, cooldown: Duration) {
+        *self.breaker.lock().unwrap() = Some(CircuitBreaker {
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            consecutive_failures: 0,
+            opened_at: None,
+            probe_started: None,
+        });
+    }
+
+    pub fn metrics(&self) -> ThrottleMetrics {
+        let available_tokens = self.limiter.lock().unwrap().as_mut().map(|bucket| {
+            bucket.refill();
+            bucket.tokens
+        });
+        let breaker = self.breaker.lock().unwrap();
+        ThrottleMetrics {
+            available_tokens,
+            breaker_state: breaker.as_ref().map(|b| b.state()),
+            consecutive_failures: breaker.as_ref().map_or(0, |b| b.consecutive_failures),
+        }
+    }
+
+    /// wait for the rate limit, or the response rejecting the request
+    async fn acquire(&self) -> Result<(), HttpResponse> {
+        if let Some(breaker) = self.breaker.lock().unwrap().as_mut()
+            && let Err(reason) = breaker.admit()
+        {
+            let body = serde_json::json!({
+                "message": reason,
+                "error_code": CIRCUIT_BREAKER_OPEN,
+            });
+            return Err(HttpResponse::new(503, &body.to_string()));
+        }
+        loop {
+            let wait = match self.limiter.lock().unwrap().as_mut() {
+                Some(bucket) => bucket.try_take(),
+                None => Ok(()),
+            };
+            match wait {
+                Ok(()) => return Ok(()),
+                Err(wait) => tokio::time::sleep(wait).await,
+            }
+        }
+    }
+
+    fn record(&self, result: &Result<HttpResponse, String>) {
+        if let Some(breaker) = self.breaker.lock().unwrap().as_mut() {
+            let success = match result {
+                Ok(response) => response.status != 429 && response.status < 500,
+                Err(_) => false,
+            };
+            breaker.record(success);
+        }
+    }
+}
+
+impl HttpTransport for ThrottledTransport {
+    fn get<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<HttpResponse, String>> {
+        Box::pin(async move {
+            if let Err(rejected) = self.acquire().await {
+                return Ok(rejected);
+            }
+            let result = self.inner.get(url).await;
+            self.record(&result);
+            result
+        })
+    }
+
+    fn head<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<HttpResponse, String>> {
+        Box::pin(async move {
+            if let Err(rejected) = self.acquire().await {
+                return Ok(rejected);
+            }
+            let result = self.inner.head(url).await;
+            self.record(&result);
+            result
+        })
+    }
+
+    fn post<'a>(
+        &'a self,
+        url: &'a str,
+        body: &'a Value,
+    ) -> BoxFuture<'a, Result<HttpResponse, String>> {
+        Box::pin(async move {
+            if let Err(rejected) = self.acquire().await {
+                return Ok(rejected);
+            }
+            let result = self.inner.post(url, body).await;
+            self.record(&result);
+            result
+        })
+    }
+
+    fn post_bytes<'a>(
+        &'a self,
+        url: &'a str,
+        content_type: &'a str,
+        body: &'a [u8],
+    ) -> BoxFuture<'a, Result<HttpResponse, String>> {
+        Box::pin(async move {
+            if let Err(rejected) = self.acquire().await {
+                return Ok(rejected);
+            }
+            let result = self.inner.post_bytes(url, content_type, body).await;
+            self.record(&result);
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Aptos, AptosType, error::AptosError, transport::MockTransport};
+
+    #[tokio::test]
+    async fn test_circuit_breaker_short_circuits_after_failures() {
+        let transport = Arc::new(
+            MockTransport::new()
+                .on_get("/accounts/0xa", 503, "overloaded")
+                .on_get("/accounts/0xa", 503, "overloaded")
+                .on_get(
+                    "/accounts/0xa",
+                    200,
+                    r#"{"sequence_number":"1","authentication_key":"0xa"}"#,
+                ),
+        );
+        let client = Aptos::with_transport(AptosType::Mainnet, Arc::clone(&transport) as _)
+            .with_circuit_breaker(2, Duration::from_millis(50));
+        assert!(client.get_account_info("0xa").await.is_err());
+        assert!(client.get_account_info("0xa").await.is_err());
+        let metrics = client.throttle_metrics().unwrap();
+        assert_eq!(metrics.breaker_state, Some(BreakerState::Open));
+        assert_eq!(metrics.consecutive_failures, 2);
+        assert!(matches!(
+            client.get_account_info("0xa").await,
+            Err(AptosError::ServiceUnavailable(_))
+        ));
+        assert_eq!(transport.requests().len(), 2);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(
+            client.throttle_metrics().unwrap().breaker_state,
+            Some(BreakerState::HalfOpen)
+        );
+        assert!(client.get_account_info("0xa").await.is_ok());
+        let metrics = client.throttle_metrics().unwrap();
+        assert_eq!(metrics.breaker_state, Some(BreakerState::Closed));
+        assert_eq!(metrics.consecutive_failures, 0);
+    }
+
+    #[tokio::test]
+    async fn test_half_open_breaker_lets_one_probe_through() {
+        let mut breaker = CircuitBreaker {
+            failure_threshold: 1,
+            cooldown: Duration::from_millis(50),
+            consecutive_failures: 0,
+            opened_at: None,
+            probe_started: None,
+        };
+        breaker.record(false);
+        assert!(breaker.admit().is_err());
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+        assert!(breaker.admit().is_ok());
+        assert!(breaker.admit().unwrap_err().contains("probe"));
+        // a probe that never reports back frees the slot after the cooldown
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(breaker.admit().is_ok());
+        breaker.record(true);
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        assert!(breaker.admit().is_ok());
+        assert!(breaker.admit().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_service_unavailable_is_told_by_error_code() {
+        // a node's own 503 is an api error, only the breaker's rejection is not
+        let transport = Arc::new(MockTransport::new().on_get(
+            "/accounts/0xa",
+            503,
+            r#"{"message":"service unavailable: overloaded","error_code":"internal_error"}"#,
+        ));
+        let client = Aptos::with_transport(AptosType::Mainnet, transport)
+            .with_circuit_breaker(1, Duration::from_secs(60));
+        assert!(matches!(
+            client.get_account_info("0xa").await,
+            Err(AptosError::Api { status: 503, .. })
+        ));
+        let rejected = client.get_account_info("0xa").await.unwrap_err();
+        assert!(matches!(rejected, AptosError::ServiceUnavailable(_)));
+        assert!(
+            rejected
+                .to_string()
+                .starts_with("service unavailable: circuit breaker open after 1")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_delays_requests_over_budget() {
+        let transport = Arc::new(MockTransport::new().on_get("/", 200, "{}"));
+        let client = Aptos::with_transport(AptosType::Mainnet, transport).with_rate_limit(50.0);
+        let started = Instant::now();
+        for _ in 0..55 {
+            let _ = client.get_chain_info().await;
+        }
+        // 50 burst tokens, the other 5 wait 20ms each
+        assert!(started.elapsed() >= Duration::from_millis(80));
+        assert!(client.throttle_metrics().unwrap().available_tokens.unwrap() < 1.0);
+    }
+}
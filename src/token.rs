@@ -1,25 +1,17 @@
 /// This module is used for token management utilities to create, register, and manage tokens on Aptos.
+use crate::Aptos;
 use crate::{
-    Aptos,
-    dex::DexAggregator,
-    global::mainnet::{
-        protocol_address::{
-            ANIMESWAP_PROTOCOL_ADDRESS, AUXSWAP_PROTOCOL_ADDRESS, CELLANASWAP_PROTOCOL_ADDRESS,
-            LIQUIDSWAP_PROTOCOL_ADDRESS, PANCAKESWAP_FACTORY_PROTOCOL_ADDRESS,
-            THALA_PROTOCOL_ADDRESS,
-        },
-        sys_address::X_3,
-        token_address::{USDC, USDT, WORMHOLE_USDC},
-    },
-};
-use crate::{
+    contract::Contract,
+    fungible_asset::FungibleAsset,
     global::mainnet::{
         sys_address::X_1,
         sys_module::{coin, managed_coin},
     },
-    types::ContractCall,
+    types::{CallKind, ContractCall, ContractWriteResult, MoveArgument, StoreStatus},
     wallet::Wallet,
 };
+#[cfg(feature = "dex")]
+use crate::{dex::DexAggregator, global::mainnet::sys_address::X_3};
 use serde_json::Value;
 use serde_json::json;
 use std::sync::Arc;
@@ -41,12 +33,11 @@ impl TokenManager {
     /// # Example
     /// ```rust
     /// use std::sync::Arc;
-    /// use crate::{Aptos, Wallet, token::TokenManager};
-    /// use crate::global::rpc::APTOS_MAINNET_URL;
+    /// use aptos_network_sdk::{Aptos, AptosType, token::TokenManager, wallet::Wallet};
     ///
     /// async fn example() -> Result<(), String> {
-    /// let client = Arc::new(Aptos::new(APTOS_MAINNET_URL));
-    /// let wallet = Arc::new(Wallet::from_private_key("0x..."));
+    /// let client = Arc::new(Aptos::new(AptosType::Mainnet));
+    /// let wallet = Arc::new(Wallet::from_private_key("0x...")?);
     ///
     /// let result = TokenManager::create_token(
     ///     client,
@@ -73,11 +64,12 @@ impl TokenManager {
             function_name: managed_coin::initialize.to_string(),
             type_arguments: vec![],
             arguments: vec![
-                json!(name),
-                json!(symbol),
-                json!(decimals),
-                json!(initial_supply.to_string()),
+                MoveArgument::String(name.to_string()),
+                MoveArgument::String(symbol.to_string()),
+                MoveArgument::U8(decimals),
+                MoveArgument::U64(initial_supply),
             ],
+            kind: CallKind::Entry,
         };
         crate::contract::Contract::write(client, wallet, contract_call)
             .await
@@ -94,11 +86,10 @@ impl TokenManager {
     /// # Example
     /// ```rust
     /// use std::sync::Arc;
-    /// use crate::{Aptos, Wallet, token::TokenManager};
-    /// use crate::global::rpc::APTOS_MAINNET_URL;
+    /// use aptos_network_sdk::{Aptos, AptosType, token::TokenManager, wallet::Wallet};
     /// async fn example() -> Result<(), String> {
-    /// let client = Arc::new(Aptos::new(APTOS_MAINNET_URL));
-    /// let wallet = Arc::new(Wallet::from_private_key("0x..."));
+    /// let client = Arc::new(Aptos::new(AptosType::Mainnet));
+    /// let wallet = Arc::new(Wallet::from_private_key("0x...")?);
     /// let token_type = "0x123::my_token::MyToken";
     ///
     /// let result = TokenManager::register_token(client, wallet, token_type).await?;
@@ -110,16 +101,90 @@ impl TokenManager {
         wallet: Arc<Wallet>,
         token_type: &str,
     ) -> Result<Value, String> {
-        let contract_call = ContractCall {
+        crate::contract::Contract::write(client, wallet, Self::build_register_call(token_type))
+            .await
+            .map(|result| json!(result))
+    }
+
+    /// `0x1::coin::register<token_type>` call, signed by the account that registers
+    pub fn build_register_call(token_type: &str) -> ContractCall {
+        ContractCall {
             module_address: X_1.to_string(),
             module_name: coin::name.to_string(),
             function_name: coin::register.to_string(),
             type_arguments: vec![token_type.to_string()],
             arguments: vec![],
+            kind: CallKind::Entry,
+        }
+    }
+
+    /// Register the wallet for `token_type` unless it already is.
+    ///
+    /// Returns the register transaction, `None` if no transaction was needed, and an
+    /// error if the wallet's store is frozen or the register transaction failed.
+    pub async fn ensure_registered(
+        client: Arc<Aptos>,
+        wallet: Arc<Wallet>,
+        token_type: &str,
+    ) -> Result<Option<ContractWriteResult>, String> {
+        match client.get_store_status(wallet.address(), token_type).await? {
+            StoreStatus::Registered => return Ok(None),
+            StoreStatus::Frozen => {
+                return Err(format!("{} store of {} is frozen", token_type, wallet.address()));
+            }
+            StoreStatus::Unregistered => {}
+        }
+        let result = Contract::write(client, wallet, Self::build_register_call(token_type)).await?;
+        if !result.success {
+            return Err(format!(
+                "register {} failed: {}",
+                token_type,
+                result.error.unwrap_or_default()
+            ));
+        }
+        Ok(Some(result))
+    }
+
+    /// Register the sender for `token_type` if needed, then send `amount` to `recipient`.
+    ///
+    /// An unregistered recipient is registered by the transfer itself through
+    /// `0x1::aptos_account::transfer_coins`.
+    /// Fungible asset metadata addresses go through `FungibleAsset::transfer`.
+    pub async fn register_and_transfer(
+        client: Arc<Aptos>,
+        wallet: Arc<Wallet>,
+        token_type: &str,
+        recipient: &str,
+        amount: u64,
+    ) -> Result<RegisterAndTransfer, String> {
+        if !token_type.contains("::") {
+            // fungible assets need no registration
+            let transfer =
+                FungibleAsset::transfer(client, wallet, token_type, recipient, amount).await?;
+            return Ok(RegisterAndTransfer {
+                register: None,
+                transfer,
+            });
+        }
+        let register =
+            Self::ensure_registered(Arc::clone(&client), Arc::clone(&wallet), token_type).await?;
+        let (module_name, function_name) = match client.get_store_status(recipient, token_type).await? {
+            StoreStatus::Registered => (coin::name, coin::transfer),
+            StoreStatus::Unregistered => ("aptos_account", "transfer_coins"),
+            StoreStatus::Frozen => {
+                return Err(format!("{} store of {} is frozen", token_type, recipient));
+            }
         };
-        crate::contract::Contract::write(client, wallet, contract_call)
-            .await
-            .map(|result| json!(result))
+        let transfer_call = ContractCall {
+            module_address: X_1.to_string(),
+            module_name: module_name.to_string(),
+            function_name: function_name.to_string(),
+            type_arguments: vec![token_type.to_string()],
+            arguments: vec![MoveArgument::Address(recipient.to_string()), MoveArgument::U64(amount)],
+            kind: CallKind::Entry,
+        };
+        let transfer = Contract::write(client, wallet, transfer_call).await?;
+        Ok(RegisterAndTransfer { register, transfer })
     }
 
     /// mint token
@@ -134,12 +199,11 @@ impl TokenManager {
     /// # Example
     /// ```rust
     /// use std::sync::Arc;
-    /// use crate::{Aptos, Wallet, token::TokenManager};
-    /// use crate::global::rpc::APTOS_MAINNET_URL;
+    /// use aptos_network_sdk::{Aptos, AptosType, token::TokenManager, wallet::Wallet};
     ///
     /// async fn example() -> Result<(), String> {
-    /// let client = Arc::new(Aptos::new(APTOS_MAINNET_URL));
-    /// let wallet = Arc::new(Wallet::from_private_key("0x..."));
+    /// let client = Arc::new(Aptos::new(AptosType::Mainnet));
+    /// let wallet = Arc::new(Wallet::from_private_key("0x...")?);
     /// let token_type = "0x123::my_token::MyToken";
     ///
     /// let result = TokenManager::mint_token(
@@ -164,7 +228,8 @@ impl TokenManager {
             module_name: managed_coin::name.to_string(),
             function_name: managed_coin::mint.to_string(),
             type_arguments: vec![token_type.to_string()],
-            arguments: vec![json!(recipient), json!(amount.to_string())],
+            arguments: vec![MoveArgument::Address(recipient.to_string()), MoveArgument::U64(amount)],
+            kind: CallKind::Entry,
         };
         crate::contract::Contract::write(client, wallet, contract_call)
             .await
@@ -183,14 +248,15 @@ impl TokenManager {
             module_name: managed_coin::name.to_string(),
             function_name: managed_coin::burn.to_string(),
             type_arguments: vec![token_type.to_string()],
-            arguments: vec![json!(amount.to_string())],
+            arguments: vec![MoveArgument::U64(amount)],
+            kind: CallKind::Entry,
         };
         crate::contract::Contract::write(client, wallet, contract_call)
             .await
             .map(|result| json!(result))
     }
 
-    /// get token metadata
+    /// get token metadata, a json view of `Aptos::get_coin_info`
     ///
     /// # Params
     /// client - aptos client
@@ -199,11 +265,10 @@ impl TokenManager {
     /// # Example
     /// ```rust
     /// use std::sync::Arc;
-    /// use crate::{Aptos, token::TokenManager};
-    /// use crate::global::rpc::APTOS_MAINNET_URL;
+    /// use aptos_network_sdk::{Aptos, AptosType, token::TokenManager};
     ///
-    /// # async fn example() -> Result<(), String> {
-    /// let client = Arc::new(Aptos::new(APTOS_MAINNET_URL));
+    /// async fn example() -> Result<(), String> {
+    /// let client = Arc::new(Aptos::new(AptosType::Mainnet));
     /// let token_type = "0x1::aptos_coin::AptosCoin";
     ///
     /// let metadata = TokenManager::get_token_metadata(client, token_type).await?;
@@ -211,16 +276,14 @@ impl TokenManager {
     /// Ok(())
     /// }
     /// ```
-    pub async fn get_token_metadata(
-        client: Arc<Aptos>,
-        token_type: &str,
-    ) -> Result<Value, String> {
-        let resource_type = format!("0x1::coin::CoinInfo<{}>", token_type);
-        client
-            .get_account_resource(X_1, &resource_type)
-            .await
-            .map(|opt| opt.map(|r| r.data).unwrap_or(Value::Null))
-            .map_err(|e| e.to_string())
+    pub async fn get_token_metadata(client: Arc<Aptos>, token_type: &str) -> Result<Value, String> {
+        let info = client.get_coin_info(token_type).await?;
+        Ok(json!({
+            "name": info.name,
+            "symbol": info.symbol,
+            "decimals": info.decimals,
+            "supply": info.supply.map(|supply| supply.to_string()),
+        }))
     }
 
     /// get token balance
@@ -233,11 +296,10 @@ impl TokenManager {
     /// # Example
     /// ```rust
     /// use std::sync::Arc;
-    /// use crate::{Aptos, token::TokenManager};
-    /// use crate::global::rpc::APTOS_MAINNET_URL;
+    /// use aptos_network_sdk::{Aptos, AptosType, token::TokenManager};
     ///
     /// async fn example() -> Result<(), String> {
-    /// let client = Arc::new(Aptos::new(APTOS_MAINNET_URL));
+    /// let client = Arc::new(Aptos::new(AptosType::Mainnet));
     /// let address = "0x123...";
     /// let token_type = "0x1::aptos_coin::AptosCoin";
     ///
@@ -251,6 +313,9 @@ impl TokenManager {
         address: &str,
         token_type: &str,
     ) -> Result<u64, String> {
+        if !token_type.contains("::") {
+            return FungibleAsset::balance(client, address, token_type).await;
+        }
         client.get_token_balance(address, token_type).await
     }
 }
@@ -268,8 +333,7 @@ impl TokenUtils {
     ///
     /// # Example
     /// ```rust
-    /// use crate::token::TokenUtils;
-    /// use crate::global::rpc::APTOS_MAINNET_URL;
+    /// use aptos_network_sdk::token::TokenUtils;
     ///
     /// let token_type = TokenUtils::build_standard_token_type(
     ///     "0x123",
@@ -289,7 +353,7 @@ impl TokenUtils {
     ///
     /// # Example
     /// ```rust
-    /// use crate::token::TokenUtils;
+    /// use aptos_network_sdk::token::TokenUtils;
     ///
     /// let token_type = "0x123::my_collection::MyToken";
     /// if let Some((creator, collection, name)) = TokenUtils::parse_token_type(token_type) {
@@ -315,7 +379,7 @@ impl TokenUtils {
     ///
     /// # Example
     /// ```rust
-    /// use crate::token::TokenUtils;
+    /// use aptos_network_sdk::token::TokenUtils;
     ///
     /// assert!(TokenUtils::is_valid_token_address("0x1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef"));
     /// assert!(!TokenUtils::is_valid_token_address("invalid_address"));
@@ -325,8 +389,11 @@ impl TokenUtils {
     }
 }
 
+/// token search across DEX pools
+#[cfg(feature = "dex")]
 pub struct TokenSearchManager;
 
+#[cfg(feature = "dex")]
 impl TokenSearchManager {
     /// get token by symbol
     ///
@@ -337,11 +404,10 @@ impl TokenSearchManager {
     /// # Example
     /// ```rust
     /// use std::sync::Arc;
-    /// use crate::{Aptos, token::TokenSearchManager};
-    /// use crate::global::rpc::APTOS_MAINNET_URL;
+    /// use aptos_network_sdk::{Aptos, AptosType, token::TokenSearchManager};
     ///
     /// async fn example() -> Result<(), String> {
-    /// let client = Arc::new(Aptos::new(APTOS_MAINNET_URL));
+    /// let client = Arc::new(Aptos::new(AptosType::Mainnet));
     ///
     /// let results = TokenSearchManager::get_token_by_symbol(client, "USDC").await?;
     /// for token in results {
@@ -349,26 +415,37 @@ impl TokenSearchManager {
     /// }
     /// Ok(())
     /// }
+    /// ```
     pub async fn get_token_by_symbol(
         client: Arc<Aptos>,
         symbol: &str,
     ) -> Result<Vec<TokenSearchResult>, String> {
         let mut results = Vec::new();
         let search_symbol = symbol.to_uppercase();
-        let protocol_addresses = vec![
-            // Thala
-            THALA_PROTOCOL_ADDRESS,
-            // Liquidswap
-            LIQUIDSWAP_PROTOCOL_ADDRESS,
-            // PancakeSwap
-            PANCAKESWAP_FACTORY_PROTOCOL_ADDRESS,
-            USDC,          // USDC
-            USDT,          // USDT
-            WORMHOLE_USDC, // Wormhole USDC
-        ];
-        for address in protocol_addresses {
+        let network = client.network_config();
+        let protocol_addresses: Vec<String> = [
+            &network.thala,
+            &network.liquidswap,
+            &network.pancakeswap,
+            &network.usdc,
+            &network.usdt,
+            &network.wormhole_usdc,
+        ]
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect();
+        // only modules that can define coins are worth parsing
+        let search_module = symbol.to_lowercase();
+        let relevant_modules = [search_module.as_str(), "coin", "token", "asset"];
+        for address in &protocol_addresses {
             if let Ok(modules) = client.get_account_module_vec(address).await {
-                for module in modules {
+                for module in modules.into_iter().filter(|module| {
+                    module.name().is_some_and(|name| {
+                        let name = name.to_lowercase();
+                        relevant_modules.iter().any(|m| name.contains(m))
+                    })
+                }) {
                     if let Some(abi) = module.abi {
                         if let Some(abi_obj) = abi.as_object() {
                             if let Some(token_info) =
@@ -536,16 +613,21 @@ impl TokenSearchManager {
     ) -> Result<Vec<TokenSearchResult>, String> {
         let mut results = Vec::new();
         // Check the liquidity pools of major DEXs
-        let dex_addresses = vec![
-            LIQUIDSWAP_PROTOCOL_ADDRESS,
-            THALA_PROTOCOL_ADDRESS,
-            PANCAKESWAP_FACTORY_PROTOCOL_ADDRESS,
-            ANIMESWAP_PROTOCOL_ADDRESS,
-            AUXSWAP_PROTOCOL_ADDRESS,
-            CELLANASWAP_PROTOCOL_ADDRESS,
-        ];
+        let network = client.network_config();
+        let dex_addresses: Vec<String> = [
+            &network.liquidswap,
+            &network.thala,
+            &network.pancakeswap,
+            &network.animeswap,
+            &network.auxswap,
+            &network.cellana,
+        ]
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect();
         for dex_address in dex_addresses {
-            if let Ok(resources) = client.get_account_resource_vec(dex_address).await {
+            if let Ok(resources) = client.get_account_resource_vec(&dex_address).await {
                 for resource in resources {
                     if resource.r#type.contains("::liquidity_pool::")
                         || resource.r#type.contains("::Pool<")
@@ -637,11 +719,10 @@ impl TokenSearchManager {
     /// # Example
     /// ```rust
     /// use std::sync::Arc;
-    /// use crate::{Aptos, token::TokenSearchManager};
-    /// use crate::global::rpc::APTOS_MAINNET_URL;
+    /// use aptos_network_sdk::{Aptos, AptosType, token::TokenSearchManager};
     ///
     /// async fn example() -> Result<(), String> {
-    /// let client = Arc::new(Aptos::new(APTOS_MAINNET_URL));
+    /// let client = Arc::new(Aptos::new(AptosType::Mainnet));
     ///
     /// let top_tokens = TokenSearchManager::get_top_token_vec(client).await?;
     /// for token in top_tokens {
@@ -653,10 +734,10 @@ impl TokenSearchManager {
     pub async fn get_top_token_vec(client: Arc<Aptos>) -> Result<Vec<TopToken>, String> {
         let mut top_tokens = Vec::new();
         let base_token = "0x1::aptos_coin::AptosCoin";
-        if let Ok(resources) = client
-            .get_account_resource_vec(LIQUIDSWAP_PROTOCOL_ADDRESS)
-            .await
-        {
+        let Some(liquidswap) = client.network_config().liquidswap.clone() else {
+            return Ok(top_tokens);
+        };
+        if let Ok(resources) = client.get_account_resource_vec(&liquidswap).await {
             for resource in resources {
                 if resource.r#type.contains("::liquidity_pool::LiquidityPool<")
                     && resource.r#type.contains(base_token)
@@ -748,11 +829,10 @@ impl TokenSearchManager {
     /// # Example
     /// ```rust
     /// use std::sync::Arc;
-    /// use crate::{Aptos, token::TokenSearchManager};
-    /// use crate::global::rpc::APTOS_MAINNET_URL;
+    /// use aptos_network_sdk::{Aptos, AptosType, token::TokenSearchManager};
     ///
     /// async fn example() -> Result<(), String> {
-    /// let client = Arc::new(Aptos::new(APTOS_MAINNET_URL));
+    /// let client = Arc::new(Aptos::new(AptosType::Mainnet));
     /// let token_address = "0x1::aptos_coin::AptosCoin";
     ///
     /// let pairs = TokenSearchManager::get_token_trading_pairs(client, token_address).await?;
@@ -792,6 +872,14 @@ pub struct TokenSearchResult {
     pub verified: bool,
 }
 
+/// transactions sent by `TokenManager::register_and_transfer`
+#[derive(Debug, Clone)]
+pub struct RegisterAndTransfer {
+    /// `None` if the sender was already registered
+    pub register: Option<ContractWriteResult>,
+    pub transfer: ContractWriteResult,
+}
+
 /// top token
 #[derive(Debug, Clone)]
 pub struct TopToken {
@@ -821,3 +909,109 @@ pub struct TradePair {
     pub dexes: Vec<String>,
     pub total_liquidity: u64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AptosType, transport::MockTransport};
+
+    #[tokio::test]
+    async fn test_ensure_registered_skips_registered_wallet() {
+        let wallet = Arc::new(Wallet::new().unwrap());
+        let transport = Arc::new(MockTransport::new().on_get(
+            "/resource/0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>",
+            200,
+            r#"{"type":"0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>","data":{"coin":{"value":"1"},"frozen":false}}"#,
+        ));
+        let client = Arc::new(Aptos::with_transport(
+            AptosType::Mainnet,
+            Arc::clone(&transport) as _,
+        ));
+        let register =
+            TokenManager::ensure_registered(client, wallet, "0x1::aptos_coin::AptosCoin")
+                .await
+                .unwrap();
+        assert!(register.is_none());
+        assert!(transport.requests().iter().all(|r| r.method == "GET"));
+
+        let call = TokenManager::build_register_call("0x1::aptos_coin::AptosCoin");
+        assert_eq!(
+            (call.module_name.as_str(), call.function_name.as_str()),
+            ("coin", "register")
+        );
+        assert_eq!(call.type_arguments, vec!["0x1::aptos_coin::AptosCoin"]);
+    }
+
+    #[tokio::test]
+    async fn test_register_and_transfer_to_unregistered_recipient() {
+        const COIN: &str = "0xcafe::coin::Cafe";
+        let wallet = Arc::new(Wallet::new().unwrap());
+        let recipient = format!("0x{}", "b".repeat(64));
+        let aptos_account = json!({
+            "bytecode": "0x",
+            "abi": {
+                "name": "aptos_account",
+                "exposed_functions": [
+                    {"name": "transfer_coins", "params": ["&signer", "address", "u64"]}
+                ]
+            }
+        });
+        let transport = Arc::new(
+            MockTransport::new()
+                .on_get(
+                    &format!(
+                        "/accounts/{}/resource/0x1::coin::CoinStore<{}>",
+                        wallet.address(),
+                        COIN
+                    ),
+                    200,
+                    &json!({"type": format!("0x1::coin::CoinStore<{}>", COIN), "data": {"coin": {"value": "500"}, "frozen": false}})
+                        .to_string(),
+                )
+                .on_get(
+                    &format!("/accounts/{}/resource/", recipient),
+                    404,
+                    r#"{"message":"Resource not found","error_code":"resource_not_found"}"#,
+                )
+                .on_get(
+                    "/accounts/",
+                    200,
+                    r#"{"sequence_number":"3","authentication_key":"0x01"}"#,
+                )
+                .on_get("/module/aptos_account", 200, &aptos_account.to_string())
+                .on_get(
+                    "/v1/",
+                    200,
+                    r#"{"chain_id":1,"epoch":"1","ledger_version":"1","ledger_timestamp":"1","node_role":"full_node","block_height":"1"}"#,
+                )
+                .on_get(
+                    "/estimate_gas_price",
+                    200,
+                    r#"{"deprioritized_gas_estimate":100,"gas_estimate":100,"prioritized_gas_estimate":150}"#,
+                )
+                .on_post(
+                    "/transactions/simulate",
+                    200,
+                    r#"[{"version":"0","hash":"0x0","gas_used":"90","success":true,"vm_status":"Executed successfully","type":"user_transaction","sender":"0x1","sequence_number":"3","max_gas_amount":"180","gas_unit_price":"100","expiration_timestamp_secs":"1","payload":{"type":"entry_function_payload","function":"0x1::m::f","type_arguments":[],"arguments":[]},"signature":{"type":"ed25519_signature","public_key":"0x01","signature":"0x02"}}]"#,
+                ),
+        );
+        let client = Arc::new(Aptos::with_transport(
+            AptosType::Mainnet,
+            Arc::clone(&transport) as _,
+        ));
+        let result = TokenManager::register_and_transfer(client, wallet, COIN, &recipient, 250)
+            .await
+            .unwrap();
+        // the sender holds a store, only the transfer is sent
+        assert!(result.register.is_none());
+        let submitted = transport
+            .requests()
+            .into_iter()
+            .find(|r| r.method == "POST" && r.url.ends_with("/transactions"))
+            .unwrap();
+        let payload = &submitted.body.unwrap()["transaction"]["payload"];
+        assert_eq!(payload["function"], "0x1::aptos_account::transfer_coins");
+        assert_eq!(payload["type_arguments"], json!([COIN]));
+        assert_eq!(payload["arguments"], json!([recipient, "250"]));
+    }
+}
@@ -1,19 +1,34 @@
 use crate::{
     Aptos,
-    types::{ContractCall, EntryFunctionPayload},
-    wallet::Wallet,
+    fungible_asset::FungibleAsset,
+    signing,
+    types::{CoinStore, ContractCall, EntryFunctionPayload, StoreStatus},
+    wallet::{MultiEd25519Signature, MultiEd25519Wallet, SingleKeyWallet, Wallet},
 };
-use aptos_network_tool::{address::address_to_bytes, signature::serialize_transaction_and_sign};
 use futures::future::join_all;
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::HashMap,
+    future::Future,
     sync::Arc,
     time::{SystemTime, UNIX_EPOCH},
 };
 use tokio::sync::Semaphore;
 
+/// max gas amount sent with fee simulations, the node replaces it with its estimate
+pub const SIMULATION_MAX_GAS_AMOUNT: u64 = 200_000;
+
+/// max gas amount of replacement transactions
+pub const REPLACEMENT_MAX_GAS_AMOUNT: u64 = 2000;
+/// seconds until a replacement transaction expires
+pub const REPLACEMENT_EXPIRATION_SECS: u64 = 60;
+
+/// max gas amount of sponsored calls, paid by the sponsor
+pub const SPONSORED_MAX_GAS_AMOUNT: u64 = 20_000;
+/// seconds until a sponsored call expires
+pub const SPONSORED_EXPIRATION_SECS: u64 = 60;
+
 pub struct Trade;
 
 impl Trade {
@@ -30,17 +45,12 @@ impl Trade {
     ) -> Result<Value, String> {
         let sequence_number = match sequence_number {
             Some(seq) => seq,
-            None => {
-                let account_info = client
-                    .get_account_info(&sender.address().unwrap())
-                    .await
-                    .unwrap();
-                account_info.sequence_number.parse().unwrap()
-            }
+            None => client.get_account_sequence_number(sender.address()).await?,
         };
+        let chain_id = client.chain_id().await?;
         let current_timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
-            .unwrap()
+            .map_err(|e| e.to_string())?
             .as_secs();
         let expiration_timestamp = current_timestamp + expiration_secs;
         // build transaction payload
@@ -57,7 +67,8 @@ impl Trade {
             "max_gas_amount": max_gas_amount.to_string(),
             "gas_unit_price": gas_unit_price.to_string(),
             "expiration_timestamp_secs": expiration_timestamp.to_string(),
-            "payload": payload
+            "payload": payload,
+            "chain_id": chain_id
         });
         Ok(raw_txn)
     }
@@ -65,7 +76,7 @@ impl Trade {
     /// build token transfer
     pub async fn create_token_transfer_tx(
         client: Arc<Aptos>,
-        sender: Wallet,
+        sender: Arc<Wallet>,
         recipient: &str,
         token_type: &str,
         amount: u64,
@@ -74,18 +85,14 @@ impl Trade {
         max_gas_amount: u64,
         gas_unit_price: u64,
     ) -> Result<Value, String> {
-        let chain_id = client.get_chain_info().await.unwrap().chain_id;
+        let chain_id = client.get_chain_info().await?.chain_id;
         let sequence_number = match sequence_number {
             Some(seq) => seq,
-            None => {
-                client
-                    .get_account_sequence_number(&sender.address()?)
-                    .await?
-            }
+            None => client.get_account_sequence_number(sender.address()).await?,
         };
         let current_timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
-            .unwrap()
+            .map_err(|e| e.to_string())?
             .as_secs();
         let expiration_timestamp = current_timestamp + expiration_secs;
         // build transaction payload
@@ -97,7 +104,7 @@ impl Trade {
         });
         // build raw transaction
         let raw_txn = json!({
-            "sender": sender.address().unwrap(),
+            "sender": sender.address(),
             "sequence_number": sequence_number.to_string(),
             "max_gas_amount": max_gas_amount.to_string(),
             "gas_unit_price": gas_unit_price.to_string(),
@@ -108,6 +115,68 @@ impl Trade {
         Ok(raw_txn)
     }
 
+    /// Build a transfer that cannot abort on an unregistered recipient.
+    ///
+    /// A coin is sent with `0x1::coin::transfer` when the recipient already has the
+    /// coin store, otherwise with `0x1::aptos_account::transfer_coins`, which registers
+    /// it on the fly. A fungible asset metadata address is sent with
+    /// `0x1::primary_fungible_store::transfer`, which creates the recipient's primary
+    /// store if needed.
+    pub async fn create_safe_transfer_tx(
+        client: Arc<Aptos>,
+        sender: Arc<Wallet>,
+        params: &SafeTransferParams,
+    ) -> Result<Value, String> {
+        let chain_id = client.get_chain_info().await?.chain_id;
+        let sender_address = sender.address();
+        let sequence_number = match params.sequence_number {
+            Some(seq) => seq,
+            None => client.get_account_sequence_number(sender_address).await?,
+        };
+        let recipient = params.recipient.as_str();
+        let token_type = params.token_type.as_str();
+        let amount = params.amount.to_string();
+        // build transaction payload
+        let payload = if !token_type.contains("::") {
+            json!({
+                "type": "entry_function_payload",
+                "function": "0x1::primary_fungible_store::transfer",
+                "type_arguments": ["0x1::fungible_asset::Metadata"],
+                "arguments": [token_type, recipient, amount]
+            })
+        } else {
+            let function = match client.get_store_status(recipient, token_type).await? {
+                StoreStatus::Registered => "0x1::coin::transfer",
+                StoreStatus::Unregistered => "0x1::aptos_account::transfer_coins",
+                StoreStatus::Frozen => {
+                    return Err(format!("{} store of {} is frozen", token_type, recipient));
+                }
+            };
+            json!({
+                "type": "entry_function_payload",
+                "function": function,
+                "type_arguments": [token_type],
+                "arguments": [recipient, amount]
+            })
+        };
+        let expiration_timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| e.to_string())?
+            .as_secs()
+            + params.expiration_secs;
+        // build raw transaction
+        let raw_txn = json!({
+            "sender": sender_address,
+            "sequence_number": sequence_number.to_string(),
+            "max_gas_amount": params.max_gas_amount.to_string(),
+            "gas_unit_price": params.gas_unit_price.to_string(),
+            "expiration_timestamp_secs": expiration_timestamp.to_string(),
+            "payload": payload,
+            "chain_id": chain_id
+        });
+        Ok(raw_txn)
+    }
+
     /// create sign and submit transfer tx
     pub async fn create_sign_submit_transfer_tx(
         client: Arc<Aptos>,
@@ -130,35 +199,255 @@ impl Trade {
             max_gas_amount,
             gas_unit_price,
         )
+        .await?;
+        let signed_txn = Trade::sign_transaction(&client, wallet, raw_txn).await?;
+        let result = client
+            .submit_transaction(&signed_txn)
+            .await
+            .map_err(|e| format!("submit transaction error: {:?}", e))?;
+        Ok(result.hash)
+    }
+
+    /// Sign `raw_txn` with `wallet` over its BCS signing message, ready for
+    /// `Aptos::submit_transaction`.
+    pub async fn sign_transaction(
+        client: &Aptos,
+        wallet: Arc<Wallet>,
+        raw_txn: Value,
+    ) -> Result<Value, String> {
+        let raw_txn_bcs = client.raw_transaction_bcs(&raw_txn).await?;
+        Self::sign_raw_transaction_bcs(wallet, raw_txn, &raw_txn_bcs)
+    }
+
+    /// Like `sign_transaction` for a raw transaction calling `call`, whose typed
+    /// arguments are signed as their own type.
+    pub async fn sign_contract_call(
+        client: &Aptos,
+        wallet: Arc<Wallet>,
+        raw_txn: Value,
+        call: &ContractCall,
+    ) -> Result<Value, String> {
+        let raw_txn_bcs = client.contract_call_transaction_bcs(&raw_txn, call).await?;
+        Self::sign_raw_transaction_bcs(wallet, raw_txn, &raw_txn_bcs)
+    }
+
+    fn sign_raw_transaction_bcs(
+        wallet: Arc<Wallet>,
+        raw_txn: Value,
+        raw_txn_bcs: &[u8],
+    ) -> Result<Value, String> {
+        let signature = wallet
+            .sign(&signing::signing_message(raw_txn_bcs))
+            .map_err(|e| format!("wallet sign error: {}", e))?;
+        Self::create_signed_transaction_tx(wallet, raw_txn, signature)
+    }
+
+    /// Set the node's max gas amount and gas unit price estimates on `raw_txn`,
+    /// from a simulation of it. Returns the simulation, which may have aborted.
+    pub async fn estimate_gas(
+        client: Arc<Aptos>,
+        wallet: Arc<Wallet>,
+        raw_txn: &mut Value,
+    ) -> Result<TransactionInfo, String> {
+        raw_txn["max_gas_amount"] = json!(SIMULATION_MAX_GAS_AMOUNT.to_string());
+        let simulated = Self::simulate(client, wallet, raw_txn.clone()).await?;
+        let max_gas_amount = Self::simulated_max_gas(&simulated)
+            .ok_or_else(|| "simulation returned no gas estimate".to_string())?;
+        raw_txn["max_gas_amount"] = json!(max_gas_amount.to_string());
+        if let Some(gas_unit_price) = simulated.get_gas_unit_price() {
+            raw_txn["gas_unit_price"] = json!(gas_unit_price.to_string());
+        }
+        Ok(simulated)
+    }
+
+    /// max gas amount the node estimated for a simulated transaction, its gas used
+    /// when it has no estimate
+    fn simulated_max_gas(simulated: &TransactionInfo) -> Option<u64> {
+        match &simulated.transaction_type {
+            TransactionType::UserTransaction(user_txn) => user_txn.max_gas_amount.clone(),
+            _ => None,
+        }
+        .or(simulated.max_gas_amount.clone())
+        .and_then(|max| max.parse().ok())
+        .or(simulated.get_gas_used())
+    }
+
+    /// Replace a pending transaction with `new_payload` at a higher gas price.
+    ///
+    /// Mempool keeps the highest gas price transaction for a sequence number, so
+    /// `new_gas_unit_price` must be above the stuck one's. The stuck sequence number
+    /// is the account's on-chain one, see `get_stuck_sequence_number`. Returns the
+    /// hash of the replacement.
+    pub async fn replace_transaction(
+        client: Arc<Aptos>,
+        wallet: Arc<Wallet>,
+        sequence_number: u64,
+        new_payload: Value,
+        new_gas_unit_price: u64,
+    ) -> Result<String, String> {
+        let chain_id = client.get_chain_info().await?.chain_id;
+        let expiration_timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| e.to_string())?
+            .as_secs()
+            + REPLACEMENT_EXPIRATION_SECS;
+        let raw_txn = json!({
+            "sender": wallet.address(),
+            "sequence_number": sequence_number.to_string(),
+            "max_gas_amount": REPLACEMENT_MAX_GAS_AMOUNT.to_string(),
+            "gas_unit_price": new_gas_unit_price.to_string(),
+            "expiration_timestamp_secs": expiration_timestamp.to_string(),
+            "payload": new_payload,
+            "chain_id": chain_id
+        });
+        let signed_txn = Trade::sign_transaction(&client, wallet, raw_txn).await?;
+        let result = client
+            .submit_transaction(&signed_txn)
+            .await
+            .map_err(|e| format!("submit transaction error: {:?}", e))?;
+        Ok(result.hash)
+    }
+
+    /// Cancel a pending transaction by replacing it with a 0 APT self transfer.
+    ///
+    /// Uses the node's prioritized gas estimate, at least one above its regular one.
+    pub async fn cancel_transaction(
+        client: Arc<Aptos>,
+        wallet: Arc<Wallet>,
+        sequence_number: u64,
+    ) -> Result<String, String> {
+        let estimation = client.get_gas_estimation().await?;
+        let gas_unit_price = estimation
+            .prioritized_gas_estimate
+            .unwrap_or(0)
+            .max(estimation.gas_estimate + 1);
+        let payload = json!({
+            "type": "entry_function_payload",
+            "function": "0x1::aptos_account::transfer",
+            "type_arguments": [],
+            "arguments": [wallet.address(), "0"]
+        });
+        Self::replace_transaction(client, wallet, sequence_number, payload, gas_unit_price).await
+    }
+
+    /// Sequence number of the account's oldest not yet executed transaction.
+    ///
+    /// A transaction submitted with this sequence number that is not on chain is stuck;
+    /// any submitted after it wait behind it.
+    pub async fn get_stuck_sequence_number(
+        client: Arc<Aptos>,
+        address: &str,
+    ) -> Result<u64, String> {
+        Ok(client.get_account_sequence_number(address).await?)
+    }
+
+    /// Simulate a raw transaction from `wallet` with a zeroed signature.
+    pub async fn simulate(
+        client: Arc<Aptos>,
+        wallet: Arc<Wallet>,
+        raw_txn: Value,
+    ) -> Result<TransactionInfo, String> {
+        let signed_txn = Self::create_signed_transaction_tx(wallet, raw_txn, vec![0u8; 64])?;
+        client.simulate_transaction(&signed_txn).await
+    }
+
+    /// Estimate the fee of an APT transfer by simulating it.
+    pub async fn estimate_transfer_fee(
+        client: Arc<Aptos>,
+        wallet: Arc<Wallet>,
+        recipient: &str,
+        amount: u64,
+    ) -> Result<FeeEstimate, String> {
+        Self::estimate_token_transfer_fee(
+            client,
+            wallet,
+            recipient,
+            "0x1::aptos_coin::AptosCoin",
+            amount,
+        )
         .await
-        .unwrap();
-        // serialize transaction and sign
-        let message_to_sign = serialize_transaction_and_sign(&raw_txn)?;
-        // wallet sign
-        match wallet.sign(&message_to_sign) {
-            Ok(signature_bytes) => {
-                // create signed transaction tx
-                match Trade::create_signed_transaction_tx(
-                    Arc::clone(&wallet),
-                    raw_txn,
-                    signature_bytes,
-                ) {
-                    Ok(signed_txn) => {
-                        // submit transaction
-                        match client.submit_transaction(&signed_txn).await {
-                            Ok(result) => {
-                                return Ok(result.hash);
-                            }
-                            Err(e) => return Err(format!("submit transaction error: {:?}", e)),
-                        }
-                    }
-                    Err(e) => return Err(format!("build signed transaction error: {:?}", e)),
-                }
-            }
-            Err(e) => {
-                return Err(format!("wallet sign error:{:?}", e).to_string());
-            }
+    }
+
+    /// Estimate the fee of a transfer by simulating it.
+    ///
+    /// `token_type` is a coin type, sent with `0x1::aptos_account::transfer_coins`, or a
+    /// fungible asset metadata address, sent with `0x1::primary_fungible_store::transfer`.
+    pub async fn estimate_token_transfer_fee(
+        client: Arc<Aptos>,
+        wallet: Arc<Wallet>,
+        recipient: &str,
+        token_type: &str,
+        amount: u64,
+    ) -> Result<FeeEstimate, String> {
+        let payload = if token_type.contains("::") {
+            json!({
+                "type": "entry_function_payload",
+                "function": "0x1::aptos_account::transfer_coins",
+                "type_arguments": [token_type],
+                "arguments": [recipient, amount.to_string()]
+            })
+        } else {
+            json!({
+                "type": "entry_function_payload",
+                "function": "0x1::primary_fungible_store::transfer",
+                "type_arguments": ["0x1::fungible_asset::Metadata"],
+                "arguments": [token_type, recipient, amount.to_string()]
+            })
+        };
+        let raw_txn =
+            Self::create_entry_function_tx(Arc::clone(&client), Arc::clone(&wallet), payload, 60)
+                .await?;
+        let gas_unit_price: u64 = raw_txn["gas_unit_price"]
+            .as_str()
+            .and_then(|price| price.parse().ok())
+            .unwrap_or(0);
+        let simulated = Self::simulate(client, wallet, raw_txn).await?;
+        if !simulated.success {
+            return Err(format!(
+                "transfer simulation failed: {}",
+                simulated.vm_status
+            ));
         }
+        let gas_used = simulated
+            .get_gas_used()
+            .ok_or_else(|| "simulation returned no gas used".to_string())?;
+        let gas_unit_price = simulated.get_gas_unit_price().unwrap_or(gas_unit_price);
+        let max_gas_units = Self::simulated_max_gas(&simulated).unwrap_or(gas_used);
+        let est_fee_octas = gas_used.saturating_mul(gas_unit_price);
+        Ok(FeeEstimate {
+            gas_unit_price,
+            max_gas_units,
+            est_fee_octas,
+            est_fee_apt: est_fee_octas as f64 / 100_000_000.0,
+        })
+    }
+
+    /// Raw transaction calling `payload` from `sender` at its next sequence number and
+    /// the node's gas unit price estimate. Its max gas amount is a simulation
+    /// allowance, `estimate_gas` replaces it before signing.
+    pub async fn create_entry_function_tx(
+        client: Arc<Aptos>,
+        sender: Arc<Wallet>,
+        payload: Value,
+        expiration_secs: u64,
+    ) -> Result<Value, String> {
+        let chain_id = client.get_chain_info().await?.chain_id;
+        let sequence_number = client.get_account_sequence_number(sender.address()).await?;
+        let gas_unit_price = client.get_gas_estimation().await?.gas_estimate;
+        let expiration_timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| e.to_string())?
+            .as_secs()
+            + expiration_secs;
+        Ok(json!({
+            "sender": sender.address(),
+            "sequence_number": sequence_number.to_string(),
+            "max_gas_amount": SIMULATION_MAX_GAS_AMOUNT.to_string(),
+            "gas_unit_price": gas_unit_price.to_string(),
+            "expiration_timestamp_secs": expiration_timestamp.to_string(),
+            "payload": payload,
+            "chain_id": chain_id
+        }))
     }
 
     /// build call contract tx
@@ -173,22 +462,19 @@ impl Trade {
     ) -> Result<Value, String> {
         let sequence_number = match sequence_number {
             Some(seq) => seq,
-            None => client
-                .get_account_sequence_number(&sender.address().unwrap())
-                .await
-                .unwrap(),
+            None => client.get_account_sequence_number(sender.address()).await?,
         };
-        let chain_id = client.get_chain_info().await.unwrap().chain_id;
+        let chain_id = client.get_chain_info().await?.chain_id;
         // current timestamp
-        let current_timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
+        let current_timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| e.to_string())?
             .as_secs();
         // expiration time
         let expiration_timestamp = current_timestamp + expiration_secs;
         // build raw transaction
         let raw_txn = json!({
-            "sender": sender.address()?,
+            "sender": sender.address(),
             "sequence_number": sequence_number.to_string(),
             "max_gas_amount": max_gas_amount.to_string(),
             "gas_unit_price": gas_unit_price.to_string(),
@@ -220,12 +506,14 @@ impl Trade {
             .iter()
             .for_each(|s| type_args.push(s.as_bytes().to_vec()));
         let mut args: Vec<Vec<u8>> = Vec::new();
-        arguments
-            .iter()
-            .for_each(|s| args.push(s.as_str().unwrap().to_string().as_bytes().to_vec()));
+        arguments.iter().for_each(|s| match s.as_str() {
+            Some(v) => args.push(v.as_bytes().to_vec()),
+            // vectors and other non-string arguments
+            None => args.push(s.to_string().into_bytes()),
+        });
         let payload = EntryFunctionPayload {
-            module_address: address_to_bytes(module_address).unwrap().to_vec(),
-            module_name: address_to_bytes(module_name).unwrap().to_vec(),
+            module_address: signing::address_bytes(module_address)?.to_vec(),
+            module_name: module_name.as_bytes().to_vec(),
             function_name: function_vec,
             type_arguments: type_args,
             arguments: args,
@@ -242,6 +530,27 @@ impl Trade {
         .await
     }
 
+    /// Message to sign for `raw_txn`: the `APTOS::RawTransaction` prefix hash and the
+    /// BCS of the raw transaction.
+    ///
+    /// With `Wallet::sign_raw` and `attach_signature` this splits
+    /// `create_sign_submit_transfer_tx` for offline signers: build and serialize
+    /// online, sign on the offline machine, attach and submit online.
+    pub async fn serialize_for_signing(client: &Aptos, raw_txn: &Value) -> Result<Vec<u8>, String> {
+        Ok(signing::signing_message(
+            &client.raw_transaction_bcs(raw_txn).await?,
+        ))
+    }
+
+    /// signed transaction from `raw_txn` and a signature made offline, for
+    /// `Aptos::submit_transaction`
+    pub fn attach_signature(raw_txn: Value, signature: Signature) -> Value {
+        json!({
+            "transaction": raw_txn,
+            "signature": signature
+        })
+    }
+
     ///  build signed transaction
     pub fn create_signed_transaction_tx(
         wallet: Arc<Wallet>,
@@ -261,6 +570,185 @@ impl Trade {
         }))
     }
 
+    /// BCS of a signed transaction from an ed25519 account, for
+    /// `Aptos::submit_bcs_transaction`: the raw transaction, from
+    /// `Aptos::raw_transaction_bcs`, followed by the ed25519 authenticator.
+    pub fn create_signed_transaction_bcs(
+        wallet: Arc<Wallet>,
+        raw_txn_bcs: &[u8],
+        signature: Vec<u8>,
+    ) -> Result<Vec<u8>, String> {
+        if signature.len() != 64 {
+            return Err(format!(
+                "invalid ed25519 signature length: {}",
+                signature.len()
+            ));
+        }
+        let mut bytes = raw_txn_bcs.to_vec();
+        // variant index of TransactionAuthenticator::Ed25519
+        bytes.push(0);
+        let authenticator = (wallet.public_key_bytes()?, signature);
+        bytes.extend(bcs::to_bytes(&authenticator).map_err(|e| format!("bcs error: {}", e))?);
+        Ok(bytes)
+    }
+
+    /// build signed transaction for a multi-ed25519 account
+    pub fn create_multi_ed25519_signed_transaction_tx(
+        wallet: Arc<MultiEd25519Wallet>,
+        raw_txn: Value,
+        signature: MultiEd25519Signature,
+    ) -> Result<Value, String> {
+        if signature.signatures.len() < wallet.threshold() as usize {
+            return Err(format!(
+                "not enough signatures: {} of {}",
+                signature.signatures.len(),
+                wallet.threshold()
+            ));
+        }
+        Ok(json!({
+            "transaction": raw_txn,
+            "signature": {
+                "type": "multi_ed25519_signature",
+                "public_keys": wallet.public_keys_hex(),
+                "signatures": signature.signatures.iter().map(hex::encode).collect::<Vec<_>>(),
+                "threshold": wallet.threshold(),
+                "bitmap": hex::encode(signature.bitmap)
+            }
+        }))
+    }
+
+    /// BCS of a signed transaction from a secp256k1 single key account, for
+    /// `Aptos::submit_bcs_transaction`: the raw transaction, from
+    /// `Aptos::raw_transaction_bcs`, followed by the single sender authenticator with
+    /// the `AnyPublicKey` and `AnySignature`. `signature` comes from `SingleKeyWallet::sign`.
+    pub fn create_single_key_signed_transaction_bcs(
+        wallet: Arc<SingleKeyWallet>,
+        raw_txn_bcs: &[u8],
+        signature: Vec<u8>,
+    ) -> Result<Vec<u8>, String> {
+        if signature.len() != 64 {
+            return Err(format!(
+                "invalid secp256k1 signature length: {}",
+                signature.len()
+            ));
+        }
+        let mut bytes = raw_txn_bcs.to_vec();
+        // variant indexes of TransactionAuthenticator::SingleSender and
+        // AccountAuthenticator::SingleKey
+        bytes.extend([4, 2]);
+        bytes.extend(wallet.any_public_key_bytes());
+        bytes.extend(SingleKeyWallet::any_signature_bytes(&signature));
+        Ok(bytes)
+    }
+
+    /// Message the sender and the fee payer of a fee payer transaction sign, see
+    /// `signing::fee_payer_signing_message`. `raw_txn_bcs` comes from
+    /// `Aptos::raw_transaction_bcs`.
+    pub fn fee_payer_signing_message(
+        raw_txn_bcs: &[u8],
+        fee_payer_address: &str,
+    ) -> Result<Vec<u8>, String> {
+        signing::fee_payer_signing_message(raw_txn_bcs, fee_payer_address)
+    }
+
+    /// build signed fee payer transaction, both signatures are over
+    /// `fee_payer_signing_message`
+    pub fn create_fee_payer_signed_transaction_tx(
+        raw_txn: Value,
+        sender: Signature,
+        fee_payer_address: &str,
+        fee_payer: Signature,
+    ) -> Result<Value, String> {
+        Ok(json!({
+            "transaction": raw_txn,
+            "signature": {
+                "type": "fee_payer_signature",
+                "sender": sender,
+                "secondary_signer_addresses": [],
+                "secondary_signers": [],
+                "fee_payer_address": fee_payer_address,
+                "fee_payer_signer": fee_payer
+            }
+        }))
+    }
+
+    /// Call an entry function from `user` with `sponsor` paying the gas as fee payer.
+    ///
+    /// Returns the hash of the submitted transaction.
+    pub async fn sponsored_call(
+        client: Arc<Aptos>,
+        user: Arc<Wallet>,
+        sponsor: Arc<Wallet>,
+        contract_call: &ContractCall,
+    ) -> Result<String, String> {
+        let sponsor_address = sponsor.address().to_string();
+        Self::sponsored_call_with(
+            client,
+            user,
+            &sponsor_address,
+            contract_call,
+            |message| async move {
+                Ok(Signature::Ed25519 {
+                    public_key: sponsor.public_key_hex()?,
+                    signature: hex::encode(sponsor.sign(&message)?),
+                })
+            },
+        )
+        .await
+    }
+
+    /// Like `sponsored_call`, the fee payer signature comes from `sign_as_sponsor`
+    /// given the signing message, so the sponsor key can live in another service.
+    pub async fn sponsored_call_with<F, Fut>(
+        client: Arc<Aptos>,
+        user: Arc<Wallet>,
+        sponsor_address: &str,
+        contract_call: &ContractCall,
+        sign_as_sponsor: F,
+    ) -> Result<String, String>
+    where
+        F: FnOnce(Vec<u8>) -> Fut,
+        Fut: Future<Output = Result<Signature, String>>,
+    {
+        let sequence_number = client.get_account_sequence_number(user.address()).await?;
+        let chain_id = client.chain_id().await?;
+        let gas_unit_price = client.get_gas_estimation().await?.gas_estimate;
+        let expiration_timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| e.to_string())?
+            .as_secs()
+            + SPONSORED_EXPIRATION_SECS;
+        let raw_txn = json!({
+            "sender": user.address(),
+            "sequence_number": sequence_number.to_string(),
+            "max_gas_amount": SPONSORED_MAX_GAS_AMOUNT.to_string(),
+            "gas_unit_price": gas_unit_price.to_string(),
+            "expiration_timestamp_secs": expiration_timestamp.to_string(),
+            "payload": contract_call.entry_function_payload(),
+            "chain_id": chain_id
+        });
+        let raw_txn_bcs = client
+            .contract_call_transaction_bcs(&raw_txn, contract_call)
+            .await?;
+        let message = Self::fee_payer_signing_message(&raw_txn_bcs, sponsor_address)?;
+        let sender = Signature::Ed25519 {
+            public_key: user.public_key_hex()?,
+            signature: hex::encode(user.sign(&message)?),
+        };
+        let fee_payer = sign_as_sponsor(message).await?;
+        let signed_txn = Self::create_fee_payer_signed_transaction_tx(
+            raw_txn,
+            sender,
+            sponsor_address,
+            fee_payer,
+        )?;
+        let result = client
+            .submit_transaction(&signed_txn)
+            .await
+            .map_err(|e| format!("submit transaction error: {:?}", e))?;
+        Ok(result.hash)
+    }
+
     /// Retrieves transaction history for a specified address with pagination support
     ///
     /// # Params
@@ -277,7 +765,9 @@ impl Trade {
     /// # Examples
     /// ```rust
     /// use std::sync::Arc;
+    /// use aptos_network_sdk::{Aptos, AptosType, trade::{Trade, TransactionQuery}};
     ///
+    /// # async fn example() {
     /// let client = Arc::new(Aptos::new(AptosType::Mainnet));
     /// let query = TransactionQuery {
     ///     start: Some(0),
@@ -288,6 +778,7 @@ impl Trade {
     ///     Ok(transactions) => println!("Search {} transactions", transactions.len()),
     ///     Err(e) => println!("Error: {}", e),
     /// }
+    /// # }
     /// ```
     pub async fn get_address_transactions(
         client: Arc<Aptos>,
@@ -315,7 +806,9 @@ impl Trade {
     /// # Examples
     /// ```
     /// use std::sync::Arc;
+    /// use aptos_network_sdk::{Aptos, AptosType, trade::Trade};
     ///
+    /// # async fn example() {
     /// let client = Arc::new(Aptos::new(AptosType::Mainnet));
     ///
     /// // Find all transactions where 0x1234... and 0x5678... interacted
@@ -331,6 +824,7 @@ impl Trade {
     ///     },
     ///     Err(e) => println!("Error: {}", e),
     /// }
+    /// # }
     /// ```
     ///
     pub async fn get_transactions_involving_both_addresses(
@@ -369,7 +863,9 @@ impl Trade {
     /// # Examples
     /// ```
     /// use std::sync::Arc;
+    /// use aptos_network_sdk::{Aptos, AptosType, trade::Trade};
     ///
+    /// # async fn example() {
     /// let client = Arc::new(Aptos::new(AptosType::Mainnet));
     ///
     /// // Find all payments from Alice (0x5678...) to Bob (0x1234...)
@@ -390,6 +886,7 @@ impl Trade {
     ///     },
     ///     Err(e) => println!("Error: {}", e),
     /// }
+    /// # }
     /// ```
     ///
     pub async fn get_transactions_by_recipient_sender(
@@ -409,6 +906,101 @@ impl Trade {
         Ok(filtered_transactions)
     }
 
+    /// Latest APT payments received by `address`, newest first.
+    pub async fn get_incoming_transfers(
+        client: Arc<Aptos>,
+        address: &str,
+        limit: u64,
+    ) -> Result<Vec<TransferInfo>, String> {
+        Self::get_incoming_coin_transfers(client, address, "0x1::aptos_coin::AptosCoin", limit)
+            .await
+    }
+
+    /// Latest `token_type` payments received by `address`, newest first.
+    ///
+    /// Walks the `deposit_events` of the account's CoinStore back from the newest, `limit`
+    /// at a time, and looks up the sender of each deposit. Deposits made by `address`
+    /// itself (e.g. swap outputs) are skipped before counting towards `limit`, at most
+    /// `DEFAULT_MAX_EVENTS` deposits are scanned.
+    /// Deposits into fungible asset stores only emit module events, which the fullnode
+    /// cannot list per account, they need an indexer.
+    pub async fn get_incoming_coin_transfers(
+        client: Arc<Aptos>,
+        address: &str,
+        token_type: &str,
+        limit: u64,
+    ) -> Result<Vec<TransferInfo>, String> {
+        let coin_store_type = format!("0x1::coin::CoinStore<{}>", token_type);
+        let Some(coin_store) = client
+            .get_account_resource_typed::<CoinStore>(address, &coin_store_type)
+            .await?
+        else {
+            return Ok(Vec::new());
+        };
+        let counter = coin_store.deposit_events.counter;
+        if counter == 0 || limit == 0 {
+            return Ok(Vec::new());
+        }
+        let scan_floor = counter.saturating_sub(crate::DEFAULT_MAX_EVENTS.max(limit));
+        let mut transfers = Vec::new();
+        let mut end = counter;
+        while end > scan_floor && (transfers.len() as u64) < limit {
+            let start = end.saturating_sub(limit).max(scan_floor);
+            let events = client
+                .get_account_event_vec(
+                    address,
+                    &format!("{}/deposit_events", coin_store_type),
+                    Some(end - start),
+                    Some(start),
+                )
+                .await?;
+            if events.is_empty() {
+                break;
+            }
+            let lookups = events.iter().map(|event| {
+                let client = Arc::clone(&client);
+                let version = event.version.as_ref().and_then(|v| v.parse::<u64>().ok());
+                async move {
+                    match version {
+                        Some(version) => client
+                            .get_transaction_info_by_version(version)
+                            .await
+                            .map(Some),
+                        None => Ok(None),
+                    }
+                }
+            });
+            let transactions = join_all(lookups).await;
+            for (event, transaction) in events.iter().zip(transactions).rev() {
+                let Some(transaction) = transaction? else {
+                    continue;
+                };
+                let Some(sender) = transaction.get_sender() else {
+                    continue;
+                };
+                if same_address(sender, address) {
+                    continue;
+                }
+                let amount = event.data["amount"]
+                    .as_str()
+                    .and_then(|v| v.parse().ok())
+                    .or_else(|| event.data["amount"].as_u64())
+                    .unwrap_or(0);
+                transfers.push(TransferInfo {
+                    from: sender.to_string(),
+                    to: address.to_string(),
+                    amount,
+                    token_type: token_type.to_string(),
+                });
+                if transfers.len() as u64 == limit {
+                    break;
+                }
+            }
+            end = start;
+        }
+        Ok(transfers)
+    }
+
     /// Check if the transaction involves the specified address
     fn transaction_involves_address(transaction: &TransactionInfo, address: &str) -> bool {
         match &transaction.transaction_type {
@@ -510,8 +1102,73 @@ impl Trade {
         }
     }
 
-    /// Get events of a specific type in transaction events
-    pub fn get_events_by_type<'a>(
+    /// Type the arguments of an entry function payload with the function's abi, an
+    /// `exposed_functions` entry as returned by `Aptos::get_function_abi`.
+    ///
+    /// The abi does not name parameters, arguments are keyed `arg0`, `arg1`, ... by
+    /// position after the signers. Arguments that do not match their parameter type
+    /// are kept as `TypedValue::Raw`.
+    pub fn decode_payload(payload: &Payload, abi: &Value) -> Result<DecodedCall, String> {
+        let function_name = payload.function.rsplit("::").next().unwrap_or_default();
+        if abi["name"].as_str() != Some(function_name) {
+            return Err(format!("abi does not describe {}", payload.function));
+        }
+        let params: Vec<&str> = abi["params"]
+            .as_array()
+            .map(|params| params.iter().filter_map(|p| p.as_str()).collect())
+            .unwrap_or_default();
+        let params: Vec<&str> = params
+            .into_iter()
+            .skip_while(|p| *p == "signer" || *p == "&signer")
+            .collect();
+        if params.len() != payload.arguments.len() {
+            return Err(format!(
+                "{} expects {} args, got {}",
+                payload.function,
+                params.len(),
+                payload.arguments.len()
+            ));
+        }
+        Ok(DecodedCall {
+            function: payload.function.clone(),
+            type_arguments: payload.type_arguments.clone(),
+            param_types: params.iter().map(|p| p.to_string()).collect(),
+            arguments: params
+                .iter()
+                .zip(&payload.arguments)
+                .enumerate()
+                .map(|(index, (param, argument))| {
+                    (format!("arg{}", index), TypedValue::decode(param, argument))
+                })
+                .collect(),
+        })
+    }
+
+    /// Decode the entry function call of a user transaction, fetching the function's abi.
+    pub async fn decode_entry_function_payload(
+        client: Arc<Aptos>,
+        transaction: &TransactionInfo,
+    ) -> Result<DecodedCall, String> {
+        let user_txn = Self::get_user_transaction(transaction).ok_or("not a user transaction")?;
+        let payload = &user_txn.payload;
+        let mut parts = payload.function.splitn(3, "::");
+        let (Some(address), Some(module), Some(function)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(format!(
+                "not an entry function payload: {}",
+                payload.payload_type
+            ));
+        };
+        let abi = client
+            .get_function_abi(address, module, function)
+            .await?
+            .ok_or_else(|| format!("function not found: {}", payload.function))?;
+        Self::decode_payload(payload, &abi)
+    }
+
+    /// Events whose type contains `event_type`, e.g. `Swap` also matches `FlashSwapEvent`.
+    pub fn get_events_containing<'a>(
         transaction: &'a TransactionInfo,
         event_type: &str,
     ) -> Vec<&'a Event> {
@@ -522,6 +1179,34 @@ impl Trade {
             .collect()
     }
 
+    /// events of exactly `full_type`, e.g. `0x1::coin::DepositEvent`
+    pub fn get_events_exact<'a>(
+        transaction: &'a TransactionInfo,
+        full_type: &str,
+    ) -> Vec<&'a Event> {
+        transaction
+            .events
+            .iter()
+            .filter(|event| event.r#type == full_type)
+            .collect()
+    }
+
+    /// events of the handle `creation_number` of `account_address`
+    pub fn get_events_by_guid<'a>(
+        transaction: &'a TransactionInfo,
+        account_address: &str,
+        creation_number: u64,
+    ) -> Vec<&'a Event> {
+        transaction
+            .events
+            .iter()
+            .filter(|event| {
+                same_address(&event.guid.account_address, account_address)
+                    && event.guid.creation_number.parse::<u64>() == Ok(creation_number)
+            })
+            .collect()
+    }
+
     /// Analyze resource changes in transactions
     pub fn analyze_resource_changes(transaction: &TransactionInfo) -> ResourceChanges {
         let mut changes = ResourceChanges::default();
@@ -546,6 +1231,176 @@ impl Trade {
         }
         changes
     }
+
+    /// Resources written by a transaction as `(resource type, address, data)`.
+    pub fn resource_writes(transaction: &TransactionInfo) -> Vec<(String, String, Value)> {
+        transaction
+            .changes
+            .iter()
+            .filter(|change| change.change_type == "write_resource")
+            .filter_map(|change| {
+                let resource = change.data.as_ref()?;
+                Some((
+                    resource.get("type")?.as_str()?.to_string(),
+                    change.address.clone().unwrap_or_default(),
+                    resource.get("data").cloned().unwrap_or(Value::Null),
+                ))
+            })
+            .collect()
+    }
+
+    /// Coin balance changes of `address` in a transaction.
+    ///
+    /// `balance` is the CoinStore balance written by the transaction, `delta` sums
+    /// the store's deposit and withdraw events. For the sender's APT the gas fee
+    /// is also subtracted, it is charged without an event.
+    pub fn balance_changes(transaction: &TransactionInfo, address: &str) -> Vec<BalanceChange> {
+        let mut balance_changes = Vec::new();
+        for (resource_type, owner, data) in Self::resource_writes(transaction) {
+            if !same_address(&owner, address) {
+                continue;
+            }
+            let Some(coin_type) = resource_type
+                .strip_prefix("0x1::coin::CoinStore<")
+                .and_then(|t| t.strip_suffix('>'))
+            else {
+                continue;
+            };
+            let Ok(store) = serde_json::from_value::<CoinStore>(data) else {
+                continue;
+            };
+            let balance = store.coin.value;
+            let deposit_num = Some(store.deposit_events.guid.id.creation_num.as_str());
+            let withdraw_num = Some(store.withdraw_events.guid.id.creation_num.as_str());
+            let mut delta: i128 = 0;
+            for event in &transaction.events {
+                let amount = event.data["amount"]
+                    .as_str()
+                    .and_then(|v| v.parse::<i128>().ok())
+                    .unwrap_or(0);
+                let from_store = same_address(&event.guid.account_address, address);
+                let module_event_matches =
+                    same_address(event.data["account"].as_str().unwrap_or_default(), address)
+                        && event.data["coin_type"].as_str() == Some(coin_type);
+                if (from_store && Some(event.guid.creation_number.as_str()) == deposit_num)
+                    || (event.r#type == "0x1::coin::CoinDeposit" && module_event_matches)
+                {
+                    delta += amount;
+                } else if (from_store && Some(event.guid.creation_number.as_str()) == withdraw_num)
+                    || (event.r#type == "0x1::coin::CoinWithdraw" && module_event_matches)
+                {
+                    delta -= amount;
+                }
+            }
+            if coin_type == "0x1::aptos_coin::AptosCoin"
+                && transaction
+                    .get_sender()
+                    .is_some_and(|sender| same_address(sender, address))
+            {
+                let fee = transaction.get_gas_used().unwrap_or(0) as i128
+                    * transaction.get_gas_unit_price().unwrap_or(0) as i128;
+                delta -= fee;
+            }
+            balance_changes.push(BalanceChange {
+                coin_type: coin_type.to_string(),
+                balance,
+                delta,
+            });
+        }
+        balance_changes
+    }
+
+    /// Expected balance changes of `address` if a simulated transaction is submitted,
+    /// for a confirmation screen.
+    ///
+    /// Covers coin stores like `balance_changes` and primary fungible stores, whose
+    /// `coin_type` is the asset metadata address. Build `simulation` with `simulate`.
+    pub fn preview_balance_changes(
+        simulation: &TransactionInfo,
+        address: &str,
+    ) -> Vec<BalanceChange> {
+        let mut changes = Self::balance_changes(simulation, address);
+        let is_sender = simulation
+            .get_sender()
+            .is_some_and(|sender| same_address(sender, address));
+        for (resource_type, store, data) in Self::resource_writes(simulation) {
+            if resource_type != "0x1::fungible_asset::FungibleStore" {
+                continue;
+            }
+            let Some(metadata) = data["metadata"]["inner"].as_str() else {
+                continue;
+            };
+            let owned = FungibleAsset::primary_store_address(address, metadata)
+                .is_ok_and(|primary| same_address(&primary, &store));
+            if !owned {
+                continue;
+            }
+            let mut delta: i128 = 0;
+            for event in &simulation.events {
+                if !same_address(event.data["store"].as_str().unwrap_or_default(), &store) {
+                    continue;
+                }
+                let amount = event.data["amount"]
+                    .as_str()
+                    .and_then(|v| v.parse::<i128>().ok())
+                    .unwrap_or(0);
+                match event.r#type.as_str() {
+                    "0x1::fungible_asset::Deposit" => delta += amount,
+                    "0x1::fungible_asset::Withdraw" => delta -= amount,
+                    _ => {}
+                }
+            }
+            if is_sender && same_address(metadata, "0xa") {
+                delta -= simulation.get_gas_used().unwrap_or(0) as i128
+                    * simulation.get_gas_unit_price().unwrap_or(0) as i128;
+            }
+            changes.push(BalanceChange {
+                coin_type: metadata.to_string(),
+                balance: data["balance"]
+                    .as_str()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(0),
+                delta,
+            });
+        }
+        changes
+    }
+}
+
+/// compare addresses ignoring case and leading zeros
+fn same_address(a: &str, b: &str) -> bool {
+    let normalize = |address: &str| {
+        address
+            .trim_start_matches("0x")
+            .trim_start_matches('0')
+            .to_lowercase()
+    };
+    normalize(a) == normalize(b)
+}
+
+/// parameters of `Trade::create_safe_transfer_tx`
+#[derive(Debug, Clone)]
+pub struct SafeTransferParams {
+    pub recipient: String,
+    /// a coin type or a fungible asset metadata address
+    pub token_type: String,
+    pub amount: u64,
+    /// `None` for the sender's next sequence number
+    pub sequence_number: Option<u64>,
+    pub expiration_secs: u64,
+    pub max_gas_amount: u64,
+    pub gas_unit_price: u64,
+}
+
+/// simulated fee of a transaction
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeeEstimate {
+    pub gas_unit_price: u64,
+    /// the node's max gas amount estimate
+    pub max_gas_units: u64,
+    /// gas used times gas unit price
+    pub est_fee_octas: u64,
+    pub est_fee_apt: f64,
 }
 
 /// batch transaction processor
@@ -589,30 +1444,36 @@ impl BatchTradeHandle {
         Ok(final_results)
     }
 
-    /// Read resources in batches
+    /// Read `resource_types` of every address, at most `concurrency` addresses at a time.
+    ///
+    /// Each address maps to its resources or the error reading them.
     pub async fn batch_get_resources(
         client: Arc<Aptos>,
         addresses: Vec<String>,
         resource_types: Vec<&str>,
-    ) -> Result<HashMap<String, HashMap<String, Option<Value>>>, String> {
-        let mut all_results = HashMap::new();
-        for address in addresses {
-            match crate::contract::Contract::batch_get_resources(
-                Arc::clone(&client),
-                &address,
-                resource_types.clone(),
-            )
-            .await
-            {
-                Ok(resources) => {
-                    all_results.insert(address, resources);
-                }
-                Err(e) => {
-                    eprintln!("Failed to get resources for address: {}", e);
-                }
+        concurrency: usize,
+    ) -> HashMap<String, Result<HashMap<String, Option<Value>>, String>> {
+        let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+        let tasks = addresses.into_iter().map(|address| {
+            let client = Arc::clone(&client);
+            let semaphore = Arc::clone(&semaphore);
+            let resource_types = resource_types.clone();
+            async move {
+                let resources = match semaphore.acquire().await {
+                    Ok(_permit) => {
+                        crate::contract::Contract::batch_get_resources(
+                            client,
+                            &address,
+                            resource_types,
+                        )
+                        .await
+                    }
+                    Err(e) => Err(e.to_string()),
+                };
+                (address, resources)
             }
-        }
-        Ok(all_results)
+        });
+        join_all(tasks).await.into_iter().collect()
     }
 }
 
@@ -659,6 +1520,8 @@ pub enum TransactionType {
     BlockMetadataTransaction(BlockMetadataTransaction),
     #[serde(rename = "state_checkpoint_transaction")]
     StateCheckpointTransaction(StateCheckpointTransaction),
+    #[serde(rename = "block_epilogue_transaction")]
+    BlockEpilogueTransaction(BlockEpilogueTransaction),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -701,11 +1564,16 @@ pub struct BlockMetadataTransaction {
     pub proposer: String,
     pub failed_proposer_indices: Vec<u64>,
     pub previous_block_votes_bitvec: Vec<u8>,
+    // timestamp and events are normally taken by the flattened `TransactionInfo`
+    #[serde(default)]
     pub timestamp: String,
+    #[serde(default)]
     pub events: Vec<Event>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+// every field is shared with `TransactionInfo`, which takes them when flattened
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct StateCheckpointTransaction {
     pub timestamp: String,
     pub version: String,
@@ -721,15 +1589,31 @@ pub struct StateCheckpointTransaction {
     pub events: Vec<Event>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockEpilogueTransaction {
+    #[serde(default)]
+    pub block_end_info: Option<Value>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Payload {
     #[serde(rename = "type")]
     pub payload_type: String,
+    /// empty for script and multisig payloads
+    #[serde(default)]
     pub function: String,
+    #[serde(default)]
     pub type_arguments: Vec<String>,
+    #[serde(default)]
     pub arguments: Vec<Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub code: Option<Code>,
+    /// account a `multisig_payload` executes as
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multisig_address: Option<String>,
+    /// entry function of a `multisig_payload`, `None` when only its hash is on chain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction_payload: Option<Box<Payload>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -750,6 +1634,8 @@ pub enum Signature {
         public_keys: Vec<String>,
         signatures: Vec<String>,
         threshold: u8,
+        #[serde(default)]
+        bitmap: Option<String>,
     },
     #[serde(rename = "single_key_signature")]
     SingleKey {
@@ -766,6 +1652,8 @@ pub enum Signature {
     FeePayer {
         sender: Box<Signature>,
         #[serde(default)]
+        fee_payer_address: Option<String>,
+        #[serde(default, alias = "fee_payer_signer")]
         fee_payer: Option<Box<Signature>>,
     },
 }
@@ -822,6 +1710,128 @@ pub struct TransferInfo {
     pub token_type: String,
 }
 
+/// entry function call with typed arguments, see `Trade::decode_payload`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DecodedCall {
+    /// e.g. `0x1::coin::transfer`
+    pub function: String,
+    pub type_arguments: Vec<String>,
+    /// parameter types without the signers, `param_types[i]` is the type of `arg{i}`
+    pub param_types: Vec<String>,
+    /// `arg0`, `arg1`, ... by position
+    pub arguments: HashMap<String, TypedValue>,
+}
+
+impl DecodedCall {
+    /// argument at `index`
+    pub fn argument(&self, index: usize) -> Option<&TypedValue> {
+        self.arguments.get(&format!("arg{}", index))
+    }
+}
+
+/// move value of an entry function argument, typed by its abi parameter
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum TypedValue {
+    Bool(bool),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    /// decimal string
+    U256(String),
+    Address(String),
+    String(String),
+    /// `vector<u8>`
+    Bytes(Vec<u8>),
+    Vector(Vec<TypedValue>),
+    Option(Option<Box<TypedValue>>),
+    /// address of an `0x1::object::Object<T>`
+    Object(String),
+    /// structs, and arguments that do not match their parameter type
+    Raw(Value),
+}
+
+impl TypedValue {
+    /// decode the json `argument` of a parameter of type `param`
+    pub fn decode(param: &str, argument: &Value) -> Self {
+        Self::try_decode(param, argument).unwrap_or_else(|| TypedValue::Raw(argument.clone()))
+    }
+
+    fn try_decode(param: &str, argument: &Value) -> Option<Self> {
+        let unsigned = |argument: &Value| match argument {
+            Value::String(s) => s.parse::<u128>().ok(),
+            other => other.as_u64().map(u128::from),
+        };
+        let address = |argument: &Value| {
+            argument
+                .as_str()
+                .filter(|s| {
+                    s.strip_prefix("0x").is_some_and(|hex| {
+                        !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit())
+                    })
+                })
+                .map(|s| s.to_string())
+        };
+        Some(match param {
+            "bool" => match argument {
+                Value::Bool(value) => TypedValue::Bool(*value),
+                Value::String(s) => TypedValue::Bool(s.parse().ok()?),
+                _ => return None,
+            },
+            "u8" => TypedValue::U8(unsigned(argument)?.try_into().ok()?),
+            "u16" => TypedValue::U16(unsigned(argument)?.try_into().ok()?),
+            "u32" => TypedValue::U32(unsigned(argument)?.try_into().ok()?),
+            "u64" => TypedValue::U64(unsigned(argument)?.try_into().ok()?),
+            "u128" => TypedValue::U128(unsigned(argument)?),
+            "u256" => match argument {
+                Value::String(s) if !s.is_empty() && s.chars().all(|c| c.is_ascii_digit()) => {
+                    TypedValue::U256(s.clone())
+                }
+                Value::Number(n) if n.is_u64() => TypedValue::U256(n.to_string()),
+                _ => return None,
+            },
+            "address" => TypedValue::Address(address(argument)?),
+            "0x1::string::String" => TypedValue::String(argument.as_str()?.to_string()),
+            "vector<u8>" => match argument {
+                Value::String(s) => {
+                    TypedValue::Bytes(hex::decode(s.strip_prefix("0x").unwrap_or(s)).ok()?)
+                }
+                Value::Array(items) => TypedValue::Bytes(
+                    items
+                        .iter()
+                        .map(|item| unsigned(item)?.try_into().ok())
+                        .collect::<Option<Vec<u8>>>()?,
+                ),
+                _ => return None,
+            },
+            _ if param.starts_with("0x1::object::Object<") => {
+                TypedValue::Object(address(argument).or_else(|| address(argument.get("inner")?))?)
+            }
+            _ if param.starts_with("0x1::option::Option<") && param.ends_with('>') => {
+                let inner = &param["0x1::option::Option<".len()..param.len() - 1];
+                let value = match argument.get("vec") {
+                    Some(vec) => vec.as_array()?.first(),
+                    None if argument.is_null() => None,
+                    None => Some(argument),
+                };
+                TypedValue::Option(value.map(|value| Box::new(Self::decode(inner, value))))
+            }
+            _ if param.starts_with("vector<") && param.ends_with('>') => {
+                let inner = &param["vector<".len()..param.len() - 1];
+                TypedValue::Vector(
+                    argument
+                        .as_array()?
+                        .iter()
+                        .map(|item| Self::decode(inner, item))
+                        .collect(),
+                )
+            }
+            _ => return None,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ResourceChanges {
     pub resources_modified: usize,
@@ -830,6 +1840,50 @@ pub struct ResourceChanges {
     pub table_items_deleted: usize,
 }
 
+/// what a transaction does, see `TransactionInfo::summary`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TxKind {
+    Transfer,
+    Swap,
+    NftPurchase,
+    /// any other entry function call
+    ContractCall,
+    /// not a user transaction, or a script / module publish
+    Other,
+}
+
+/// human readable summary of a transaction
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TxSummary {
+    pub kind: TxKind,
+    pub hash: String,
+    pub version: String,
+    pub success: bool,
+    pub sender: Option<String>,
+    /// entry function, e.g. `0x1::coin::transfer`
+    pub function: Option<String>,
+    /// transfer recipient
+    pub recipient: Option<String>,
+    /// token and raw amount sent or swapped in
+    pub spent: Option<(String, u128)>,
+    /// token and raw amount swapped out
+    pub received: Option<(String, u128)>,
+    pub dex_names: Vec<String>,
+    /// gas used times gas unit price, in octas
+    pub gas_fee: Option<u64>,
+    /// microseconds
+    pub timestamp: Option<u64>,
+}
+
+/// coin balance change of an account in a transaction
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BalanceChange {
+    pub coin_type: String,
+    /// balance after the transaction
+    pub balance: u64,
+    pub delta: i128,
+}
+
 impl TransactionInfo {
     /// Check if the transaction was successful
     pub fn is_successful(&self) -> bool {
@@ -846,6 +1900,19 @@ impl TransactionInfo {
         self.gas_used.parse().ok()
     }
 
+    /// Get the gas unit price of a user or pending transaction.
+    pub fn get_gas_unit_price(&self) -> Option<u64> {
+        match &self.transaction_type {
+            TransactionType::UserTransaction(user_txn) => {
+                user_txn.gas_unit_price.as_ref()?.parse().ok()
+            }
+            TransactionType::PendingTransaction(pending_txn) => {
+                pending_txn.gas_unit_price.parse().ok()
+            }
+            _ => None,
+        }
+    }
+
     /// Check whether it is a user transaction
     pub fn is_user_transaction(&self) -> bool {
         matches!(self.transaction_type, TransactionType::UserTransaction(_))
@@ -860,7 +1927,48 @@ impl TransactionInfo {
         }
     }
 
-    fn extract_received_from_event(event: &Event) -> Vec<(String, u64)> {
+    /// Account paying the gas of a sponsored user or pending transaction, `None` when
+    /// the sender pays it or for other transactions.
+    pub fn fee_payer_address(&self) -> Option<&str> {
+        let signature = match &self.transaction_type {
+            TransactionType::UserTransaction(user_txn) => &user_txn.signature,
+            TransactionType::PendingTransaction(pending_txn) => pending_txn.signature.as_ref()?,
+            _ => return None,
+        };
+        match signature {
+            Signature::FeePayer {
+                fee_payer_address, ..
+            } => fee_payer_address.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Account the transaction acts as: the multisig account of a multisig payload,
+    /// otherwise the sender, whoever paid the gas.
+    pub fn effective_sender(&self) -> Option<&str> {
+        let payload = match &self.transaction_type {
+            TransactionType::UserTransaction(user_txn) => &user_txn.payload,
+            TransactionType::PendingTransaction(pending_txn) => &pending_txn.payload,
+            _ => return None,
+        };
+        payload
+            .multisig_address
+            .as_deref()
+            .or_else(|| self.get_sender())
+    }
+
+    /// sequence number of a user or pending transaction
+    pub fn get_sequence_number(&self) -> Option<u64> {
+        match &self.transaction_type {
+            TransactionType::UserTransaction(user_txn) => user_txn.sequence_number.parse().ok(),
+            TransactionType::PendingTransaction(pending_txn) => {
+                pending_txn.sequence_number.parse().ok()
+            }
+            _ => None,
+        }
+    }
+
+    fn extract_received_from_event(event: &Event) -> Vec<(String, u128)> {
         let mut result = Vec::new();
         if let serde_json::Value::Object(data) = &event.data {
             if event.r#type.contains("Swap") {
@@ -906,7 +2014,7 @@ impl TransactionInfo {
         result
     }
 
-    fn extract_spent_from_event(event: &Event) -> Vec<(String, u64)> {
+    fn extract_spent_from_event(event: &Event) -> Vec<(String, u128)> {
         let mut result = Vec::new();
         if let serde_json::Value::Object(data) = &event.data {
             if event.r#type.contains("Swap") {
@@ -973,7 +2081,7 @@ impl TransactionInfo {
         }
     }
 
-    pub fn get_spent_token(&self) -> Option<(String, u64)> {
+    pub fn get_spent_token(&self) -> Option<(String, u128)> {
         if !self.success {
             return None;
         }
@@ -1008,7 +2116,7 @@ impl TransactionInfo {
         None
     }
 
-    pub fn get_received_token(&self) -> Option<(String, u64)> {
+    pub fn get_received_token(&self) -> Option<(String, u128)> {
         if !self.success {
             return None;
         }
@@ -1042,7 +2150,7 @@ impl TransactionInfo {
         None
     }
 
-    fn guess_decimals_from_amount(amount: u64) -> u8 {
+    fn guess_decimals_from_amount(amount: u128) -> u8 {
         let amount_str = amount.to_string();
         let len = amount_str.len();
         if len > 6 && amount_str.ends_with("000000") {
@@ -1096,18 +2204,19 @@ impl TransactionInfo {
         })
     }
 
-    fn parse_amount_simple(value: &serde_json::Value) -> Option<u64> {
+    /// parse an amount, as u128 since FA supplies and some amounts exceed u64
+    fn parse_amount_simple(value: &serde_json::Value) -> Option<u128> {
         if let Some(s) = value.as_str() {
-            if let Ok(n) = s.parse::<u64>() {
+            if let Ok(n) = s.parse::<u128>() {
                 return Some(n);
             }
         }
         if let Some(n) = value.as_u64() {
-            return Some(n);
+            return Some(n as u128);
         }
         if let Some(n) = value.as_i64() {
             if n >= 0 {
-                return Some(n as u64);
+                return Some(n as u128);
             }
         }
         None
@@ -1150,36 +2259,31 @@ impl TransactionInfo {
         }
     }
 
-    pub fn calculate_all_token_balances(&self) {
-        let mut spent_map: HashMap<String, u64> = HashMap::new();
-        let mut received_map: HashMap<String, u64> = HashMap::new();
-        for event in &self.events {
-            let spent = Self::extract_spent_from_event(event);
-            for (token, amount) in spent {
-                *spent_map.entry(token).or_insert(0) += amount;
-            }
-        }
+    /// Net amount per token moved by the swap and fungible asset events, received
+    /// minus spent, tokens netting to zero are left out.
+    pub fn net_balance_changes(&self) -> HashMap<String, i128> {
+        let mut changes: HashMap<String, i128> = HashMap::new();
         for event in &self.events {
-            let received = Self::extract_received_from_event(event);
-            for (token, amount) in received {
-                *received_map.entry(token).or_insert(0) += amount;
+            for (token, amount) in Self::extract_spent_from_event(event) {
+                *changes.entry(token).or_insert(0) -= amount as i128;
             }
-        }
-        for (token, total) in &spent_map {
-            let decimals = Self::get_decimals_for_token(token);
-        }
-        for (token, total) in &received_map {
-            let decimals = Self::get_decimals_for_token(token);
-        }
-        let all_tokens: HashSet<_> = spent_map.keys().chain(received_map.keys()).collect();
-        for token in all_tokens {
-            let spent = spent_map.get(token).copied().unwrap_or(0);
-            let received = received_map.get(token).copied().unwrap_or(0);
-            let net = received as i128 - spent as i128;
-            if net != 0 {
-                let decimals = Self::get_decimals_for_token(token);
+            for (token, amount) in Self::extract_received_from_event(event) {
+                *changes.entry(token).or_insert(0) += amount as i128;
             }
         }
+        changes.retain(|_, net| *net != 0);
+        changes
+    }
+
+    /// `net_balance_changes` scaled by the decimals of each token
+    pub fn net_balance_changes_formatted(&self) -> HashMap<String, f64> {
+        self.net_balance_changes()
+            .into_iter()
+            .map(|(token, net)| {
+                let decimals = Self::get_decimals_for_token(&token);
+                (token, net as f64 / 10f64.powi(decimals as i32))
+            })
+            .collect()
     }
 
     pub fn get_liquidity_pool_addresses(&self) -> Vec<String> {
@@ -1288,6 +2392,69 @@ impl TransactionInfo {
         dex_names.dedup();
         dex_names
     }
+
+    /// Classify the transaction and pull out its amounts, tokens and counterparty.
+    pub fn summary(&self) -> TxSummary {
+        let user_txn = Trade::get_user_transaction(self);
+        let function = user_txn.map(|txn| txn.payload.function.clone());
+        let dex_names = self.get_dex_names();
+        let mut summary = TxSummary {
+            kind: TxKind::Other,
+            hash: self.hash.clone(),
+            version: self.version.clone(),
+            success: self.success,
+            sender: self.get_sender().map(|s| s.to_string()),
+            function: function.clone().filter(|f| !f.is_empty()),
+            recipient: None,
+            spent: None,
+            received: None,
+            dex_names: vec![],
+            gas_fee: self
+                .get_gas_used()
+                .zip(self.get_gas_unit_price())
+                .map(|(used, price)| used.saturating_mul(price)),
+            timestamp: self.get_timestamp().filter(|t| *t > 0),
+        };
+        let Some(function) = summary.function.clone() else {
+            return summary;
+        };
+        if let Some(transfer) = Trade::get_transfer_info(self) {
+            summary.kind = TxKind::Transfer;
+            summary.recipient = Some(transfer.to);
+            summary.spent = Some((transfer.token_type, transfer.amount as u128));
+            return summary;
+        }
+        let spent = self.get_spent_token();
+        let received = self.get_received_token();
+        if Self::is_nft_purchase(&function) {
+            summary.kind = TxKind::NftPurchase;
+            summary.spent = spent;
+        } else if (spent.is_some() && received.is_some())
+            || !dex_names.is_empty()
+            || function.contains("swap")
+        {
+            summary.kind = TxKind::Swap;
+            summary.spent = spent;
+            summary.received = received;
+            summary.dex_names = dex_names;
+        } else {
+            summary.kind = TxKind::ContractCall;
+        }
+        summary
+    }
+
+    /// marketplace buy functions, as built by `NFTMarketplace::purchase_nft`
+    fn is_nft_purchase(function: &str) -> bool {
+        let mut parts = function.rsplit("::");
+        let (Some(name), Some(module)) = (parts.next(), parts.next()) else {
+            return false;
+        };
+        module.contains("market")
+            && matches!(
+                name,
+                "purchase" | "buy" | "buy_token" | "buy_nft" | "buy_tokens" | "fill_listing"
+            )
+    }
 }
 
 #[cfg(test)]
@@ -1297,6 +2464,901 @@ mod tests {
     use super::*;
     use std::sync::Arc;
 
+    fn transfer_transaction() -> TransactionInfo {
+        serde_json::from_value(transfer_transaction_json()).unwrap()
+    }
+
+    fn transfer_transaction_json() -> Value {
+        let coin_store = |balance: &str| {
+            json!({
+                "type": "0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>",
+                "data": {
+                    "coin": {"value": balance},
+                    "deposit_events": {"counter": "1", "guid": {"id": {"addr": "0x0", "creation_num": "2"}}},
+                    "withdraw_events": {"counter": "1", "guid": {"id": {"addr": "0x0", "creation_num": "3"}}},
+                    "frozen": false
+                }
+            })
+        };
+        json!({
+            "version": "1",
+            "hash": "0x01",
+            "state_change_hash": "0x02",
+            "event_root_hash": "0x03",
+            "state_checkpoint_hash": null,
+            "gas_used": "5",
+            "success": true,
+            "vm_status": "Executed successfully",
+            "accumulator_root_hash": "0x04",
+            "changes": [
+                {"type": "write_resource", "address": "0x0a", "state_key_hash": "0x05", "data": coin_store("900")},
+                {"type": "write_resource", "address": "0xb", "state_key_hash": "0x06", "data": coin_store("100")},
+                {"type": "write_table_item", "state_key_hash": "0x07", "handle": "0x08", "key": "0x09", "value": "0x0a"}
+            ],
+            "events": [
+                {"guid": {"creation_number": "3", "account_address": "0xa"}, "sequence_number": "0", "type": "0x1::coin::WithdrawEvent", "data": {"amount": "100"}},
+                {"guid": {"creation_number": "2", "account_address": "0xb"}, "sequence_number": "0", "type": "0x1::coin::DepositEvent", "data": {"amount": "100"}}
+            ],
+            "timestamp": "1700000000000000",
+            "type": "user_transaction",
+            "sender": "0xa",
+            "sequence_number": "0",
+            "max_gas_amount": "2000",
+            "gas_unit_price": "100",
+            "expiration_timestamp_secs": "1700000030",
+            "payload": {"type": "entry_function_payload", "function": "0x1::coin::transfer", "type_arguments": ["0x1::aptos_coin::AptosCoin"], "arguments": ["0xb", "100"]},
+            "signature": {"type": "ed25519_signature", "public_key": "0x0b", "signature": "0x0c"}
+        })
+    }
+
+    #[test]
+    fn test_transaction_summary() {
+        let summary = transfer_transaction().summary();
+        assert_eq!(summary.kind, TxKind::Transfer);
+        assert_eq!(summary.recipient.as_deref(), Some("0xb"));
+        assert_eq!(
+            summary.spent,
+            Some(("0x1::aptos_coin::AptosCoin".to_string(), 100))
+        );
+        assert_eq!(summary.gas_fee, Some(500));
+        assert_eq!(summary.timestamp, Some(1700000000000000));
+
+        let mut swap = transfer_transaction_json();
+        swap["payload"]["function"] = json!("0xc7::router::swap_exact_input");
+        swap["events"] = json!([{
+            "guid": {"creation_number": "0", "account_address": "0x0"}, "sequence_number": "0",
+            "type": "0xc7::pool::SwapEvent",
+            "data": {"amount_in": "100", "from_token": "0x1::aptos_coin::AptosCoin", "amount_out": "42", "to_token": "0xf::usdc::USDC"}
+        }]);
+        let summary = serde_json::from_value::<TransactionInfo>(swap)
+            .unwrap()
+            .summary();
+        assert_eq!(summary.kind, TxKind::Swap);
+        assert_eq!(summary.received, Some(("0xf::usdc::USDC".to_string(), 42)));
+
+        let mut purchase = transfer_transaction_json();
+        purchase["payload"]["function"] = json!("0x2c7b::marketplace::purchase");
+        let summary = serde_json::from_value::<TransactionInfo>(purchase)
+            .unwrap()
+            .summary();
+        assert_eq!(summary.kind, TxKind::NftPurchase);
+
+        let mut call = transfer_transaction_json();
+        call["payload"]["function"] = json!("0x1::delegation_pool::add_stake");
+        let summary = serde_json::from_value::<TransactionInfo>(call)
+            .unwrap()
+            .summary();
+        assert_eq!(summary.kind, TxKind::ContractCall);
+        assert_eq!(summary.recipient, None);
+    }
+
+    #[test]
+    fn test_net_balance_changes_of_swap() {
+        let mut swap = transfer_transaction_json();
+        swap["events"] = json!([{
+            "guid": {"creation_number": "0", "account_address": "0x0"}, "sequence_number": "0",
+            "type": "0xc7::pool::SwapEvent",
+            "data": {"amount_in": "150000000", "from_token": "0x1::aptos_coin::AptosCoin", "amount_out": "42", "to_token": "0xf::usdc::USDC"}
+        }]);
+        let swap: TransactionInfo = serde_json::from_value(swap).unwrap();
+        let changes = swap.net_balance_changes();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes["0x1::aptos_coin::AptosCoin"], -150_000_000);
+        assert_eq!(changes["0xf::usdc::USDC"], 42);
+        let formatted = swap.net_balance_changes_formatted();
+        assert!((formatted["0x1::aptos_coin::AptosCoin"] + 1.5).abs() < 1e-9);
+        assert!(formatted["0xf::usdc::USDC"] > 0.0);
+        assert!(transfer_transaction().net_balance_changes().is_empty());
+    }
+
+    #[test]
+    fn test_decode_payload() {
+        let abi = json!({
+            "name": "place_order", "is_entry": true, "generic_type_params": [{"constraints": []}],
+            "params": ["&signer", "address", "u64", "u8", "bool", "vector<u8>", "0x1::string::String",
+                "0x1::option::Option<u128>", "vector<address>", "0x1::object::Object<0x1::fungible_asset::Metadata>", "0x2::m::Order", "u64"]
+        });
+        let payload: Payload = serde_json::from_value(json!({
+            "type": "entry_function_payload", "function": "0x2::market::place_order",
+            "type_arguments": ["0x1::aptos_coin::AptosCoin"],
+            "arguments": ["0xb", "100", 7, true, "0x0102", "memo", {"vec": ["5"]}, ["0xc", "0xd"],
+                {"inner": "0xe"}, {"price": "1"}, "not a number"]
+        }))
+        .unwrap();
+        let call = Trade::decode_payload(&payload, &abi).unwrap();
+        assert_eq!(call.function, "0x2::market::place_order");
+        assert_eq!(call.param_types.len(), 11);
+        assert_eq!(call.arguments.len(), 11);
+        let expected = [
+            TypedValue::Address("0xb".to_string()),
+            TypedValue::U64(100),
+            TypedValue::U8(7),
+            TypedValue::Bool(true),
+            TypedValue::Bytes(vec![1, 2]),
+            TypedValue::String("memo".to_string()),
+            TypedValue::Option(Some(Box::new(TypedValue::U128(5)))),
+            TypedValue::Vector(vec![
+                TypedValue::Address("0xc".to_string()),
+                TypedValue::Address("0xd".to_string()),
+            ]),
+            TypedValue::Object("0xe".to_string()),
+            TypedValue::Raw(json!({"price": "1"})),
+            TypedValue::Raw(json!("not a number")),
+        ];
+        for (index, value) in expected.iter().enumerate() {
+            assert_eq!(call.argument(index), Some(value), "arg{}", index);
+        }
+
+        let mut short = payload.clone();
+        short.arguments.pop();
+        assert!(Trade::decode_payload(&short, &abi).is_err());
+        let other_abi = json!({"name": "cancel_order", "params": []});
+        assert!(Trade::decode_payload(&payload, &other_abi).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_decode_entry_function_payload() {
+        let module = json!({
+            "bytecode": "0x",
+            "abi": {"address": "0x1", "name": "coin", "exposed_functions": [
+                {"name": "transfer", "is_entry": true, "is_view": false, "generic_type_params": [{"constraints": []}],
+                 "params": ["&signer", "address", "u64"], "return": []}
+            ]}
+        });
+        let transport = crate::transport::MockTransport::new().on_get(
+            "/accounts/0x1/module/coin",
+            200,
+            &module.to_string(),
+        );
+        let client = Arc::new(Aptos::with_transport(
+            AptosType::Mainnet,
+            Arc::new(transport),
+        ));
+        let call = Trade::decode_entry_function_payload(client, &transfer_transaction())
+            .await
+            .unwrap();
+        assert_eq!(call.type_arguments, vec!["0x1::aptos_coin::AptosCoin"]);
+        assert_eq!(call.param_types, vec!["address", "u64"]);
+        assert_eq!(
+            call.arguments.get("arg0"),
+            Some(&TypedValue::Address("0xb".to_string()))
+        );
+        assert_eq!(call.arguments.get("arg1"), Some(&TypedValue::U64(100)));
+    }
+
+    #[test]
+    fn test_event_filters() {
+        let mut transaction = transfer_transaction_json();
+        transaction["events"]
+            .as_array_mut()
+            .unwrap()
+            .push(json!({"guid": {"creation_number": "4", "account_address": "0xa"}, "sequence_number": "0", "type": "0x1::coin::FlashWithdrawEvent", "data": {}}));
+        let transaction: TransactionInfo = serde_json::from_value(transaction).unwrap();
+        assert_eq!(
+            Trade::get_events_containing(&transaction, "WithdrawEvent").len(),
+            2
+        );
+        let exact = Trade::get_events_exact(&transaction, "0x1::coin::WithdrawEvent");
+        assert_eq!(exact.len(), 1);
+        assert_eq!(exact[0].guid.creation_number, "3");
+        assert!(Trade::get_events_exact(&transaction, "WithdrawEvent").is_empty());
+        let by_guid = Trade::get_events_by_guid(&transaction, "0x0a", 2);
+        assert!(by_guid.is_empty());
+        let by_guid = Trade::get_events_by_guid(&transaction, "0x0b", 2);
+        assert_eq!(by_guid[0].r#type, "0x1::coin::DepositEvent");
+    }
+
+    #[tokio::test]
+    async fn test_batch_get_resources_across_addresses() {
+        let transport = crate::transport::MockTransport::new()
+            .on_get(
+                "/accounts/0xa/resource/0x1::account::Account",
+                200,
+                r#"{"type":"0x1::account::Account","data":{"sequence_number":"1"}}"#,
+            )
+            .on_get(
+                "/accounts/0xb/resource/0x1::account::Account",
+                200,
+                r#"{"type":"0x1::account::Account","data":{"sequence_number":"2"}}"#,
+            )
+            .on_get(
+                "/accounts/0xd/resource/0x1::account::Account",
+                503,
+                r#"{"message":"service unavailable"}"#,
+            );
+        let client = Arc::new(Aptos::with_transport(
+            AptosType::Mainnet,
+            Arc::new(transport),
+        ));
+        let addresses: Vec<String> = ["0xa", "0xb", "0xc", "0xd"]
+            .iter()
+            .map(|address| address.to_string())
+            .collect();
+        let results = BatchTradeHandle::batch_get_resources(
+            client,
+            addresses,
+            vec!["0x1::account::Account"],
+            2,
+        )
+        .await;
+        assert_eq!(results.len(), 4);
+        let sequence_number = |address: &str| {
+            results[address].as_ref().unwrap()["0x1::account::Account"]
+                .as_ref()
+                .map(|data| data["sequence_number"].clone())
+        };
+        assert_eq!(sequence_number("0xa"), Some(json!("1")));
+        assert_eq!(sequence_number("0xb"), Some(json!("2")));
+        assert_eq!(sequence_number("0xc"), None);
+        // a failed read is the address's error, not a missing resource
+        let err = results["0xd"].as_ref().unwrap_err();
+        assert!(err.contains("0x1::account::Account of 0xd"));
+    }
+
+    #[test]
+    fn test_preview_balance_changes() {
+        let store = FungibleAsset::primary_store_address("0xa", "0xfa").unwrap();
+        let mut simulation = transfer_transaction_json();
+        simulation["changes"].as_array_mut().unwrap().push(json!({
+            "type": "write_resource", "address": store, "state_key_hash": "0x0d",
+            "data": {"type": "0x1::fungible_asset::FungibleStore", "data": {"metadata": {"inner": "0xfa"}, "balance": "70", "frozen": false}}
+        }));
+        simulation["events"].as_array_mut().unwrap().push(json!({
+            "guid": {"creation_number": "0", "account_address": "0x0"}, "sequence_number": "0",
+            "type": "0x1::fungible_asset::Withdraw", "data": {"store": store, "amount": "30"}
+        }));
+        let simulation: TransactionInfo = serde_json::from_value(simulation).unwrap();
+        let preview = Trade::preview_balance_changes(&simulation, "0xa");
+        assert_eq!(
+            preview,
+            vec![
+                BalanceChange {
+                    coin_type: "0x1::aptos_coin::AptosCoin".to_string(),
+                    balance: 900,
+                    delta: -600,
+                },
+                BalanceChange {
+                    coin_type: "0xfa".to_string(),
+                    balance: 70,
+                    delta: -30,
+                },
+            ]
+        );
+        assert_eq!(Trade::preview_balance_changes(&simulation, "0xb").len(), 1);
+    }
+
+    #[test]
+    fn test_parse_amount_above_u64() {
+        assert_eq!(
+            TransactionInfo::parse_amount_simple(&json!("18446744073709551616")),
+            Some(1u128 << 64)
+        );
+        assert_eq!(TransactionInfo::parse_amount_simple(&json!(42)), Some(42));
+        assert_eq!(TransactionInfo::parse_amount_simple(&json!(-1)), None);
+    }
+
+    #[tokio::test]
+    async fn test_get_incoming_transfers() {
+        let mut from_b = transfer_transaction_json();
+        from_b["sender"] = json!("0xb");
+        let from_self = transfer_transaction_json();
+        let transport = crate::transport::MockTransport::new()
+            .on_get(
+                "/accounts/0xa/resource/0x1::coin::CoinStore",
+                200,
+                &json!({
+                    "type": "0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>",
+                    "data": {
+                        "coin": {"value": "900"},
+                        "deposit_events": {"counter": "5", "guid": {"id": {"addr": "0xa", "creation_num": "2"}}},
+                        "withdraw_events": {"counter": "1", "guid": {"id": {"addr": "0xa", "creation_num": "3"}}},
+                        "frozen": false
+                    }
+                })
+                .to_string(),
+            )
+            .on_get(
+                "/deposit_events?limit=2&start=3",
+                200,
+                r#"[
+                    {"version": "10", "guid": {"creation_number": "2", "account_address": "0xa"}, "sequence_number": "3", "type": "0x1::coin::DepositEvent", "data": {"amount": "250"}},
+                    {"version": "11", "guid": {"creation_number": "2", "account_address": "0xa"}, "sequence_number": "4", "type": "0x1::coin::DepositEvent", "data": {"amount": "75"}}
+                ]"#,
+            )
+            .on_get(
+                "/deposit_events?limit=2&start=1",
+                200,
+                r#"[
+                    {"version": "8", "guid": {"creation_number": "2", "account_address": "0xa"}, "sequence_number": "1", "type": "0x1::coin::DepositEvent", "data": {"amount": "40"}},
+                    {"version": "9", "guid": {"creation_number": "2", "account_address": "0xa"}, "sequence_number": "2", "type": "0x1::coin::DepositEvent", "data": {"amount": "60"}}
+                ]"#,
+            )
+            .on_get("/transactions/by_version/8", 200, &from_b.to_string())
+            .on_get("/transactions/by_version/9", 200, &from_b.to_string())
+            .on_get("/transactions/by_version/10", 200, &from_b.to_string())
+            .on_get("/transactions/by_version/11", 200, &from_self.to_string());
+        let transport = Arc::new(transport);
+        let client = Arc::new(Aptos::with_transport(
+            AptosType::Mainnet,
+            Arc::clone(&transport) as _,
+        ));
+        // the own deposit is skipped before the limit applies, an older page fills it
+        let transfers = Trade::get_incoming_transfers(Arc::clone(&client), "0xa", 2)
+            .await
+            .unwrap();
+        let amounts: Vec<u64> = transfers.iter().map(|t| t.amount).collect();
+        assert_eq!(amounts, vec![250, 60]);
+        assert_eq!(transfers[0].from, "0xb");
+        assert_eq!(transfers[0].to, "0xa");
+        assert!(
+            !transport
+                .requests()
+                .iter()
+                .any(|r| r.url.contains("start=0"))
+        );
+        assert!(
+            Trade::get_incoming_transfers(client, "0xc", 2)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_safe_transfer_function_by_token_kind() {
+        let wallet = Arc::new(Wallet::new().unwrap());
+        let transport = crate::transport::MockTransport::new()
+            .on_get(
+                "/v1/",
+                200,
+                r#"{"chain_id":1,"epoch":"1","ledger_version":"1","ledger_timestamp":"1","node_role":"full_node","block_height":"1"}"#,
+            )
+            .on_get(
+                "/resource/0x1::coin::CoinStore<0xc::usdc::USDC>",
+                200,
+                r#"{"type":"0x1::coin::CoinStore<0xc::usdc::USDC>","data":{"coin":{"value":"5"},"frozen":false}}"#,
+            )
+            .on_get(
+                "/resource/0x1::coin::CoinStore<0xc::frozen::FROZEN>",
+                200,
+                r#"{"type":"0x1::coin::CoinStore<0xc::frozen::FROZEN>","data":{"coin":{"value":"5"},"frozen":true}}"#,
+            );
+        let client = Arc::new(Aptos::with_transport(
+            AptosType::Mainnet,
+            Arc::new(transport),
+        ));
+        let params = |token_type: &str| SafeTransferParams {
+            recipient: "0xb".to_string(),
+            token_type: token_type.to_string(),
+            amount: 10,
+            sequence_number: Some(4),
+            expiration_secs: 30,
+            max_gas_amount: 2000,
+            gas_unit_price: 100,
+        };
+        let transfer = |token_type: &'static str| {
+            let (client, wallet) = (Arc::clone(&client), Arc::clone(&wallet));
+            async move {
+                Trade::create_safe_transfer_tx(client, wallet, &params(token_type))
+                    .await
+                    .unwrap()["payload"]
+                    .clone()
+            }
+        };
+        let registered = transfer("0xc::usdc::USDC").await;
+        assert_eq!(registered["function"], "0x1::coin::transfer");
+        let fungible_asset = transfer("0xfa").await;
+        assert_eq!(
+            fungible_asset["function"],
+            "0x1::primary_fungible_store::transfer"
+        );
+        assert_eq!(
+            fungible_asset["type_arguments"],
+            json!(["0x1::fungible_asset::Metadata"])
+        );
+        assert_eq!(fungible_asset["arguments"], json!(["0xfa", "0xb", "10"]));
+        let frozen = Trade::create_safe_transfer_tx(
+            Arc::clone(&client),
+            Arc::clone(&wallet),
+            &params("0xc::frozen::FROZEN"),
+        )
+        .await
+        .unwrap_err();
+        assert!(frozen.contains("is frozen"));
+    }
+
+    #[tokio::test]
+    async fn test_estimate_transfer_fee_from_simulation() {
+        let mut simulated = transfer_transaction_json();
+        simulated["gas_used"] = json!("12");
+        simulated["max_gas_amount"] = json!("18");
+        simulated["gas_unit_price"] = json!("150");
+        let wallet = Arc::new(Wallet::new().unwrap());
+        let transport = Arc::new(
+            crate::transport::MockTransport::new()
+                .on_get(
+                    "/estimate_gas_price",
+                    200,
+                    r#"{"gas_estimate":100,"prioritized_gas_estimate":150}"#,
+                )
+                .on_get(
+                    "/v1/",
+                    200,
+                    r#"{"chain_id":1,"epoch":"1","ledger_version":"1","ledger_timestamp":"1","node_role":"full_node","block_height":"1"}"#,
+                )
+                .on_get(
+                    &format!("/accounts/{}", wallet.address()),
+                    200,
+                    r#"{"sequence_number":"3","authentication_key":"0x00"}"#,
+                )
+                .on_post(
+                    "/transactions/simulate",
+                    200,
+                    &json!([simulated]).to_string(),
+                ),
+        );
+        let client = Arc::new(Aptos::with_transport(AptosType::Mainnet, transport.clone()));
+        let fee =
+            Trade::estimate_transfer_fee(Arc::clone(&client), Arc::clone(&wallet), "0xb", 100)
+                .await
+                .unwrap();
+        assert_eq!(
+            fee,
+            FeeEstimate {
+                gas_unit_price: 150,
+                max_gas_units: 18,
+                est_fee_octas: 1800,
+                est_fee_apt: 0.000018,
+            }
+        );
+        let request = transport
+            .requests()
+            .into_iter()
+            .find(|r| r.method == "POST")
+            .unwrap();
+        assert!(request.url.contains("estimate_max_gas_amount=true"));
+        let body = request.body.unwrap();
+        assert_eq!(body["transaction"]["sequence_number"], "3");
+        assert_eq!(body["transaction"]["gas_unit_price"], "100");
+        assert_eq!(body["signature"]["signature"], "00".repeat(64));
+
+        Trade::estimate_token_transfer_fee(client, wallet, "0xb", "0xa11ce", 5)
+            .await
+            .unwrap();
+        let body = transport.requests().last().unwrap().body.clone().unwrap();
+        let payload = &body["transaction"]["payload"];
+        assert_eq!(payload["function"], "0x1::primary_fungible_store::transfer");
+        assert_eq!(payload["arguments"], json!(["0xa11ce", "0xb", "5"]));
+    }
+
+    #[test]
+    fn test_fee_payer_and_effective_sender() {
+        let plain = transfer_transaction();
+        assert_eq!(plain.fee_payer_address(), None);
+        assert_eq!(plain.effective_sender(), Some("0xa"));
+
+        let mut sponsored = transfer_transaction_json();
+        sponsored["signature"] = json!({
+            "type": "fee_payer_signature",
+            "sender": {"type": "ed25519_signature", "public_key": "0x0b", "signature": "0x0c"},
+            "secondary_signer_addresses": [],
+            "secondary_signers": [],
+            "fee_payer_address": "0xfee",
+            "fee_payer_signer": {"type": "ed25519_signature", "public_key": "0x0d", "signature": "0x0e"}
+        });
+        let sponsored: TransactionInfo = serde_json::from_value(sponsored).unwrap();
+        assert_eq!(sponsored.fee_payer_address(), Some("0xfee"));
+        assert_eq!(sponsored.get_sender(), Some("0xa"));
+        assert_eq!(sponsored.effective_sender(), Some("0xa"));
+        assert!(matches!(
+            Trade::get_user_transaction(&sponsored).unwrap().signature,
+            Signature::FeePayer {
+                fee_payer: Some(_),
+                ..
+            }
+        ));
+
+        let mut multisig = transfer_transaction_json();
+        multisig["payload"] = json!({
+            "type": "multisig_payload",
+            "multisig_address": "0xm",
+            "transaction_payload": {"type": "entry_function_payload", "function": "0x1::coin::transfer", "type_arguments": [], "arguments": ["0xb", "100"]}
+        });
+        let multisig: TransactionInfo = serde_json::from_value(multisig).unwrap();
+        assert_eq!(multisig.get_sender(), Some("0xa"));
+        assert_eq!(multisig.effective_sender(), Some("0xm"));
+        let payload = &Trade::get_user_transaction(&multisig).unwrap().payload;
+        assert_eq!(
+            payload.transaction_payload.as_ref().unwrap().function,
+            "0x1::coin::transfer"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_submit_bcs_transfer() {
+        let transport = Arc::new(crate::transport::MockTransport::new().on_post(
+            "/transactions",
+            202,
+            &transfer_transaction_json().to_string(),
+        ));
+        let client = Aptos::with_transport(AptosType::Testnet, transport.clone());
+        let wallet = Arc::new(Wallet::new().unwrap());
+        let raw_txn = json!({
+            "sender": wallet.address(),
+            "sequence_number": "4",
+            "max_gas_amount": "2000",
+            "gas_unit_price": "100",
+            "expiration_timestamp_secs": "1700000000",
+            "payload": {
+                "type": "entry_function_payload",
+                "function": "0x1::aptos_account::transfer",
+                "type_arguments": [],
+                "arguments": [wallet.address(), 5]
+            },
+            "chain_id": 2
+        });
+        let raw_txn_bcs = client.raw_transaction_bcs(&raw_txn).await.unwrap();
+        let message = crate::signing::signing_message(&raw_txn_bcs);
+        let signature = wallet.sign(&message).unwrap();
+        let bytes = Trade::create_signed_transaction_bcs(
+            Arc::clone(&wallet),
+            &raw_txn_bcs,
+            signature.clone(),
+        )
+        .unwrap();
+        let submitted = client.submit_bcs_transaction(&bytes).await.unwrap();
+        assert_eq!(submitted.hash, "0x01");
+        let requests = transport.requests();
+        let (content_type, body) = requests[0].raw_body.clone().unwrap();
+        assert_eq!(content_type, "application/x.aptos.signed_transaction+bcs");
+        assert!(requests[0].body.is_none());
+        // raw transaction, ed25519 variant, then the public key and signature
+        assert_eq!(&body[..raw_txn_bcs.len()], raw_txn_bcs.as_slice());
+        assert_eq!(body[raw_txn_bcs.len()], 0);
+        let (public_key, decoded_signature): (Vec<u8>, Vec<u8>) =
+            bcs::from_bytes(&body[raw_txn_bcs.len() + 1..]).unwrap();
+        assert_eq!(public_key, wallet.public_key_bytes().unwrap());
+        assert_eq!(decoded_signature, signature);
+        assert!(wallet.verify(&message, &decoded_signature).unwrap());
+        assert!(Trade::create_signed_transaction_bcs(wallet, &raw_txn_bcs, vec![0; 10]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_offline_signing_matches_combined_flow() {
+        let client = Aptos::with_transport(
+            AptosType::Testnet,
+            Arc::new(crate::transport::MockTransport::new()),
+        );
+        let wallet = Arc::new(Wallet::new().unwrap());
+        let raw_txn = json!({
+            "sender": "0xa",
+            "sequence_number": "7",
+            "max_gas_amount": "2000",
+            "gas_unit_price": "100",
+            "expiration_timestamp_secs": "1700000000",
+            "payload": {
+                "type": "entry_function_payload",
+                "function": "0x1::aptos_account::transfer",
+                "type_arguments": [],
+                "arguments": ["0xb", "5"]
+            },
+            "chain_id": 2
+        });
+        // online: serialize, offline: sign, online: attach
+        let message = Trade::serialize_for_signing(&client, &raw_txn)
+            .await
+            .unwrap();
+        // sha3_256("APTOS::RawTransaction"), then the raw transaction field by field
+        let mut expected =
+            hex::decode("b5e97db07fa0bd0e5598aa3643a9bc6f6693bddc1a9fec9e674a461eaa00b193")
+                .unwrap();
+        expected.extend(hex::decode(format!("{:0>64}", "a")).unwrap());
+        expected.extend(7u64.to_le_bytes());
+        expected.push(2);
+        expected.extend(hex::decode(format!("{:0>64}", "1")).unwrap());
+        expected.extend(b"\x0daptos_account\x08transfer\x00\x02\x20");
+        expected.extend(hex::decode(format!("{:0>64}", "b")).unwrap());
+        expected.push(8);
+        expected.extend(5u64.to_le_bytes());
+        expected.extend(2000u64.to_le_bytes());
+        expected.extend(100u64.to_le_bytes());
+        expected.extend(1_700_000_000u64.to_le_bytes());
+        expected.push(2);
+        assert_eq!(message, expected);
+        let signature = wallet.sign_raw(&message).unwrap();
+        let signed_txn = Trade::attach_signature(raw_txn.clone(), signature);
+
+        let combined = Trade::sign_transaction(&client, Arc::clone(&wallet), raw_txn)
+            .await
+            .unwrap();
+        assert_eq!(signed_txn, combined);
+        assert_eq!(signed_txn["signature"]["type"], "ed25519_signature");
+        let signature =
+            hex::decode(signed_txn["signature"]["signature"].as_str().unwrap()).unwrap();
+        assert!(wallet.verify(&message, &signature).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_sponsored_call() {
+        let transport = Arc::new(
+            crate::transport::MockTransport::new()
+                .on_get(
+                    "/accounts/",
+                    200,
+                    r#"{"sequence_number":"4","authentication_key":"0x01"}"#,
+                )
+                .on_get(
+                    "/estimate_gas_price",
+                    200,
+                    r#"{"deprioritized_gas_estimate":100,"gas_estimate":110,"prioritized_gas_estimate":150}"#,
+                )
+                .on_get(
+                    "/v1/",
+                    200,
+                    r#"{"chain_id":2,"epoch":"1","ledger_version":"1","ledger_timestamp":"1","node_role":"full_node","block_height":"1"}"#,
+                )
+                .on_post(
+                    "/transactions",
+                    202,
+                    &transfer_transaction_json().to_string(),
+                ),
+        );
+        let client = Arc::new(Aptos::with_transport(AptosType::Testnet, transport.clone()));
+        let user = Arc::new(Wallet::new().unwrap());
+        let sponsor = Arc::new(Wallet::new().unwrap());
+        let call = ContractCall::builder("0x1", "aptos_account", "transfer")
+            .arg_address(sponsor.address())
+            .arg_u64(5)
+            .build();
+        let hash = Trade::sponsored_call(
+            Arc::clone(&client),
+            Arc::clone(&user),
+            Arc::clone(&sponsor),
+            &call,
+        )
+        .await
+        .unwrap();
+        assert_eq!(hash, "0x01");
+        let submitted = transport
+            .requests()
+            .into_iter()
+            .find(|r| r.method == "POST")
+            .unwrap()
+            .body
+            .unwrap();
+        let transaction = &submitted["transaction"];
+        assert_eq!(transaction["sender"], user.address());
+        assert_eq!(transaction["sequence_number"], "4");
+        assert_eq!(transaction["gas_unit_price"], "110");
+        assert_eq!(transaction["chain_id"], 2);
+        let signature = &submitted["signature"];
+        assert_eq!(signature["type"], "fee_payer_signature");
+        assert_eq!(signature["fee_payer_address"], sponsor.address());
+        let raw_txn_bcs = client.raw_transaction_bcs(transaction).await.unwrap();
+        let message = Trade::fee_payer_signing_message(&raw_txn_bcs, sponsor.address()).unwrap();
+        assert_eq!(message[32], 1);
+        assert_eq!(&message[33..33 + raw_txn_bcs.len()], raw_txn_bcs.as_slice());
+        assert!(message.ends_with(&crate::signing::address_bytes(sponsor.address()).unwrap()));
+        let signed_by = |wallet: &Wallet, signature: &Value| {
+            assert_eq!(signature["type"], "ed25519_signature");
+            assert_eq!(signature["public_key"], wallet.public_key_hex().unwrap());
+            let bytes = hex::decode(signature["signature"].as_str().unwrap()).unwrap();
+            wallet.verify(&message, &bytes).unwrap()
+        };
+        assert!(signed_by(&user, &signature["sender"]));
+        assert!(signed_by(&sponsor, &signature["fee_payer_signer"]));
+
+        // a sponsor service refusing to sign stops the submission
+        let posts = transport.requests().len();
+        let err = Trade::sponsored_call_with(client, user, sponsor.address(), &call, |_| async {
+            Err("sponsor declined".to_string())
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(err, "sponsor declined");
+        assert!(
+            transport.requests()[posts..]
+                .iter()
+                .all(|r| r.method == "GET")
+        );
+    }
+
+    /// Submits a sponsored transfer to testnet, the node verifies both signatures.
+    /// Run with funded testnet keys:
+    /// `APTOS_TESTNET_USER_KEY=.. APTOS_TESTNET_SPONSOR_KEY=.. cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore]
+    async fn test_sponsored_call_on_testnet() {
+        let key = |name: &str| {
+            let key = std::env::var(name).unwrap_or_else(|_| panic!("{} is not set", name));
+            Arc::new(Wallet::from_private_key(&key).unwrap())
+        };
+        let user = key("APTOS_TESTNET_USER_KEY");
+        let sponsor = key("APTOS_TESTNET_SPONSOR_KEY");
+        let client = Arc::new(Aptos::new(AptosType::Testnet));
+        let call = ContractCall::builder("0x1", "aptos_account", "transfer")
+            .arg_address(sponsor.address())
+            .arg_u64(0)
+            .build();
+        let hash = Trade::sponsored_call(Arc::clone(&client), user, sponsor, &call)
+            .await
+            .unwrap();
+        let txn = client.waiting_transaction(&hash, 30).await.unwrap();
+        assert!(txn.success, "{}", txn.vm_status);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_transaction_reuses_sequence_number() {
+        let transport = Arc::new(
+            crate::transport::MockTransport::new()
+                .on_get(
+                    "/estimate_gas_price",
+                    200,
+                    r#"{"deprioritized_gas_estimate":100,"gas_estimate":100,"prioritized_gas_estimate":150}"#,
+                )
+                .on_get(
+                    "/v1/",
+                    200,
+                    r#"{"chain_id":1,"epoch":"1","ledger_version":"1","ledger_timestamp":"1","node_role":"full_node","block_height":"1"}"#,
+                )
+                .on_post(
+                    "/transactions",
+                    202,
+                    &transfer_transaction_json().to_string(),
+                ),
+        );
+        let client = Arc::new(Aptos::with_transport(AptosType::Mainnet, transport.clone()));
+        let wallet = Arc::new(Wallet::new().unwrap());
+        let hash = Trade::cancel_transaction(client, Arc::clone(&wallet), 7)
+            .await
+            .unwrap();
+        assert_eq!(hash, "0x01");
+        let submitted = transport
+            .requests()
+            .into_iter()
+            .find(|r| r.method == "POST")
+            .unwrap()
+            .body
+            .unwrap();
+        let transaction = &submitted["transaction"];
+        assert_eq!(transaction["sequence_number"], "7");
+        assert_eq!(transaction["gas_unit_price"], "150");
+        assert_eq!(
+            transaction["payload"]["function"],
+            "0x1::aptos_account::transfer"
+        );
+        assert_eq!(transaction["payload"]["arguments"][0], wallet.address());
+        assert_eq!(submitted["signature"]["type"], "ed25519_signature");
+    }
+
+    #[test]
+    fn test_resource_writes() {
+        let writes = Trade::resource_writes(&transfer_transaction());
+        assert_eq!(writes.len(), 2);
+        assert_eq!(
+            writes[0].0,
+            "0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>"
+        );
+        assert_eq!(writes[0].1, "0x0a");
+        assert_eq!(writes[0].2["coin"]["value"], "900");
+    }
+
+    #[test]
+    fn test_balance_changes() {
+        let transaction = transfer_transaction();
+        let sender = Trade::balance_changes(&transaction, "0xa");
+        assert_eq!(
+            sender,
+            vec![BalanceChange {
+                coin_type: "0x1::aptos_coin::AptosCoin".to_string(),
+                balance: 900,
+                delta: -600,
+            }]
+        );
+        let recipient = Trade::balance_changes(&transaction, "0x000b");
+        assert_eq!(recipient[0].balance, 100);
+        assert_eq!(recipient[0].delta, 100);
+        assert!(Trade::balance_changes(&transaction, "0xc").is_empty());
+    }
+
+    #[test]
+    fn test_create_multi_ed25519_signed_transaction_tx() {
+        let wallets: Vec<Wallet> = (0..3).map(|_| Wallet::new().unwrap()).collect();
+        let wallet = Arc::new(MultiEd25519Wallet::new(wallets, 2).unwrap());
+        let signature = wallet.sign(b"raw txn").unwrap();
+        let signed = Trade::create_multi_ed25519_signed_transaction_tx(
+            Arc::clone(&wallet),
+            json!({"sender": wallet.address()}),
+            signature,
+        )
+        .unwrap();
+        match serde_json::from_value::<Signature>(signed["signature"].clone()).unwrap() {
+            Signature::MultiEd25519 {
+                public_keys,
+                signatures,
+                threshold,
+                bitmap,
+            } => {
+                assert_eq!(public_keys.len(), 3);
+                assert_eq!(signatures.len(), 2);
+                assert_eq!(threshold, 2);
+                assert_eq!(bitmap.as_deref(), Some("c0000000"));
+            }
+            other => panic!("unexpected signature {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_submit_single_key_signed_transaction_bcs() {
+        let transport = Arc::new(crate::transport::MockTransport::new().on_post(
+            "/transactions",
+            202,
+            &transfer_transaction_json().to_string(),
+        ));
+        let client = Aptos::with_transport(AptosType::Testnet, transport.clone());
+        let wallet = Arc::new(SingleKeyWallet::new().unwrap());
+        let raw_txn = json!({
+            "sender": wallet.address(),
+            "sequence_number": "4",
+            "max_gas_amount": "2000",
+            "gas_unit_price": "100",
+            "expiration_timestamp_secs": "1700000000",
+            "payload": {
+                "type": "entry_function_payload",
+                "function": "0x1::aptos_account::transfer",
+                "type_arguments": [],
+                "arguments": [wallet.address(), 5]
+            },
+            "chain_id": 2
+        });
+        let raw_txn_bcs = client.raw_transaction_bcs(&raw_txn).await.unwrap();
+        let message = crate::signing::signing_message(&raw_txn_bcs);
+        let signature = wallet.sign(&message).unwrap();
+        let bytes = Trade::create_single_key_signed_transaction_bcs(
+            Arc::clone(&wallet),
+            &raw_txn_bcs,
+            signature.clone(),
+        )
+        .unwrap();
+        client.submit_bcs_transaction(&bytes).await.unwrap();
+        let requests = transport.requests();
+        let (content_type, body) = requests[0].raw_body.clone().unwrap();
+        assert_eq!(content_type, "application/x.aptos.signed_transaction+bcs");
+        // raw transaction, single sender, single key, secp256k1 key (65 bytes) and
+        // secp256k1 signature (64 bytes)
+        let mut expected = raw_txn_bcs.clone();
+        expected.extend([4, 2, 1, 65]);
+        expected.extend(wallet.public_key_bytes());
+        expected.extend([1, 64]);
+        expected.extend(&signature);
+        assert_eq!(body, expected);
+        assert!(wallet.verify(&message, &signature));
+        assert!(
+            Trade::create_single_key_signed_transaction_bcs(wallet, &raw_txn_bcs, vec![0; 65])
+                .is_err()
+        );
+    }
+
     #[tokio::test]
     async fn test_get_specific_transaction() {
         let client = Aptos::new(AptosType::Mainnet);
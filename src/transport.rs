@@ -0,0 +1,371 @@
+/// Http transport used by the `Aptos` client.
+///
+/// The default transport is backed by `reqwest`, `MockTransport` returns canned
+/// responses so parsing logic can be tested without a live node.
+use crate::log::traced;
+use futures::future::BoxFuture;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Debug,
+    sync::Mutex,
+};
+
+/// http response
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: u16,
+    /// lowercase header names
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+impl HttpResponse {
+    pub fn new(status: u16, body: &str) -> Self {
+        HttpResponse {
+            status,
+            headers: HashMap::new(),
+            body: body.to_string(),
+        }
+    }
+
+    /// add a header
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.insert(name.to_lowercase(), value.to_string());
+        self
+    }
+
+    /// get a header by case insensitive name
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_lowercase()).map(|v| v.as_str())
+    }
+
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// deserialize the json body
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, String> {
+        serde_json::from_str(&self.body).map_err(|e| format!("response parsing error: {}", e))
+    }
+}
+
+/// http transport
+pub trait HttpTransport: Send + Sync + Debug {
+    /// send a get request
+    fn get<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<HttpResponse, String>>;
+
+    /// send a head request, transports without one send a get and drop the body
+    fn head<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<HttpResponse, String>> {
+        Box::pin(async move {
+            let mut response = self.get(url).await?;
+            response.body.clear();
+            Ok(response)
+        })
+    }
+
+    /// send a post request with a json body
+    fn post<'a>(
+        &'a self,
+        url: &'a str,
+        body: &'a Value,
+    ) -> BoxFuture<'a, Result<HttpResponse, String>>;
+
+    /// send a post request with a raw body, e.g. a bcs signed transaction, transports
+    /// only speaking json reject it
+    fn post_bytes<'a>(
+        &'a self,
+        _url: &'a str,
+        content_type: &'a str,
+        _body: &'a [u8],
+    ) -> BoxFuture<'a, Result<HttpResponse, String>> {
+        Box::pin(async move {
+            Err(format!(
+                "transport does not support {} request bodies",
+                content_type
+            ))
+        })
+    }
+}
+
+/// reqwest backed transport
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_client(client: reqwest::Client) -> Self {
+        ReqwestTransport { client }
+    }
+
+    async fn read_response(response: reqwest::Response) -> Result<HttpResponse, String> {
+        let status = response.status().as_u16();
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| {
+                value
+                    .to_str()
+                    .ok()
+                    .map(|v| (name.as_str().to_lowercase(), v.to_string()))
+            })
+            .collect();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| format!("http error: {}", e))?;
+        Ok(HttpResponse {
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+impl HttpTransport for ReqwestTransport {
+    fn get<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<HttpResponse, String>> {
+        Box::pin(traced("GET", url, async move {
+            let response = self
+                .client
+                .get(url)
+                .send()
+                .await
+                .map_err(|e| format!("http error: {}", e))?;
+            Self::read_response(response).await
+        }))
+    }
+
+    fn head<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<HttpResponse, String>> {
+        Box::pin(traced("HEAD", url, async move {
+            let response = self
+                .client
+                .head(url)
+                .send()
+                .await
+                .map_err(|e| format!("http error: {}", e))?;
+            Self::read_response(response).await
+        }))
+    }
+
+    fn post<'a>(
+        &'a self,
+        url: &'a str,
+        body: &'a Value,
+    ) -> BoxFuture<'a, Result<HttpResponse, String>> {
+        Box::pin(traced("POST", url, async move {
+            let response = self
+                .client
+                .post(url)
+                .header("Content-Type", "application/json")
+                .json(body)
+                .send()
+                .await
+                .map_err(|e| format!("http error: {}", e))?;
+            Self::read_response(response).await
+        }))
+    }
+
+    fn post_bytes<'a>(
+        &'a self,
+        url: &'a str,
+        content_type: &'a str,
+        body: &'a [u8],
+    ) -> BoxFuture<'a, Result<HttpResponse, String>> {
+        Box::pin(traced("POST", url, async move {
+            let response = self
+                .client
+                .post(url)
+                .header("Content-Type", content_type)
+                .body(body.to_vec())
+                .send()
+                .await
+                .map_err(|e| format!("http error: {}", e))?;
+            Self::read_response(response).await
+        }))
+    }
+}
+
+/// recorded mock request
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+    pub method: String,
+    pub url: String,
+    pub body: Option<Value>,
+    /// raw body and its content type, for `post_bytes`
+    pub raw_body: Option<(String, Vec<u8>)>,
+}
+
+/// Transport returning canned responses, for tests.
+///
+/// A response is matched when the percent-decoded request url contains its
+/// registered path, the longest matching path wins. Several responses registered for the same path are
+/// returned in order, the last one is repeated. Unmatched requests get a 404. Head
+/// requests are answered by the get responses, without their body.
+#[derive(Debug, Default)]
+pub struct MockTransport {
+    responses: Mutex<Vec<(String, String, VecDeque<HttpResponse>)>>,
+    requests: Mutex<Vec<MockRequest>>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// register a response for get requests whose url contains `path`
+    pub fn on_get(self, path: &str, status: u16, body: &str) -> Self {
+        self.push("GET", path, HttpResponse::new(status, body));
+        self
+    }
+
+    /// register a response for post requests whose url contains `path`
+    pub fn on_post(self, path: &str, status: u16, body: &str) -> Self {
+        self.push("POST", path, HttpResponse::new(status, body));
+        self
+    }
+
+    /// register a full response
+    pub fn on(self, method: &str, path: &str, response: HttpResponse) -> Self {
+        self.push(method, path, response);
+        self
+    }
+
+    /// register a response on a shared transport
+    pub fn push(&self, method: &str, path: &str, response: HttpResponse) {
+        let mut responses = self.responses.lock().unwrap();
+        match responses
+            .iter_mut()
+            .find(|(m, p, _)| m.eq_ignore_ascii_case(method) && p == path)
+        {
+            Some((_, _, queue)) => queue.push_back(response),
+            None => responses.push((
+                method.to_uppercase(),
+                path.to_string(),
+                VecDeque::from([response]),
+            )),
+        }
+    }
+
+    /// requests received so far
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    fn respond(&self, request: MockRequest) -> HttpResponse {
+        let (method, url) = (request.method.clone(), percent_decode(&request.url));
+        self.requests.lock().unwrap().push(request);
+        let mut responses = self.responses.lock().unwrap();
+        let matched = responses
+            .iter_mut()
+            .filter(|(m, p, _)| {
+                (*m == method || (method == "HEAD" && m == "GET")) && url.contains(p.as_str())
+            })
+            .max_by_key(|(m, p, _)| (p.len(), *m == method));
+        match matched {
+            Some((_, _, queue)) if queue.len() > 1 => queue.pop_front().unwrap(),
+            Some((_, _, queue)) => queue.front().cloned().unwrap(),
+            None => HttpResponse::new(404, r#"{"message":"not found","error_code":"not_found"}"#),
+        }
+    }
+}
+
+impl HttpTransport for MockTransport {
+    fn get<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<HttpResponse, String>> {
+        Box::pin(async move {
+            Ok(self.respond(MockRequest {
+                method: "GET".to_string(),
+                url: url.to_string(),
+                body: None,
+                raw_body: None,
+            }))
+        })
+    }
+
+    fn head<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<HttpResponse, String>> {
+        Box::pin(async move {
+            let mut response = self.respond(MockRequest {
+                method: "HEAD".to_string(),
+                url: url.to_string(),
+                body: None,
+                raw_body: None,
+            });
+            response.body.clear();
+            Ok(response)
+        })
+    }
+
+    fn post<'a>(
+        &'a self,
+        url: &'a str,
+        body: &'a Value,
+    ) -> BoxFuture<'a, Result<HttpResponse, String>> {
+        Box::pin(async move {
+            Ok(self.respond(MockRequest {
+                method: "POST".to_string(),
+                url: url.to_string(),
+                body: Some(body.clone()),
+                raw_body: None,
+            }))
+        })
+    }
+
+    fn post_bytes<'a>(
+        &'a self,
+        url: &'a str,
+        content_type: &'a str,
+        body: &'a [u8],
+    ) -> BoxFuture<'a, Result<HttpResponse, String>> {
+        Box::pin(async move {
+            Ok(self.respond(MockRequest {
+                method: "POST".to_string(),
+                url: url.to_string(),
+                body: None,
+                raw_body: Some((content_type.to_string(), body.to_vec())),
+            }))
+        })
+    }
+}
+
+/// Percent-encode a url path segment such as a resource type, keeping unreserved
+/// characters and `:`.
+pub(crate) fn encode_path_segment(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b':' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn percent_decode(url: &str) -> String {
+    let bytes = url.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| url.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
@@ -1,9 +1,10 @@
 use std::collections::HashMap;
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use serde_json::Value;
+use sha3::{Digest, Sha3_256};
 
-use crate::trade::TransactionInfo;
+use crate::trade::{Signature, TransactionInfo};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccountInfo {
@@ -17,12 +18,70 @@ pub struct Resource {
     pub data: serde_json::Value,
 }
 
+/// 0x1::coin::CoinStore<T> resource data
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CoinStore {
+    pub coin: CoinValue,
+    #[serde(default)]
+    pub frozen: bool,
+    #[serde(default)]
+    pub deposit_events: EventHandle,
+    #[serde(default)]
+    pub withdraw_events: EventHandle,
+}
+
+/// coin or fungible asset metadata
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CoinInfo {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    /// `None` when the supply is untracked or held in a parallel aggregator
+    pub supply: Option<u128>,
+}
+
+/// 0x1::coin::Coin<T>
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CoinValue {
+    #[serde(deserialize_with = "crate::event::string_or_u64")]
+    pub value: u64,
+}
+
+/// 0x1::event::EventHandle
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventHandle {
+    #[serde(deserialize_with = "crate::event::string_or_u64")]
+    pub counter: u64,
+    pub guid: EventHandleGuid,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventHandleGuid {
+    pub id: EventHandleId,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventHandleId {
+    pub addr: String,
+    pub creation_num: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Module {
     pub bytecode: String,
     pub abi: Option<serde_json::Value>,
 }
 
+impl Module {
+    /// module name from the abi
+    pub fn name(&self) -> Option<&str> {
+        self.abi.as_ref()?.get("name")?.as_str()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChainInfo {
     pub chain_id: u8,
@@ -31,6 +90,129 @@ pub struct ChainInfo {
     pub ledger_timestamp: String,
     pub node_role: String,
     pub block_height: String,
+    #[serde(default)]
+    pub oldest_ledger_version: String,
+    #[serde(default)]
+    pub oldest_block_height: String,
+    #[serde(default)]
+    pub git_hash: Option<String>,
+}
+
+/// status of a transaction hash, see `Aptos::get_transaction_status`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxStatus {
+    /// the node does not know the hash, it was never submitted, expired or the node
+    /// has not seen it yet
+    NotFound,
+    /// in the mempool, not committed yet
+    Pending,
+    Success,
+    /// committed but aborted, with the vm status
+    Failed(String),
+}
+
+impl TxStatus {
+    /// committed, successfully or not
+    pub fn is_committed(&self) -> bool {
+        matches!(self, TxStatus::Success | TxStatus::Failed(_))
+    }
+}
+
+/// store an account holds for a coin or fungible asset, see `Aptos::get_store_status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreStatus {
+    /// no store yet, coins need a register first
+    Unregistered,
+    Registered,
+    /// the store exists but transfers in and out of it abort
+    Frozen,
+}
+
+impl StoreStatus {
+    /// the account can receive into this store
+    pub fn can_receive(&self) -> bool {
+        *self == StoreStatus::Registered
+    }
+}
+
+/// outcome of one transaction of `Aptos::simulate_batch`
+#[derive(Debug, Clone)]
+pub struct SimulationResult {
+    /// the simulated transaction executed without aborting
+    pub success: bool,
+    pub vm_status: String,
+    pub gas_used: u64,
+    /// `None` when the simulation request itself failed
+    pub transaction: Option<TransactionInfo>,
+    pub error: Option<String>,
+}
+
+impl From<Result<TransactionInfo, String>> for SimulationResult {
+    fn from(result: Result<TransactionInfo, String>) -> Self {
+        match result {
+            Ok(transaction) => SimulationResult {
+                success: transaction.success,
+                vm_status: transaction.vm_status.clone(),
+                gas_used: transaction.get_gas_used().unwrap_or(0),
+                transaction: Some(transaction),
+                error: None,
+            },
+            Err(e) => SimulationResult {
+                success: false,
+                vm_status: String::new(),
+                gas_used: 0,
+                transaction: None,
+                error: Some(e),
+            },
+        }
+    }
+}
+
+/// outcome of `Aptos::submit_transaction_idempotent`
+#[derive(Debug, Clone)]
+pub enum TxSubmission {
+    /// submitted by this call
+    Submitted(TransactionInfo),
+    /// a transaction with the same sender and sequence number is already committed
+    Committed(TransactionInfo),
+    /// a transaction with the same sender and sequence number is still in the mempool
+    Pending { hash: String },
+}
+
+impl TxSubmission {
+    pub fn hash(&self) -> &str {
+        match self {
+            TxSubmission::Submitted(transaction) | TxSubmission::Committed(transaction) => {
+                &transaction.hash
+            }
+            TxSubmission::Pending { hash } => hash,
+        }
+    }
+}
+
+/// object owned by an account, see `Aptos::get_owned_objects`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectInfo {
+    pub object_address: String,
+    /// main resource of the object, e.g. `0x4::token::Token`
+    pub r#type: String,
+    pub owner: String,
+}
+
+/// node health, see `Aptos::health`
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthStatus {
+    /// the node answered its `/-/healthy` check
+    pub healthy: bool,
+    pub chain_id: u8,
+    pub epoch: u64,
+    pub ledger_version: u64,
+    /// versions below this were pruned
+    pub oldest_ledger_version: u64,
+    /// ledger timestamp in microseconds
+    pub ledger_timestamp: u64,
+    pub node_role: String,
+    pub git_hash: Option<String>,
 }
 
 // #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,6 +239,9 @@ pub struct Event {
     pub sequence_number: String,
     pub r#type: String,
     pub data: serde_json::Value,
+    /// version of the emitting transaction, set by the account events endpoints
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -66,6 +251,44 @@ pub struct GasEstimation {
     pub prioritized_gas_estimate: Option<u64>,
 }
 
+/// Gas parameters of `0x1::gas_schedule::GasScheduleV2` by name, e.g.
+/// `"txn.storage_fee_per_state_byte"`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GasSchedule {
+    pub feature_version: u64,
+    pub entries: HashMap<String, u64>,
+}
+
+impl GasSchedule {
+    /// parse the data of the `GasScheduleV2` resource
+    pub fn from_resource(data: &Value) -> Result<Self, String> {
+        #[derive(Deserialize)]
+        struct Entry {
+            key: String,
+            val: u64,
+        }
+        #[derive(Deserialize)]
+        struct GasScheduleV2 {
+            feature_version: u64,
+            entries: Vec<Entry>,
+        }
+        let schedule: GasScheduleV2 =
+            crate::decode_move_value(data.clone()).map_err(|e| format!("gas schedule: {}", e))?;
+        Ok(GasSchedule {
+            feature_version: schedule.feature_version,
+            entries: schedule
+                .entries
+                .into_iter()
+                .map(|entry| (entry.key, entry.val))
+                .collect(),
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Option<u64> {
+        self.entries.get(name).copied()
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct ViewRequest {
     pub function: String,
@@ -73,6 +296,29 @@ pub struct ViewRequest {
     pub arguments: Vec<serde_json::Value>,
 }
 
+impl ViewRequest {
+    /// new view request for `address::module::function`
+    pub fn new(function: &str) -> Self {
+        ViewRequest {
+            function: function.to_string(),
+            type_arguments: Vec::new(),
+            arguments: Vec::new(),
+        }
+    }
+
+    /// add a type argument
+    pub fn type_arg(mut self, type_argument: &str) -> Self {
+        self.type_arguments.push(type_argument.to_string());
+        self
+    }
+
+    /// add an argument
+    pub fn arg(mut self, argument: impl Into<serde_json::Value>) -> Self {
+        self.arguments.push(argument.into());
+        self
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct TableRequest {
     pub key_type: String,
@@ -80,13 +326,278 @@ pub struct TableRequest {
     pub key: serde_json::Value,
 }
 
+/// signing scheme of an account
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountScheme {
+    Ed25519,
+    MultiEd25519,
+    SingleKey,
+    MultiKey,
+    /// an on-chain `0x1::multisig_account`, owners approve its transactions
+    MultisigAccount,
+    /// the account never sent a transaction, its key is not known on chain
+    Unknown,
+}
+
+impl AccountScheme {
+    /// scheme of the sender of a transaction signature
+    pub fn from_signature(signature: &Signature) -> Self {
+        match signature {
+            Signature::Ed25519 { .. } => AccountScheme::Ed25519,
+            Signature::MultiEd25519 { .. } => AccountScheme::MultiEd25519,
+            Signature::SingleKey { .. } => AccountScheme::SingleKey,
+            Signature::MultiKey { .. } => AccountScheme::MultiKey,
+            Signature::FeePayer { sender, .. } => AccountScheme::from_signature(sender),
+        }
+    }
+
+    /// Authentication key of the sender of a transaction signature,
+    /// `sha3_256(public key bytes || scheme)`. Single and multi key public keys are
+    /// bcs `AnyPublicKey`s. `None` when a key is not valid hex.
+    pub fn authentication_key(signature: &Signature) -> Option<String> {
+        let key = |hex_key: &str| hex::decode(hex_key.trim_start_matches("0x")).ok();
+        let mut hasher = Sha3_256::new();
+        match signature {
+            Signature::Ed25519 { public_key, .. } => {
+                hasher.update(key(public_key)?);
+                hasher.update([0u8]);
+            }
+            Signature::MultiEd25519 {
+                public_keys,
+                threshold,
+                ..
+            } => {
+                for public_key in public_keys {
+                    hasher.update(key(public_key)?);
+                }
+                hasher.update([*threshold, 1u8]);
+            }
+            Signature::SingleKey { public_key, .. } => {
+                hasher.update(key(public_key)?);
+                hasher.update([2u8]);
+            }
+            Signature::MultiKey {
+                public_keys,
+                threshold,
+                ..
+            } => {
+                // bcs vector length, at most 32 keys fit in one uleb128 byte
+                hasher.update([public_keys.len() as u8]);
+                for public_key in public_keys {
+                    hasher.update(key(public_key)?);
+                }
+                hasher.update([*threshold, 3u8]);
+            }
+            Signature::FeePayer { sender, .. } => return AccountScheme::authentication_key(sender),
+        }
+        Some(format!("0x{}", hex::encode(hasher.finalize())))
+    }
+}
+
+/// whether a call targets a view or an entry function
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CallKind {
+    View,
+    #[default]
+    Entry,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContractCall {
     pub module_address: String,
     pub module_name: String,
     pub function_name: String,
     pub type_arguments: Vec<String>,
-    pub arguments: Vec<Value>,
+    pub arguments: Vec<MoveArgument>,
+    #[serde(default)]
+    pub kind: CallKind,
+}
+
+impl ContractCall {
+    /// call of a `#[view]` function, for `Contract::read`
+    pub fn view(
+        module_address: &str,
+        module_name: &str,
+        function_name: &str,
+        type_arguments: Vec<String>,
+        arguments: Vec<Value>,
+    ) -> Self {
+        ContractCall {
+            module_address: module_address.to_string(),
+            module_name: module_name.to_string(),
+            function_name: function_name.to_string(),
+            type_arguments,
+            arguments: arguments.into_iter().map(MoveArgument::from).collect(),
+            kind: CallKind::View,
+        }
+    }
+
+    /// call of an entry function, for `Contract::write`
+    pub fn entry(
+        module_address: &str,
+        module_name: &str,
+        function_name: &str,
+        type_arguments: Vec<String>,
+        arguments: Vec<Value>,
+    ) -> Self {
+        ContractCall {
+            kind: CallKind::Entry,
+            ..Self::view(
+                module_address,
+                module_name,
+                function_name,
+                type_arguments,
+                arguments,
+            )
+        }
+    }
+
+    /// entry function call built from typed arguments, e.g.
+    /// `ContractCall::builder("0x1", "coin", "transfer").type_arg(APT).arg_address(to).arg_u64(100).build()`
+    pub fn builder(
+        module_address: &str,
+        module_name: &str,
+        function_name: &str,
+    ) -> ContractCallBuilder {
+        ContractCallBuilder {
+            call: Self::entry(module_address, module_name, function_name, vec![], vec![]),
+        }
+    }
+
+    /// `address::module::function`
+    pub fn function_id(&self) -> String {
+        format!(
+            "{}::{}::{}",
+            self.module_address, self.module_name, self.function_name
+        )
+    }
+
+    /// json arguments, as the node api takes them
+    pub fn json_arguments(&self) -> Vec<Value> {
+        self.arguments.iter().map(MoveArgument::to_json).collect()
+    }
+
+    /// json `entry_function_payload` of the call
+    pub fn entry_function_payload(&self) -> Value {
+        serde_json::json!({
+            "type": "entry_function_payload",
+            "function": self.function_id(),
+            "type_arguments": self.type_arguments,
+            "arguments": self.json_arguments()
+        })
+    }
+}
+
+/// Argument of a `ContractCall`. Typed arguments are BCS encoded by their own type
+/// when signing, `Json` ones by the type of the parameter they are passed to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MoveArgument {
+    U8(u8),
+    U64(u64),
+    U128(u128),
+    Bool(bool),
+    Address(String),
+    String(String),
+    /// already encoded json argument
+    Json(Value),
+}
+
+impl MoveArgument {
+    /// json encoding of the node api, 64 and 128 bit integers as decimal strings
+    pub fn to_json(&self) -> Value {
+        match self {
+            MoveArgument::U8(value) => Value::from(*value),
+            MoveArgument::U64(value) => Value::String(value.to_string()),
+            MoveArgument::U128(value) => Value::String(value.to_string()),
+            MoveArgument::Bool(value) => Value::Bool(*value),
+            MoveArgument::Address(address) => Value::String(address.clone()),
+            MoveArgument::String(value) => Value::String(value.clone()),
+            MoveArgument::Json(value) => value.clone(),
+        }
+    }
+}
+
+impl From<Value> for MoveArgument {
+    fn from(value: Value) -> Self {
+        MoveArgument::Json(value)
+    }
+}
+
+/// compares the json encoding
+impl PartialEq<Value> for MoveArgument {
+    fn eq(&self, other: &Value) -> bool {
+        self.to_json() == *other
+    }
+}
+
+impl Serialize for MoveArgument {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_json().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for MoveArgument {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Value::deserialize(deserializer).map(MoveArgument::Json)
+    }
+}
+
+/// Builds a `ContractCall` from typed arguments.
+#[derive(Debug, Clone)]
+pub struct ContractCallBuilder {
+    call: ContractCall,
+}
+
+impl ContractCallBuilder {
+    pub fn type_arg(mut self, type_argument: &str) -> Self {
+        self.call.type_arguments.push(type_argument.to_string());
+        self
+    }
+
+    pub fn arg_u8(self, value: u8) -> Self {
+        self.arg(MoveArgument::U8(value))
+    }
+
+    pub fn arg_u64(self, value: u64) -> Self {
+        self.arg(MoveArgument::U64(value))
+    }
+
+    pub fn arg_u128(self, value: u128) -> Self {
+        self.arg(MoveArgument::U128(value))
+    }
+
+    pub fn arg_bool(self, value: bool) -> Self {
+        self.arg(MoveArgument::Bool(value))
+    }
+
+    pub fn arg_address(self, address: &str) -> Self {
+        self.arg(MoveArgument::Address(address.to_string()))
+    }
+
+    pub fn arg_string(self, value: &str) -> Self {
+        self.arg(MoveArgument::String(value.to_string()))
+    }
+
+    pub fn arg(mut self, argument: MoveArgument) -> Self {
+        self.call.arguments.push(argument);
+        self
+    }
+
+    /// target a `#[view]` function instead of an entry function
+    pub fn view(mut self) -> Self {
+        self.call.kind = CallKind::View;
+        self
+    }
+
+    /// arguments recorded so far
+    pub fn arguments(&self) -> &[MoveArgument] {
+        &self.call.arguments
+    }
+
+    pub fn build(self) -> ContractCall {
+        self.call
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -101,14 +612,84 @@ pub struct ContractWriteResult {
     pub success: bool,
     pub transaction_hash: String,
     pub gas_used: String,
+    /// gas unit price of the confirmed transaction, in octas
+    #[serde(default)]
+    pub gas_unit_price: Option<u64>,
     pub events: Vec<Value>,
     pub error: Option<String>,
 }
 
+impl ContractReadResult {
+    /// return value at `index` of the view output
+    pub fn value(&self, index: usize) -> Result<&Value, String> {
+        if !self.success {
+            return Err(self
+                .error
+                .clone()
+                .unwrap_or_else(|| "contract read failed".to_string()));
+        }
+        let value = match &self.data {
+            Value::Array(values) => values.get(index),
+            value if index == 0 => Some(value),
+            _ => None,
+        };
+        value.ok_or_else(|| format!("view returned no value at index {}", index))
+    }
+
+    /// decode the return value at `index`, integers may be encoded as json strings
+    pub fn get<T: DeserializeOwned>(&self, index: usize) -> Result<T, String> {
+        crate::decode_move_value(self.value(index)?.clone())
+            .map_err(|e| format!("return value {}: {}", index, e))
+    }
+
+    /// decode the whole view output, e.g. into `(u64, u64)` for a function returning
+    /// two values
+    pub fn decode<T: DeserializeOwned>(&self) -> Result<T, String> {
+        if !self.success {
+            return Err(self
+                .error
+                .clone()
+                .unwrap_or_else(|| "contract read failed".to_string()));
+        }
+        crate::decode_move_value(self.data.clone())
+    }
+
+    pub fn as_u64(&self, index: usize) -> Result<u64, String> {
+        self.get(index)
+    }
+
+    pub fn as_u128(&self, index: usize) -> Result<u128, String> {
+        self.get(index)
+    }
+
+    pub fn as_str(&self, index: usize) -> Result<&str, String> {
+        self.value(index)?
+            .as_str()
+            .ok_or_else(|| format!("return value {} is not a string", index))
+    }
+
+    pub fn as_bool(&self, index: usize) -> Result<bool, String> {
+        self.value(index)?
+            .as_bool()
+            .ok_or_else(|| format!("return value {} is not a bool", index))
+    }
+}
+
 impl ContractWriteResult {
     pub fn gas_used_as_u64(&self) -> u64 {
         self.gas_used.parse::<u64>().unwrap_or(0)
     }
+
+    /// transaction fee in octas, `gas_used * gas_unit_price`
+    pub fn fee_octas(&self) -> Option<u64> {
+        let gas_used = self.gas_used.parse::<u64>().ok()?;
+        gas_used.checked_mul(self.gas_unit_price?)
+    }
+
+    /// transaction fee in APT
+    pub fn fee_apt(&self) -> Option<f64> {
+        self.fee_octas().map(|fee| fee as f64 / 100_000_000.0)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -137,3 +718,53 @@ pub struct RawTransactionForSigning {
     pub expiration_timestamp_secs: u64,
     pub chain_id: u8,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_contract_call_builder_arguments() {
+        let builder = ContractCall::builder("0x1", "coin", "transfer")
+            .type_arg("0x1::aptos_coin::AptosCoin")
+            .arg_address("0xb")
+            .arg_u64(u64::MAX)
+            .arg_u128(u128::MAX)
+            .arg_u8(7)
+            .arg_bool(true)
+            .arg_string("memo");
+        assert_eq!(builder.arguments()[0], MoveArgument::Address("0xb".into()));
+        let call = builder.build();
+        assert_eq!(call.kind, CallKind::Entry);
+        assert_eq!(call.function_id(), "0x1::coin::transfer");
+        assert_eq!(call.type_arguments, vec!["0x1::aptos_coin::AptosCoin"]);
+        assert_eq!(call.arguments[1], MoveArgument::U64(u64::MAX));
+        assert_eq!(
+            call.json_arguments(),
+            vec![
+                json!("0xb"),
+                json!(u64::MAX.to_string()),
+                json!(u128::MAX.to_string()),
+                json!(7),
+                json!(true),
+                json!("memo"),
+            ]
+        );
+        // typed arguments survive a json round trip as their json encoding
+        let decoded: ContractCall =
+            serde_json::from_value(serde_json::to_value(&call).unwrap()).unwrap();
+        assert_eq!(decoded.arguments, call.json_arguments());
+    }
+
+    #[test]
+    fn test_contract_call_builder_view() {
+        let call = ContractCall::builder("0x1", "coin", "balance")
+            .type_arg("0x1::aptos_coin::AptosCoin")
+            .arg(MoveArgument::Json(json!(["0xa"])))
+            .view()
+            .build();
+        assert_eq!(call.kind, CallKind::View);
+        assert_eq!(call.arguments, vec![json!(["0xa"])]);
+    }
+}
@@ -1,12 +1,155 @@
+use aptos_network_tool::address::address_to_bytes;
 use ring::signature::Ed25519KeyPair;
 use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
 use sha3::{Digest, Sha3_256};
+use std::sync::Arc;
+
+use crate::{Aptos, contract::Contract, trade::Signature, types::ContractCall};
 
 use ring::signature::KeyPair;
 
+/// prefix of off-chain signed messages
+pub const SIGNED_MESSAGE_PREFIX: &str = "APTOS\nmessage: ";
+
+/// off-chain signed message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedMessage {
+    /// hex encoded ed25519 signature
+    pub signature: String,
+    /// hex encoded public key
+    pub public_key: String,
+    /// the prefixed message that was signed
+    pub full_message: String,
+}
+
+/// build the prefixed message that gets signed
+pub fn full_message(message: &str) -> String {
+    format!("{}{}", SIGNED_MESSAGE_PREFIX, message)
+}
+
+/// Verify a signature made with `Wallet::sign_message`.
+///
+/// `message` is the original message, the prefix is added here. Keys and signatures
+/// are hex, with or without `0x`.
+pub fn verify_signature(public_key: &str, message: &str, signature: &str) -> bool {
+    let (Ok(public_key), Ok(signature)) = (
+        hex::decode(public_key.trim_start_matches("0x")),
+        hex::decode(signature.trim_start_matches("0x")),
+    ) else {
+        return false;
+    };
+    ring::signature::UnparsedPublicKey::new(&ring::signature::ED25519, &public_key)
+        .verify(full_message(message).as_bytes(), &signature)
+        .is_ok()
+}
+
+/// Format an address per AIP-40.
+///
+/// Special addresses `0x0` to `0xf` use the short form, every other address the
+/// long form of 64 lowercase hex digits. AIP-40 has no mixed case checksum, the
+/// canonical long form is what wallets display and compare.
+pub fn format_address_aip40(address: &str) -> Result<String, String> {
+    let hex_part = address
+        .strip_prefix("0x")
+        .or_else(|| address.strip_prefix("0X"))
+        .unwrap_or(address);
+    if hex_part.is_empty()
+        || hex_part.len() > 64
+        || !hex_part.chars().all(|c| c.is_ascii_hexdigit())
+    {
+        return Err(format!("Invalid address: {}", address));
+    }
+    let hex_part = hex_part.to_lowercase();
+    let trimmed = hex_part.trim_start_matches('0');
+    if trimmed.len() <= 1 {
+        let last = trimmed.chars().next().unwrap_or('0');
+        return Ok(format!("0x{}", last));
+    }
+    Ok(format!("0x{:0>64}", hex_part))
+}
+
+/// AIP-80 prefix of ed25519 private keys
+pub const AIP80_ED25519_PREFIX: &str = "ed25519-priv-";
+
+/// AIP-80 prefix of secp256k1 private keys
+pub const AIP80_SECP256K1_PREFIX: &str = "secp256k1-priv-";
+
+/// Parse a 32 byte private key from hex, with or without `0x`, in either case, or in
+/// the AIP-80 form `<scheme>-priv-0x...` for the given prefix.
+fn parse_private_key(private_key: &str, aip80_prefix: &str) -> Result<[u8; 32], String> {
+    let key = private_key.trim();
+    let key = match key.split_once("-priv-") {
+        Some((scheme, rest)) if format!("{}-priv-", scheme).eq_ignore_ascii_case(aip80_prefix) => {
+            rest
+        }
+        Some((scheme, _)) => {
+            return Err(format!(
+                "Invalid private key: expected a {}... key, got a {} key",
+                aip80_prefix, scheme
+            ));
+        }
+        None => key,
+    };
+    let hex_part = key
+        .strip_prefix("0x")
+        .or_else(|| key.strip_prefix("0X"))
+        .unwrap_or(key);
+    let bytes = hex::decode(hex_part).map_err(|e| format!("Invalid private key hex: {}", e))?;
+    bytes.try_into().map_err(|bytes: Vec<u8>| {
+        format!(
+            "Invalid private key length: {} bytes, expected 32",
+            bytes.len()
+        )
+    })
+}
+
+/// PKCS8 v2 document around an ed25519 seed and its public key, as `ring` generates
+fn ed25519_pkcs8(seed: &[u8; 32]) -> Result<Vec<u8>, String> {
+    const PREFIX: [u8; 16] = [
+        0x30, 0x53, 0x02, 0x01, 0x01, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22, 0x04,
+        0x20,
+    ];
+    const PUBLIC_KEY_TAG: [u8; 5] = [0xa1, 0x23, 0x03, 0x21, 0x00];
+    let keypair = Ed25519KeyPair::from_seed_unchecked(seed)
+        .map_err(|e| format!("Invalid ed25519 private key: {}", e))?;
+    let mut pkcs8 = PREFIX.to_vec();
+    pkcs8.extend_from_slice(seed);
+    pkcs8.extend_from_slice(&PUBLIC_KEY_TAG);
+    pkcs8.extend_from_slice(keypair.public_key().as_ref());
+    Ok(pkcs8)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "WalletKeypair")]
 pub struct Wallet {
     keypair: Vec<u8>,
+    /// derived once from the keypair
+    #[serde(skip_serializing)]
+    address: String,
+    /// account the key was rotated onto, see `with_account_address`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    account_address: Option<String>,
+}
+
+/// serialized form of `Wallet`, the address is derived again on load
+#[derive(Deserialize)]
+struct WalletKeypair {
+    keypair: Vec<u8>,
+    #[serde(default)]
+    account_address: Option<String>,
+}
+
+impl TryFrom<WalletKeypair> for Wallet {
+    type Error = String;
+
+    fn try_from(serialized: WalletKeypair) -> Result<Self, String> {
+        let wallet = Wallet::from_pkcs8_bytes(&serialized.keypair)?;
+        match serialized.account_address {
+            Some(account_address) => wallet.with_account_address(&account_address),
+            None => Ok(wallet),
+        }
+    }
 }
 
 impl Wallet {
@@ -15,22 +158,51 @@ impl Wallet {
         let rng = ring::rand::SystemRandom::new();
         let pkcs8_bytes = Ed25519KeyPair::generate_pkcs8(&rng)
             .map_err(|e| format!("Failed to generate keypair: {}", e))?;
-
-        Ok(Wallet {
-            keypair: pkcs8_bytes.as_ref().to_vec(),
-        })
+        Self::from_pkcs8_bytes(pkcs8_bytes.as_ref())
     }
 
     pub fn from_pkcs8_bytes(pkcs8_bytes: &[u8]) -> Result<Self, String> {
-        let _ = Ed25519KeyPair::from_pkcs8(pkcs8_bytes)
+        let keypair = Ed25519KeyPair::from_pkcs8(pkcs8_bytes)
             .map_err(|e| format!("Invalid PKCS8 format: {}", e))?;
-
+        let mut hasher = Sha3_256::new();
+        hasher.update(keypair.public_key().as_ref());
+        hasher.update([0u8]);
         Ok(Wallet {
             keypair: pkcs8_bytes.to_vec(),
+            address: format!("0x{}", hex::encode(hasher.finalize())),
+            account_address: None,
         })
     }
 
-    /// create wallet from private key
+    /// Sign for the account at `account_address` instead of the one derived from
+    /// the key, for an account whose authentication key was rotated to this key,
+    /// see `rotate_authentication_key`.
+    pub fn with_account_address(mut self, account_address: &str) -> Result<Self, String> {
+        let address = format_address_aip40(account_address)?;
+        self.account_address = Some(format!("0x{:0>64}", address.trim_start_matches("0x")));
+        Ok(self)
+    }
+
+    /// Create a wallet from a 32 byte ed25519 private key.
+    ///
+    /// Accepts hex with or without `0x`, in either case, and AIP-80 keys such as
+    /// `ed25519-priv-0x...`.
+    pub fn from_private_key(private_key: &str) -> Result<Self, String> {
+        Self::from_private_key_bytes(&parse_private_key(private_key, AIP80_ED25519_PREFIX)?)
+    }
+
+    /// create a wallet from the raw 32 byte ed25519 private key
+    pub fn from_private_key_bytes(private_key: &[u8]) -> Result<Self, String> {
+        let seed: &[u8; 32] = private_key.try_into().map_err(|_| {
+            format!(
+                "Invalid private key length: {} bytes, expected 32",
+                private_key.len()
+            )
+        })?;
+        Self::from_pkcs8_bytes(&ed25519_pkcs8(seed)?)
+    }
+
+    /// create wallet from a hex encoded PKCS8 keypair, as `private_key_hex` returns
     pub fn from_private_key_hex(private_key_hex: &str) -> Result<Self, String> {
         let pkcs8_bytes =
             hex::decode(private_key_hex).map_err(|e| format!("Invalid hex: {}", e))?;
@@ -64,14 +236,15 @@ impl Wallet {
         Ok(hex::encode(public_key))
     }
 
-    /// get public key address
-    pub fn address(&self) -> Result<String, String> {
-        let public_key = self.public_key_bytes()?;
-        let mut hasher = Sha3_256::new();
-        hasher.update(&public_key);
-        hasher.update(&[0u8]);
-        let result = hasher.finalize();
-        Ok(format!("0x{}", hex::encode(result)))
+    /// get the account address, derived when the wallet is built unless set by
+    /// `with_account_address`
+    pub fn address(&self) -> &str {
+        self.account_address.as_deref().unwrap_or(&self.address)
+    }
+
+    /// get the address in the AIP-40 display format
+    pub fn address_checksummed(&self) -> String {
+        format_address_aip40(self.address()).unwrap_or_else(|_| self.address().to_string())
     }
 
     /// sign
@@ -81,6 +254,26 @@ impl Wallet {
         Ok(signature.as_ref().to_vec())
     }
 
+    /// Sign a transaction signing message, e.g. from `Trade::serialize_for_signing`,
+    /// without a client. Attach the result with `Trade::attach_signature`.
+    pub fn sign_raw(&self, message: &[u8]) -> Result<Signature, String> {
+        Ok(Signature::Ed25519 {
+            public_key: self.public_key_hex()?,
+            signature: hex::encode(self.sign(message)?),
+        })
+    }
+
+    /// sign an off-chain message, e.g. a login nonce
+    pub fn sign_message(&self, message: &str) -> Result<SignedMessage, String> {
+        let full_message = full_message(message);
+        let signature = self.sign(full_message.as_bytes())?;
+        Ok(SignedMessage {
+            signature: hex::encode(signature),
+            public_key: self.public_key_hex()?,
+            full_message,
+        })
+    }
+
     /// verify message
     pub fn verify(&self, message: &[u8], signature: &[u8]) -> Result<bool, String> {
         let public_key = self.public_key_bytes()?;
@@ -115,3 +308,641 @@ impl Wallet {
         }
     }
 }
+
+/// authentication key scheme of ed25519 keys, as passed to `rotate_authentication_key`
+pub const ED25519_SCHEME: u8 = 0;
+
+/// `0x1::account::RotationProofChallenge` wrapped in its `SignedMessage` type info
+#[derive(Serialize)]
+struct RotationProofChallenge {
+    type_address: [u8; 32],
+    module_name: Vec<u8>,
+    struct_name: Vec<u8>,
+    sequence_number: u64,
+    originator: [u8; 32],
+    current_auth_key: [u8; 32],
+    new_public_key: Vec<u8>,
+}
+
+/// BCS bytes of the rotation proof challenge both keys sign to rotate the
+/// authentication key of `originator`
+pub fn rotation_proof_challenge(
+    sequence_number: u64,
+    originator: &str,
+    current_auth_key: &str,
+    new_public_key: &[u8],
+) -> Result<Vec<u8>, String> {
+    // short addresses such as `0x1` are zero padded to 32 bytes
+    let address = |address: &str| {
+        let hex_part = address.trim_start_matches("0x");
+        address_to_bytes(&format!("0x{:0>64}", hex_part))
+    };
+    let challenge = RotationProofChallenge {
+        type_address: address("0x1")?,
+        module_name: b"account".to_vec(),
+        struct_name: b"RotationProofChallenge".to_vec(),
+        sequence_number,
+        originator: address(originator)?,
+        current_auth_key: address(current_auth_key)?,
+        new_public_key: new_public_key.to_vec(),
+    };
+    bcs::to_bytes(&challenge).map_err(|e| format!("bcs error: {}", e))
+}
+
+impl Wallet {
+    /// Rotate the authentication key of `current`'s account to `new_key`.
+    ///
+    /// Both keys sign the rotation proof challenge built from the account's current
+    /// sequence number and authentication key. The account keeps its address, so
+    /// after rotation it is driven by
+    /// `new_key.with_account_address(current.address())`; `new_key.address()` alone
+    /// is a different, unrelated account. If the new key is lost the account is
+    /// unusable, keep `new_key` backed up before calling this.
+    pub async fn rotate_authentication_key(
+        client: Arc<Aptos>,
+        current: Arc<Wallet>,
+        new_key: &Wallet,
+    ) -> Result<Value, String> {
+        let account = client.get_account_info(current.address()).await?;
+        let sequence_number = account
+            .sequence_number
+            .parse::<u64>()
+            .map_err(|e| format!("invalid sequence number: {}", e))?;
+        let new_public_key = new_key.public_key_bytes()?;
+        let challenge = rotation_proof_challenge(
+            sequence_number,
+            current.address(),
+            &account.authentication_key,
+            &new_public_key,
+        )?;
+        let call = Self::rotation_call(
+            &current.public_key_bytes()?,
+            &new_public_key,
+            &current.sign(&challenge)?,
+            &new_key.sign(&challenge)?,
+        );
+        Contract::write(client, current, call)
+            .await
+            .map(|result| json!(result))
+    }
+
+    /// build the `0x1::account::rotate_authentication_key` call from both proofs
+    fn rotation_call(
+        current_public_key: &[u8],
+        new_public_key: &[u8],
+        cap_rotate_key: &[u8],
+        cap_update_table: &[u8],
+    ) -> ContractCall {
+        let hex_arg = |bytes: &[u8]| json!(format!("0x{}", hex::encode(bytes)));
+        ContractCall::entry(
+            "0x1",
+            "account",
+            "rotate_authentication_key",
+            vec![],
+            vec![
+                json!(ED25519_SCHEME),
+                hex_arg(current_public_key),
+                json!(ED25519_SCHEME),
+                hex_arg(new_public_key),
+                hex_arg(cap_rotate_key),
+                hex_arg(cap_update_table),
+            ],
+        )
+    }
+}
+
+/// max number of keys in a multi-ed25519 account
+pub const MAX_MULTI_ED25519_KEYS: usize = 32;
+
+/// multi-ed25519 signature, the signatures are ordered by key index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiEd25519Signature {
+    pub signatures: Vec<Vec<u8>>,
+    /// bit `i` (most significant bit first) is set when key `i` signed
+    pub bitmap: [u8; 4],
+}
+
+impl MultiEd25519Signature {
+    /// combine `(key index, signature)` pairs, e.g. collected from several co-signers
+    pub fn new(mut parts: Vec<(u8, Vec<u8>)>) -> Result<Self, String> {
+        parts.sort_by_key(|(index, _)| *index);
+        let mut bitmap = [0u8; 4];
+        for (index, _) in &parts {
+            let index = *index as usize;
+            if index >= MAX_MULTI_ED25519_KEYS {
+                return Err(format!("key index out of range: {}", index));
+            }
+            if bitmap[index / 8] & (0x80 >> (index % 8)) != 0 {
+                return Err(format!("duplicate signature for key index: {}", index));
+            }
+            bitmap[index / 8] |= 0x80 >> (index % 8);
+        }
+        Ok(MultiEd25519Signature {
+            signatures: parts.into_iter().map(|(_, sig)| sig).collect(),
+            bitmap,
+        })
+    }
+
+    /// key indexes present in the bitmap
+    pub fn signer_indexes(&self) -> Vec<u8> {
+        (0..MAX_MULTI_ED25519_KEYS as u8)
+            .filter(|i| self.bitmap[*i as usize / 8] & (0x80 >> (i % 8)) != 0)
+            .collect()
+    }
+
+    /// concatenated signatures followed by the bitmap
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = self.signatures.concat();
+        bytes.extend_from_slice(&self.bitmap);
+        bytes
+    }
+}
+
+/// K-of-N multi-ed25519 wallet.
+///
+/// Holds the public keys of every owner and the private keys available locally,
+/// which may be fewer than the threshold when signatures are collected from
+/// several parties with `MultiEd25519Signature::new`.
+#[derive(Debug, Clone)]
+pub struct MultiEd25519Wallet {
+    public_keys: Vec<Vec<u8>>,
+    signers: Vec<(u8, Wallet)>,
+    threshold: u8,
+}
+
+impl MultiEd25519Wallet {
+    /// create from wallets holding every key
+    pub fn new(wallets: Vec<Wallet>, threshold: u8) -> Result<Self, String> {
+        let public_keys = wallets
+            .iter()
+            .map(|w| w.public_key_hex())
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::from_public_keys(&public_keys, wallets, threshold)
+    }
+
+    /// create from all owner public keys (hex) and the locally held wallets
+    pub fn from_public_keys(
+        public_keys: &[String],
+        wallets: Vec<Wallet>,
+        threshold: u8,
+    ) -> Result<Self, String> {
+        if public_keys.is_empty() || public_keys.len() > MAX_MULTI_ED25519_KEYS {
+            return Err(format!("invalid number of keys: {}", public_keys.len()));
+        }
+        if threshold == 0 || threshold as usize > public_keys.len() {
+            return Err(format!(
+                "invalid threshold: {} of {}",
+                threshold,
+                public_keys.len()
+            ));
+        }
+        let public_keys = public_keys
+            .iter()
+            .map(|k| {
+                hex::decode(k.trim_start_matches("0x")).map_err(|e| format!("Invalid hex: {}", e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut signers = Vec::new();
+        for wallet in wallets {
+            let public_key = wallet.public_key_bytes()?;
+            let index = public_keys
+                .iter()
+                .position(|k| *k == public_key)
+                .ok_or_else(|| {
+                    format!("wallet key is not an owner: {}", hex::encode(&public_key))
+                })?;
+            signers.push((index as u8, wallet));
+        }
+        signers.sort_by_key(|(index, _)| *index);
+        signers.dedup_by_key(|(index, _)| *index);
+        Ok(MultiEd25519Wallet {
+            public_keys,
+            signers,
+            threshold,
+        })
+    }
+
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    /// get public keys hex
+    pub fn public_keys_hex(&self) -> Vec<String> {
+        self.public_keys.iter().map(hex::encode).collect()
+    }
+
+    /// public keys followed by the threshold
+    pub fn public_key_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = self.public_keys.concat();
+        bytes.push(self.threshold);
+        bytes
+    }
+
+    /// get multi-ed25519 account address
+    pub fn address(&self) -> String {
+        let mut hasher = Sha3_256::new();
+        hasher.update(self.public_key_bytes());
+        hasher.update([1u8]);
+        format!("0x{}", hex::encode(hasher.finalize()))
+    }
+
+    /// sign with the locally held keys, returning `(key index, signature)` pairs
+    pub fn sign_partial(&self, message: &[u8]) -> Result<Vec<(u8, Vec<u8>)>, String> {
+        self.signers
+            .iter()
+            .map(|(index, wallet)| Ok((*index, wallet.sign(message)?)))
+            .collect()
+    }
+
+    /// sign with the first `threshold` locally held keys
+    pub fn sign(&self, message: &[u8]) -> Result<MultiEd25519Signature, String> {
+        if self.signers.len() < self.threshold as usize {
+            return Err(format!(
+                "not enough keys to sign: {} of {}",
+                self.signers.len(),
+                self.threshold
+            ));
+        }
+        let parts = self
+            .signers
+            .iter()
+            .take(self.threshold as usize)
+            .map(|(index, wallet)| Ok((*index, wallet.sign(message)?)))
+            .collect::<Result<Vec<_>, String>>()?;
+        MultiEd25519Signature::new(parts)
+    }
+
+    /// verify that a signature meets the threshold and every signature is valid
+    pub fn verify(&self, message: &[u8], signature: &MultiEd25519Signature) -> bool {
+        let indexes = signature.signer_indexes();
+        if indexes.len() < self.threshold as usize || indexes.len() != signature.signatures.len() {
+            return false;
+        }
+        indexes
+            .iter()
+            .zip(&signature.signatures)
+            .all(|(index, sig)| {
+                self.public_keys.get(*index as usize).is_some_and(|key| {
+                    ring::signature::UnparsedPublicKey::new(&ring::signature::ED25519, key)
+                        .verify(message, sig)
+                        .is_ok()
+                })
+            })
+    }
+}
+
+/// authentication key scheme byte of single key accounts
+pub const SINGLE_KEY_SCHEME: u8 = 2;
+
+/// `AnyPublicKey` / `AnySignature` variant index of secp256k1 ecdsa
+pub const ANY_KEY_SECP256K1_ECDSA: u8 = 1;
+
+/// Secp256k1 ecdsa account under the single key scheme.
+///
+/// Messages are hashed with sha3-256 before signing and signatures are 64 byte
+/// `r || s` in low-s form, as the chain expects.
+#[derive(Debug, Clone)]
+pub struct SingleKeyWallet {
+    signing_key: k256::ecdsa::SigningKey,
+}
+
+impl SingleKeyWallet {
+    /// create new wallet
+    pub fn new() -> Result<Self, String> {
+        use ring::rand::SecureRandom;
+        let rng = ring::rand::SystemRandom::new();
+        let mut secret = [0u8; 32];
+        loop {
+            rng.fill(&mut secret)
+                .map_err(|e| format!("Failed to generate key: {}", e))?;
+            if let Ok(signing_key) = k256::ecdsa::SigningKey::from_slice(&secret) {
+                secret.fill(0);
+                return Ok(SingleKeyWallet { signing_key });
+            }
+        }
+    }
+
+    /// create wallet from a 32 byte hex private key, with or without `0x`, or an
+    /// AIP-80 `secp256k1-priv-0x...` key
+    pub fn from_private_key_hex(private_key_hex: &str) -> Result<Self, String> {
+        let secret = parse_private_key(private_key_hex, AIP80_SECP256K1_PREFIX)?;
+        let signing_key = k256::ecdsa::SigningKey::from_slice(&secret)
+            .map_err(|e| format!("Invalid secp256k1 private key: {}", e))?;
+        Ok(SingleKeyWallet { signing_key })
+    }
+
+    /// get private key hex
+    pub fn private_key_hex(&self) -> String {
+        hex::encode(self.signing_key.to_bytes())
+    }
+
+    /// get the 65 byte uncompressed public key
+    pub fn public_key_bytes(&self) -> Vec<u8> {
+        self.signing_key
+            .verifying_key()
+            .to_encoded_point(false)
+            .as_bytes()
+            .to_vec()
+    }
+
+    /// get public key hex
+    pub fn public_key_hex(&self) -> String {
+        hex::encode(self.public_key_bytes())
+    }
+
+    /// bcs `AnyPublicKey`: variant, length, uncompressed key
+    pub fn any_public_key_bytes(&self) -> Vec<u8> {
+        let public_key = self.public_key_bytes();
+        let mut bytes = vec![ANY_KEY_SECP256K1_ECDSA, public_key.len() as u8];
+        bytes.extend(public_key);
+        bytes
+    }
+
+    /// get single key account address
+    pub fn address(&self) -> String {
+        let mut hasher = Sha3_256::new();
+        hasher.update(self.any_public_key_bytes());
+        hasher.update([SINGLE_KEY_SCHEME]);
+        format!("0x{}", hex::encode(hasher.finalize()))
+    }
+
+    /// sign, returning the 64 byte signature
+    pub fn sign(&self, message: &[u8]) -> Result<Vec<u8>, String> {
+        use k256::ecdsa::signature::hazmat::PrehashSigner;
+        let digest = Sha3_256::digest(message);
+        let signature: k256::ecdsa::Signature = self
+            .signing_key
+            .sign_prehash(&digest)
+            .map_err(|e| format!("Failed to sign: {}", e))?;
+        let signature = signature.normalize_s().unwrap_or(signature);
+        Ok(signature.to_bytes().to_vec())
+    }
+
+    /// bcs `AnySignature` of a signature from `sign`
+    pub fn any_signature_bytes(signature: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![ANY_KEY_SECP256K1_ECDSA, signature.len() as u8];
+        bytes.extend_from_slice(signature);
+        bytes
+    }
+
+    /// verify message, high-s signatures are rejected
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        use k256::ecdsa::signature::hazmat::PrehashVerifier;
+        let Ok(signature) = k256::ecdsa::Signature::from_slice(signature) else {
+            return false;
+        };
+        if signature.normalize_s().is_some() {
+            return false;
+        }
+        self.signing_key
+            .verifying_key()
+            .verify_prehash(&Sha3_256::digest(message), &signature)
+            .is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_message_round_trip() {
+        let wallet = Wallet::new().unwrap();
+        let signed = wallet.sign_message("nonce: 42").unwrap();
+        assert_eq!(signed.full_message, "APTOS\nmessage: nonce: 42");
+        assert!(verify_signature(
+            &signed.public_key,
+            "nonce: 42",
+            &signed.signature
+        ));
+        assert!(verify_signature(
+            &format!("0x{}", signed.public_key),
+            "nonce: 42",
+            &format!("0x{}", signed.signature)
+        ));
+    }
+
+    #[test]
+    fn test_verify_signature_rejects_tampered_message() {
+        let wallet = Wallet::new().unwrap();
+        let signed = wallet.sign_message("nonce: 42").unwrap();
+        assert!(!verify_signature(
+            &signed.public_key,
+            "nonce: 43",
+            &signed.signature
+        ));
+        let other = Wallet::new().unwrap().public_key_hex().unwrap();
+        assert!(!verify_signature(&other, "nonce: 42", &signed.signature));
+        assert!(!verify_signature(&signed.public_key, "nonce: 42", "zz"));
+    }
+
+    #[test]
+    fn test_multi_ed25519_sign_and_verify() {
+        let wallets: Vec<Wallet> = (0..3).map(|_| Wallet::new().unwrap()).collect();
+        let multi = MultiEd25519Wallet::new(wallets, 2).unwrap();
+        let signature = multi.sign(b"payload").unwrap();
+        assert_eq!(signature.signatures.len(), 2);
+        assert_eq!(signature.bitmap, [0b1100_0000, 0, 0, 0]);
+        assert_eq!(signature.signer_indexes(), vec![0, 1]);
+        assert_eq!(signature.to_bytes().len(), 2 * 64 + 4);
+        assert!(multi.verify(b"payload", &signature));
+        assert!(!multi.verify(b"tampered", &signature));
+        assert_eq!(multi.address().len(), 66);
+    }
+
+    #[test]
+    fn test_multi_ed25519_combines_partial_signatures() {
+        let wallets: Vec<Wallet> = (0..3).map(|_| Wallet::new().unwrap()).collect();
+        let public_keys: Vec<String> = wallets
+            .iter()
+            .map(|w| w.public_key_hex().unwrap())
+            .collect();
+        let first = MultiEd25519Wallet::from_public_keys(&public_keys, vec![wallets[2].clone()], 2)
+            .unwrap();
+        let second =
+            MultiEd25519Wallet::from_public_keys(&public_keys, vec![wallets[0].clone()], 2)
+                .unwrap();
+        assert!(first.sign(b"payload").is_err());
+        let mut parts = first.sign_partial(b"payload").unwrap();
+        parts.extend(second.sign_partial(b"payload").unwrap());
+        let signature = MultiEd25519Signature::new(parts).unwrap();
+        assert_eq!(signature.bitmap, [0b1010_0000, 0, 0, 0]);
+        assert!(first.verify(b"payload", &signature));
+        assert_eq!(first.address(), second.address());
+        assert!(MultiEd25519Signature::new(vec![(1, vec![]), (1, vec![])]).is_err());
+    }
+
+    #[test]
+    fn test_single_key_secp256k1_known_key() {
+        // private key 1, its public key is the curve generator
+        let wallet = SingleKeyWallet::from_private_key_hex(
+            "0x0000000000000000000000000000000000000000000000000000000000000001",
+        )
+        .unwrap();
+        assert_eq!(
+            wallet.public_key_hex(),
+            "0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798\
+             483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8"
+        );
+        let any_public_key = wallet.any_public_key_bytes();
+        assert_eq!(&any_public_key[..2], &[0x01, 0x41]);
+        assert_eq!(any_public_key.len(), 67);
+        let mut hasher = Sha3_256::new();
+        hasher.update(&any_public_key);
+        hasher.update([0x02]);
+        assert_eq!(
+            wallet.address(),
+            format!("0x{}", hex::encode(hasher.finalize()))
+        );
+
+        let signature = wallet.sign(b"payload").unwrap();
+        assert_eq!(signature.len(), 64);
+        // rfc6979 signatures are deterministic
+        assert_eq!(signature, wallet.sign(b"payload").unwrap());
+        assert!(wallet.verify(b"payload", &signature));
+        assert!(!wallet.verify(b"tampered", &signature));
+        let parsed = k256::ecdsa::Signature::from_slice(&signature).unwrap();
+        let high_s =
+            k256::ecdsa::Signature::from_scalars(parsed.r().to_bytes(), (-*parsed.s()).to_bytes())
+                .unwrap();
+        assert!(!wallet.verify(b"payload", &high_s.to_bytes()));
+        assert_eq!(
+            &SingleKeyWallet::any_signature_bytes(&signature)[..2],
+            &[0x01, 0x40]
+        );
+        assert!(SingleKeyWallet::from_private_key_hex("00").is_err());
+        let generated = SingleKeyWallet::new().unwrap();
+        let restored = SingleKeyWallet::from_private_key_hex(&generated.private_key_hex()).unwrap();
+        assert_eq!(generated.address(), restored.address());
+    }
+
+    #[test]
+    fn test_from_private_key_forms() {
+        // rfc 8032 test vector 1
+        let seed = "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+        let public_key = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+        for key in [
+            seed.to_string(),
+            format!("0x{}", seed),
+            format!("0X{}", seed.to_uppercase()),
+            format!("ed25519-priv-0x{}", seed),
+            format!("  ed25519-priv-{}\n", seed),
+        ] {
+            let wallet = Wallet::from_private_key(&key).unwrap();
+            assert_eq!(wallet.public_key_hex().unwrap(), public_key);
+        }
+        let from_bytes = Wallet::from_private_key_bytes(&hex::decode(seed).unwrap()).unwrap();
+        assert_eq!(from_bytes.public_key_hex().unwrap(), public_key);
+        // the PKCS8 export round trips
+        let restored = Wallet::from_private_key_hex(&from_bytes.private_key_hex()).unwrap();
+        assert_eq!(restored.address(), from_bytes.address());
+
+        let err = |key: &str| Wallet::from_private_key(key).unwrap_err();
+        assert!(err("0x1234").contains("length: 2 bytes"));
+        assert!(err(&format!("{}00", seed)).contains("length: 33 bytes"));
+        assert!(err("0xzz").contains("hex"));
+        assert!(err(&format!("secp256k1-priv-0x{}", seed)).contains("secp256k1"));
+        assert!(Wallet::from_private_key_bytes(&[1; 31]).is_err());
+        assert!(
+            SingleKeyWallet::from_private_key_hex(&format!("secp256k1-priv-0x{}", seed)).is_ok()
+        );
+    }
+
+    #[test]
+    fn test_address_cached_and_survives_serde() {
+        let wallet = Wallet::new().unwrap();
+        let mut hasher = Sha3_256::new();
+        hasher.update(wallet.public_key_bytes().unwrap());
+        hasher.update([0u8]);
+        assert_eq!(
+            wallet.address(),
+            format!("0x{}", hex::encode(hasher.finalize()))
+        );
+        let serialized = serde_json::to_string(&wallet).unwrap();
+        assert!(!serialized.contains("address"));
+        let restored: Wallet = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(restored.address(), wallet.address());
+        assert!(serde_json::from_str::<Wallet>(r#"{"keypair":[1,2,3]}"#).is_err());
+        assert_eq!(wallet.address_checksummed(), wallet.address());
+
+        // a rotated key signs for the account it was rotated onto
+        let public_key = wallet.public_key_bytes().unwrap();
+        let rotated = wallet.with_account_address("0xABC").unwrap();
+        assert_eq!(rotated.address(), format!("0x{:0>64}", "abc"));
+        assert_eq!(rotated.public_key_bytes().unwrap(), public_key);
+        let restored: Wallet =
+            serde_json::from_str(&serde_json::to_string(&rotated).unwrap()).unwrap();
+        assert_eq!(restored.address(), rotated.address());
+        assert!(Wallet::new().unwrap().with_account_address("0xzz").is_err());
+    }
+
+    #[test]
+    fn test_format_address_aip40() {
+        assert_eq!(format_address_aip40("0x1").unwrap(), "0x1");
+        assert_eq!(
+            format_address_aip40(
+                "0x000000000000000000000000000000000000000000000000000000000000000A"
+            )
+            .unwrap(),
+            "0xa"
+        );
+        assert_eq!(format_address_aip40("0x0").unwrap(), "0x0");
+        assert_eq!(
+            format_address_aip40("0x10").unwrap(),
+            "0x0000000000000000000000000000000000000000000000000000000000000010"
+        );
+        assert_eq!(
+            format_address_aip40("0xABCDEF").unwrap(),
+            "0x0000000000000000000000000000000000000000000000000000000000abcdef"
+        );
+        assert!(format_address_aip40("0x").is_err());
+        assert!(format_address_aip40("0xzz").is_err());
+        assert!(format_address_aip40(&format!("0x{}", "1".repeat(65))).is_err());
+    }
+
+    #[test]
+    fn test_rotation_proof_challenge_bcs() {
+        let new_public_key = [7u8; 32];
+        let challenge = rotation_proof_challenge(5, "0xa", "0xb", &new_public_key).unwrap();
+        let mut expected = Vec::new();
+        let address = |last: u8| {
+            let mut bytes = [0u8; 32];
+            bytes[31] = last;
+            bytes
+        };
+        expected.extend(address(1));
+        expected.push(7);
+        expected.extend(b"account");
+        expected.push(22);
+        expected.extend(b"RotationProofChallenge");
+        expected.extend(5u64.to_le_bytes());
+        expected.extend(address(0xa));
+        expected.extend(address(0xb));
+        expected.push(32);
+        expected.extend(new_public_key);
+        assert_eq!(challenge, expected);
+
+        let current = Wallet::new().unwrap();
+        let next = Wallet::new().unwrap();
+        let cap_rotate_key = current.sign(&challenge).unwrap();
+        let cap_update_table = next.sign(&challenge).unwrap();
+        assert!(current.verify(&challenge, &cap_rotate_key).unwrap());
+        assert!(next.verify(&challenge, &cap_update_table).unwrap());
+        let call = Wallet::rotation_call(
+            &current.public_key_bytes().unwrap(),
+            &next.public_key_bytes().unwrap(),
+            &cap_rotate_key,
+            &cap_update_table,
+        );
+        assert_eq!(
+            call.function_id(),
+            "0x1::account::rotate_authentication_key"
+        );
+        assert_eq!(call.arguments.len(), 6);
+        assert_eq!(call.arguments[0], json!(0));
+        assert_eq!(
+            call.arguments[3],
+            json!(format!("0x{}", next.public_key_hex().unwrap()))
+        );
+    }
+}
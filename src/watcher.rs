@@ -0,0 +1,282 @@
+/// Push notifications for balance changes and price conditions.
+///
+/// Every watch polls in its own background task and publishes `WatchEvent`s on the
+/// watcher's broadcast channel. Polling pauses while nobody is subscribed, and the
+/// `WatchHandle` a watch returns stops its task when stopped or dropped.
+#[cfg(feature = "dex")]
+use crate::dex::DexAggregator;
+use crate::{Aptos, token::TokenManager};
+use std::{future::Future, sync::Arc, time::Duration};
+use tokio::{sync::broadcast, task::JoinHandle};
+
+/// default time between two polls of a watch
+pub const DEFAULT_WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+const WATCH_CHANNEL_CAPACITY: usize = 100;
+
+/// a triggered watch
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatchEvent {
+    /// the balance moved by at least the watch threshold since the last event
+    Balance {
+        address: String,
+        token: String,
+        previous: u64,
+        current: u64,
+    },
+    /// the price predicate became true
+    Price {
+        dex: String,
+        token: String,
+        base_token: String,
+        price: f64,
+    },
+}
+
+/// Stops its watch when dropped.
+#[derive(Debug)]
+pub struct WatchHandle {
+    task: JoinHandle<()>,
+}
+
+impl WatchHandle {
+    /// same as dropping the handle
+    pub fn stop(self) {}
+
+    pub fn is_stopped(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+impl Drop for WatchHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// edge triggered price predicate, `check` is true only when the predicate turns true
+#[cfg(feature = "dex")]
+struct PriceCondition<F> {
+    predicate: F,
+    met: bool,
+}
+
+#[cfg(feature = "dex")]
+impl<F: Fn(f64) -> bool> PriceCondition<F> {
+    fn check(&mut self, price: f64) -> bool {
+        let met = (self.predicate)(price);
+        let triggered = met && !self.met;
+        self.met = met;
+        triggered
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Watcher {
+    client: Arc<Aptos>,
+    interval: Duration,
+    sender: broadcast::Sender<WatchEvent>,
+}
+
+impl Watcher {
+    pub fn new(client: Arc<Aptos>) -> Self {
+        let (sender, _) = broadcast::channel(WATCH_CHANNEL_CAPACITY);
+        Watcher {
+            client,
+            interval: DEFAULT_WATCH_INTERVAL,
+            sender,
+        }
+    }
+
+    /// time between two polls of watches started afterwards
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<WatchEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Emit when the balance of `token` held by `address` moves by at least
+    /// `threshold` from the last emitted balance, any change for a threshold of 0.
+    ///
+    /// `token` is a coin type or a fungible asset metadata address. The first poll
+    /// sets the starting balance.
+    pub fn watch_balance(&self, address: &str, token: &str, threshold: u64) -> WatchHandle {
+        let client = Arc::clone(&self.client);
+        let (address, token) = (address.to_string(), token.to_string());
+        let (event_address, event_token) = (address.clone(), token.clone());
+        let mut last: Option<u64> = None;
+        self.spawn(
+            move || {
+                let (client, address, token) =
+                    (Arc::clone(&client), address.clone(), token.clone());
+                async move {
+                    TokenManager::get_token_balance(client, &address, &token)
+                        .await
+                        .ok()
+                }
+            },
+            move |current| {
+                let previous = *last.get_or_insert(current);
+                if current.abs_diff(previous) < threshold.max(1) {
+                    return None;
+                }
+                last = Some(current);
+                Some(WatchEvent::Balance {
+                    address: event_address.clone(),
+                    token: event_token.clone(),
+                    previous,
+                    current,
+                })
+            },
+        )
+    }
+
+    /// Emit when `predicate` becomes true for the price of `pair.0` in `pair.1` on `dex`,
+    /// e.g. `("Liquidswap", (token, APT), |price| price > 2.0)`.
+    ///
+    /// The event fires once per crossing, the predicate has to turn false again before
+    /// the watch fires another time.
+    #[cfg(feature = "dex")]
+    pub fn watch_price<F>(&self, dex: &str, pair: (&str, &str), predicate: F) -> WatchHandle
+    where
+        F: Fn(f64) -> bool + Send + 'static,
+    {
+        let client = Arc::clone(&self.client);
+        let (dex, token, base_token) = (dex.to_string(), pair.0.to_string(), pair.1.to_string());
+        let mut condition = PriceCondition {
+            predicate,
+            met: false,
+        };
+        let (dex_name, token_name, base_name) = (dex.clone(), token.clone(), base_token.clone());
+        self.spawn(
+            move || {
+                let (client, dex, token, base_token) = (
+                    Arc::clone(&client),
+                    dex.clone(),
+                    token.clone(),
+                    base_token.clone(),
+                );
+                async move {
+                    DexAggregator::get_token_price_on_dex(client, &dex, &token, &base_token)
+                        .await
+                        .ok()
+                        .map(|price| price.price)
+                }
+            },
+            move |price| {
+                condition.check(price).then(|| WatchEvent::Price {
+                    dex: dex_name.clone(),
+                    token: token_name.clone(),
+                    base_token: base_name.clone(),
+                    price,
+                })
+            },
+        )
+    }
+
+    /// poll `fetch` every interval while someone is subscribed, publishing what
+    /// `trigger` makes of each result
+    fn spawn<T, Fetch, Fut, Trigger>(&self, fetch: Fetch, mut trigger: Trigger) -> WatchHandle
+    where
+        T: Send + 'static,
+        Fetch: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = Option<T>> + Send + 'static,
+        Trigger: FnMut(T) -> Option<WatchEvent> + Send + 'static,
+    {
+        let sender = self.sender.clone();
+        let interval = self.interval;
+        let task = tokio::spawn(async move {
+            loop {
+                if sender.receiver_count() > 0
+                    && let Some(value) = fetch().await
+                    && let Some(event) = trigger(value)
+                {
+                    let _ = sender.send(event);
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+        WatchHandle { task }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AptosType, transport::MockTransport};
+
+    const COIN_STORE: &str =
+        "/accounts/0xa/resource/0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>";
+
+    fn coin_store(value: u64) -> String {
+        format!(
+            r#"{{"type":"0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>","data":{{"coin":{{"value":"{}"}}}}}}"#,
+            value
+        )
+    }
+
+    #[tokio::test]
+    async fn test_watch_balance_emits_changes_over_threshold() {
+        let transport = MockTransport::new()
+            .on_get(COIN_STORE, 200, &coin_store(100))
+            .on_get(COIN_STORE, 200, &coin_store(105))
+            .on_get(COIN_STORE, 200, &coin_store(150))
+            .on_get(COIN_STORE, 200, &coin_store(150));
+        let client = Arc::new(Aptos::with_transport(
+            AptosType::Mainnet,
+            Arc::new(transport),
+        ));
+        let watcher = Watcher::new(client).with_interval(Duration::from_millis(10));
+        let mut events = watcher.subscribe();
+        let handle = watcher.watch_balance("0xa", "0x1::aptos_coin::AptosCoin", 10);
+        let event = tokio::time::timeout(Duration::from_secs(2), events.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            event,
+            WatchEvent::Balance {
+                address: "0xa".to_string(),
+                token: "0x1::aptos_coin::AptosCoin".to_string(),
+                previous: 100,
+                current: 150,
+            }
+        );
+        handle.stop();
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(events.try_recv().is_err());
+    }
+
+    #[cfg(feature = "dex")]
+    #[tokio::test]
+    async fn test_dropped_handle_stops_watch() {
+        let client = Arc::new(Aptos::with_transport(
+            AptosType::Mainnet,
+            Arc::new(MockTransport::new()),
+        ));
+        let watcher = Watcher::new(client).with_interval(Duration::from_millis(10));
+        let handle = watcher.watch_price("Liquidswap", ("0x1::a::A", "0x1::b::B"), |p| p > 1.0);
+        assert!(!handle.is_stopped());
+        let task = handle.task.abort_handle();
+        drop(handle);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(task.is_finished());
+    }
+
+    #[cfg(feature = "dex")]
+    #[test]
+    fn test_price_condition_fires_once_per_crossing() {
+        let mut condition = PriceCondition {
+            predicate: |price: f64| price > 2.0,
+            met: false,
+        };
+        let fired: Vec<bool> = [1.0, 2.5, 3.0, 1.5, 2.1]
+            .into_iter()
+            .map(|price| condition.check(price))
+            .collect();
+        assert_eq!(fired, vec![false, true, false, false, true]);
+    }
+}